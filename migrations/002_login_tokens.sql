@@ -0,0 +1,9 @@
+-- Single-use magic-link tokens (sent by the DM bot's "settings" command)
+CREATE TABLE IF NOT EXISTS login_tokens (
+    token TEXT PRIMARY KEY,
+    bluesky_did TEXT NOT NULL,
+    expires_at TIMESTAMPTZ NOT NULL,
+    created_at TIMESTAMPTZ DEFAULT NOW() NOT NULL
+);
+
+CREATE INDEX IF NOT EXISTS idx_login_tokens_expires ON login_tokens(expires_at);
@@ -0,0 +1,8 @@
+-- Reader documents created for a user's post, so re-saves update instead of duplicating
+CREATE TABLE IF NOT EXISTS saved_documents (
+    user_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    post_uri TEXT NOT NULL,
+    readwise_id TEXT NOT NULL,
+    saved_at TIMESTAMPTZ DEFAULT NOW() NOT NULL,
+    PRIMARY KEY (user_id, post_uri)
+);
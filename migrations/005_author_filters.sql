@@ -0,0 +1,4 @@
+-- Per-user author filters for bookmark auto-saves (handles or DIDs)
+ALTER TABLE user_settings
+    ADD COLUMN IF NOT EXISTS author_allowlist TEXT[] NOT NULL DEFAULT '{}',
+    ADD COLUMN IF NOT EXISTS author_denylist TEXT[] NOT NULL DEFAULT '{}';
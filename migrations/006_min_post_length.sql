@@ -0,0 +1,3 @@
+-- Shortest post (in characters) that bookmark sync will save
+ALTER TABLE user_settings
+    ADD COLUMN IF NOT EXISTS min_post_length INTEGER NOT NULL DEFAULT 0;
@@ -0,0 +1,3 @@
+-- Supports newest-first keyset paging of a user's saves
+CREATE INDEX IF NOT EXISTS idx_processed_bookmarks_user_keyset
+    ON processed_bookmarks (user_id, processed_at DESC, id DESC);
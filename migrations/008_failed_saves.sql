@@ -0,0 +1,17 @@
+-- Bookmarks whose save failed, retried with backoff until they succeed or give up
+CREATE TABLE IF NOT EXISTS failed_saves (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    user_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    post_uri TEXT NOT NULL,
+    error TEXT NOT NULL,
+    attempts INTEGER NOT NULL DEFAULT 1,
+    next_attempt_at TIMESTAMPTZ NOT NULL,
+    permanently_failed BOOLEAN NOT NULL DEFAULT FALSE,
+    created_at TIMESTAMPTZ DEFAULT NOW() NOT NULL,
+    updated_at TIMESTAMPTZ DEFAULT NOW() NOT NULL,
+    UNIQUE(user_id, post_uri)
+);
+
+CREATE INDEX IF NOT EXISTS idx_failed_saves_due
+    ON failed_saves (next_attempt_at)
+    WHERE NOT permanently_failed;
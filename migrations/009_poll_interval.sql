@@ -0,0 +1,3 @@
+-- Per-user bookmark poll interval (NULL uses the server default)
+ALTER TABLE user_settings
+    ADD COLUMN IF NOT EXISTS poll_interval_secs INTEGER;
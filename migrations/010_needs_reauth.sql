@@ -0,0 +1,3 @@
+-- Set when a user's tokens are rejected; cleared on their next login
+ALTER TABLE users
+    ADD COLUMN IF NOT EXISTS needs_reauth BOOLEAN NOT NULL DEFAULT FALSE;
@@ -0,0 +1,3 @@
+-- Per-user Readwise category for saved highlights (NULL uses the server default)
+ALTER TABLE user_settings
+    ADD COLUMN IF NOT EXISTS highlight_category TEXT;
@@ -0,0 +1,3 @@
+-- Save each bookmarked image's alt text as its own highlight
+ALTER TABLE user_settings
+    ADD COLUMN IF NOT EXISTS save_image_alt_text BOOLEAN NOT NULL DEFAULT FALSE;
@@ -0,0 +1,5 @@
+-- Opt-in daily digest DM, sent at the configured hour in the user's timezone
+ALTER TABLE user_settings
+    ADD COLUMN IF NOT EXISTS daily_digest BOOLEAN NOT NULL DEFAULT FALSE,
+    ADD COLUMN IF NOT EXISTS timezone TEXT,
+    ADD COLUMN IF NOT EXISTS last_digest_at TIMESTAMPTZ;
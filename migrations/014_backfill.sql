@@ -0,0 +1,8 @@
+-- One-time import of a user's existing bookmarks when sync is first enabled
+ALTER TABLE user_settings
+    ADD COLUMN IF NOT EXISTS backfilled BOOLEAN NOT NULL DEFAULT FALSE,
+    ADD COLUMN IF NOT EXISTS backfill_total INTEGER,
+    ADD COLUMN IF NOT EXISTS backfill_done INTEGER NOT NULL DEFAULT 0;
+
+-- Accounts already syncing have had their history picked up by polling
+UPDATE user_settings SET backfilled = TRUE WHERE bookmark_sync_enabled;
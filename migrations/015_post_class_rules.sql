@@ -0,0 +1,4 @@
+-- Per-user overrides for saving replies, self-threads, quotes, and top-level
+-- posts, as `class=target[:category]` entries (empty keeps the defaults)
+ALTER TABLE user_settings
+    ADD COLUMN IF NOT EXISTS post_class_rules TEXT[] NOT NULL DEFAULT '{}';
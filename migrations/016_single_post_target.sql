@@ -0,0 +1,4 @@
+-- Where a user's single posts are saved: 'highlight' or 'document'
+-- (NULL keeps the default, highlights)
+ALTER TABLE user_settings
+    ADD COLUMN IF NOT EXISTS single_post_target TEXT;
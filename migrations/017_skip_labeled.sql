@@ -0,0 +1,5 @@
+-- Skip auto-saving bookmarks whose post or author carries these labels
+-- (an empty list uses the built-in adult/graphic content labels)
+ALTER TABLE user_settings
+    ADD COLUMN IF NOT EXISTS skip_labeled BOOLEAN NOT NULL DEFAULT FALSE,
+    ADD COLUMN IF NOT EXISTS skip_labels TEXT[] NOT NULL DEFAULT '{}';
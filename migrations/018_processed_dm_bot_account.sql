@@ -0,0 +1,4 @@
+-- DID of the bot account that received (and answered) each DM; NULL for
+-- rows from before multiple bot accounts
+ALTER TABLE processed_dms
+    ADD COLUMN IF NOT EXISTS bot_account TEXT;
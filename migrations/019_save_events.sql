@@ -0,0 +1,15 @@
+-- Every save attempt and how it went, kept as an audit trail; never updated
+CREATE TABLE IF NOT EXISTS save_events (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    user_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    post_uri TEXT NOT NULL,
+    -- What was saved (highlight, document, ...); NULL when the attempt failed
+    kind TEXT,
+    status TEXT NOT NULL,
+    readwise_id TEXT,
+    error TEXT,
+    at TIMESTAMPTZ DEFAULT NOW() NOT NULL
+);
+
+CREATE INDEX IF NOT EXISTS idx_save_events_user_at
+    ON save_events (user_id, at DESC);
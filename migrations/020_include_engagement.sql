@@ -0,0 +1,3 @@
+-- Add like/repost/reply counts to saved posts
+ALTER TABLE user_settings
+    ADD COLUMN IF NOT EXISTS include_engagement BOOLEAN NOT NULL DEFAULT FALSE;
@@ -0,0 +1,2 @@
+-- DPoP key each user's tokens are bound to, needed to refresh them
+ALTER TABLE user_tokens ADD COLUMN IF NOT EXISTS dpop_key TEXT;
@@ -0,0 +1,18 @@
+-- Links extracted from posts and saved to Reader, keyed by normalized URL,
+-- so a link shared in several posts is only saved once
+CREATE TABLE IF NOT EXISTS saved_links (
+    user_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    url TEXT NOT NULL,
+    readwise_id TEXT NOT NULL,
+    saved_at TIMESTAMPTZ DEFAULT NOW() NOT NULL,
+    PRIMARY KEY (user_id, url)
+);
+
+-- Links used to be tracked alongside post documents
+INSERT INTO saved_links (user_id, url, readwise_id, saved_at)
+SELECT user_id, post_uri, readwise_id, saved_at
+FROM saved_documents
+WHERE post_uri NOT LIKE 'at://%' AND readwise_id <> ''
+ON CONFLICT DO NOTHING;
+
+DELETE FROM saved_documents WHERE post_uri NOT LIKE 'at://%';
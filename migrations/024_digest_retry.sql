@@ -0,0 +1,2 @@
+-- When a digest that failed to send may be tried again
+ALTER TABLE user_settings ADD COLUMN IF NOT EXISTS digest_retry_at TIMESTAMPTZ;
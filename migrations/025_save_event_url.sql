@@ -0,0 +1,2 @@
+-- The link a `link` event saved; NULL for post and alt-text events
+ALTER TABLE save_events ADD COLUMN IF NOT EXISTS url TEXT;
@@ -0,0 +1,195 @@
+//! AT-URI parsing
+//!
+//! Validates post URIs (`at://{did or handle}/app.bsky.feed.post/{rkey}`)
+//! before they're sent to the API, so bad input fails with a clear error.
+//! bsky.app post links are accepted where users paste them.
+
+use std::fmt;
+
+use thiserror::Error;
+
+/// Collection NSID for Bluesky posts
+pub const POST_COLLECTION: &str = "app.bsky.feed.post";
+
+/// AT-URI parse errors
+#[derive(Error, Debug, PartialEq, Eq)]
+pub enum AtUriError {
+    #[error("Not an AT-URI (expected at://...): {0}")]
+    Scheme(String),
+
+    #[error("AT-URI must have an authority, collection, and record key: {0}")]
+    Segments(String),
+
+    #[error("Invalid DID or handle in AT-URI: {0}")]
+    Authority(String),
+
+    #[error("Not a post (collection {0}, expected app.bsky.feed.post)")]
+    Collection(String),
+
+    #[error("Invalid record key in AT-URI: {0}")]
+    RecordKey(String),
+
+    #[error("Not a bsky.app post URL or AT-URI: {0}")]
+    PostUrl(String),
+}
+
+/// A validated post AT-URI
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AtUri {
+    /// DID, or a handle (lowercased) when the URI wasn't resolved
+    pub did_or_handle: String,
+    pub collection: String,
+    pub rkey: String,
+}
+
+impl fmt::Display for AtUri {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "at://{}/{}/{}",
+            self.did_or_handle, self.collection, self.rkey
+        )
+    }
+}
+
+/// Parse and normalize a post AT-URI
+pub fn parse_at_uri(uri: &str) -> Result<AtUri, AtUriError> {
+    let rest = uri
+        .trim()
+        .strip_prefix("at://")
+        .ok_or_else(|| AtUriError::Scheme(uri.to_string()))?;
+
+    let segments: Vec<&str> = rest.split('/').collect();
+    let [authority, collection, rkey] = segments.as_slice() else {
+        return Err(AtUriError::Segments(uri.to_string()));
+    };
+    if authority.is_empty() || collection.is_empty() || rkey.is_empty() {
+        return Err(AtUriError::Segments(uri.to_string()));
+    }
+
+    let did_or_handle = if authority.starts_with("did:") {
+        authority.to_string()
+    } else if is_handle(authority) {
+        authority.to_ascii_lowercase()
+    } else {
+        return Err(AtUriError::Authority(authority.to_string()));
+    };
+
+    if *collection != POST_COLLECTION {
+        return Err(AtUriError::Collection(collection.to_string()));
+    }
+
+    if rkey.len() > 512
+        || !rkey
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '.' | '-' | '_' | ':' | '~'))
+    {
+        return Err(AtUriError::RecordKey(rkey.to_string()));
+    }
+
+    Ok(AtUri {
+        did_or_handle,
+        collection: collection.to_string(),
+        rkey: rkey.to_string(),
+    })
+}
+
+/// Parse a post AT-URI or a `https://bsky.app/profile/{actor}/post/{rkey}` link
+pub fn parse_post_reference(input: &str) -> Result<AtUri, AtUriError> {
+    let input = input.trim();
+    if input.starts_with("at://") {
+        return parse_at_uri(input);
+    }
+
+    let path = input
+        .strip_prefix("https://bsky.app/profile/")
+        .ok_or_else(|| AtUriError::PostUrl(input.to_string()))?;
+    let path = path
+        .split(['?', '#'])
+        .next()
+        .unwrap_or_default()
+        .trim_end_matches('/');
+    match path.split('/').collect::<Vec<_>>().as_slice() {
+        [actor, "post", rkey] => {
+            parse_at_uri(&format!("at://{}/{}/{}", actor, POST_COLLECTION, rkey))
+        }
+        _ => Err(AtUriError::PostUrl(input.to_string())),
+    }
+}
+
+/// Loose handle check: dot-separated labels of letters, digits, and hyphens
+fn is_handle(authority: &str) -> bool {
+    authority.contains('.')
+        && authority.split('.').all(|label| {
+            !label.is_empty() && label.chars().all(|c| c.is_ascii_alphanumeric() || c == '-')
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_valid_uri() {
+        let uri = parse_at_uri("at://did:plc:abc123/app.bsky.feed.post/3kxyz").unwrap();
+        assert_eq!(uri.did_or_handle, "did:plc:abc123");
+        assert_eq!(uri.rkey, "3kxyz");
+        assert_eq!(
+            uri.to_string(),
+            "at://did:plc:abc123/app.bsky.feed.post/3kxyz"
+        );
+
+        let uri = parse_at_uri("at://Alice.BSKY.social/app.bsky.feed.post/3kxyz").unwrap();
+        assert_eq!(uri.did_or_handle, "alice.bsky.social");
+    }
+
+    #[test]
+    fn test_parse_post_reference() {
+        let uri =
+            parse_post_reference("https://bsky.app/profile/Alice.bsky.social/post/3kxyz?ref=1")
+                .unwrap();
+        assert_eq!(
+            uri.to_string(),
+            "at://alice.bsky.social/app.bsky.feed.post/3kxyz"
+        );
+        let uri = parse_post_reference(" at://did:plc:abc123/app.bsky.feed.post/3kxyz ").unwrap();
+        assert_eq!(uri.did_or_handle, "did:plc:abc123");
+
+        assert!(matches!(
+            parse_post_reference("https://bsky.app/profile/alice.bsky.social"),
+            Err(AtUriError::PostUrl(_))
+        ));
+        assert!(matches!(
+            parse_post_reference("https://example.com/profile/a.b/post/3kxyz"),
+            Err(AtUriError::PostUrl(_))
+        ));
+    }
+
+    #[test]
+    fn test_missing_rkey_rejected() {
+        assert!(matches!(
+            parse_at_uri("at://did:plc:abc123/app.bsky.feed.post"),
+            Err(AtUriError::Segments(_))
+        ));
+        assert!(matches!(
+            parse_at_uri("at://did:plc:abc123/app.bsky.feed.post/"),
+            Err(AtUriError::Segments(_))
+        ));
+    }
+
+    #[test]
+    fn test_wrong_scheme_rejected() {
+        assert!(matches!(
+            parse_at_uri("https://bsky.app/profile/alice.bsky.social/post/3kxyz"),
+            Err(AtUriError::Scheme(_))
+        ));
+    }
+
+    #[test]
+    fn test_non_post_collection_rejected() {
+        assert_eq!(
+            parse_at_uri("at://did:plc:abc123/app.bsky.feed.like/3kxyz"),
+            Err(AtUriError::Collection("app.bsky.feed.like".to_string()))
+        );
+    }
+}
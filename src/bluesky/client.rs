@@ -4,13 +4,19 @@
 
 use anyhow::{anyhow, Result};
 use async_trait::async_trait;
-use reqwest::Client;
+use reqwest::{Client, StatusCode};
 use serde::{Deserialize, Serialize};
+use thiserror::Error;
 use tracing::{debug, instrument};
 
+use super::handles::{resolve_did_document, PLC_DIRECTORY};
 use super::types::*;
+use crate::http_client::WithRequestId;
 
 /// Trait for Bluesky API operations (for testability)
+///
+/// `get_post_record`, `get_convo_for_member`, and `get_profile` fail unless
+/// implemented, so test clients only write the calls they exercise.
 #[async_trait]
 pub trait BlueskyClient: Send + Sync {
     /// Get user's bookmarks
@@ -19,8 +25,34 @@ pub trait BlueskyClient: Send + Sync {
     /// Get a post thread
     async fn get_post_thread(&self, uri: &str) -> Result<ThreadResponse>;
 
+    /// Fetch a single post record from the author's PDS, without the AppView
+    async fn get_post_record(&self, did: &str, rkey: &str) -> Result<PostView>;
+
     /// Send a DM
     async fn send_dm(&self, convo_id: &str, text: &str) -> Result<()>;
+
+    /// The account's DM conversation with `did`, starting one if needed
+    async fn get_convo_for_member(&self, did: &str) -> Result<ConvoView>;
+
+    /// List the account's DM conversations
+    async fn list_convos(&self) -> Result<ConvoListResponse>;
+
+    /// Get the most recent messages in a conversation
+    async fn get_messages(&self, convo_id: &str) -> Result<MessagesResponse>;
+
+    /// Mark a conversation as read
+    async fn mark_convo_read(&self, convo_id: &str) -> Result<()>;
+
+    /// Resolve a handle to its DID
+    async fn resolve_handle(&self, handle: &str) -> Result<String>;
+
+    /// Current profile (DID, handle, display name) for a DID or handle
+    async fn get_profile(&self, actor: &str) -> Result<Author> {
+        Err(anyhow!(
+            "Profiles aren't supported by this client ({})",
+            actor
+        ))
+    }
 }
 
 /// Bluesky public data service base URL
@@ -29,35 +61,135 @@ const BSKY_PUBLIC_API: &str = "https://public.api.bsky.app";
 /// Bluesky authenticated API base URL
 const BSKY_API: &str = "https://bsky.social";
 
+/// Largest `depth`/`parentHeight` getPostThread accepts
+pub const MAX_THREAD_FETCH_DEPTH: usize = 1000;
+
+/// Thread depth fetched unless configured otherwise
+const DEFAULT_THREAD_DEPTH: usize = 100;
+
 /// Bluesky chat API proxy header value
 const BSKY_CHAT_PROXY: &str = "did:web:api.bsky.chat#bsky_chat";
 
+/// The server rejected the access token (revoked, expired, or invalid)
+#[derive(Debug, Error)]
+#[error("Access token rejected: {0}")]
+pub struct TokenRejected(pub String);
+
+/// The post doesn't exist (deleted) or is hidden by a block
+#[derive(Debug, Error)]
+#[error("Post not found: {0}")]
+pub struct PostNotFound(pub String);
+
+/// An API call came back with an error status
+#[derive(Debug, Error)]
+#[error("{api} error {status}: {body}")]
+pub struct BlueskyApiError {
+    pub api: &'static str,
+    pub status: StatusCode,
+    pub body: String,
+}
+
+/// Whether an error means the caller's access token is no longer accepted
+pub fn is_token_rejected(error: &anyhow::Error) -> bool {
+    error.downcast_ref::<TokenRejected>().is_some()
+}
+
+/// Error for a failed authenticated call, flagging rejected tokens
+fn auth_error(api: &'static str, status: StatusCode, body: String) -> anyhow::Error {
+    let rejected = status == StatusCode::UNAUTHORIZED
+        || body.contains("ExpiredToken")
+        || body.contains("InvalidToken");
+    if rejected {
+        TokenRejected(format!("{} {}", status, body)).into()
+    } else {
+        BlueskyApiError { api, status, body }.into()
+    }
+}
+
 /// Concrete HTTP client for Bluesky API
+#[derive(Clone)]
 pub struct HttpBlueskyClient {
     http: Client,
+    /// Base URL for authenticated API calls
+    base_url: String,
+    /// Base URL for unauthenticated public API calls
+    public_url: String,
     /// Access token for authenticated requests
     access_token: Option<String>,
     /// DID of the authenticated user
     did: Option<String>,
+    /// `depth` and `parentHeight` for getPostThread
+    thread_depth: usize,
+    /// PLC directory base URL for DID document lookups
+    plc_url: String,
 }
 
 impl HttpBlueskyClient {
     /// Create a new unauthenticated client (for public API only)
     pub fn new() -> Self {
+        Self::with_client(Client::new())
+    }
+
+    /// Create a new authenticated client
+    pub fn with_auth(access_token: String, did: String) -> Self {
+        Self::new().authenticated(access_token, did)
+    }
+
+    /// Create an unauthenticated client on a shared `reqwest::Client`
+    pub fn with_client(http: Client) -> Self {
         Self {
-            http: Client::new(),
+            http,
+            base_url: BSKY_API.to_string(),
+            public_url: BSKY_PUBLIC_API.to_string(),
             access_token: None,
             did: None,
+            thread_depth: DEFAULT_THREAD_DEPTH,
+            plc_url: PLC_DIRECTORY.to_string(),
         }
     }
 
-    /// Create a new authenticated client
-    pub fn with_auth(access_token: String, did: String) -> Self {
-        Self {
-            http: Client::new(),
-            access_token: Some(access_token),
-            did: Some(did),
+    /// Authenticate requests as the given account
+    pub fn authenticated(mut self, access_token: String, did: String) -> Self {
+        self.access_token = Some(access_token);
+        self.did = Some(did);
+        self
+    }
+
+    /// Point authenticated API calls at a different server (e.g., a self-hosted PDS)
+    pub fn with_base_url(mut self, base_url: &str) -> Self {
+        self.base_url = base_url.trim_end_matches('/').to_string();
+        self
+    }
+
+    /// Fetch threads this deep (each way), clamped to what the API allows
+    pub fn with_thread_depth(mut self, depth: usize) -> Self {
+        self.thread_depth = depth.clamp(1, MAX_THREAD_FETCH_DEPTH);
+        self
+    }
+
+    /// Point public API calls at a different server
+    pub fn with_public_url(mut self, public_url: &str) -> Self {
+        self.public_url = public_url.trim_end_matches('/').to_string();
+        self
+    }
+
+    /// Look up did:plc documents in a different PLC directory
+    pub fn with_plc_url(mut self, plc_url: &str) -> Self {
+        self.plc_url = plc_url.trim_end_matches('/').to_string();
+        self
+    }
+
+    /// Check the public API is reachable via its `_health` endpoint
+    pub async fn check_public_api(&self) -> Result<()> {
+        let url = format!("{}/xrpc/_health", self.public_url);
+        let response = self.http.get(&url).with_request_id().send().await?;
+        if !response.status().is_success() {
+            return Err(anyhow!(
+                "Public API health check failed: {}",
+                response.status()
+            ));
         }
+        Ok(())
     }
 
     /// Update the access token (e.g., after refresh)
@@ -65,6 +197,63 @@ impl HttpBlueskyClient {
         self.access_token = Some(token);
     }
 
+    /// Log in with a handle and app password via com.atproto.server.createSession
+    #[instrument(skip(self, password))]
+    pub async fn login_with_app_password(
+        &self,
+        handle: &str,
+        password: &str,
+    ) -> Result<AtpSession> {
+        #[derive(Serialize)]
+        struct CreateSessionInput<'a> {
+            identifier: &'a str,
+            password: &'a str,
+        }
+
+        let url = format!("{}/xrpc/com.atproto.server.createSession", self.base_url);
+        let response = self
+            .http
+            .post(&url)
+            .json(&CreateSessionInput {
+                identifier: handle,
+                password,
+            })
+            .with_request_id()
+            .send()
+            .await?;
+
+        if !response.status().is_success() {
+            let status = response.status();
+            let body = response.text().await.unwrap_or_default();
+            return Err(anyhow!("Login failed {}: {}", status, body));
+        }
+
+        let session: AtpSession = response.json().await?;
+        debug!("Logged in as {}", session.did);
+        Ok(session)
+    }
+
+    /// Get a fresh session via com.atproto.server.refreshSession
+    #[instrument(skip_all)]
+    pub async fn refresh_session(&self, refresh_jwt: &str) -> Result<AtpSession> {
+        let url = format!("{}/xrpc/com.atproto.server.refreshSession", self.base_url);
+        let response = self
+            .http
+            .post(&url)
+            .header("Authorization", format!("Bearer {}", refresh_jwt))
+            .with_request_id()
+            .send()
+            .await?;
+
+        if !response.status().is_success() {
+            let status = response.status();
+            let body = response.text().await.unwrap_or_default();
+            return Err(anyhow!("Session refresh failed {}: {}", status, body));
+        }
+
+        Ok(response.json().await?)
+    }
+
     /// Make an authenticated GET request
     async fn auth_get<T: for<'de> Deserialize<'de>>(&self, url: &str) -> Result<T> {
         let token = self
@@ -76,13 +265,41 @@ impl HttpBlueskyClient {
             .http
             .get(url)
             .header("Authorization", format!("Bearer {}", token))
+            .with_request_id()
             .send()
             .await?;
 
         if !response.status().is_success() {
             let status = response.status();
             let body = response.text().await.unwrap_or_default();
-            return Err(anyhow!("API error {}: {}", status, body));
+            return Err(auth_error("API", status, body));
+        }
+
+        Ok(response.json().await?)
+    }
+
+    /// Make an authenticated GET request to the chat API
+    async fn chat_get<T: for<'de> Deserialize<'de>>(&self, endpoint: &str) -> Result<T> {
+        let token = self
+            .access_token
+            .as_ref()
+            .ok_or_else(|| anyhow!("Authentication required"))?;
+
+        let url = format!("{}/xrpc/{}", self.base_url, endpoint);
+
+        let response = self
+            .http
+            .get(&url)
+            .header("Authorization", format!("Bearer {}", token))
+            .header("atproto-proxy", BSKY_CHAT_PROXY)
+            .with_request_id()
+            .send()
+            .await?;
+
+        if !response.status().is_success() {
+            let status = response.status();
+            let body = response.text().await.unwrap_or_default();
+            return Err(auth_error("Chat API", status, body));
         }
 
         Ok(response.json().await?)
@@ -99,7 +316,7 @@ impl HttpBlueskyClient {
             .as_ref()
             .ok_or_else(|| anyhow!("Authentication required"))?;
 
-        let url = format!("{}/xrpc/{}", BSKY_API, endpoint);
+        let url = format!("{}/xrpc/{}", self.base_url, endpoint);
 
         let response = self
             .http
@@ -107,13 +324,14 @@ impl HttpBlueskyClient {
             .header("Authorization", format!("Bearer {}", token))
             .header("atproto-proxy", BSKY_CHAT_PROXY)
             .json(body)
+            .with_request_id()
             .send()
             .await?;
 
         if !response.status().is_success() {
             let status = response.status();
             let body = response.text().await.unwrap_or_default();
-            return Err(anyhow!("Chat API error {}: {}", status, body));
+            return Err(auth_error("Chat API", status, body));
         }
 
         Ok(response.json().await?)
@@ -130,7 +348,10 @@ impl Default for HttpBlueskyClient {
 impl BlueskyClient for HttpBlueskyClient {
     #[instrument(skip(self))]
     async fn get_bookmarks(&self, cursor: Option<&str>) -> Result<BookmarkResponse> {
-        let mut url = format!("{}/xrpc/app.bsky.bookmark.getBookmarks?limit=50", BSKY_API);
+        let mut url = format!(
+            "{}/xrpc/app.bsky.bookmark.getBookmarks?limit=50",
+            self.base_url
+        );
         if let Some(c) = cursor {
             url.push_str(&format!("&cursor={}", c));
         }
@@ -143,21 +364,86 @@ impl BlueskyClient for HttpBlueskyClient {
     async fn get_post_thread(&self, uri: &str) -> Result<ThreadResponse> {
         // Public API doesn't require auth for public posts
         let url = format!(
-            "{}/xrpc/app.bsky.feed.getPostThread?uri={}&depth=100&parentHeight=100",
-            BSKY_PUBLIC_API,
-            urlencoding::encode(uri)
+            "{}/xrpc/app.bsky.feed.getPostThread?uri={}&depth={}&parentHeight={}",
+            self.public_url,
+            urlencoding::encode(uri),
+            self.thread_depth,
+            self.thread_depth
         );
 
+        /// The requested post may itself come back as a placeholder
+        #[derive(Deserialize)]
+        struct RawThread {
+            thread: ThreadNode,
+        }
+
         debug!("Fetching post thread");
-        let response = self.http.get(&url).send().await?;
+        let response = self.http.get(&url).with_request_id().send().await?;
 
         if !response.status().is_success() {
             let status = response.status();
             let body = response.text().await.unwrap_or_default();
-            return Err(anyhow!("API error {}: {}", status, body));
+            if status == StatusCode::BAD_REQUEST && body.contains("NotFound") {
+                return Err(PostNotFound(uri.to_string()).into());
+            }
+            return Err(BlueskyApiError {
+                api: "API",
+                status,
+                body,
+            }
+            .into());
         }
 
-        Ok(response.json().await?)
+        match response.json::<RawThread>().await?.thread {
+            ThreadNode::Post(thread) => Ok(ThreadResponse { thread: *thread }),
+            ThreadNode::NotFound { .. } | ThreadNode::Blocked { .. } => {
+                Err(PostNotFound(uri.to_string()).into())
+            }
+            ThreadNode::Unknown => Err(anyhow!("Unexpected thread type for {}", uri)),
+        }
+    }
+
+    #[instrument(skip(self))]
+    async fn get_post_record(&self, did: &str, rkey: &str) -> Result<PostView> {
+        let document = resolve_did_document(&self.http, &self.plc_url, did).await?;
+        let pds = document
+            .pds_endpoints()
+            .first()
+            .map(|pds| pds.trim_end_matches('/').to_string())
+            .ok_or_else(|| anyhow!("No PDS in the DID document for {}", did))?;
+        let url = format!(
+            "{}/xrpc/com.atproto.repo.getRecord?repo={}&collection=app.bsky.feed.post&rkey={}",
+            pds,
+            urlencoding::encode(did),
+            urlencoding::encode(rkey)
+        );
+
+        debug!("Fetching post record from {}", pds);
+        let response = self.http.get(&url).with_request_id().send().await?;
+
+        if !response.status().is_success() {
+            let status = response.status();
+            let body = response.text().await.unwrap_or_default();
+            if status == StatusCode::BAD_REQUEST && body.contains("RecordNotFound") {
+                return Err(
+                    PostNotFound(format!("at://{}/app.bsky.feed.post/{}", did, rkey)).into(),
+                );
+            }
+            return Err(BlueskyApiError {
+                api: "PDS",
+                status,
+                body,
+            }
+            .into());
+        }
+
+        let record: RecordResponse = response.json().await?;
+        Ok(record.into_post_view(Author {
+            did: did.to_string(),
+            handle: document.handles().unwrap_or(did).to_string(),
+            display_name: None,
+            labels: Vec::new(),
+        }))
     }
 
     #[instrument(skip(self))]
@@ -195,6 +481,103 @@ impl BlueskyClient for HttpBlueskyClient {
 
         Ok(())
     }
+
+    #[instrument(skip(self))]
+    async fn get_convo_for_member(&self, did: &str) -> Result<ConvoView> {
+        #[derive(Deserialize)]
+        struct ConvoOutput {
+            convo: ConvoView,
+        }
+
+        let endpoint = format!(
+            "chat.bsky.convo.getConvoForMembers?members={}",
+            urlencoding::encode(did)
+        );
+        let output: ConvoOutput = self.chat_get(&endpoint).await?;
+        Ok(output.convo)
+    }
+
+    #[instrument(skip(self))]
+    async fn list_convos(&self) -> Result<ConvoListResponse> {
+        debug!("Listing conversations");
+        self.chat_get("chat.bsky.convo.listConvos?limit=50").await
+    }
+
+    #[instrument(skip(self))]
+    async fn get_messages(&self, convo_id: &str) -> Result<MessagesResponse> {
+        debug!("Fetching messages");
+        self.chat_get(&format!(
+            "chat.bsky.convo.getMessages?convoId={}&limit=20",
+            urlencoding::encode(convo_id)
+        ))
+        .await
+    }
+
+    #[instrument(skip(self))]
+    async fn mark_convo_read(&self, convo_id: &str) -> Result<()> {
+        #[derive(Serialize)]
+        struct UpdateReadInput<'a> {
+            #[serde(rename = "convoId")]
+            convo_id: &'a str,
+        }
+
+        let _: serde_json::Value = self
+            .chat_post("chat.bsky.convo.updateRead", &UpdateReadInput { convo_id })
+            .await?;
+        Ok(())
+    }
+
+    #[instrument(skip(self))]
+    async fn resolve_handle(&self, handle: &str) -> Result<String> {
+        #[derive(Deserialize)]
+        struct ResolveHandleOutput {
+            did: String,
+        }
+
+        let url = format!(
+            "{}/xrpc/com.atproto.identity.resolveHandle?handle={}",
+            self.public_url,
+            urlencoding::encode(handle)
+        );
+        let response = self.http.get(&url).with_request_id().send().await?;
+
+        if !response.status().is_success() {
+            let status = response.status();
+            let body = response.text().await.unwrap_or_default();
+            return Err(BlueskyApiError {
+                api: "API",
+                status,
+                body,
+            }
+            .into());
+        }
+
+        let output: ResolveHandleOutput = response.json().await?;
+        Ok(output.did)
+    }
+
+    #[instrument(skip(self))]
+    async fn get_profile(&self, actor: &str) -> Result<Author> {
+        let url = format!(
+            "{}/xrpc/app.bsky.actor.getProfile?actor={}",
+            self.public_url,
+            urlencoding::encode(actor)
+        );
+        let response = self.http.get(&url).with_request_id().send().await?;
+
+        if !response.status().is_success() {
+            let status = response.status();
+            let body = response.text().await.unwrap_or_default();
+            return Err(BlueskyApiError {
+                api: "API",
+                status,
+                body,
+            }
+            .into());
+        }
+
+        Ok(response.json().await?)
+    }
 }
 
 #[cfg(test)]
@@ -215,4 +598,75 @@ mod tests {
         assert_eq!(client.access_token.as_deref(), Some("test_token"));
         assert_eq!(client.did.as_deref(), Some("did:plc:test"));
     }
+
+    /// Serve canned createSession/refreshSession responses on a local port
+    async fn mock_pds() -> String {
+        use axum::{http::HeaderMap, routing::post, Json, Router};
+        use serde_json::json;
+
+        let app = Router::new()
+            .route(
+                "/xrpc/com.atproto.server.createSession",
+                post(|Json(body): Json<serde_json::Value>| async move {
+                    assert_eq!(body["identifier"], "bot.bsky.social");
+                    Json(json!({
+                        "did": "did:plc:bot",
+                        "handle": "bot.bsky.social",
+                        "accessJwt": "access-1",
+                        "refreshJwt": "refresh-1",
+                        "active": true
+                    }))
+                }),
+            )
+            .route(
+                "/xrpc/com.atproto.server.refreshSession",
+                post(|headers: HeaderMap| async move {
+                    assert_eq!(headers["authorization"], "Bearer refresh-1");
+                    Json(json!({
+                        "did": "did:plc:bot",
+                        "handle": "bot.bsky.social",
+                        "accessJwt": "access-2",
+                        "refreshJwt": "refresh-2"
+                    }))
+                }),
+            );
+
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+        format!("http://{}", addr)
+    }
+
+    #[tokio::test]
+    async fn test_app_password_login_and_refresh() {
+        let client = HttpBlueskyClient::new().with_base_url(&mock_pds().await);
+
+        let session = client
+            .login_with_app_password("bot.bsky.social", "app-pass")
+            .await
+            .unwrap();
+        assert_eq!(session.did, "did:plc:bot");
+        assert_eq!(session.access_jwt, "access-1");
+        assert_eq!(session.refresh_jwt, "refresh-1");
+
+        let refreshed = client.refresh_session(&session.refresh_jwt).await.unwrap();
+        assert_eq!(refreshed.access_jwt, "access-2");
+        assert_eq!(refreshed.refresh_jwt, "refresh-2");
+    }
+
+    #[test]
+    fn test_rejected_tokens_are_flagged() {
+        let unauthorized = auth_error("API", StatusCode::UNAUTHORIZED, String::new());
+        assert!(is_token_rejected(&unauthorized));
+
+        let expired = auth_error(
+            "API",
+            StatusCode::BAD_REQUEST,
+            r#"{"error":"ExpiredToken"}"#.to_string(),
+        );
+        assert!(is_token_rejected(&expired));
+
+        let server_error = auth_error("API", StatusCode::BAD_GATEWAY, String::new());
+        assert!(!is_token_rejected(&server_error));
+    }
 }
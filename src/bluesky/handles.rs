@@ -0,0 +1,244 @@
+//! Handle → DID resolution with a TTL cache, and DID document lookup
+//!
+//! Login, DM URL conversion, and author filters all resolve handles; the
+//! cache keeps repeat lookups off DNS and the network. Failed lookups are
+//! cached briefly so a typo doesn't trigger a lookup on every poll.
+//!
+//! Login and the post record fallback both find an account's PDS from its
+//! DID document via `resolve_did_document`.
+
+use std::collections::HashMap;
+use std::sync::{Arc, RwLock};
+use std::time::{Duration, Instant};
+
+use anyhow::{anyhow, bail, Result};
+use async_trait::async_trait;
+use atproto_identity::model::Document;
+use atproto_identity::resolve::{resolve_handle, DnsResolver, HickoryDnsResolver};
+use atproto_identity::web::did_web_to_url;
+
+use super::BlueskyClient;
+use crate::http_client::WithRequestId;
+
+/// How long a failed lookup is remembered (capped at the cache TTL)
+const NEGATIVE_TTL: Duration = Duration::from_secs(60);
+
+/// Most handles kept at once; DMs can name arbitrary handles, so the cache
+/// can't grow with them unchecked
+const MAX_ENTRIES: usize = 10_000;
+
+/// Public PLC directory for did:plc documents
+pub const PLC_DIRECTORY: &str = "https://plc.directory";
+
+/// Fetch a DID's document
+///
+/// did:plc documents come from the PLC directory at `plc_url` (tests point
+/// this at a mock); did:web documents from the DID's own host.
+pub async fn resolve_did_document(
+    http: &reqwest::Client,
+    plc_url: &str,
+    did: &str,
+) -> Result<Document> {
+    let url = if did.starts_with("did:plc:") {
+        format!("{}/{}", plc_url.trim_end_matches('/'), did)
+    } else if did.starts_with("did:web:") {
+        did_web_to_url(did)?
+    } else {
+        bail!("Unsupported DID method: {}", did);
+    };
+
+    let response = http.get(&url).with_request_id().send().await?;
+    if !response.status().is_success() {
+        bail!(
+            "DID document lookup for {} failed: {}",
+            did,
+            response.status()
+        );
+    }
+    Ok(response.json().await?)
+}
+
+/// Resolves a handle to its DID
+#[async_trait]
+pub trait HandleResolver: Send + Sync {
+    async fn resolve_handle(&self, handle: &str) -> Result<String>;
+}
+
+/// Resolver using the handle's DNS TXT record and `.well-known` endpoint
+pub struct IdentityHandleResolver {
+    http: reqwest::Client,
+    dns: Arc<dyn DnsResolver>,
+}
+
+impl IdentityHandleResolver {
+    /// Resolver using the system DNS configuration
+    pub fn new(http: reqwest::Client) -> Self {
+        Self {
+            http,
+            dns: Arc::new(HickoryDnsResolver::create_resolver(&[])),
+        }
+    }
+}
+
+#[async_trait]
+impl HandleResolver for IdentityHandleResolver {
+    async fn resolve_handle(&self, handle: &str) -> Result<String> {
+        Ok(resolve_handle(&self.http, &*self.dns, handle).await?)
+    }
+}
+
+/// Resolver backed by a Bluesky client's `resolveHandle` call
+pub struct ClientHandleResolver<B>(pub B);
+
+#[async_trait]
+impl<B: BlueskyClient> HandleResolver for ClientHandleResolver<B> {
+    async fn resolve_handle(&self, handle: &str) -> Result<String> {
+        self.0.resolve_handle(handle).await
+    }
+}
+
+/// A cached lookup: the DID, or why resolution failed
+struct CacheEntry {
+    result: Result<String, String>,
+    expires_at: Instant,
+}
+
+/// TTL cache in front of a `HandleResolver`
+pub struct HandleCache {
+    inner: Arc<dyn HandleResolver>,
+    ttl: Duration,
+    max_entries: usize,
+    entries: RwLock<HashMap<String, CacheEntry>>,
+}
+
+impl HandleCache {
+    /// Cache lookups from `inner` for `ttl`
+    pub fn new(inner: Arc<dyn HandleResolver>, ttl: Duration) -> Self {
+        Self {
+            inner,
+            ttl,
+            max_entries: MAX_ENTRIES,
+            entries: RwLock::new(HashMap::new()),
+        }
+    }
+
+    /// Resolve a handle, using a cached result while it's fresh
+    pub async fn resolve_cached(&self, handle: &str) -> Result<String> {
+        let handle = handle.trim().trim_start_matches('@').to_ascii_lowercase();
+        if let Some(result) = self.cached(&handle) {
+            return result.map_err(|e| anyhow!(e));
+        }
+
+        let result = self
+            .inner
+            .resolve_handle(&handle)
+            .await
+            .map_err(|e| format!("Could not resolve {}: {}", handle, e));
+        let ttl = if result.is_ok() {
+            self.ttl
+        } else {
+            NEGATIVE_TTL.min(self.ttl)
+        };
+
+        let mut entries = self.entries.write().unwrap_or_else(|e| e.into_inner());
+        let now = Instant::now();
+        entries.retain(|_, entry| entry.expires_at > now);
+        if entries.len() >= self.max_entries {
+            // Still full of live entries: drop the one closest to expiring
+            let soonest = entries
+                .iter()
+                .min_by_key(|(_, entry)| entry.expires_at)
+                .map(|(handle, _)| handle.clone());
+            if let Some(soonest) = soonest {
+                entries.remove(&soonest);
+            }
+        }
+        entries.insert(
+            handle,
+            CacheEntry {
+                result: result.clone(),
+                expires_at: now + ttl,
+            },
+        );
+        result.map_err(|e| anyhow!(e))
+    }
+
+    fn cached(&self, handle: &str) -> Option<Result<String, String>> {
+        let entries = self.entries.read().unwrap_or_else(|e| e.into_inner());
+        entries
+            .get(handle)
+            .filter(|entry| entry.expires_at > Instant::now())
+            .map(|entry| entry.result.clone())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    /// Counts lookups; handles starting with "typo" fail
+    #[derive(Default)]
+    struct CountingResolver {
+        calls: AtomicUsize,
+    }
+
+    #[async_trait]
+    impl HandleResolver for CountingResolver {
+        async fn resolve_handle(&self, handle: &str) -> Result<String> {
+            self.calls.fetch_add(1, Ordering::SeqCst);
+            if handle.starts_with("typo") {
+                return Err(anyhow!("no such handle"));
+            }
+            Ok(format!("did:plc:{}", handle.replace('.', "")))
+        }
+    }
+
+    #[tokio::test]
+    async fn test_second_resolve_within_ttl_is_cached() {
+        let resolver = Arc::new(CountingResolver::default());
+        let cache = HandleCache::new(resolver.clone(), Duration::from_secs(3600));
+
+        let first = cache.resolve_cached("alice.bsky.social").await.unwrap();
+        let second = cache.resolve_cached("@Alice.bsky.social").await.unwrap();
+
+        assert_eq!(first, "did:plc:alicebskysocial");
+        assert_eq!(second, first);
+        assert_eq!(resolver.calls.load(Ordering::SeqCst), 1);
+    }
+
+    #[tokio::test]
+    async fn test_failures_are_cached_briefly() {
+        let resolver = Arc::new(CountingResolver::default());
+        let cache = HandleCache::new(resolver.clone(), Duration::from_secs(3600));
+
+        assert!(cache.resolve_cached("typo.bsky.social").await.is_err());
+        assert!(cache.resolve_cached("typo.bsky.social").await.is_err());
+        assert_eq!(resolver.calls.load(Ordering::SeqCst), 1);
+
+        // Once an entry expires, the next call goes back to the resolver
+        let cache = HandleCache::new(resolver.clone(), Duration::ZERO);
+        cache.resolve_cached("bob.bsky.social").await.unwrap();
+        cache.resolve_cached("bob.bsky.social").await.unwrap();
+        assert_eq!(resolver.calls.load(Ordering::SeqCst), 3);
+    }
+
+    #[tokio::test]
+    async fn test_cache_drops_expired_then_oldest_entries() {
+        let resolver = Arc::new(CountingResolver::default());
+        let cache = HandleCache {
+            max_entries: 2,
+            ..HandleCache::new(resolver.clone(), Duration::from_secs(3600))
+        };
+
+        // Failures expire first, so the typo goes when the cache fills
+        cache.resolve_cached("typo.bsky.social").await.unwrap_err();
+        cache.resolve_cached("alice.bsky.social").await.unwrap();
+        cache.resolve_cached("bob.bsky.social").await.unwrap();
+        assert_eq!(cache.entries.read().unwrap().len(), 2);
+
+        cache.resolve_cached("alice.bsky.social").await.unwrap();
+        cache.resolve_cached("typo.bsky.social").await.unwrap_err();
+        assert_eq!(resolver.calls.load(Ordering::SeqCst), 4);
+    }
+}
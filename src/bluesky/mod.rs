@@ -2,11 +2,19 @@
 //!
 //! Handles AT Protocol API calls for bookmarks, DMs, and posts.
 
+pub mod aturi;
 pub mod bookmarks;
 pub mod chat;
 pub mod client;
+pub mod handles;
 pub mod oauth;
 pub mod types;
 
-pub use client::{BlueskyClient, HttpBlueskyClient};
+pub use aturi::{parse_at_uri, parse_post_reference, AtUri, AtUriError};
+pub use client::{
+    is_token_rejected, BlueskyApiError, BlueskyClient, HttpBlueskyClient, PostNotFound,
+    TokenRejected,
+};
+pub use handles::{HandleCache, HandleResolver};
+pub use oauth::{OAuthError, OAuthService};
 pub use types::*;
@@ -1,3 +1,834 @@
 //! OAuth flow helpers
+//!
+//! AT Protocol OAuth (PAR + PKCE + DPoP) built on atproto-oauth.
+//!
+//! The PAR, token and refresh requests go through `dpop_post` instead of the
+//! crate's `oauth_init`/`oauth_complete`/`oauth_refresh`. Those mint a DPoP
+//! proof without a nonce and only pick one up from a `use_dpop_nonce`
+//! rejection, which they keep to themselves, so every call would pay that
+//! extra round trip. `dpop_post` sends the same form parameters and client
+//! assertion, built with the crate's `auth_dpop` and `mint`, plus the
+//! issuer's cached nonce.
 
-// TODO: Implement OAuth flow using atproto-oauth-axum
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+
+use async_trait::async_trait;
+use atproto_identity::key::{generate_key, identify_key, to_public, KeyData, KeyType};
+use atproto_identity::model::Document;
+use atproto_oauth::dpop::auth_dpop;
+use atproto_oauth::jwt::{mint, Claims, Header, JoseClaims};
+use atproto_oauth::pkce;
+use atproto_oauth::resources::{pds_resources, AuthorizationServer};
+use atproto_oauth::workflow::{
+    OAuthClient, OAuthRequest, OAuthRequestState, ParResponse, TokenResponse,
+};
+use chrono::{DateTime, Duration, Utc};
+use serde::de::DeserializeOwned;
+use thiserror::Error;
+use tracing::{info, instrument};
+
+use super::handles::{resolve_did_document, HandleCache, PLC_DIRECTORY};
+use uuid::Uuid;
+
+/// Scope requested during login unless configured otherwise
+pub const DEFAULT_SCOPE: &str = "atproto transition:generic";
+
+/// Scope granting access to the user's DMs
+pub const CHAT_SCOPE: &str = "transition:chat.bsky";
+
+/// How long a pending login may wait for the callback
+const PENDING_LOGIN_TTL_MINUTES: i64 = 10;
+
+/// `client_assertion_type` for `private_key_jwt` client authentication
+const CLIENT_ASSERTION_TYPE: &str = "urn:ietf:params:oauth:client-assertion-type:jwt-bearer";
+
+/// OAuth flow errors
+#[derive(Error, Debug)]
+pub enum OAuthError {
+    #[error("Unknown or expired login state")]
+    UnknownState,
+
+    #[error("Could not resolve identity: {0}")]
+    Resolution(String),
+
+    #[error("No PDS endpoint found for this account")]
+    NoPds,
+
+    #[error("Invalid PDS URL: {0}")]
+    InvalidPds(String),
+
+    #[error("Invalid key: {0}")]
+    Key(String),
+
+    #[error("OAuth request failed: {0}")]
+    Request(String),
+
+    #[error("Token subject {0} does not match the requested account")]
+    SubjectMismatch(String),
+
+    #[error("Token response did not identify the account")]
+    MissingSubject,
+
+    #[error("Authorization response came from {0}, not the server the login started with")]
+    IssuerMismatch(String),
+
+    #[error("Token subject {0} is not an account on the authorization server")]
+    ForeignSubject(String),
+}
+
+/// A login waiting for the user to authorize us
+#[derive(Clone)]
+pub struct PendingLogin {
+    pub request: OAuthRequest,
+    pub authorization_server: AuthorizationServer,
+    /// None when the PDS was given directly; the token subject decides
+    pub did: Option<String>,
+    pub handle: String,
+}
+
+/// In-memory store of pending logins, keyed by OAuth state
+#[derive(Default)]
+pub struct OAuthStateStore {
+    pending: Mutex<HashMap<String, PendingLogin>>,
+}
+
+impl OAuthStateStore {
+    /// Create an empty store
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Remember a pending login
+    pub fn insert(&self, login: PendingLogin) {
+        let mut pending = self.pending.lock().unwrap_or_else(|e| e.into_inner());
+        pending.insert(login.request.oauth_state.clone(), login);
+    }
+
+    /// Take a pending login by state. Each state can only be used once.
+    pub fn take(&self, state: &str) -> Option<PendingLogin> {
+        let mut pending = self.pending.lock().unwrap_or_else(|e| e.into_inner());
+        pending
+            .remove(state)
+            .filter(|login| login.request.expires_at > Utc::now())
+    }
+
+    /// Drop expired pending logins, returning how many were removed
+    pub fn cleanup_expired(&self) -> usize {
+        let mut pending = self.pending.lock().unwrap_or_else(|e| e.into_inner());
+        let before = pending.len();
+        let now = Utc::now();
+        pending.retain(|_, login| login.request.expires_at > now);
+        before - pending.len()
+    }
+}
+
+/// Latest DPoP nonce from each authorization server, keyed by issuer
+///
+/// Servers reject proofs without their current nonce (`use_dpop_nonce`);
+/// sending the last one we saw up front avoids that extra round trip.
+#[derive(Default)]
+pub struct DpopNonceStore {
+    nonces: Mutex<HashMap<String, String>>,
+}
+
+impl DpopNonceStore {
+    /// Create an empty store
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The nonce to send to `issuer`, if it has issued one
+    pub fn get(&self, issuer: &str) -> Option<String> {
+        let nonces = self.nonces.lock().unwrap_or_else(|e| e.into_inner());
+        nonces.get(issuer).cloned()
+    }
+
+    /// Remember the nonce `issuer` last handed out, replacing any older one
+    pub fn set(&self, issuer: &str, nonce: &str) {
+        let mut nonces = self.nonces.lock().unwrap_or_else(|e| e.into_inner());
+        nonces.insert(issuer.to_string(), nonce.to_string());
+    }
+}
+
+/// Identity and tokens from a completed login
+#[derive(Debug, Clone)]
+pub struct CompletedLogin {
+    pub did: String,
+    pub handle: String,
+    pub access_token: String,
+    pub refresh_token: Option<String>,
+    pub expires_at: Option<DateTime<Utc>>,
+    /// Private DPoP key the tokens are bound to; refreshes must prove it
+    pub dpop_key: Option<String>,
+}
+
+/// Tokens from a refresh-token grant
+#[derive(Debug, Clone)]
+pub struct RefreshedTokens {
+    pub access_token: String,
+    /// Refresh tokens are single-use; this replaces the one spent
+    pub refresh_token: String,
+    pub expires_at: Option<DateTime<Utc>>,
+}
+
+/// Trait for the OAuth login flow (for testability)
+#[async_trait]
+pub trait OAuthService: Send + Sync {
+    /// Start a login, returning the authorization URL to send the user to
+    ///
+    /// `pds_override` skips handle resolution and uses that PDS directly.
+    async fn initiate_login(
+        &self,
+        handle: &str,
+        pds_override: Option<&str>,
+    ) -> Result<String, OAuthError>;
+
+    /// Exchange the callback code for tokens
+    ///
+    /// `iss` is the callback's issuer parameter (RFC 9207), checked against
+    /// the authorization server the login was started with.
+    async fn complete_login(
+        &self,
+        code: &str,
+        state: &str,
+        iss: Option<&str>,
+    ) -> Result<CompletedLogin, OAuthError>;
+
+    /// Trade a user's refresh token for new tokens
+    ///
+    /// `dpop_key` is the key stored with the tokens at login.
+    async fn refresh_tokens(
+        &self,
+        did: &str,
+        refresh_token: &str,
+        dpop_key: &str,
+    ) -> Result<RefreshedTokens, OAuthError>;
+
+    /// Drop expired pending logins, returning how many were removed
+    fn cleanup_expired(&self) -> usize;
+}
+
+/// OAuth client settings
+pub struct OAuthConfig {
+    pub client_id: String,
+    pub redirect_uri: String,
+    /// Multibase-encoded private key used to sign client assertions
+    pub signing_key: String,
+    /// HTTP client for calls to PDSes and authorization servers
+    pub http: reqwest::Client,
+    /// Shared handle → DID cache
+    pub handles: Arc<HandleCache>,
+    /// Space-separated scope requested at login
+    pub scope: String,
+}
+
+/// OAuth service backed by atproto-oauth
+pub struct AtprotoOAuthService {
+    http: reqwest::Client,
+    client: OAuthClient,
+    /// PLC directory for did:plc documents
+    plc_url: String,
+    handles: Arc<HandleCache>,
+    scope: String,
+    states: OAuthStateStore,
+    nonces: DpopNonceStore,
+}
+
+impl AtprotoOAuthService {
+    /// Create a new OAuth service
+    pub fn new(config: OAuthConfig) -> Result<Self, OAuthError> {
+        let signing_key =
+            identify_key(&config.signing_key).map_err(|e| OAuthError::Key(e.to_string()))?;
+        let http = config.http;
+
+        Ok(Self {
+            client: OAuthClient {
+                redirect_uri: config.redirect_uri,
+                client_id: config.client_id,
+                private_signing_key_data: signing_key,
+            },
+            plc_url: PLC_DIRECTORY.to_string(),
+            http,
+            handles: config.handles,
+            scope: config.scope,
+            states: OAuthStateStore::new(),
+            nonces: DpopNonceStore::new(),
+        })
+    }
+
+    /// Signed `private_key_jwt` assertion identifying us to `server`
+    fn client_assertion(&self, server: &AuthorizationServer) -> Result<String, OAuthError> {
+        let signing_key = &self.client.private_signing_key_data;
+        let header =
+            Header::try_from(signing_key.clone()).map_err(|e| OAuthError::Key(e.to_string()))?;
+        let claims = Claims::new(JoseClaims {
+            issuer: Some(self.client.client_id.clone()),
+            subject: Some(self.client.client_id.clone()),
+            audience: Some(server.issuer.clone()),
+            json_web_token_id: Some(Uuid::new_v4().simple().to_string()),
+            issued_at: Some(Utc::now().timestamp() as u64),
+            ..Default::default()
+        });
+        mint(signing_key, &header, &claims).map_err(|e| OAuthError::Key(e.to_string()))
+    }
+
+    /// POST a form to an authorization server endpoint with a DPoP proof
+    ///
+    /// The proof carries the issuer's cached nonce. Any nonce the server
+    /// returns is cached, and a `use_dpop_nonce` rejection is retried once.
+    async fn dpop_post<T: DeserializeOwned>(
+        &self,
+        issuer: &str,
+        url: &str,
+        dpop_key: &KeyData,
+        params: &[(&str, &str)],
+    ) -> Result<T, OAuthError> {
+        let mut retried = false;
+        loop {
+            let proof = dpop_proof(dpop_key, url, self.nonces.get(issuer))?;
+            let response = self
+                .http
+                .post(url)
+                .header("DPoP", proof)
+                .form(params)
+                .send()
+                .await
+                .map_err(|e| OAuthError::Request(e.to_string()))?;
+
+            let new_nonce = response
+                .headers()
+                .get("DPoP-Nonce")
+                .and_then(|value| value.to_str().ok())
+                .filter(|nonce| self.nonces.get(issuer).as_deref() != Some(*nonce))
+                .map(str::to_string);
+            if let Some(nonce) = &new_nonce {
+                self.nonces.set(issuer, nonce);
+            }
+
+            let status = response.status();
+            if status.is_success() {
+                return response
+                    .json()
+                    .await
+                    .map_err(|e| OAuthError::Request(e.to_string()));
+            }
+
+            let challenge = response
+                .headers()
+                .get(reqwest::header::WWW_AUTHENTICATE)
+                .and_then(|value| value.to_str().ok())
+                .map(str::to_string);
+            let body = response.text().await.unwrap_or_default();
+            if !retried && new_nonce.is_some() && is_nonce_error(challenge.as_deref(), &body) {
+                retried = true;
+                continue;
+            }
+            return Err(OAuthError::Request(format!(
+                "{} returned {}: {}",
+                url, status, body
+            )));
+        }
+    }
+
+    /// PAR request state; its scope is what's sent to the authorization server
+    fn request_state(&self, state: &str, nonce: &str, code_challenge: String) -> OAuthRequestState {
+        OAuthRequestState {
+            state: state.to_string(),
+            nonce: nonce.to_string(),
+            code_challenge,
+            scope: self.scope.clone(),
+        }
+    }
+
+    /// Find the account's PDS, from the override or by resolving the handle
+    async fn resolve_account(
+        &self,
+        handle: &str,
+        pds_override: Option<&str>,
+    ) -> Result<ResolvedAccount, OAuthError> {
+        if let Some(pds) = pds_override {
+            return Ok(ResolvedAccount {
+                pds: validate_pds_url(pds)?,
+                did: None,
+                handle: handle.to_string(),
+            });
+        }
+
+        let did = if handle.starts_with("did:") {
+            handle.to_string()
+        } else {
+            self.handles
+                .resolve_cached(handle)
+                .await
+                .map_err(|e| OAuthError::Resolution(e.to_string()))?
+        };
+        let (document, pds) = self.resolve_pds(&did).await?;
+        Ok(ResolvedAccount {
+            pds,
+            did: Some(document.id.clone()),
+            handle: document.handles().unwrap_or(handle).to_string(),
+        })
+    }
+
+    /// A DID's document and the PDS it names
+    async fn resolve_pds(&self, did: &str) -> Result<(Document, String), OAuthError> {
+        let document = resolve_did_document(&self.http, &self.plc_url, did)
+            .await
+            .map_err(|e| OAuthError::Resolution(e.to_string()))?;
+        let pds = document
+            .pds_endpoints()
+            .first()
+            .map(|pds| pds.to_string())
+            .ok_or(OAuthError::NoPds)?;
+        Ok((document, pds))
+    }
+
+    /// Check a token subject we didn't resolve up front belongs to `server`
+    ///
+    /// With a PDS override the account is only known from the token
+    /// response, so an authorization server could name any DID. Only accept
+    /// it if the DID's own PDS uses that same authorization server.
+    async fn verify_subject(
+        &self,
+        sub: &str,
+        server: &AuthorizationServer,
+    ) -> Result<(), OAuthError> {
+        let (_, pds) = self.resolve_pds(sub).await?;
+        let (_, subject_server) = pds_resources(&self.http, &pds)
+            .await
+            .map_err(|e| OAuthError::Request(format!("PDS {} unreachable: {}", pds, e)))?;
+        check_subject_server(sub, server, &subject_server)
+    }
+}
+
+/// Where an account lives, as found at the start of login
+struct ResolvedAccount {
+    pds: String,
+    did: Option<String>,
+    handle: String,
+}
+
+/// Check a user-supplied PDS URL is an absolute HTTPS URL
+fn validate_pds_url(pds: &str) -> Result<String, OAuthError> {
+    let url = url::Url::parse(pds).map_err(|e| OAuthError::InvalidPds(e.to_string()))?;
+    if url.scheme() != "https" || url.host_str().is_none() {
+        return Err(OAuthError::InvalidPds(format!(
+            "{} is not an HTTPS URL",
+            pds
+        )));
+    }
+    Ok(url.as_str().trim_end_matches('/').to_string())
+}
+
+#[async_trait]
+impl OAuthService for AtprotoOAuthService {
+    #[instrument(skip(self))]
+    async fn initiate_login(
+        &self,
+        handle: &str,
+        pds_override: Option<&str>,
+    ) -> Result<String, OAuthError> {
+        let ResolvedAccount { pds, did, handle } =
+            self.resolve_account(handle, pds_override).await?;
+
+        // Also serves as the reachability check for an overridden PDS
+        let (resource, authorization_server) = pds_resources(&self.http, &pds)
+            .await
+            .map_err(|e| OAuthError::Request(format!("PDS {} unreachable: {}", pds, e)))?;
+        info!(
+            "PDS {} is resource {} with authorization servers {:?}; using {}",
+            pds, resource.resource, resource.authorization_servers, authorization_server.issuer
+        );
+
+        let (pkce_verifier, code_challenge) = pkce::generate();
+        let dpop_key =
+            generate_key(KeyType::P256Private).map_err(|e| OAuthError::Key(e.to_string()))?;
+        let state = Uuid::new_v4().simple().to_string();
+        let nonce = Uuid::new_v4().simple().to_string();
+
+        let request_state = self.request_state(&state, &nonce, code_challenge);
+
+        let client_assertion = self.client_assertion(&authorization_server)?;
+        let par: ParResponse = self
+            .dpop_post(
+                &authorization_server.issuer,
+                &authorization_server.pushed_authorization_request_endpoint,
+                &dpop_key,
+                &[
+                    ("response_type", "code"),
+                    ("code_challenge", &request_state.code_challenge),
+                    ("code_challenge_method", "S256"),
+                    ("client_id", &self.client.client_id),
+                    ("state", &request_state.state),
+                    ("redirect_uri", &self.client.redirect_uri),
+                    ("scope", &request_state.scope),
+                    ("client_assertion_type", CLIENT_ASSERTION_TYPE),
+                    ("client_assertion", &client_assertion),
+                    ("login_hint", &handle),
+                ],
+            )
+            .await?;
+
+        let signing_public_key = to_public(&self.client.private_signing_key_data)
+            .map_err(|e| OAuthError::Key(e.to_string()))?;
+        let now = Utc::now();
+
+        self.states.insert(PendingLogin {
+            request: OAuthRequest {
+                oauth_state: state,
+                issuer: authorization_server.issuer.clone(),
+                authorization_server: authorization_server.issuer.clone(),
+                nonce,
+                pkce_verifier,
+                signing_public_key: signing_public_key.to_string(),
+                dpop_private_key: dpop_key.to_string(),
+                created_at: now,
+                expires_at: now + Duration::minutes(PENDING_LOGIN_TTL_MINUTES),
+            },
+            authorization_server: authorization_server.clone(),
+            did,
+            handle,
+        });
+
+        Ok(format!(
+            "{}?client_id={}&request_uri={}",
+            authorization_server.authorization_endpoint,
+            urlencoding::encode(&self.client.client_id),
+            urlencoding::encode(&par.request_uri)
+        ))
+    }
+
+    #[instrument(skip(self, code))]
+    async fn complete_login(
+        &self,
+        code: &str,
+        state: &str,
+        iss: Option<&str>,
+    ) -> Result<CompletedLogin, OAuthError> {
+        let pending = self.states.take(state).ok_or(OAuthError::UnknownState)?;
+        check_issuer(&pending.authorization_server, iss)?;
+        let dpop_key: KeyData = identify_key(&pending.request.dpop_private_key)
+            .map_err(|e| OAuthError::Key(e.to_string()))?;
+
+        let server = &pending.authorization_server;
+        let client_assertion = self.client_assertion(server)?;
+        let tokens: TokenResponse = self
+            .dpop_post(
+                &server.issuer,
+                &server.token_endpoint,
+                &dpop_key,
+                &[
+                    ("client_id", &self.client.client_id),
+                    ("redirect_uri", &self.client.redirect_uri),
+                    ("grant_type", "authorization_code"),
+                    ("code", code),
+                    ("code_verifier", &pending.request.pkce_verifier),
+                    ("client_assertion_type", CLIENT_ASSERTION_TYPE),
+                    ("client_assertion", &client_assertion),
+                ],
+            )
+            .await?;
+
+        let did = match (pending.did, tokens.sub.clone()) {
+            (Some(did), Some(sub)) if sub != did => return Err(OAuthError::SubjectMismatch(sub)),
+            (Some(did), _) => did,
+            (None, Some(sub)) => {
+                self.verify_subject(&sub, server).await?;
+                sub
+            }
+            (None, None) => return Err(OAuthError::MissingSubject),
+        };
+
+        Ok(CompletedLogin {
+            did,
+            handle: pending.handle,
+            access_token: tokens.access_token.clone(),
+            refresh_token: Some(tokens.refresh_token.clone()),
+            expires_at: Some(Utc::now() + Duration::seconds(i64::from(tokens.expires_in))),
+            dpop_key: Some(pending.request.dpop_private_key.clone()),
+        })
+    }
+
+    #[instrument(skip(self, refresh_token, dpop_key))]
+    async fn refresh_tokens(
+        &self,
+        did: &str,
+        refresh_token: &str,
+        dpop_key: &str,
+    ) -> Result<RefreshedTokens, OAuthError> {
+        let dpop_key = identify_key(dpop_key).map_err(|e| OAuthError::Key(e.to_string()))?;
+        let (_, pds) = self.resolve_pds(did).await?;
+        let (_, server) = pds_resources(&self.http, &pds)
+            .await
+            .map_err(|e| OAuthError::Request(format!("PDS {} unreachable: {}", pds, e)))?;
+
+        let client_assertion = self.client_assertion(&server)?;
+        let tokens: TokenResponse = self
+            .dpop_post(
+                &server.issuer,
+                &server.token_endpoint,
+                &dpop_key,
+                &[
+                    ("client_id", &self.client.client_id),
+                    ("grant_type", "refresh_token"),
+                    ("refresh_token", refresh_token),
+                    ("client_assertion_type", CLIENT_ASSERTION_TYPE),
+                    ("client_assertion", &client_assertion),
+                ],
+            )
+            .await?;
+        if let Some(sub) = tokens.sub.filter(|sub| sub != did) {
+            return Err(OAuthError::SubjectMismatch(sub));
+        }
+
+        Ok(RefreshedTokens {
+            access_token: tokens.access_token,
+            refresh_token: tokens.refresh_token,
+            expires_at: Some(Utc::now() + Duration::seconds(i64::from(tokens.expires_in))),
+        })
+    }
+
+    fn cleanup_expired(&self) -> usize {
+        self.states.cleanup_expired()
+    }
+}
+
+/// DPoP proof for a POST to `url`, carrying the server's nonce if known
+fn dpop_proof(key: &KeyData, url: &str, nonce: Option<String>) -> Result<String, OAuthError> {
+    let (proof, header, mut claims) =
+        auth_dpop(key, "POST", url).map_err(|e| OAuthError::Key(e.to_string()))?;
+    if nonce.is_none() {
+        return Ok(proof);
+    }
+    claims.jose.nonce = nonce;
+    mint(key, &header, &claims).map_err(|e| OAuthError::Key(e.to_string()))
+}
+
+/// Whether a rejection asks for a (new) DPoP nonce, per header or JSON body
+fn is_nonce_error(www_authenticate: Option<&str>, body: &str) -> bool {
+    www_authenticate.is_some_and(|value| value.contains("use_dpop_nonce"))
+        || serde_json::from_str::<serde_json::Value>(body)
+            .is_ok_and(|body| body["error"] == "use_dpop_nonce")
+}
+
+/// Reject a callback whose issuer isn't the server we sent the user to
+///
+/// Guards against mix-up attacks. A missing `iss` is only accepted from
+/// servers that don't advertise sending it.
+fn check_issuer(server: &AuthorizationServer, iss: Option<&str>) -> Result<(), OAuthError> {
+    match iss {
+        Some(iss) if iss != server.issuer => Err(OAuthError::IssuerMismatch(iss.to_string())),
+        None if server.authorization_response_iss_parameter_supported => {
+            Err(OAuthError::IssuerMismatch("(none)".to_string()))
+        }
+        _ => Ok(()),
+    }
+}
+
+/// Reject a subject whose PDS is served by a different authorization server
+fn check_subject_server(
+    sub: &str,
+    server: &AuthorizationServer,
+    subject_server: &AuthorizationServer,
+) -> Result<(), OAuthError> {
+    if subject_server.issuer != server.issuer {
+        return Err(OAuthError::ForeignSubject(sub.to_string()));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::bluesky::handles::IdentityHandleResolver;
+    use crate::test_support::{MockAuthServer, AUTH_SERVER_NONCE};
+
+    fn pending_login(state: &str, expires_at: DateTime<Utc>) -> PendingLogin {
+        PendingLogin {
+            request: OAuthRequest {
+                oauth_state: state.to_string(),
+                issuer: "https://bsky.social".to_string(),
+                authorization_server: "https://bsky.social".to_string(),
+                nonce: "nonce".to_string(),
+                pkce_verifier: "verifier".to_string(),
+                signing_public_key: String::new(),
+                dpop_private_key: String::new(),
+                created_at: Utc::now(),
+                expires_at,
+            },
+            authorization_server: AuthorizationServer::default(),
+            did: Some("did:plc:test".to_string()),
+            handle: "test.bsky.social".to_string(),
+        }
+    }
+
+    fn test_service() -> AtprotoOAuthService {
+        let signing_key = generate_key(KeyType::P256Private).unwrap();
+        let mut service = AtprotoOAuthService::new(OAuthConfig {
+            client_id: "https://autosave.example.com/client-metadata.json".to_string(),
+            redirect_uri: "https://autosave.example.com/auth/callback".to_string(),
+            signing_key: signing_key.to_string(),
+            http: reqwest::Client::new(),
+            handles: Arc::new(HandleCache::new(
+                Arc::new(IdentityHandleResolver::new(reqwest::Client::new())),
+                std::time::Duration::from_secs(3600),
+            )),
+            scope: "atproto transition:generic transition:chat.bsky".to_string(),
+        })
+        .unwrap();
+        // Any attempt to resolve through PLC would fail
+        service.plc_url = "https://plc.invalid".to_string();
+        service
+    }
+
+    #[test]
+    fn test_configured_scope_in_request_state() {
+        let request_state = test_service().request_state("state", "nonce", "challenge".to_string());
+        assert_eq!(
+            request_state.scope,
+            "atproto transition:generic transition:chat.bsky"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_pds_override_bypasses_resolution() {
+        let account = test_service()
+            .resolve_account("did:plc:unresolvable", Some("https://pds.example.com/"))
+            .await
+            .unwrap();
+
+        assert_eq!(account.pds, "https://pds.example.com");
+        assert_eq!(account.did, None);
+        assert_eq!(account.handle, "did:plc:unresolvable");
+    }
+
+    #[tokio::test]
+    async fn test_override_subject_must_belong_to_the_server() {
+        let server = AuthorizationServer {
+            issuer: "https://auth.example".to_string(),
+            ..Default::default()
+        };
+        let elsewhere = AuthorizationServer {
+            issuer: "https://bsky.social".to_string(),
+            ..Default::default()
+        };
+        assert!(check_subject_server("did:plc:mine", &server, &server.clone()).is_ok());
+        assert!(matches!(
+            check_subject_server("did:plc:victim", &server, &elsewhere),
+            Err(OAuthError::ForeignSubject(sub)) if sub == "did:plc:victim"
+        ));
+
+        // A subject that can't be resolved isn't trusted either
+        assert!(matches!(
+            test_service()
+                .verify_subject("did:plc:unresolvable", &server)
+                .await,
+            Err(OAuthError::Resolution(_))
+        ));
+    }
+
+    #[test]
+    fn test_pds_override_must_be_https() {
+        assert!(validate_pds_url("https://pds.example.com").is_ok());
+        assert!(matches!(
+            validate_pds_url("http://pds.example.com"),
+            Err(OAuthError::InvalidPds(_))
+        ));
+        assert!(matches!(
+            validate_pds_url("pds.example.com"),
+            Err(OAuthError::InvalidPds(_))
+        ));
+    }
+
+    #[test]
+    fn test_state_is_single_use() {
+        let store = OAuthStateStore::new();
+        store.insert(pending_login("abc", Utc::now() + Duration::minutes(5)));
+
+        assert!(store.take("abc").is_some());
+        assert!(store.take("abc").is_none());
+    }
+
+    #[tokio::test]
+    async fn test_mismatched_issuer_rejected() {
+        let service = test_service();
+        let mut pending = pending_login("abc", Utc::now() + Duration::minutes(5));
+        pending.authorization_server.issuer = "https://bsky.social".to_string();
+        pending
+            .authorization_server
+            .authorization_response_iss_parameter_supported = true;
+        service.states.insert(pending);
+
+        let result = service
+            .complete_login("code", "abc", Some("https://attacker.example"))
+            .await;
+        assert!(matches!(
+            result,
+            Err(OAuthError::IssuerMismatch(iss)) if iss == "https://attacker.example"
+        ));
+        // The state is spent either way
+        assert!(service.states.take("abc").is_none());
+
+        let server = AuthorizationServer {
+            issuer: "https://bsky.social".to_string(),
+            authorization_response_iss_parameter_supported: true,
+            ..Default::default()
+        };
+        assert!(check_issuer(&server, Some("https://bsky.social")).is_ok());
+        assert!(check_issuer(&server, None).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_cached_nonce_sent_on_next_request() {
+        let server = MockAuthServer::start().await;
+        let service = test_service();
+        let dpop_key = generate_key(KeyType::P256Private).unwrap();
+        let token_url = format!("{}/token", server.url);
+
+        // First call learns the nonce from the rejection and retries with it
+        let _: serde_json::Value = service
+            .dpop_post("https://auth.example", &token_url, &dpop_key, &[])
+            .await
+            .unwrap();
+        // Later calls to the same issuer send it up front
+        let _: serde_json::Value = service
+            .dpop_post("https://auth.example", &token_url, &dpop_key, &[])
+            .await
+            .unwrap();
+
+        let nonces: Vec<_> = server
+            .received("/token")
+            .into_iter()
+            .map(|request| request["nonce"].clone())
+            .collect();
+        assert_eq!(
+            nonces,
+            vec![
+                serde_json::Value::Null,
+                AUTH_SERVER_NONCE.into(),
+                AUTH_SERVER_NONCE.into()
+            ]
+        );
+        assert!(service.nonces.get("https://other.example").is_none());
+    }
+
+    #[test]
+    fn test_cleanup_drops_only_expired_states() {
+        let store = OAuthStateStore::new();
+        store.insert(pending_login("old", Utc::now() - Duration::minutes(1)));
+        store.insert(pending_login("fresh", Utc::now() + Duration::minutes(5)));
+
+        assert_eq!(store.cleanup_expired(), 1);
+        assert_eq!(store.cleanup_expired(), 0);
+        assert!(store.take("fresh").is_some());
+    }
+
+    #[test]
+    fn test_expired_state_rejected() {
+        let store = OAuthStateStore::new();
+        store.insert(pending_login("old", Utc::now() - Duration::minutes(1)));
+
+        assert!(store.take("old").is_none());
+    }
+}
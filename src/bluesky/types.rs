@@ -3,6 +3,57 @@
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 
+/// Session from createSession/refreshSession (app-password login)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AtpSession {
+    pub did: String,
+    pub handle: String,
+    pub access_jwt: String,
+    pub refresh_jwt: String,
+}
+
+/// Conversation list from chat.bsky.convo.listConvos
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ConvoListResponse {
+    pub cursor: Option<String>,
+    pub convos: Vec<ConvoView>,
+}
+
+/// A DM conversation
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ConvoView {
+    pub id: String,
+    #[serde(default)]
+    pub members: Vec<Author>,
+    pub unread_count: u32,
+}
+
+/// Messages from chat.bsky.convo.getMessages (newest first)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MessagesResponse {
+    pub cursor: Option<String>,
+    pub messages: Vec<MessageView>,
+}
+
+/// A DM message (deleted messages have no text)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MessageView {
+    pub id: String,
+    #[serde(default)]
+    pub text: Option<String>,
+    pub sender: MessageSender,
+    pub sent_at: DateTime<Utc>,
+}
+
+/// Sender of a DM message
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MessageSender {
+    pub did: String,
+}
+
 /// Bookmark response from getBookmarks
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct BookmarkResponse {
@@ -12,12 +63,42 @@ pub struct BookmarkResponse {
 
 /// A single bookmark view
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
 pub struct BookmarkView {
     pub subject: StrongRef,
     pub created_at: DateTime<Utc>,
-    // item can be postView, blockedPost, or notFoundPost
-    // We'll handle this as JSON for now
-    pub item: serde_json::Value,
+    pub item: BookmarkItem,
+}
+
+/// The bookmarked post, or why it can't be shown
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "$type")]
+pub enum BookmarkItem {
+    #[serde(rename = "app.bsky.feed.defs#postView")]
+    Post(Box<PostView>),
+    /// A repost; the post worth saving is its subject
+    #[serde(rename = "app.bsky.feed.defs#repostView")]
+    Repost(Box<RepostView>),
+    /// The post was deleted
+    #[serde(rename = "app.bsky.feed.defs#notFoundPost")]
+    NotFound { uri: String },
+    /// The post's author blocks (or is blocked by) the user
+    #[serde(rename = "app.bsky.feed.defs#blockedPost")]
+    Blocked { uri: String },
+    /// Any item type we don't know about yet
+    #[serde(other)]
+    Unknown,
+}
+
+/// A repost record and who made it
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RepostView {
+    pub uri: String,
+    pub cid: String,
+    /// The account that reposted
+    pub by: Author,
+    /// The original post
+    pub subject: StrongRef,
 }
 
 /// Strong reference to a record (uri + cid)
@@ -27,6 +108,33 @@ pub struct StrongRef {
     pub cid: String,
 }
 
+/// A post record fetched straight from its repo (com.atproto.repo.getRecord)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RecordResponse {
+    pub uri: String,
+    pub cid: String,
+    pub value: PostRecord,
+}
+
+impl RecordResponse {
+    /// A post view of the bare record, with no counts or embed views
+    ///
+    /// The record carries no index time, so its creation time stands in.
+    pub fn into_post_view(self, author: Author) -> PostView {
+        PostView {
+            uri: self.uri,
+            cid: self.cid,
+            author,
+            indexed_at: self.value.created_at,
+            record: self.value,
+            labels: Vec::new(),
+            like_count: None,
+            repost_count: None,
+            reply_count: None,
+        }
+    }
+}
+
 /// Thread response from getPostThread
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ThreadResponse {
@@ -37,35 +145,244 @@ pub struct ThreadResponse {
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ThreadViewPost {
     pub post: PostView,
-    pub parent: Option<Box<ThreadViewPost>>,
-    pub replies: Option<Vec<ThreadViewPost>>,
+    pub parent: Option<Box<ThreadNode>>,
+    pub replies: Option<Vec<ThreadNode>>,
+}
+
+impl ThreadViewPost {
+    /// The parent post, unless there is none or it didn't load
+    pub fn parent_post(&self) -> Option<&ThreadViewPost> {
+        match self.parent.as_deref() {
+            Some(ThreadNode::Post(parent)) => Some(parent),
+            _ => None,
+        }
+    }
+
+    /// Replies that loaded, skipping placeholders
+    pub fn reply_posts(&self) -> impl Iterator<Item = &ThreadViewPost> {
+        self.replies
+            .iter()
+            .flatten()
+            .filter_map(|reply| match reply {
+                ThreadNode::Post(post) => Some(post.as_ref()),
+                _ => None,
+            })
+    }
+
+    /// Whether part of the author's thread came back as a placeholder that
+    /// may load later (see `missing_posts`)
+    pub fn is_partial(&self) -> bool {
+        !self.missing_posts().is_empty()
+    }
+
+    /// URIs of not-found posts in the author's own chain
+    ///
+    /// Counts a not-found parent anywhere up the chain, and not-found replies
+    /// by the post's author, following their self-replies down. Other
+    /// people's replies aren't part of what's saved, so their gaps don't
+    /// count. Blocked posts are a lasting choice, not a blip, so they don't
+    /// either.
+    pub fn missing_posts(&self) -> Vec<&str> {
+        let mut missing = Vec::new();
+        let mut current = self;
+        while let Some(parent) = current.parent.as_deref() {
+            match parent {
+                ThreadNode::Post(parent) => current = parent,
+                ThreadNode::NotFound { uri } => {
+                    missing.push(uri.as_str());
+                    break;
+                }
+                ThreadNode::Blocked { .. } | ThreadNode::Unknown => break,
+            }
+        }
+        self.missing_self_replies(&self.post.author.did, &mut missing);
+        missing
+    }
+
+    fn missing_self_replies<'a>(&'a self, did: &str, missing: &mut Vec<&'a str>) {
+        let prefix = format!("at://{}/", did);
+        for reply in self.replies.iter().flatten() {
+            match reply {
+                ThreadNode::Post(post) if post.post.author.did == did => {
+                    post.missing_self_replies(did, missing)
+                }
+                ThreadNode::NotFound { uri } if uri.starts_with(&prefix) => missing.push(uri),
+                _ => {}
+            }
+        }
+    }
+}
+
+/// A parent or reply in a thread view, or why it can't be shown
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "$type")]
+pub enum ThreadNode {
+    #[serde(rename = "app.bsky.feed.defs#threadViewPost")]
+    Post(Box<ThreadViewPost>),
+    /// Deleted, or not available right now
+    #[serde(rename = "app.bsky.feed.defs#notFoundPost")]
+    NotFound { uri: String },
+    /// The author blocks (or is blocked by) the viewer
+    #[serde(rename = "app.bsky.feed.defs#blockedPost")]
+    Blocked { uri: String },
+    /// Any node type we don't know about yet
+    #[serde(other)]
+    Unknown,
 }
 
 /// A post view
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
 pub struct PostView {
     pub uri: String,
     pub cid: String,
     pub author: Author,
     pub record: PostRecord,
     pub indexed_at: DateTime<Utc>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub labels: Vec<Label>,
+    /// Engagement counts as of the fetch; absent from bare records
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub like_count: Option<u64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub repost_count: Option<u64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub reply_count: Option<u64>,
+}
+
+impl PostView {
+    /// Whether the post or its author carries any of these labels
+    pub fn has_label(&self, values: &[String]) -> bool {
+        self.labels
+            .iter()
+            .chain(&self.author.labels)
+            .any(|label| !label.neg && values.contains(&label.val))
+    }
+}
+
+impl From<ThreadViewPost> for ThreadNode {
+    fn from(post: ThreadViewPost) -> Self {
+        Self::Post(Box::new(post))
+    }
 }
 
 /// Post author
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
 pub struct Author {
     pub did: String,
     pub handle: String,
     pub display_name: Option<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub labels: Vec<Label>,
+}
+
+/// Labels that mark adult or graphic content
+pub const ADULT_CONTENT_LABELS: &[&str] = &["porn", "sexual", "nudity", "graphic-media", "gore"];
+
+/// A moderation label on a post or account
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Label {
+    /// DID of the labeler that applied it
+    pub src: String,
+    /// The label itself, e.g. `porn` or `graphic-media`
+    pub val: String,
+    /// Set when this retracts an earlier label
+    #[serde(default)]
+    pub neg: bool,
 }
 
 /// Post record content
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
 pub struct PostRecord {
     pub text: String,
     pub created_at: DateTime<Utc>,
     pub reply: Option<ReplyRef>,
     pub facets: Option<Vec<Facet>>,
+    pub embed: Option<Embed>,
+}
+
+impl PostRecord {
+    /// The record this post quotes, with or without media alongside
+    pub fn quoted(&self) -> Option<&StrongRef> {
+        match &self.embed {
+            Some(Embed::Record { record }) => Some(record),
+            Some(Embed::RecordWithMedia { record, .. }) => Some(&record.record),
+            _ => None,
+        }
+    }
+}
+
+/// Embedded media, link card, or quoted record
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "$type")]
+pub enum Embed {
+    #[serde(rename = "app.bsky.embed.images")]
+    Images { images: Vec<EmbedImage> },
+    #[serde(rename = "app.bsky.embed.external")]
+    External { external: EmbedExternal },
+    #[serde(rename = "app.bsky.embed.record")]
+    Record { record: StrongRef },
+    #[serde(rename = "app.bsky.embed.recordWithMedia")]
+    RecordWithMedia {
+        record: EmbedRecord,
+        media: Box<Embed>,
+    },
+    /// Any embed type we don't handle (e.g., video)
+    #[serde(other)]
+    Unknown,
+}
+
+/// An image in an images embed
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct EmbedImage {
+    pub image: Blob,
+    #[serde(default)]
+    pub alt: String,
+    pub aspect_ratio: Option<AspectRatio>,
+}
+
+/// Image dimensions hint
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AspectRatio {
+    pub width: u32,
+    pub height: u32,
+}
+
+/// External link card
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EmbedExternal {
+    pub uri: String,
+    #[serde(default)]
+    pub title: String,
+    #[serde(default)]
+    pub description: String,
+    pub thumb: Option<Blob>,
+}
+
+/// Quoted record inside a recordWithMedia embed
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EmbedRecord {
+    pub record: StrongRef,
+}
+
+/// Reference to uploaded binary data
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Blob {
+    #[serde(rename = "ref")]
+    pub reference: BlobLink,
+    pub mime_type: String,
+    pub size: u64,
+}
+
+/// CID link to a blob
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BlobLink {
+    #[serde(rename = "$link")]
+    pub link: String,
 }
 
 /// Reply reference indicating this is part of a thread
@@ -84,6 +401,7 @@ pub struct Facet {
 
 /// Byte range for a facet
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
 pub struct ByteSlice {
     pub byte_start: usize,
     pub byte_end: usize,
@@ -100,3 +418,260 @@ pub enum FacetFeature {
     #[serde(rename = "app.bsky.richtext.facet#tag")]
     Tag { tag: String },
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_bookmark_item_post_view() {
+        let item: BookmarkItem = serde_json::from_value(json!({
+            "$type": "app.bsky.feed.defs#postView",
+            "uri": "at://did:plc:abc/app.bsky.feed.post/xyz",
+            "cid": "bafycid",
+            "author": { "did": "did:plc:abc", "handle": "abc.bsky.social", "displayName": "Abc" },
+            "record": {
+                "$type": "app.bsky.feed.post",
+                "text": "Hello",
+                "createdAt": "2025-01-01T00:00:00Z"
+            },
+            "indexedAt": "2025-01-01T00:00:01Z",
+            "likeCount": 3
+        }))
+        .unwrap();
+
+        match item {
+            BookmarkItem::Post(post) => {
+                assert_eq!(post.record.text, "Hello");
+                assert_eq!(post.author.display_name.as_deref(), Some("Abc"));
+            }
+            other => panic!("Expected a post, got {:?}", other),
+        }
+    }
+
+    fn blob() -> serde_json::Value {
+        json!({
+            "$type": "blob",
+            "ref": { "$link": "bafkreiblob" },
+            "mimeType": "image/jpeg",
+            "size": 12345
+        })
+    }
+
+    fn quoted() -> serde_json::Value {
+        json!({
+            "uri": "at://did:plc:abc/app.bsky.feed.post/quoted",
+            "cid": "bafyquoted"
+        })
+    }
+
+    #[test]
+    fn test_embed_images() {
+        let embed: Embed = serde_json::from_value(json!({
+            "$type": "app.bsky.embed.images",
+            "images": [{
+                "alt": "A cat",
+                "image": blob(),
+                "aspectRatio": { "width": 800, "height": 600 }
+            }]
+        }))
+        .unwrap();
+
+        let Embed::Images { images } = embed else {
+            panic!("Expected images embed");
+        };
+        assert_eq!(images[0].alt, "A cat");
+        assert_eq!(images[0].image.reference.link, "bafkreiblob");
+        assert_eq!(images[0].aspect_ratio.as_ref().unwrap().width, 800);
+    }
+
+    #[test]
+    fn test_embed_external() {
+        let embed: Embed = serde_json::from_value(json!({
+            "$type": "app.bsky.embed.external",
+            "external": {
+                "uri": "https://example.com/article",
+                "title": "An article",
+                "description": "About things",
+                "thumb": blob()
+            }
+        }))
+        .unwrap();
+
+        let Embed::External { external } = embed else {
+            panic!("Expected external embed");
+        };
+        assert_eq!(external.uri, "https://example.com/article");
+        assert_eq!(external.title, "An article");
+        assert!(external.thumb.is_some());
+    }
+
+    #[test]
+    fn test_embed_record_and_record_with_media() {
+        let record: Embed = serde_json::from_value(json!({
+            "$type": "app.bsky.embed.record",
+            "record": quoted()
+        }))
+        .unwrap();
+        assert!(matches!(record, Embed::Record { record } if record.cid == "bafyquoted"));
+
+        let with_media: Embed = serde_json::from_value(json!({
+            "$type": "app.bsky.embed.recordWithMedia",
+            "record": { "record": quoted() },
+            "media": {
+                "$type": "app.bsky.embed.images",
+                "images": [{ "alt": "", "image": blob() }]
+            }
+        }))
+        .unwrap();
+        let Embed::RecordWithMedia { record, media } = with_media else {
+            panic!("Expected recordWithMedia embed");
+        };
+        assert_eq!(
+            record.record.uri,
+            "at://did:plc:abc/app.bsky.feed.post/quoted"
+        );
+        assert!(matches!(*media, Embed::Images { .. }));
+    }
+
+    #[test]
+    fn test_unknown_embed_does_not_fail_post() {
+        let record: PostRecord = serde_json::from_value(json!({
+            "$type": "app.bsky.feed.post",
+            "text": "Watch this",
+            "createdAt": "2025-01-01T00:00:00Z",
+            "embed": {
+                "$type": "app.bsky.embed.video",
+                "video": blob()
+            }
+        }))
+        .unwrap();
+        assert!(matches!(record.embed, Some(Embed::Unknown)));
+    }
+
+    #[test]
+    fn test_bookmark_item_repost() {
+        let item: BookmarkItem = serde_json::from_value(json!({
+            "$type": "app.bsky.feed.defs#repostView",
+            "uri": "at://did:plc:bob/app.bsky.feed.repost/r1",
+            "cid": "bafyrepost",
+            "by": { "did": "did:plc:bob", "handle": "bob.bsky.social" },
+            "subject": quoted()
+        }))
+        .unwrap();
+
+        let BookmarkItem::Repost(repost) = item else {
+            panic!("Expected a repost");
+        };
+        assert_eq!(repost.by.handle, "bob.bsky.social");
+        assert_eq!(
+            repost.subject.uri,
+            "at://did:plc:abc/app.bsky.feed.post/quoted"
+        );
+    }
+
+    #[test]
+    fn test_thread_placeholders_are_distinguished() {
+        let post = |rkey: &str| {
+            json!({
+                "uri": format!("at://did:plc:abc/app.bsky.feed.post/{}", rkey),
+                "cid": "bafycid",
+                "author": { "did": "did:plc:abc", "handle": "abc.bsky.social" },
+                "record": { "text": rkey, "createdAt": "2025-01-01T00:00:00Z" },
+                "indexedAt": "2025-01-01T00:00:01Z"
+            })
+        };
+        let response: ThreadResponse = serde_json::from_value(json!({
+            "thread": {
+                "$type": "app.bsky.feed.defs#threadViewPost",
+                "post": post("2"),
+                "parent": {
+                    "$type": "app.bsky.feed.defs#notFoundPost",
+                    "uri": "at://did:plc:abc/app.bsky.feed.post/1",
+                    "notFound": true
+                },
+                "replies": [
+                    { "$type": "app.bsky.feed.defs#threadViewPost", "post": post("3") },
+                    {
+                        "$type": "app.bsky.feed.defs#blockedPost",
+                        "uri": "at://did:plc:spam/app.bsky.feed.post/x",
+                        "blocked": true,
+                        "author": { "did": "did:plc:spam" }
+                    }
+                ]
+            }
+        }))
+        .unwrap();
+
+        let thread = response.thread;
+        assert!(matches!(
+            thread.parent.as_deref(),
+            Some(ThreadNode::NotFound { .. })
+        ));
+        assert!(thread.parent_post().is_none());
+        assert_eq!(thread.reply_posts().count(), 1);
+        assert!(thread.is_partial());
+
+        // A blocked reply alone doesn't make the thread partial
+        let thread = ThreadViewPost {
+            parent: None,
+            ..thread
+        };
+        assert!(!thread.is_partial());
+
+        // Nor does a blocked parent
+        let thread = ThreadViewPost {
+            parent: Some(Box::new(
+                serde_json::from_value(json!({
+                    "$type": "app.bsky.feed.defs#blockedPost",
+                    "uri": "at://did:plc:spam/app.bsky.feed.post/y",
+                    "blocked": true,
+                    "author": { "did": "did:plc:spam" }
+                }))
+                .unwrap(),
+            )),
+            ..thread
+        };
+        assert!(!thread.is_partial());
+
+        // A missing reply counts only when it's the author's own
+        let not_found = |uri: &str| ThreadNode::NotFound {
+            uri: uri.to_string(),
+        };
+        let thread = ThreadViewPost {
+            parent: None,
+            replies: Some(vec![not_found("at://did:plc:other/app.bsky.feed.post/4")]),
+            ..thread
+        };
+        assert!(!thread.is_partial());
+        let thread = ThreadViewPost {
+            replies: Some(vec![not_found("at://did:plc:abc/app.bsky.feed.post/4")]),
+            ..thread
+        };
+        assert_eq!(
+            thread.missing_posts(),
+            vec!["at://did:plc:abc/app.bsky.feed.post/4"]
+        );
+    }
+
+    #[test]
+    fn test_bookmark_item_not_found_and_blocked() {
+        let not_found: BookmarkItem = serde_json::from_value(json!({
+            "$type": "app.bsky.feed.defs#notFoundPost",
+            "uri": "at://did:plc:abc/app.bsky.feed.post/gone",
+            "notFound": true
+        }))
+        .unwrap();
+        assert!(matches!(not_found, BookmarkItem::NotFound { uri } if uri.ends_with("/gone")));
+
+        let blocked: BookmarkItem = serde_json::from_value(json!({
+            "$type": "app.bsky.feed.defs#blockedPost",
+            "uri": "at://did:plc:abc/app.bsky.feed.post/hidden",
+            "blocked": true,
+            "author": { "did": "did:plc:abc" }
+        }))
+        .unwrap();
+        assert!(matches!(blocked, BookmarkItem::Blocked { .. }));
+    }
+}
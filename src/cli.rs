@@ -0,0 +1,78 @@
+//! Command-line dispatch
+//!
+//! `serve` (the default with no arguments) runs the app, `migrate` runs
+//! database migrations and exits, and `selftest` checks credentials and
+//! connectivity. The flag forms (`--migrate`, `--migrate-only`,
+//! `--selftest`) are accepted too.
+
+use thiserror::Error;
+
+/// What the binary was asked to do
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Command {
+    /// Migrate, then run the web server and background tasks
+    Serve,
+    /// Run migrations and exit
+    Migrate,
+    /// Run the startup checks and exit
+    Selftest,
+}
+
+/// Argument parsing errors
+#[derive(Error, Debug, PartialEq, Eq)]
+pub enum CliError {
+    #[error("Unknown command {0:?}; expected serve, migrate, or selftest")]
+    UnknownCommand(String),
+    #[error("Unexpected argument {0:?}")]
+    UnexpectedArgument(String),
+}
+
+/// Pick the command from the arguments after the program name
+pub fn parse_command<I>(args: I) -> Result<Command, CliError>
+where
+    I: IntoIterator<Item = String>,
+{
+    let mut args = args.into_iter();
+    let command = match args.next().as_deref() {
+        None | Some("serve") => Command::Serve,
+        Some("migrate" | "--migrate" | "--migrate-only") => Command::Migrate,
+        Some("selftest" | "--selftest") => Command::Selftest,
+        Some(other) => return Err(CliError::UnknownCommand(other.to_string())),
+    };
+
+    match args.next() {
+        Some(extra) => Err(CliError::UnexpectedArgument(extra)),
+        None => Ok(command),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(args: &[&str]) -> Result<Command, CliError> {
+        parse_command(args.iter().map(|arg| arg.to_string()))
+    }
+
+    #[test]
+    fn test_parse_command() {
+        assert_eq!(parse(&[]), Ok(Command::Serve));
+        assert_eq!(parse(&["serve"]), Ok(Command::Serve));
+        assert_eq!(parse(&["migrate"]), Ok(Command::Migrate));
+        assert_eq!(parse(&["--migrate"]), Ok(Command::Migrate));
+        assert_eq!(parse(&["--migrate-only"]), Ok(Command::Migrate));
+        assert_eq!(parse(&["--selftest"]), Ok(Command::Selftest));
+    }
+
+    #[test]
+    fn test_parse_command_rejects_unknown() {
+        assert_eq!(
+            parse(&["deploy"]),
+            Err(CliError::UnknownCommand("deploy".to_string()))
+        );
+        assert_eq!(
+            parse(&["migrate", "now"]),
+            Err(CliError::UnexpectedArgument("now".to_string()))
+        );
+    }
+}
@@ -1,5 +1,34 @@
 use anyhow::{Context, Result};
 use serde::Deserialize;
+use thiserror::Error;
+
+use crate::bluesky::client::MAX_THREAD_FETCH_DEPTH;
+use crate::bluesky::oauth::{CHAT_SCOPE, DEFAULT_SCOPE};
+use crate::content::links::default_strip_query_params;
+use crate::content::{
+    HighlightTemplates, ThreadLimits, DEFAULT_POST_URL_TEMPLATE, DEFAULT_TITLE_TEMPLATE,
+};
+use crate::features::{parse_overrides, Feature, Features};
+use crate::logging::LogFormat;
+use crate::readwise::client::{
+    is_highlight_category, DEFAULT_HIGHLIGHT_CATEGORY, HIGHLIGHT_CATEGORIES,
+};
+use crate::services::bookmark_sync::MIN_POLL_INTERVAL;
+
+/// Every problem found by `Config::validate`
+#[derive(Error, Debug, PartialEq, Eq)]
+#[error("Invalid configuration:\n  - {}", .problems.join("\n  - "))]
+pub struct ConfigError {
+    pub problems: Vec<String>,
+}
+
+/// A Bluesky account the DM bot runs as
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BotAccount {
+    pub handle: String,
+    /// App password
+    pub password: String,
+}
 
 /// Application configuration loaded from environment and config files
 #[derive(Debug, Clone, Deserialize)]
@@ -8,21 +37,61 @@ pub struct Config {
     #[serde(default = "default_server_address")]
     pub server_address: String,
 
+    /// Public URL the app is reachable at (e.g., "https://autosave.example.com")
+    pub public_base_url: Option<String>,
+
+    /// Bluesky public API base URL (unauthenticated reads)
+    #[serde(default = "default_bsky_public_api_base")]
+    pub bsky_public_api_base: String,
+
+    /// Bluesky API base URL (bot login, bookmarks, DMs)
+    #[serde(default = "default_bsky_api_base")]
+    pub bsky_api_base: String,
+
+    /// Readwise API base URL (highlights and Reader)
+    #[serde(default = "default_readwise_api_base")]
+    pub readwise_api_base: String,
+
     /// PostgreSQL database URL
     pub database_url: String,
 
+    /// Maximum number of pooled database connections
+    #[serde(default = "default_db_max_connections")]
+    pub db_max_connections: u32,
+
+    /// Seconds to wait for a pooled connection before failing
+    #[serde(default = "default_db_acquire_timeout")]
+    pub db_acquire_timeout_secs: u64,
+
+    /// Seconds an idle pooled connection is kept open
+    #[serde(default = "default_db_idle_timeout")]
+    pub db_idle_timeout_secs: u64,
+
+    /// Base64-encoded 32-byte key for encrypting tokens at rest
+    pub encryption_key: String,
+
     /// Bluesky bot account handle for DM functionality
     pub bluesky_bot_handle: Option<String>,
 
     /// Bluesky bot account app password
     pub bluesky_bot_password: Option<String>,
 
+    /// More bot accounts as comma-separated `handle:password` pairs; each
+    /// account polls its own DMs
+    pub bluesky_bot_accounts: Option<String>,
+
     /// OAuth client ID (HTTPS URL pointing to client metadata)
     pub oauth_client_id: Option<String>,
 
     /// OAuth redirect URI
     pub oauth_redirect_uri: Option<String>,
 
+    /// Multibase private key (did:key) used to sign OAuth client assertions
+    pub oauth_signing_key: Option<String>,
+
+    /// OAuth scope to request (defaults to `atproto transition:generic`)
+    pub oauth_scope: Option<String>,
+
     /// Bookmark polling interval in seconds
     #[serde(default = "default_bookmark_poll_interval")]
     pub bookmark_poll_interval_secs: u64,
@@ -30,12 +99,152 @@ pub struct Config {
     /// DM polling interval in seconds
     #[serde(default = "default_dm_poll_interval")]
     pub dm_poll_interval_secs: u64,
+
+    /// How often expired logins and old processed-item rows are purged
+    #[serde(default = "default_cleanup_interval")]
+    pub cleanup_interval_secs: u64,
+
+    /// Days processed bookmarks and DMs are kept; unset keeps them forever
+    ///
+    /// Bookmarks and DMs older than this are ignored, so a purged one is
+    /// never handled again.
+    pub retention_days: Option<u32>,
+
+    /// Hour of day (0-23, in each user's timezone) daily digests are sent
+    #[serde(default = "default_digest_hour")]
+    pub digest_hour: u32,
+
+    /// Seconds to wait when connecting to an external API
+    #[serde(default = "default_http_connect_timeout")]
+    pub http_connect_timeout_secs: u64,
+
+    /// Seconds an outbound request may take in total
+    #[serde(default = "default_http_timeout")]
+    pub http_timeout_secs: u64,
+
+    /// Highlight title template (placeholders: {handle}, {display_name}, {date}, {url}, {text})
+    ///
+    /// Once changed from the default it titles Reader documents as well.
+    #[serde(default = "default_highlight_title_template")]
+    pub highlight_title_template: String,
+
+    /// Note added to highlights saved without one (same placeholders as the title)
+    pub highlight_note_template: Option<String>,
+
+    /// Link to saved posts, e.g. on an alternative frontend
+    /// (placeholders: {handle}, {did}, {rkey}; must include {rkey})
+    ///
+    /// Used for a highlight's source link and `{url}`; the URLs Readwise
+    /// dedups on stay on bsky.app so changing this doesn't re-save posts.
+    #[serde(default = "default_post_url_template")]
+    pub post_url_template: String,
+
+    /// Readwise category for saved highlights (books, articles, tweets, podcasts)
+    #[serde(default = "default_highlight_category")]
+    pub highlight_category: String,
+
+    /// Most Readwise saves allowed in flight at once, across all users
+    #[serde(default = "default_max_concurrent_saves")]
+    pub max_concurrent_saves: usize,
+
+    /// Bookmarks waiting to be saved before polls start deferring them to retries
+    #[serde(default = "default_save_queue_capacity")]
+    pub save_queue_capacity: usize,
+
+    /// Tasks saving queued bookmarks (still bounded by max_concurrent_saves)
+    #[serde(default = "default_save_workers")]
+    pub save_workers: usize,
+
+    /// How long resolved handle → DID lookups are cached
+    #[serde(default = "default_handle_cache_ttl")]
+    pub handle_cache_ttl_secs: u64,
+
+    /// Deepest parent/reply chain fetched and saved for a thread (API max 1000)
+    #[serde(default = "default_max_thread_depth")]
+    pub max_thread_depth: usize,
+
+    /// Comma-separated query parameters stripped from saved links
+    /// (a trailing `*` matches a prefix); unset uses the built-in tracking list
+    pub strip_query_params: Option<String>,
+
+    /// Most posts saved in one thread document
+    #[serde(default = "default_max_thread_posts")]
+    pub max_thread_posts: usize,
+
+    /// Largest Reader document HTML saved, in bytes (at least 4096); longer
+    /// threads are cut off between posts
+    #[serde(default = "default_max_document_bytes")]
+    pub max_document_bytes: usize,
+
+    /// Save a quote post with the thread it quotes, as one Reader document
+    #[serde(default = "default_expand_quotes")]
+    pub expand_quotes: bool,
+
+    /// Tag saves with the post's detected language (`lang:xx`); needs the
+    /// `language-detection` build feature
+    #[serde(default)]
+    pub detect_language: bool,
+
+    /// Comma-separated `name=on|off` feature settings that win over flags
+    /// set at runtime (features: digests, expand_quotes, detect_language)
+    pub feature_overrides: Option<String>,
+
+    /// How long feature flags read from the database are cached
+    #[serde(default = "default_feature_cache_ttl")]
+    pub feature_cache_ttl_secs: u64,
+
+    /// Log line format (`pretty` or `json`)
+    #[serde(default)]
+    pub log_format: LogFormat,
+
+    /// Post fetched by `--selftest` to check the public API
+    pub selftest_post_uri: Option<String>,
+
+    /// Readwise token checked by `--selftest`
+    pub selftest_readwise_token: Option<String>,
+
+    /// Comma-separated DIDs allowed to use the `/admin` routes
+    pub admin_did: Option<String>,
+
+    /// Comma-separated path prefixes guarded by the operator access check
+    #[serde(default = "default_protected_paths")]
+    pub protected_paths: String,
+
+    /// `user:password` required on protected paths (unless the IP is allowed)
+    pub protected_basic_auth: Option<String>,
+
+    /// Comma-separated IPs or CIDR ranges allowed on protected paths
+    pub protected_allowed_ips: Option<String>,
 }
 
 fn default_server_address() -> String {
     "0.0.0.0:3000".to_string()
 }
 
+fn default_bsky_public_api_base() -> String {
+    "https://public.api.bsky.app".to_string()
+}
+
+fn default_bsky_api_base() -> String {
+    "https://bsky.social".to_string()
+}
+
+fn default_readwise_api_base() -> String {
+    "https://readwise.io/api".to_string()
+}
+
+fn default_db_max_connections() -> u32 {
+    10
+}
+
+fn default_db_acquire_timeout() -> u64 {
+    5
+}
+
+fn default_db_idle_timeout() -> u64 {
+    600
+}
+
 fn default_bookmark_poll_interval() -> u64 {
     30
 }
@@ -44,7 +253,212 @@ fn default_dm_poll_interval() -> u64 {
     10
 }
 
+fn default_http_connect_timeout() -> u64 {
+    5
+}
+
+fn default_http_timeout() -> u64 {
+    30
+}
+
+fn default_highlight_title_template() -> String {
+    DEFAULT_TITLE_TEMPLATE.to_string()
+}
+
+fn default_post_url_template() -> String {
+    DEFAULT_POST_URL_TEMPLATE.to_string()
+}
+
+fn default_highlight_category() -> String {
+    DEFAULT_HIGHLIGHT_CATEGORY.to_string()
+}
+
+fn default_max_concurrent_saves() -> usize {
+    8
+}
+
+fn default_save_queue_capacity() -> usize {
+    500
+}
+
+fn default_protected_paths() -> String {
+    "/metrics,/admin".to_string()
+}
+
+fn default_cleanup_interval() -> u64 {
+    3600
+}
+
+fn default_digest_hour() -> u32 {
+    8
+}
+
+fn default_save_workers() -> usize {
+    8
+}
+
+fn default_handle_cache_ttl() -> u64 {
+    3600
+}
+
+fn default_feature_cache_ttl() -> u64 {
+    30
+}
+
+fn default_max_thread_depth() -> usize {
+    100
+}
+
+fn default_expand_quotes() -> bool {
+    true
+}
+
+fn default_max_thread_posts() -> usize {
+    200
+}
+
+fn default_max_document_bytes() -> usize {
+    ThreadLimits::default().max_bytes
+}
+
 impl Config {
+    /// Base URL for links sent to users (e.g., magic login links)
+    ///
+    /// Uses `public_base_url`, falling back to one derived from `server_address`.
+    pub fn base_url(&self) -> String {
+        match &self.public_base_url {
+            Some(url) => url.trim_end_matches('/').to_string(),
+            None => format!(
+                "http://{}",
+                self.server_address.replace("0.0.0.0", "localhost")
+            ),
+        }
+    }
+
+    /// Templates for saved highlight titles and default notes
+    pub fn highlight_templates(&self) -> HighlightTemplates {
+        HighlightTemplates {
+            title: self.highlight_title_template.clone(),
+            note: self
+                .highlight_note_template
+                .clone()
+                .filter(|note| !note.trim().is_empty()),
+            category: self.highlight_category.clone(),
+            post_url: self.post_url_template.clone(),
+        }
+    }
+
+    /// Query parameters stripped from extracted links
+    pub fn strip_query_params(&self) -> Vec<String> {
+        match &self.strip_query_params {
+            Some(params) => params
+                .split(',')
+                .map(str::trim)
+                .filter(|param| !param.is_empty())
+                .map(str::to_string)
+                .collect(),
+            None => default_strip_query_params(),
+        }
+    }
+
+    /// Thread size caps for saved documents, within the API's fetch limit
+    pub fn thread_limits(&self) -> ThreadLimits {
+        ThreadLimits {
+            max_depth: self.max_thread_depth.clamp(1, MAX_THREAD_FETCH_DEPTH),
+            max_posts: self.max_thread_posts.max(1),
+            max_bytes: self.max_document_bytes,
+        }
+    }
+
+    /// OAuth redirect URI, defaulting to our callback route
+    pub fn redirect_uri(&self) -> String {
+        self.oauth_redirect_uri
+            .clone()
+            .unwrap_or_else(|| format!("{}/auth/callback", self.base_url()))
+    }
+
+    /// Whether a DID may use the admin routes
+    pub fn is_admin(&self, did: &str) -> bool {
+        self.admin_did
+            .as_deref()
+            .unwrap_or_default()
+            .split(',')
+            .any(|admin| admin.trim() == did)
+    }
+
+    /// Features before any flags are set at runtime
+    pub fn default_features(&self) -> Features {
+        Features {
+            digests: true,
+            expand_quotes: self.expand_quotes,
+            detect_language: self.detect_language,
+        }
+    }
+
+    /// Parsed `feature_overrides`
+    pub fn feature_overrides(&self) -> Result<Vec<(Feature, bool)>> {
+        parse_overrides(self.feature_overrides.as_deref().unwrap_or_default())
+    }
+
+    /// Whether a bot account is configured, so DM features are on
+    pub fn dms_enabled(&self) -> bool {
+        !self.bot_accounts().is_empty()
+    }
+
+    /// Every configured bot account, the handle/password pair first
+    ///
+    /// Malformed `bluesky_bot_accounts` entries are skipped (and reported by
+    /// `validate`).
+    pub fn bot_accounts(&self) -> Vec<BotAccount> {
+        let primary = match (&self.bluesky_bot_handle, &self.bluesky_bot_password) {
+            (Some(handle), Some(password)) => Some(BotAccount {
+                handle: handle.clone(),
+                password: password.clone(),
+            }),
+            _ => None,
+        };
+        let extra = self.extra_bot_accounts().filter_map(|entry| entry.ok());
+        primary.into_iter().chain(extra).collect()
+    }
+
+    /// Parsed `bluesky_bot_accounts` entries, with the raw text of bad ones
+    fn extra_bot_accounts(&self) -> impl Iterator<Item = Result<BotAccount, &str>> {
+        self.bluesky_bot_accounts
+            .as_deref()
+            .unwrap_or_default()
+            .split(',')
+            .map(str::trim)
+            .filter(|entry| !entry.is_empty())
+            .map(|entry| match entry.split_once(':') {
+                Some((handle, password))
+                    if !handle.trim().is_empty() && !password.trim().is_empty() =>
+                {
+                    Ok(BotAccount {
+                        handle: handle.trim().to_string(),
+                        password: password.trim().to_string(),
+                    })
+                }
+                _ => Err(entry),
+            })
+    }
+
+    /// OAuth scope to request at login
+    ///
+    /// The chat scope is added when DMs are enabled, so a user's token can
+    /// read and send DMs; the consent screen then also asks for DM access.
+    pub fn oauth_scope(&self) -> Result<String> {
+        let scope = self.oauth_scope.as_deref().unwrap_or(DEFAULT_SCOPE).trim();
+        if !scope.split_whitespace().any(|s| s == "atproto") {
+            anyhow::bail!("oauth_scope must include \"atproto\": {}", scope);
+        }
+
+        if self.dms_enabled() && !scope.split_whitespace().any(|s| s == CHAT_SCOPE) {
+            Ok(format!("{} {}", scope, CHAT_SCOPE))
+        } else {
+            Ok(scope.to_string())
+        }
+    }
+
     /// Load configuration from environment variables and config files
     pub fn load() -> Result<Self> {
         // Load .env file if present
@@ -53,8 +467,32 @@ impl Config {
         let config = config::Config::builder()
             // Start with default values
             .set_default("server_address", "0.0.0.0:3000")?
+            .set_default("bsky_public_api_base", "https://public.api.bsky.app")?
+            .set_default("bsky_api_base", "https://bsky.social")?
+            .set_default("readwise_api_base", "https://readwise.io/api")?
+            .set_default("db_max_connections", 10)?
+            .set_default("db_acquire_timeout_secs", 5)?
+            .set_default("db_idle_timeout_secs", 600)?
             .set_default("bookmark_poll_interval_secs", 30)?
             .set_default("dm_poll_interval_secs", 10)?
+            .set_default("http_connect_timeout_secs", 5)?
+            .set_default("http_timeout_secs", 30)?
+            .set_default("highlight_title_template", DEFAULT_TITLE_TEMPLATE)?
+            .set_default("post_url_template", DEFAULT_POST_URL_TEMPLATE)?
+            .set_default("highlight_category", DEFAULT_HIGHLIGHT_CATEGORY)?
+            .set_default("max_concurrent_saves", 8)?
+            .set_default("save_queue_capacity", 500)?
+            .set_default("save_workers", 8)?
+            .set_default("cleanup_interval_secs", 3600)?
+            .set_default("digest_hour", 8)?
+            .set_default("protected_paths", "/metrics,/admin")?
+            .set_default("handle_cache_ttl_secs", 3600)?
+            .set_default("max_thread_depth", 100)?
+            .set_default("max_thread_posts", 200)?
+            .set_default("max_document_bytes", 1_000_000)?
+            .set_default("expand_quotes", true)?
+            .set_default("detect_language", false)?
+            .set_default("feature_cache_ttl_secs", 30)?
             // Add config file if it exists
             .add_source(config::File::with_name("config").required(false))
             // Override with environment variables (prefixed with APP_)
@@ -68,9 +506,251 @@ impl Config {
             .build()
             .context("Failed to build configuration")?;
 
-        config
+        let config: Self = config
             .try_deserialize()
-            .context("Failed to deserialize configuration")
+            .context("Failed to deserialize configuration")?;
+
+        config.validate()?;
+
+        Ok(config)
+    }
+
+    /// Check the loaded config, reporting every problem at once
+    ///
+    /// Catches settings that would otherwise only fail later, e.g. at the
+    /// first login or DM poll.
+    pub fn validate(&self) -> Result<(), ConfigError> {
+        let mut problems = Vec::new();
+
+        if let Err(e) = validate_database_url(&self.database_url) {
+            problems.push(e.to_string());
+        }
+        if let Some(url) = &self.public_base_url {
+            if let Err(e) = validate_base_url("public_base_url", url) {
+                problems.push(e.to_string());
+            }
+        }
+        for (name, url) in [
+            ("bsky_public_api_base", &self.bsky_public_api_base),
+            ("bsky_api_base", &self.bsky_api_base),
+            ("readwise_api_base", &self.readwise_api_base),
+        ] {
+            if let Err(e) = validate_base_url(name, url) {
+                problems.push(e.to_string());
+            }
+        }
+
+        match (&self.oauth_client_id, &self.oauth_signing_key) {
+            (Some(client_id), _) if !client_id.starts_with("https://") => problems.push(format!(
+                "oauth_client_id must be an https:// URL to the client metadata: {}",
+                client_id
+            )),
+            (Some(_), None) => problems
+                .push("oauth_signing_key is required when oauth_client_id is set".to_string()),
+            (None, Some(_)) => problems
+                .push("oauth_client_id is required when oauth_signing_key is set".to_string()),
+            _ => {}
+        }
+        if let Err(e) = self.oauth_scope() {
+            problems.push(e.to_string());
+        }
+
+        for (name, value) in [
+            // No permits would leave every save waiting forever
+            ("max_concurrent_saves", self.max_concurrent_saves),
+            ("save_queue_capacity", self.save_queue_capacity),
+            ("save_workers", self.save_workers),
+        ] {
+            if value == 0 {
+                problems.push(format!("{} must be at least 1", name));
+            }
+        }
+
+        if let Err(e) = crate::web::access::validate(
+            self.protected_basic_auth.as_deref(),
+            self.protected_allowed_ips.as_deref(),
+        ) {
+            problems.push(format!("{:#}", e));
+        }
+
+        if self.max_document_bytes < crate::content::MIN_DOCUMENT_BYTES {
+            problems.push(format!(
+                "max_document_bytes must be at least {}",
+                crate::content::MIN_DOCUMENT_BYTES
+            ));
+        }
+        if self.cleanup_interval_secs == 0 {
+            problems.push("cleanup_interval_secs must be at least 1".to_string());
+        }
+        if self.retention_days == Some(0) {
+            problems.push("retention_days must be at least 1 (unset to keep forever)".to_string());
+        }
+        if self.digest_hour > 23 {
+            problems.push(format!(
+                "digest_hour must be 0-23 (got {})",
+                self.digest_hour
+            ));
+        }
+        match self.feature_overrides() {
+            Ok(overrides) => {
+                // The last override for a feature wins, as in `Features::resolve`
+                let detect_language = overrides
+                    .iter()
+                    .rev()
+                    .find(|&&(feature, _)| feature == Feature::DetectLanguage)
+                    .map_or(self.detect_language, |&(_, enabled)| enabled);
+                if detect_language && !crate::content::language::AVAILABLE {
+                    problems.push(
+                        "detect_language needs a build with the language-detection feature"
+                            .to_string(),
+                    );
+                }
+            }
+            Err(e) => problems.push(e.to_string()),
+        }
+
+        if !self.post_url_template.contains("{rkey}") {
+            problems.push(format!(
+                "post_url_template must contain {{rkey}} (got {})",
+                self.post_url_template
+            ));
+        }
+
+        if !is_highlight_category(&self.highlight_category) {
+            problems.push(format!(
+                "highlight_category must be one of {} (got {})",
+                HIGHLIGHT_CATEGORIES.join(", "),
+                self.highlight_category
+            ));
+        }
+
+        let min_secs = MIN_POLL_INTERVAL.as_secs();
+        for (name, secs) in [
+            (
+                "bookmark_poll_interval_secs",
+                self.bookmark_poll_interval_secs,
+            ),
+            ("dm_poll_interval_secs", self.dm_poll_interval_secs),
+        ] {
+            if secs < min_secs {
+                problems.push(format!(
+                    "{} must be at least {} (got {})",
+                    name, min_secs, secs
+                ));
+            }
+        }
+
+        match (&self.bluesky_bot_handle, &self.bluesky_bot_password) {
+            (Some(_), None) => problems.push(
+                "bluesky_bot_password is required when bluesky_bot_handle is set (DMs)".to_string(),
+            ),
+            (None, Some(_)) => problems.push(
+                "bluesky_bot_handle is required when bluesky_bot_password is set (DMs)".to_string(),
+            ),
+            _ => {}
+        }
+
+        for entry in self.extra_bot_accounts() {
+            if let Err(entry) = entry {
+                let handle = entry.split(':').next().unwrap_or_default();
+                problems.push(format!(
+                    "bluesky_bot_accounts entries must look like handle:password (got {:?})",
+                    handle
+                ));
+            }
+        }
+        let accounts = self.bot_accounts();
+        for (i, account) in accounts.iter().enumerate() {
+            if accounts[..i].iter().any(|a| a.handle == account.handle) {
+                problems.push(format!(
+                    "bot account {} is configured twice",
+                    account.handle
+                ));
+            }
+        }
+
+        if problems.is_empty() {
+            Ok(())
+        } else {
+            Err(ConfigError { problems })
+        }
+    }
+}
+
+/// Check that the database URL is a Postgres URL
+fn validate_database_url(url: &str) -> Result<()> {
+    let parsed = url::Url::parse(url).context("database_url is not a valid URL")?;
+    if !matches!(parsed.scheme(), "postgres" | "postgresql") {
+        anyhow::bail!(
+            "database_url must be a postgres:// URL, got scheme {:?}",
+            parsed.scheme()
+        );
+    }
+    Ok(())
+}
+
+/// Check that the base URL setting `name` is an absolute http(s) URL
+fn validate_base_url(name: &str, url: &str) -> Result<()> {
+    let parsed = url::Url::parse(url).with_context(|| format!("Invalid {}: {}", name, url))?;
+    if !matches!(parsed.scheme(), "http" | "https") || parsed.host_str().is_none() {
+        anyhow::bail!("{} must be an absolute http(s) URL: {}", name, url);
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+impl Config {
+    /// Configuration with defaults for tests
+    pub fn test_default() -> Self {
+        Self {
+            server_address: default_server_address(),
+            public_base_url: None,
+            bsky_public_api_base: default_bsky_public_api_base(),
+            bsky_api_base: default_bsky_api_base(),
+            readwise_api_base: default_readwise_api_base(),
+            database_url: "postgres://localhost/test".to_string(),
+            db_max_connections: default_db_max_connections(),
+            db_acquire_timeout_secs: default_db_acquire_timeout(),
+            db_idle_timeout_secs: default_db_idle_timeout(),
+            encryption_key: "B".repeat(43) + "=",
+            bluesky_bot_handle: None,
+            bluesky_bot_password: None,
+            bluesky_bot_accounts: None,
+            oauth_client_id: None,
+            oauth_redirect_uri: None,
+            oauth_signing_key: None,
+            oauth_scope: None,
+            bookmark_poll_interval_secs: default_bookmark_poll_interval(),
+            dm_poll_interval_secs: default_dm_poll_interval(),
+            http_connect_timeout_secs: default_http_connect_timeout(),
+            http_timeout_secs: default_http_timeout(),
+            highlight_title_template: default_highlight_title_template(),
+            post_url_template: default_post_url_template(),
+            highlight_note_template: None,
+            highlight_category: default_highlight_category(),
+            max_concurrent_saves: default_max_concurrent_saves(),
+            save_queue_capacity: default_save_queue_capacity(),
+            save_workers: default_save_workers(),
+            cleanup_interval_secs: default_cleanup_interval(),
+            retention_days: None,
+            digest_hour: default_digest_hour(),
+            handle_cache_ttl_secs: default_handle_cache_ttl(),
+            max_thread_depth: default_max_thread_depth(),
+            max_thread_posts: default_max_thread_posts(),
+            max_document_bytes: default_max_document_bytes(),
+            expand_quotes: default_expand_quotes(),
+            detect_language: false,
+            feature_overrides: None,
+            feature_cache_ttl_secs: default_feature_cache_ttl(),
+            strip_query_params: None,
+            log_format: LogFormat::default(),
+            selftest_post_uri: None,
+            selftest_readwise_token: None,
+            admin_did: None,
+            protected_paths: default_protected_paths(),
+            protected_basic_auth: None,
+            protected_allowed_ips: None,
+        }
     }
 }
 
@@ -81,7 +761,230 @@ mod tests {
     #[test]
     fn test_default_values() {
         assert_eq!(default_server_address(), "0.0.0.0:3000");
+        assert_eq!(default_bsky_api_base(), "https://bsky.social");
+        assert_eq!(default_readwise_api_base(), "https://readwise.io/api");
         assert_eq!(default_bookmark_poll_interval(), 30);
         assert_eq!(default_dm_poll_interval(), 10);
+        assert_eq!(default_http_connect_timeout(), 5);
+        assert_eq!(default_http_timeout(), 30);
+        assert_eq!(default_max_concurrent_saves(), 8);
+        assert_eq!(default_save_queue_capacity(), 500);
+        assert_eq!(default_save_workers(), 8);
+        assert_eq!(default_cleanup_interval(), 3600);
+        assert_eq!(default_handle_cache_ttl(), 3600);
+        assert_eq!(default_max_thread_depth(), 100);
+        assert_eq!(default_max_thread_posts(), 200);
+        assert!(default_expand_quotes());
+        assert_eq!(default_db_max_connections(), 10);
+        assert_eq!(default_db_acquire_timeout(), 5);
+        assert_eq!(default_db_idle_timeout(), 600);
+    }
+
+    #[test]
+    fn test_public_base_url_validation() {
+        let valid = |url| validate_base_url("public_base_url", url).is_ok();
+        assert!(valid("https://autosave.example.com"));
+        assert!(valid("http://localhost:3000/"));
+        assert!(!valid("autosave.example.com"));
+        assert!(!valid("ftp://autosave.example.com"));
+        assert!(!valid("mailto:me@example.com"));
+    }
+
+    #[test]
+    fn test_base_url_fallback() {
+        let mut config = Config::test_default();
+        assert_eq!(config.base_url(), "http://localhost:3000");
+        assert_eq!(config.redirect_uri(), "http://localhost:3000/auth/callback");
+
+        config.public_base_url = Some("https://autosave.example.com/".to_string());
+        assert_eq!(config.base_url(), "https://autosave.example.com");
+        assert_eq!(
+            config.redirect_uri(),
+            "https://autosave.example.com/auth/callback"
+        );
+    }
+
+    fn problems(config: &Config) -> Vec<String> {
+        config
+            .validate()
+            .err()
+            .map(|e| e.problems)
+            .unwrap_or_default()
+    }
+
+    #[test]
+    fn test_validate_accepts_defaults() {
+        assert_eq!(Config::test_default().validate(), Ok(()));
+    }
+
+    #[test]
+    fn test_validate_database_url() {
+        let mut config = Config::test_default();
+        config.database_url = "mysql://localhost/app".to_string();
+        assert!(problems(&config)[0].contains("postgres://"));
+
+        config.database_url = "localhost:5432".to_string();
+        assert!(problems(&config)[0].contains("database_url"));
+
+        config.database_url = "postgresql://user@db:5432/app".to_string();
+        assert!(problems(&config).is_empty());
+    }
+
+    #[test]
+    fn test_validate_oauth_fields() {
+        let mut config = Config::test_default();
+        config.oauth_client_id = Some("http://example.com/client-metadata.json".to_string());
+        config.oauth_signing_key = Some("z42tm".to_string());
+        assert!(problems(&config)[0].contains("oauth_client_id must be an https://"));
+
+        config.oauth_client_id = Some("https://example.com/client-metadata.json".to_string());
+        config.oauth_signing_key = None;
+        assert!(problems(&config)[0].contains("oauth_signing_key is required"));
+
+        config.oauth_client_id = None;
+        config.oauth_signing_key = Some("z42tm".to_string());
+        assert!(problems(&config)[0].contains("oauth_client_id is required"));
+
+        config.oauth_signing_key = None;
+        config.oauth_scope = Some("transition:generic".to_string());
+        assert!(problems(&config)[0].contains("atproto"));
+    }
+
+    #[test]
+    fn test_validate_highlight_category() {
+        let mut config = Config::test_default();
+        config.highlight_category = "articles".to_string();
+        assert_eq!(config.validate(), Ok(()));
+        assert_eq!(config.highlight_templates().category, "articles");
+
+        config.highlight_category = "posts".to_string();
+        assert!(problems(&config)[0].contains("highlight_category must be one of"));
+    }
+
+    #[test]
+    fn test_validate_post_url_template() {
+        let mut config = Config::test_default();
+        config.post_url_template = "https://deer.social/profile/{did}/post/{rkey}".to_string();
+        assert_eq!(config.validate(), Ok(()));
+
+        config.post_url_template = "https://deer.social/profile/{handle}".to_string();
+        assert!(problems(&config)[0].contains("post_url_template must contain {rkey}"));
+    }
+
+    #[test]
+    fn test_validate_feature_overrides() {
+        let mut config = Config::test_default();
+        config.feature_overrides = Some("digests=off".to_string());
+        assert_eq!(config.validate(), Ok(()));
+        assert_eq!(
+            config.feature_overrides().unwrap(),
+            vec![(Feature::Digests, false)]
+        );
+
+        config.feature_overrides = Some("quotes=off".to_string());
+        assert!(problems(&config)[0].contains("unknown feature"));
+
+        config.feature_overrides = Some("detect_language=on".to_string());
+        if crate::content::language::AVAILABLE {
+            assert_eq!(config.validate(), Ok(()));
+        } else {
+            assert!(problems(&config)[0].contains("language-detection feature"));
+        }
+    }
+
+    #[test]
+    fn test_validate_poll_intervals_and_bot_credentials() {
+        let mut config = Config::test_default();
+        config.bookmark_poll_interval_secs = 1;
+        config.dm_poll_interval_secs = 0;
+        config.bluesky_bot_handle = Some("bot.bsky.social".to_string());
+
+        let problems = problems(&config);
+        assert_eq!(problems.len(), 3);
+        assert!(problems[0].starts_with("bookmark_poll_interval_secs must be at least 10"));
+        assert!(problems[1].starts_with("dm_poll_interval_secs must be at least 10"));
+        assert!(problems[2].contains("bluesky_bot_password is required"));
+
+        let message = config.validate().unwrap_err().to_string();
+        assert!(message.starts_with("Invalid configuration:"));
+        assert_eq!(message.matches("\n  - ").count(), 3);
+    }
+
+    #[test]
+    fn test_validate_rejects_zero_save_limits() {
+        let mut config = Config::test_default();
+        config.max_concurrent_saves = 0;
+        config.save_workers = 0;
+
+        assert_eq!(
+            problems(&config),
+            vec![
+                "max_concurrent_saves must be at least 1",
+                "save_workers must be at least 1"
+            ]
+        );
+    }
+
+    #[test]
+    fn test_validate_rejects_tiny_documents() {
+        let mut config = Config::test_default();
+        config.max_document_bytes = 100;
+
+        assert_eq!(
+            problems(&config),
+            vec!["max_document_bytes must be at least 4096"]
+        );
+    }
+
+    #[test]
+    fn test_bot_accounts() {
+        let mut config = Config::test_default();
+        assert!(config.bot_accounts().is_empty());
+        assert!(!config.dms_enabled());
+
+        config.bluesky_bot_handle = Some("bot.bsky.social".to_string());
+        config.bluesky_bot_password = Some("app-password".to_string());
+        config.bluesky_bot_accounts =
+            Some("second.bsky.social:pass-two, third.bsky.social : pass-three,".to_string());
+        let handles: Vec<_> = config
+            .bot_accounts()
+            .into_iter()
+            .map(|account| account.handle)
+            .collect();
+        assert_eq!(
+            handles,
+            vec!["bot.bsky.social", "second.bsky.social", "third.bsky.social"]
+        );
+        assert_eq!(config.bot_accounts()[2].password, "pass-three");
+        assert_eq!(config.validate(), Ok(()));
+
+        config.bluesky_bot_accounts =
+            Some("no-password.bsky.social,bot.bsky.social:again".to_string());
+        let problems = problems(&config);
+        assert_eq!(problems.len(), 2);
+        assert!(problems[0].contains("\"no-password.bsky.social\""));
+        assert!(problems[1].contains("bot.bsky.social is configured twice"));
+    }
+
+    #[test]
+    fn test_oauth_scope() {
+        let mut config = Config::test_default();
+        assert_eq!(config.oauth_scope().unwrap(), "atproto transition:generic");
+
+        config.bluesky_bot_handle = Some("bot.bsky.social".to_string());
+        config.bluesky_bot_password = Some("app-password".to_string());
+        assert_eq!(
+            config.oauth_scope().unwrap(),
+            "atproto transition:generic transition:chat.bsky"
+        );
+
+        config.oauth_scope = Some("atproto transition:chat.bsky".to_string());
+        assert_eq!(
+            config.oauth_scope().unwrap(),
+            "atproto transition:chat.bsky"
+        );
+
+        config.oauth_scope = Some("transition:generic".to_string());
+        assert!(config.oauth_scope().is_err());
     }
 }
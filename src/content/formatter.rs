@@ -2,78 +2,523 @@
 //!
 //! Converts Bluesky posts and threads into Readwise API payloads.
 
-use crate::bluesky::{PostView, ThreadViewPost};
-use crate::readwise::client::{Document, Highlight};
+use std::collections::HashSet;
+use std::ops::Range;
+
+use chrono::{DateTime, Utc};
+use chrono_tz::Tz;
+use tracing::{info, warn};
+
+use super::html::{html_escape, Html};
+use crate::bluesky::{
+    ByteSlice, Embed, EmbedImage, Facet, FacetFeature, PostRecord, PostView, ThreadViewPost,
+};
+use crate::readwise::client::{Document, Highlight, DEFAULT_HIGHLIGHT_CATEGORY, SAVED_USING};
+
+/// Appended to a thread document when limits cut posts off
+const THREAD_TRUNCATED_NOTE: &str = "[thread truncated]";
+
+/// Room kept after a thread's posts for the closing tags around them
+const CLOSING_MARKUP_BYTES: usize = 64;
+
+/// Smallest `ThreadLimits::max_bytes` with room for one post's author,
+/// timestamp and some of its text
+pub const MIN_DOCUMENT_BYTES: usize = 4_096;
+
+/// Ends post text cut short to fit a document
+const ELLIPSIS: &str = "…";
+
+/// Highlight body for an image post without text or alt text
+const IMAGE_POST_PLACEHOLDER: &str = "[image post]";
+
+/// Bluesky CDN prefix for full-size post images
+const BSKY_IMAGE_CDN: &str = "https://cdn.bsky.app/img/feed_fullsize/plain";
+
+/// Note on alt-text highlights; Readwise turns a `.name` note into a tag
+const ALT_TEXT_TAG_NOTE: &str = ".alt-text";
+
+/// Default highlight title
+pub const DEFAULT_TITLE_TEMPLATE: &str = "Post by @{handle}";
+
+/// Default thread document title, until the title template is customized
+const THREAD_TITLE_TEMPLATE: &str = "Thread by @{handle}";
+
+/// Default quote document title, until the title template is customized
+const QUOTE_TITLE_TEMPLATE: &str = "Quote by @{handle}";
+
+/// Default link to a saved post (placeholders: `{handle}`, `{did}`, `{rkey}`)
+pub const DEFAULT_POST_URL_TEMPLATE: &str = "https://bsky.app/profile/{handle}/post/{rkey}";
+
+/// Templates for highlight titles and notes, and links to saved posts
+///
+/// Placeholders: `{handle}`, `{display_name}`, `{date}`, `{url}`, `{text}`.
+/// Unknown placeholders are left as written.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HighlightTemplates {
+    pub title: String,
+    /// Note used when the user didn't supply one
+    pub note: Option<String>,
+    /// Readwise category highlights are filed under (see `HIGHLIGHT_CATEGORIES`)
+    pub category: String,
+    /// Highlight source link and `{url}`, e.g. on an alternative frontend
+    /// (see `DEFAULT_POST_URL_TEMPLATE`); never the dedup URL
+    pub post_url: String,
+}
+
+impl Default for HighlightTemplates {
+    fn default() -> Self {
+        Self {
+            title: DEFAULT_TITLE_TEMPLATE.to_string(),
+            note: None,
+            category: DEFAULT_HIGHLIGHT_CATEGORY.to_string(),
+            post_url: DEFAULT_POST_URL_TEMPLATE.to_string(),
+        }
+    }
+}
+
+/// How much of a thread goes into a saved document
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ThreadLimits {
+    /// Deepest parent or reply chain followed
+    pub max_depth: usize,
+    /// Most posts in one document
+    pub max_posts: usize,
+    /// Largest document HTML, in bytes; posts past it are cut off
+    pub max_bytes: usize,
+}
+
+impl Default for ThreadLimits {
+    fn default() -> Self {
+        Self {
+            max_depth: 100,
+            max_posts: 200,
+            max_bytes: 1_000_000,
+        }
+    }
+}
 
 /// Format a single post as a Readwise highlight
-pub fn format_post_as_highlight(post: &PostView, note: Option<&str>) -> Highlight {
-    let author_name = post
-        .author
-        .display_name
-        .clone()
-        .unwrap_or_else(|| post.author.handle.clone());
+///
+/// With `strip_trailing_link`, a link facet at the very end of the text is
+/// dropped (for when that link is saved on its own).
+pub fn format_post_as_highlight(
+    post: &PostView,
+    note: Option<&str>,
+    strip_trailing_link: bool,
+    templates: &HighlightTemplates,
+) -> Highlight {
+    let author_name = display_name(post);
+    let source_url = post_url(post, &templates.post_url);
 
-    let source_url = format!(
-        "https://bsky.app/profile/{}/post/{}",
-        post.author.handle,
-        extract_rkey(&post.uri)
-    );
+    let text = if post.record.text.trim().is_empty() {
+        media_text(post)
+    } else if strip_trailing_link {
+        without_trailing_link(&post.record)
+    } else {
+        post.record.text.clone()
+    };
+
+    let note = match note {
+        Some(note) => Some(note.to_string()),
+        None => templates
+            .note
+            .as_deref()
+            .map(|template| expand_template(template, post, &templates.post_url)),
+    };
 
     Highlight {
-        text: post.record.text.clone(),
-        title: Some(format!("Post by @{}", post.author.handle)),
+        text,
+        title: Some(expand_template(&templates.title, post, &templates.post_url)),
         author: Some(author_name),
         source_url: Some(source_url),
-        category: Some("tweets".to_string()),
-        note: note.map(|s| s.to_string()),
+        category: Some(templates.category.clone()),
+        note,
+        highlight_url: Some(canonical_post_url(post)),
     }
 }
 
-/// Format a thread as a Readwise Reader document
-pub fn format_thread_as_document(thread: &ThreadViewPost) -> Document {
-    let posts = collect_thread_posts(thread);
-    let html = format_posts_as_html(&posts);
-
-    let first_post = posts.first().map(|p| &p.post);
-    let (title, author, source_url) = if let Some(post) = first_post {
-        let author_name = post
-            .author
-            .display_name
-            .clone()
-            .unwrap_or_else(|| post.author.handle.clone());
-        let url = format!(
-            "https://bsky.app/profile/{}/post/{}",
-            post.author.handle,
-            extract_rkey(&post.uri)
-        );
-        (
-            format!("Thread by @{}", post.author.handle),
-            author_name,
-            url,
-        )
+/// One highlight per image alt text in a post, tagged `alt-text`
+///
+/// Images without alt text are skipped.
+pub fn format_alt_text_highlights(
+    post: &PostView,
+    templates: &HighlightTemplates,
+) -> Vec<Highlight> {
+    post_images(post)
+        .iter()
+        .enumerate()
+        .filter(|(_, image)| !image.alt.trim().is_empty())
+        .map(|(index, image)| Highlight {
+            text: image.alt.trim().to_string(),
+            title: Some(expand_template(&templates.title, post, &templates.post_url)),
+            author: Some(display_name(post)),
+            source_url: Some(post_url(post, &templates.post_url)),
+            category: Some(templates.category.clone()),
+            note: Some(ALT_TEXT_TAG_NOTE.to_string()),
+            // Distinct from the post's own highlight so Readwise keeps both
+            highlight_url: Some(format!("{}#image-{}", canonical_post_url(post), index + 1)),
+        })
+        .collect()
+}
+
+/// Highlight body for a post with no text: alt text (or a placeholder)
+/// followed by the image URLs
+fn media_text(post: &PostView) -> String {
+    let images = post_images(post);
+    let alt_text: Vec<&str> = images
+        .iter()
+        .map(|image| image.alt.trim())
+        .filter(|alt| !alt.is_empty())
+        .collect();
+
+    let mut text = if alt_text.is_empty() {
+        IMAGE_POST_PLACEHOLDER.to_string()
     } else {
-        ("Thread".to_string(), "Unknown".to_string(), String::new())
+        alt_text.join("\n\n")
+    };
+    for image in images {
+        text.push_str("\n\n");
+        text.push_str(&image_url(post, image));
+    }
+    text
+}
+
+/// Images attached to a post, directly or alongside a quote
+fn post_images(post: &PostView) -> &[EmbedImage] {
+    match &post.record.embed {
+        Some(Embed::Images { images }) => images,
+        Some(Embed::RecordWithMedia { media, .. }) => match media.as_ref() {
+            Embed::Images { images } => images,
+            _ => &[],
+        },
+        _ => &[],
+    }
+}
+
+/// CDN URL for a post image
+fn image_url(post: &PostView, image: &EmbedImage) -> String {
+    format!(
+        "{}/{}/{}@jpeg",
+        BSKY_IMAGE_CDN, post.author.did, image.image.reference.link
+    )
+}
+
+/// A post's like, repost, and reply counts on one line, if it came with any
+pub fn engagement_line(post: &PostView) -> Option<String> {
+    if post.like_count.is_none() && post.repost_count.is_none() && post.reply_count.is_none() {
+        return None;
+    }
+    let count = |n: Option<u64>, one: &str, many: &str| match n.unwrap_or(0) {
+        1 => format!("1 {}", one),
+        n => format!("{} {}", n, many),
     };
+    Some(format!(
+        "{} · {} · {}",
+        count(post.like_count, "like", "likes"),
+        count(post.repost_count, "repost", "reposts"),
+        count(post.reply_count, "reply", "replies"),
+    ))
+}
 
+/// Whether a post has neither text nor images, so a highlight would be blank
+pub fn is_empty_post(post: &PostView) -> bool {
+    post.record.text.trim().is_empty() && post_images(post).is_empty()
+}
+
+/// Format a post with nothing to highlight as a Reader document
+///
+/// Reader fetches the post page itself, picking up any media or link card.
+pub fn format_post_as_document(post: &PostView, templates: &HighlightTemplates) -> Document {
     Document {
-        url: source_url,
+        url: canonical_post_url(post),
+        html: None,
+        title: Some(document_title(post, templates, DEFAULT_TITLE_TEMPLATE)),
+        author: Some(display_name(post)),
+        tags: Some(vec!["bluesky".to_string()]),
+        published_date: Some(post.record.created_at),
+        saved_using: Some(SAVED_USING.to_string()),
+        ..Default::default()
+    }
+}
+
+/// Title for a Reader document about `post`
+///
+/// A customized title template applies to documents too; with the default
+/// one, each kind of document keeps its own title (`default_template`).
+fn document_title(
+    post: &PostView,
+    templates: &HighlightTemplates,
+    default_template: &str,
+) -> String {
+    let template = if templates.title == DEFAULT_TITLE_TEMPLATE {
+        default_template
+    } else {
+        &templates.title
+    };
+    expand_template(template, post, &templates.post_url)
+}
+
+/// Fill a highlight template's placeholders from a post
+///
+/// `{url}` links to the post using `post_url_template`. Output is plain
+/// text; nothing is escaped.
+pub fn expand_template(template: &str, post: &PostView, post_url_template: &str) -> String {
+    let mut output = String::with_capacity(template.len());
+    let mut rest = template;
+
+    while let Some(open) = rest.find('{') {
+        output.push_str(&rest[..open]);
+        let after_open = &rest[open + 1..];
+        let Some(close) = after_open.find('}') else {
+            rest = &rest[open..];
+            break;
+        };
+
+        let name = &after_open[..close];
+        match name {
+            "handle" => output.push_str(&post.author.handle),
+            "display_name" => output.push_str(&display_name(post)),
+            "date" => output.push_str(&post.record.created_at.format("%Y-%m-%d").to_string()),
+            "url" => output.push_str(&post_url(post, post_url_template)),
+            "text" => output.push_str(&post.record.text),
+            _ => {
+                output.push('{');
+                output.push_str(name);
+                output.push('}');
+            }
+        }
+        rest = &after_open[close + 1..];
+    }
+
+    output.push_str(rest);
+    output
+}
+
+/// Author's display name, falling back to their handle
+fn display_name(post: &PostView) -> String {
+    post.author
+        .display_name
+        .as_deref()
+        .map(str::trim)
+        .filter(|name| !name.is_empty())
+        .unwrap_or(&post.author.handle)
+        .to_string()
+}
+
+/// Link to a post from a URL template (`{handle}`, `{did}`, `{rkey}`)
+pub fn post_url(post: &PostView, template: &str) -> String {
+    template
+        .replace("{handle}", &post.author.handle)
+        .replace("{did}", &post.author.did)
+        .replace("{rkey}", &extract_rkey(&post.uri))
+}
+
+/// bsky.app URL for a post keyed by the author's DID
+///
+/// Readwise and Reader dedup on this URL, so it ignores `post_url_template`
+/// and survives handle changes: a re-save of the same post is the same item.
+fn canonical_post_url(post: &PostView) -> String {
+    format!(
+        "https://bsky.app/profile/{}/post/{}",
+        post.author.did,
+        extract_rkey(&post.uri)
+    )
+}
+
+/// Byte range of a facet within `text`, repaired to UTF-8 char boundaries
+///
+/// Buggy clients sometimes write indices that split a character; those are
+/// widened to whole characters, and an end past the text is cut back to it.
+/// A facet starting outside the text (or empty) is skipped. Both are logged.
+pub fn facet_range(text: &str, index: &ByteSlice) -> Option<Range<usize>> {
+    let (start, end) = (index.byte_start, index.byte_end);
+    if start >= text.len() || start >= end {
+        warn!(
+            "Skipping facet {}..{} that doesn't fit {} bytes of text",
+            start,
+            end,
+            text.len()
+        );
+        return None;
+    }
+
+    let floor = (0..=start)
+        .rev()
+        .find(|&i| text.is_char_boundary(i))
+        .unwrap_or(0);
+    let ceil = (end.min(text.len())..=text.len())
+        .find(|&i| text.is_char_boundary(i))
+        .unwrap_or(text.len());
+    if (floor, ceil) != (start, end) {
+        warn!(
+            "Repaired facet {}..{} to {}..{} on char boundaries",
+            start, end, floor, ceil
+        );
+    }
+    Some(floor..ceil)
+}
+
+/// Whether a facet marks up a link
+fn is_link_facet(facet: &Facet) -> bool {
+    facet
+        .features
+        .iter()
+        .any(|feature| matches!(feature, FacetFeature::Link { .. }))
+}
+
+/// Where a link facet ending exactly at `end` starts, if there is one
+fn trailing_link_start(record: &PostRecord, end: usize) -> Option<usize> {
+    record
+        .facets
+        .iter()
+        .flatten()
+        .filter(|facet| is_link_facet(facet))
+        .filter_map(|facet| facet_range(&record.text, &facet.index))
+        .find(|range| range.end == end)
+        .map(|range| range.start)
+}
+
+/// Post text with a trailing link facet removed (unchanged if none, or if
+/// the link is the whole post)
+fn without_trailing_link(record: &PostRecord) -> String {
+    let text = &record.text;
+    let end = text.trim_end().len();
+
+    match trailing_link_start(record, end).and_then(|start| text.get(..start)) {
+        Some(stripped) if !stripped.trim().is_empty() => stripped.trim_end().to_string(),
+        _ => text.clone(),
+    }
+}
+
+/// Length of a post's text in chars, not counting a trailing bare URL
+pub fn text_length(record: &PostRecord) -> usize {
+    let text = record.text.trim_end();
+
+    let link_start = trailing_link_start(record, text.len()).or_else(|| {
+        // Un-faceted URL as the last word
+        let start = text.rfind(char::is_whitespace).map_or(0, |i| i + 1);
+        let last = &text[start..];
+        (last.starts_with("http://") || last.starts_with("https://")).then_some(start)
+    });
+
+    link_start
+        .and_then(|start| text.get(..start))
+        .unwrap_or(text)
+        .trim()
+        .chars()
+        .count()
+}
+
+/// Format a thread as a Readwise Reader document
+///
+/// Only the author's own replies are included unless `include_other_replies` is set.
+/// Threads beyond `limits` (including its size cap, checked between posts)
+/// are cut off with a "[thread truncated]" note. Post timestamps are shown in `timezone`.
+/// A `footer` line closes the article, inside the size cap.
+pub fn format_thread_as_document(
+    thread: &ThreadViewPost,
+    include_other_replies: bool,
+    limits: &ThreadLimits,
+    timezone: Tz,
+    templates: &HighlightTemplates,
+    footer: Option<&str>,
+) -> Document {
+    let CollectedThread { posts, truncated } =
+        collect_thread_posts(thread, include_other_replies, limits);
+    let html = format_posts_as_html(&posts, truncated, timezone, limits.max_bytes, footer);
+
+    // The thread's first post stands in for the whole thread in Reader's list
+    let post = posts.first().map_or(&thread.post, |p| &p.post);
+    Document {
+        url: canonical_post_url(post),
         html: Some(html),
-        title: Some(title),
-        author: Some(author),
+        title: Some(document_title(post, templates, THREAD_TITLE_TEMPLATE)),
+        author: Some(display_name(post)),
         tags: Some(vec!["bluesky".to_string(), "thread".to_string()]),
+        summary: Some(post.record.text.clone()).filter(|text| !text.trim().is_empty()),
+        published_date: Some(post.record.created_at),
+        saved_using: Some(SAVED_USING.to_string()),
+        ..Default::default()
     }
 }
 
-/// Collect all posts in a thread (from root to leaves)
-fn collect_thread_posts(thread: &ThreadViewPost) -> Vec<&ThreadViewPost> {
+/// Format a quote post and the thread(s) it quotes as one Reader document
+///
+/// The commentary's thread comes first; each quoted thread (a quote of a
+/// quote follows its quote) is nested below it in a blockquote. Quoted
+/// posts that don't fit in `limits.max_bytes` are left out. A `footer`
+/// line closes the article.
+pub fn format_quote_as_document(
+    commentary: &ThreadViewPost,
+    quoted: &[ThreadViewPost],
+    include_other_replies: bool,
+    limits: &ThreadLimits,
+    timezone: Tz,
+    templates: &HighlightTemplates,
+    footer: Option<&str>,
+) -> Document {
+    let CollectedThread { posts, truncated } =
+        collect_thread_posts(commentary, include_other_replies, limits);
+    let footer = footer.map(footer_paragraph);
+    let max_bytes = limits
+        .max_bytes
+        .saturating_sub(footer.as_ref().map_or(0, Html::len));
+    let mut html = Html::new();
+    html.markup("<article class=\"bluesky-thread\">\n");
+    let budget = remaining_bytes(&html, max_bytes);
+    html.append(format_posts(&posts, truncated, timezone, budget, true));
+    for thread in quoted {
+        let CollectedThread { posts, truncated } = collect_thread_posts(thread, false, limits);
+        html.markup("<blockquote class=\"quoted-thread\">\n");
+        let budget = remaining_bytes(&html, max_bytes);
+        html.append(format_posts(&posts, truncated, timezone, budget, false))
+            .markup("</blockquote>\n");
+    }
+    if let Some(footer) = footer {
+        html.append(footer);
+    }
+    html.markup("</article>");
+
+    let post = &commentary.post;
+    Document {
+        url: canonical_post_url(post),
+        html: Some(html.into_string()),
+        title: Some(document_title(post, templates, QUOTE_TITLE_TEMPLATE)),
+        author: Some(display_name(post)),
+        tags: Some(vec!["bluesky".to_string(), "quote".to_string()]),
+        published_date: Some(post.record.created_at),
+        saved_using: Some(SAVED_USING.to_string()),
+        ..Default::default()
+    }
+}
+
+/// Posts to save from a thread, and whether limits cut any off
+struct CollectedThread<'t> {
+    posts: Vec<&'t ThreadViewPost>,
+    truncated: bool,
+}
+
+/// Collect all posts in a thread (from root to leaves), within `limits`
+fn collect_thread_posts<'t>(
+    thread: &'t ThreadViewPost,
+    include_other_replies: bool,
+    limits: &ThreadLimits,
+) -> CollectedThread<'t> {
     let mut posts = Vec::new();
+    let mut truncated = false;
 
-    // First, collect parent chain (going up)
-    let mut current = thread.parent.as_ref();
+    // First, collect parent chain (going up), keeping the nearest parents
+    let mut current = thread.parent_post();
     let mut parent_chain = Vec::new();
     while let Some(parent) = current {
-        parent_chain.push(parent.as_ref());
-        current = parent.parent.as_ref();
+        if parent_chain.len() >= limits.max_depth
+            || parent_chain.len() + 1 >= limits.max_posts.max(1)
+        {
+            truncated = true;
+            break;
+        }
+        parent_chain.push(parent);
+        current = parent.parent_post();
     }
     parent_chain.reverse();
     posts.extend(parent_chain);
@@ -81,67 +526,796 @@ fn collect_thread_posts(thread: &ThreadViewPost) -> Vec<&ThreadViewPost> {
     // Add the current post
     posts.push(thread);
 
-    // Add replies (going down) - just first level for now
-    if let Some(replies) = &thread.replies {
-        for reply in replies {
-            posts.push(reply);
+    // Add replies (going down), keeping only the author's self-thread
+    let mut seen: HashSet<&str> = posts.iter().map(|p| p.post.uri.as_str()).collect();
+    let filter = ReplyFilter {
+        author_did: &thread.post.author.did,
+        include_other_replies,
+        limits,
+    };
+    filter.collect(thread, 1, &mut seen, &mut posts, &mut truncated);
+
+    CollectedThread { posts, truncated }
+}
+
+/// Which replies belong in a saved thread
+struct ReplyFilter<'a> {
+    author_did: &'a str,
+    include_other_replies: bool,
+    limits: &'a ThreadLimits,
+}
+
+impl ReplyFilter<'_> {
+    /// Append kept replies depth-first, so each self-reply chain stays in order
+    ///
+    /// Sets `truncated` when a kept reply is left out because of the limits.
+    fn collect<'t>(
+        &self,
+        thread: &'t ThreadViewPost,
+        depth: usize,
+        seen: &mut HashSet<&'t str>,
+        posts: &mut Vec<&'t ThreadViewPost>,
+        truncated: &mut bool,
+    ) {
+        for reply in thread.reply_posts() {
+            let kept = self.include_other_replies || reply.post.author.did == self.author_did;
+            if !kept {
+                continue;
+            }
+            if depth > self.limits.max_depth || posts.len() >= self.limits.max_posts {
+                *truncated = true;
+                return;
+            }
+            // Skip anything already collected so a malformed cyclic thread can't loop
+            if seen.insert(reply.post.uri.as_str()) {
+                posts.push(reply);
+                self.collect(reply, depth + 1, seen, posts, truncated);
+            }
         }
     }
+}
 
-    posts
-}
-
-/// Format posts as HTML article
-fn format_posts_as_html(posts: &[&ThreadViewPost]) -> String {
-    let mut html = String::from("<article class=\"bluesky-thread\">\n");
-
-    for post in posts {
-        let author_name = post
-            .post
-            .author
-            .display_name
-            .clone()
-            .unwrap_or_else(|| post.post.author.handle.clone());
-
-        html.push_str(&format!(
-            r#"<div class="post">
-<p class="author"><strong>{}</strong> <span class="handle">@{}</span></p>
-<p class="content">{}</p>
-<p class="timestamp">{}</p>
-</div>
-"#,
-            html_escape(&author_name),
-            html_escape(&post.post.author.handle),
-            html_escape(&post.post.record.text),
-            post.post.record.created_at.format("%Y-%m-%d %H:%M:%S UTC")
-        ));
+/// Format posts as HTML article of at most `max_bytes`, noting when the
+/// thread was cut off and ending with `footer`
+fn format_posts_as_html(
+    posts: &[&ThreadViewPost],
+    truncated: bool,
+    timezone: Tz,
+    max_bytes: usize,
+    footer: Option<&str>,
+) -> String {
+    let footer = footer.map(footer_paragraph);
+    let mut html = Html::new();
+    html.markup("<article class=\"bluesky-thread\">\n");
+    let budget =
+        remaining_bytes(&html, max_bytes).saturating_sub(footer.as_ref().map_or(0, Html::len));
+    html.append(format_posts(posts, truncated, timezone, budget, true));
+    if let Some(footer) = footer {
+        html.append(footer);
     }
+    html.markup("</article>");
+    html.into_string()
+}
 
-    html.push_str("</article>");
+/// Bytes left for posts after `html`, keeping room for closing tags
+fn remaining_bytes(html: &Html, max_bytes: usize) -> usize {
+    max_bytes.saturating_sub(html.len() + CLOSING_MARKUP_BYTES)
+}
+
+/// Format posts as HTML blocks, noting when the thread was cut off
+///
+/// Posts are dropped from the end once the next one (plus the note) would
+/// go past `max_bytes`; with `keep_first`, the first post is kept anyway,
+/// shortened if it's too big on its own.
+fn format_posts(
+    posts: &[&ThreadViewPost],
+    truncated: bool,
+    timezone: Tz,
+    max_bytes: usize,
+    keep_first: bool,
+) -> Html {
+    let mut html = Html::new();
+    let mut truncated = truncated;
+    let note_bytes = truncated_note().len();
+
+    for (index, post) in posts.iter().enumerate() {
+        let mut block = format_post_block(&post.post, timezone);
+        let fits = html.len() + block.len() + note_bytes <= max_bytes;
+        let kept_anyway = keep_first && index == 0;
+        if !fits && kept_anyway {
+            info!("First post is over {} bytes, shortening it", max_bytes);
+            block =
+                shortened_post_block(&post.post, timezone, max_bytes.saturating_sub(note_bytes));
+        } else if !fits {
+            info!(
+                "Document over {} bytes, keeping {} of {} posts",
+                max_bytes,
+                index,
+                posts.len()
+            );
+            truncated = true;
+            break;
+        }
+        html.append(block);
+    }
+
+    if truncated {
+        html.append(truncated_note());
+    }
     html
 }
 
-/// Extract rkey from AT-URI (at://did/collection/rkey)
-fn extract_rkey(uri: &str) -> String {
-    uri.split('/').next_back().unwrap_or("").to_string()
+/// "[thread truncated]" paragraph
+fn truncated_note() -> Html {
+    let mut html = Html::new();
+    html.markup("<p class=\"truncated\"><em>")
+        .markup(THREAD_TRUNCATED_NOTE)
+        .markup("</em></p>\n");
+    html
 }
 
-/// Basic HTML escaping
-fn html_escape(s: &str) -> String {
-    s.replace('&', "&amp;")
-        .replace('<', "&lt;")
-        .replace('>', "&gt;")
-        .replace('"', "&quot;")
+/// Closing paragraph for a document, e.g. its engagement counts
+fn footer_paragraph(line: &str) -> Html {
+    let mut html = Html::new();
+    html.markup("<p class=\"engagement\">")
+        .text(line)
+        .markup("</p>\n");
+    html
 }
 
-/// Check if a post is part of a thread
-pub fn is_thread(post: &PostView) -> bool {
-    post.record.reply.is_some()
+/// One post as an HTML block; each image's alt text follows the post's text
+fn format_post_block(post: &PostView, timezone: Tz) -> Html {
+    let mut html = Html::new();
+    html.markup("<div class=\"post\">\n<p class=\"author\"><strong>")
+        .text(&display_name(post))
+        .markup("</strong> <span class=\"handle\">@")
+        .text(&post.author.handle)
+        .markup("</span></p>\n<p class=\"content\">")
+        .text(&post.record.text)
+        .markup("</p>\n");
+    for image in post_images(post) {
+        let alt = image.alt.trim();
+        if !alt.is_empty() {
+            html.markup("<p class=\"alt-text\">Image: ")
+                .text(alt)
+                .markup("</p>\n");
+        }
+    }
+    html.markup("<p class=\"timestamp\">")
+        .text(&local_timestamp(post.record.created_at, timezone))
+        .markup("</p>\n</div>\n");
+    html
+}
+
+/// `format_post_block` cut down to `max_bytes`, without alt text and with
+/// the post's text shortened to fit
+fn shortened_post_block(post: &PostView, timezone: Tz, max_bytes: usize) -> Html {
+    let mut bare = post.clone();
+    bare.record.text.clear();
+    bare.record.embed = None;
+    let room = max_bytes.saturating_sub(format_post_block(&bare, timezone).len() + ELLIPSIS.len());
+
+    let mut used = 0;
+    let mut buf = [0; 4];
+    let end = post
+        .record
+        .text
+        .char_indices()
+        .find(|&(_, c)| {
+            used += html_escape(c.encode_utf8(&mut buf)).len();
+            used > room
+        })
+        .map_or(post.record.text.len(), |(end, _)| end);
+    bare.record.text = format!("{}{}", &post.record.text[..end], ELLIPSIS);
+    format_post_block(&bare, timezone)
+}
+
+/// A timestamp in `timezone`, with the zone's abbreviation (e.g. `CEST`)
+pub fn local_timestamp(at: DateTime<Utc>, timezone: Tz) -> String {
+    at.with_timezone(&timezone)
+        .format("%Y-%m-%d %H:%M:%S %Z")
+        .to_string()
+}
+
+/// Extract rkey from AT-URI (at://did/collection/rkey)
+fn extract_rkey(uri: &str) -> String {
+    uri.split('/').next_back().unwrap_or("").to_string()
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::bluesky::{Author, ByteSlice, Facet, ThreadNode};
+    use chrono::Utc;
+
+    fn thread_post(did: &str, rkey: &str, replies: Vec<ThreadViewPost>) -> ThreadViewPost {
+        ThreadViewPost {
+            post: PostView {
+                uri: format!("at://{}/app.bsky.feed.post/{}", did, rkey),
+                cid: format!("cid-{}", rkey),
+                author: Author {
+                    did: did.to_string(),
+                    handle: format!("{}.bsky.social", rkey),
+                    display_name: None,
+                    labels: Vec::new(),
+                },
+                record: PostRecord {
+                    text: format!("Post {}", rkey),
+                    created_at: Utc::now(),
+                    reply: None,
+                    facets: None,
+                    embed: None,
+                },
+                indexed_at: Utc::now(),
+                labels: Vec::new(),
+                like_count: None,
+                repost_count: None,
+                reply_count: None,
+            },
+            parent: None,
+            replies: Some(replies.into_iter().map(ThreadNode::from).collect()),
+        }
+    }
+
+    fn rkeys(collected: &CollectedThread) -> Vec<String> {
+        collected
+            .posts
+            .iter()
+            .map(|p| extract_rkey(&p.post.uri))
+            .collect()
+    }
+
+    /// Post with a link facet over `link` within `text`
+    fn post_with_link(text: &str, link: &str) -> PostView {
+        let start = text.find(link).unwrap();
+        let mut post = thread_post("did:plc:op", "linked", vec![]).post;
+        post.record.text = text.to_string();
+        post.record.facets = Some(vec![Facet {
+            index: ByteSlice {
+                byte_start: start,
+                byte_end: start + link.len(),
+            },
+            features: vec![FacetFeature::Link {
+                uri: format!("https://{}", link),
+            }],
+        }]);
+        post
+    }
+
+    #[test]
+    fn test_trailing_link_stripped() {
+        let post = post_with_link(
+            "Great read: example.com/very-lo...",
+            "example.com/very-lo...",
+        );
+
+        assert_eq!(
+            format_post_as_highlight(&post, None, true, &HighlightTemplates::default()).text,
+            "Great read:"
+        );
+        assert_eq!(
+            format_post_as_highlight(&post, None, false, &HighlightTemplates::default()).text,
+            "Great read: example.com/very-lo..."
+        );
+    }
+
+    #[test]
+    fn test_facet_splitting_a_character_is_repaired() {
+        // Starts inside the 4-byte emoji before the link
+        let mut post = post_with_link("Look 👉example.com/article", "example.com/article");
+        post.record.facets.as_mut().unwrap()[0].index.byte_start -= 2;
+
+        let range = facet_range(
+            &post.record.text,
+            &post.record.facets.as_ref().unwrap()[0].index,
+        );
+        assert_eq!(range, Some(5..post.record.text.len()));
+        assert_eq!(
+            format_post_as_highlight(&post, None, true, &HighlightTemplates::default()).text,
+            "Look"
+        );
+    }
+
+    #[test]
+    fn test_facet_out_of_range_is_skipped() {
+        let text = "Short post";
+        let past_end = ByteSlice {
+            byte_start: 40,
+            byte_end: 60,
+        };
+        assert_eq!(facet_range(text, &past_end), None);
+        let overlong = ByteSlice {
+            byte_start: 6,
+            byte_end: 60,
+        };
+        assert_eq!(facet_range(text, &overlong), Some(6..10));
+
+        let mut post = post_with_link("Short post", "post");
+        post.record.facets.as_mut().unwrap()[0].index = past_end;
+        assert_eq!(
+            format_post_as_highlight(&post, None, true, &HighlightTemplates::default()).text,
+            "Short post"
+        );
+    }
+
+    #[test]
+    fn test_text_length_ignores_trailing_url() {
+        let post = post_with_link("this 👆 example.com/very-lo...", "example.com/very-lo...");
+        assert_eq!(text_length(&post.record), 6);
+
+        let mut post = thread_post("did:plc:op", "bare", vec![]).post;
+        post.record.text = "lol https://example.com/a".to_string();
+        assert_eq!(text_length(&post.record), 3);
+
+        post.record.text = "https://example.com/a".to_string();
+        assert_eq!(text_length(&post.record), 0);
+
+        post.record.text = "see https://example.com/a for more".to_string();
+        assert_eq!(text_length(&post.record), 34);
+    }
+
+    fn image(cid: &str, alt: &str) -> EmbedImage {
+        EmbedImage {
+            image: crate::bluesky::Blob {
+                reference: crate::bluesky::BlobLink {
+                    link: cid.to_string(),
+                },
+                mime_type: "image/jpeg".to_string(),
+                size: 1024,
+            },
+            alt: alt.to_string(),
+            aspect_ratio: None,
+        }
+    }
+
+    fn image_post(images: Vec<EmbedImage>) -> PostView {
+        let mut post = thread_post("did:plc:op", "pic", vec![]).post;
+        post.record.text = String::new();
+        post.record.embed = Some(Embed::Images { images });
+        post
+    }
+
+    #[test]
+    fn test_image_only_post_uses_alt_text() {
+        let post = image_post(vec![image("cid1", "A cat asleep"), image("cid2", "")]);
+        let highlight =
+            format_post_as_highlight(&post, None, false, &HighlightTemplates::default());
+
+        assert_eq!(
+            highlight.text,
+            "A cat asleep\n\n\
+             https://cdn.bsky.app/img/feed_fullsize/plain/did:plc:op/cid1@jpeg\n\n\
+             https://cdn.bsky.app/img/feed_fullsize/plain/did:plc:op/cid2@jpeg"
+        );
+        assert!(!is_empty_post(&post));
+    }
+
+    #[test]
+    fn test_image_only_post_without_alt_text() {
+        let post = image_post(vec![image("cid1", "  ")]);
+        let highlight = format_post_as_highlight(&post, None, true, &HighlightTemplates::default());
+
+        assert_eq!(
+            highlight.text,
+            "[image post]\n\nhttps://cdn.bsky.app/img/feed_fullsize/plain/did:plc:op/cid1@jpeg"
+        );
+    }
+
+    #[test]
+    fn test_post_with_nothing_is_empty() {
+        let mut post = image_post(vec![]);
+        post.record.embed = None;
+        assert!(is_empty_post(&post));
+
+        let document = format_post_as_document(&post, &HighlightTemplates::default());
+        assert_eq!(document.url, "https://bsky.app/profile/did:plc:op/post/pic");
+        assert!(document.html.is_none());
+    }
+
+    #[test]
+    fn test_mid_text_link_kept() {
+        let post = post_with_link("See example.com for details", "example.com");
+
+        assert_eq!(
+            format_post_as_highlight(&post, None, true, &HighlightTemplates::default()).text,
+            "See example.com for details"
+        );
+    }
+
+    #[test]
+    fn test_thread_keeps_only_author_replies() {
+        let thread = thread_post(
+            "did:plc:op",
+            "root",
+            vec![
+                thread_post("did:plc:op", "self", vec![]),
+                thread_post("did:plc:stranger", "other", vec![]),
+            ],
+        );
+
+        assert_eq!(
+            rkeys(&collect_thread_posts(
+                &thread,
+                false,
+                &ThreadLimits::default()
+            )),
+            ["root", "self"]
+        );
+        assert_eq!(
+            rkeys(&collect_thread_posts(
+                &thread,
+                true,
+                &ThreadLimits::default()
+            )),
+            ["root", "self", "other"]
+        );
+    }
+
+    #[test]
+    fn test_thread_follows_nested_self_replies_in_order() {
+        let thread = thread_post(
+            "did:plc:op",
+            "one",
+            vec![
+                thread_post(
+                    "did:plc:op",
+                    "two",
+                    vec![thread_post(
+                        "did:plc:op",
+                        "three",
+                        vec![thread_post("did:plc:op", "four", vec![])],
+                    )],
+                ),
+                thread_post("did:plc:stranger", "other", vec![]),
+            ],
+        );
+
+        assert_eq!(
+            rkeys(&collect_thread_posts(
+                &thread,
+                false,
+                &ThreadLimits::default()
+            )),
+            ["one", "two", "three", "four"]
+        );
+    }
+
+    #[test]
+    fn test_thread_beyond_limits_is_truncated() {
+        let thread = thread_post(
+            "did:plc:op",
+            "one",
+            vec![thread_post(
+                "did:plc:op",
+                "two",
+                vec![thread_post(
+                    "did:plc:op",
+                    "three",
+                    vec![thread_post("did:plc:op", "four", vec![])],
+                )],
+            )],
+        );
+
+        let by_posts = ThreadLimits {
+            max_depth: 100,
+            max_posts: 3,
+            ..ThreadLimits::default()
+        };
+        let collected = collect_thread_posts(&thread, false, &by_posts);
+        assert_eq!(rkeys(&collected), ["one", "two", "three"]);
+        assert!(collected.truncated);
+
+        let by_depth = ThreadLimits {
+            max_depth: 1,
+            max_posts: 100,
+            ..ThreadLimits::default()
+        };
+        let collected = collect_thread_posts(&thread, false, &by_depth);
+        assert_eq!(rkeys(&collected), ["one", "two"]);
+        assert!(collected.truncated);
+
+        let document = format_thread_as_document(
+            &thread,
+            false,
+            &by_posts,
+            Tz::UTC,
+            &HighlightTemplates::default(),
+            None,
+        );
+        assert!(document.html.unwrap().contains("[thread truncated]"));
+        let document = format_thread_as_document(
+            &thread,
+            false,
+            &ThreadLimits::default(),
+            Tz::UTC,
+            &HighlightTemplates::default(),
+            None,
+        );
+        assert!(!document.html.unwrap().contains("[thread truncated]"));
+    }
+
+    #[test]
+    fn test_footer_closes_article_within_limit() {
+        let thread = (0..50).rev().fold(None, |reply, i| {
+            let mut post = thread_post(
+                "did:plc:op",
+                &format!("p{}", i),
+                reply.into_iter().collect(),
+            );
+            post.post.record.text = "long text ".repeat(200);
+            Some(post)
+        });
+        let limits = ThreadLimits {
+            max_bytes: 20_000,
+            ..ThreadLimits::default()
+        };
+
+        let html = format_thread_as_document(
+            &thread.unwrap(),
+            false,
+            &limits,
+            Tz::UTC,
+            &HighlightTemplates::default(),
+            Some("3 likes · 0 reposts · <1> reply"),
+        )
+        .html
+        .unwrap();
+
+        assert!(html.len() <= limits.max_bytes);
+        assert!(html.ends_with(
+            "<p class=\"engagement\">3 likes · 0 reposts · &lt;1&gt; reply</p>\n</article>"
+        ));
+    }
+
+    #[test]
+    fn test_thread_document_has_author_and_summary() {
+        let mut thread = thread_post(
+            "did:plc:op",
+            "one",
+            vec![thread_post("did:plc:op", "two", vec![])],
+        );
+        thread.post.author.display_name = Some("  ".to_string());
+
+        let document = format_thread_as_document(
+            &thread,
+            false,
+            &ThreadLimits::default(),
+            Tz::UTC,
+            &HighlightTemplates::default(),
+            None,
+        );
+        let json = serde_json::to_value(&document).unwrap();
+
+        assert_eq!(json["author"], "one.bsky.social");
+        assert_eq!(json["summary"], "Post one");
+        assert_eq!(json["saved_using"], SAVED_USING);
+        assert!(json.get("notes").is_none());
+    }
+
+    #[test]
+    fn test_over_limit_document_cut_between_posts() {
+        // A 50-post self-thread of ~2 KB posts
+        let thread = (0..50).rev().fold(None, |reply, i| {
+            let mut post = thread_post(
+                "did:plc:op",
+                &format!("p{}", i),
+                reply.into_iter().collect(),
+            );
+            post.post.record.text = format!("{} {}", i, "long text ".repeat(200));
+            Some(post)
+        });
+        let limits = ThreadLimits {
+            max_bytes: 20_000,
+            ..ThreadLimits::default()
+        };
+
+        let html = format_thread_as_document(
+            &thread.unwrap(),
+            false,
+            &limits,
+            Tz::UTC,
+            &HighlightTemplates::default(),
+            None,
+        )
+        .html
+        .unwrap();
+
+        assert!(html.len() <= limits.max_bytes);
+        assert!(html.len() > limits.max_bytes / 2);
+        assert!(html.ends_with("[thread truncated]</em></p>\n</article>"));
+        // Whole posts only
+        assert_eq!(
+            html.matches("<div class=\"post\">").count(),
+            html.matches("</div>").count()
+        );
+    }
+
+    #[test]
+    fn test_oversized_first_post_is_shortened() {
+        let mut thread = thread_post("did:plc:op", "one", vec![]);
+        thread.post.record.text = "<long> text ".repeat(2_000);
+        let limits = ThreadLimits {
+            max_bytes: MIN_DOCUMENT_BYTES,
+            ..ThreadLimits::default()
+        };
+
+        let html = format_thread_as_document(
+            &thread,
+            false,
+            &limits,
+            Tz::UTC,
+            &HighlightTemplates::default(),
+            Some("3 likes · 0 reposts · 1 reply"),
+        )
+        .html
+        .unwrap();
+
+        assert!(html.len() <= limits.max_bytes, "{}", html.len());
+        assert!(html.contains("&lt;long&gt; text"));
+        assert!(html.contains("…</p>"));
+        assert!(html.ends_with("1 reply</p>\n</article>"));
+    }
+
+    #[test]
+    fn test_local_timestamp_in_user_zone() {
+        let at: DateTime<Utc> = "2026-07-01T12:30:00Z".parse().unwrap();
+
+        assert_eq!(local_timestamp(at, Tz::UTC), "2026-07-01 12:30:00 UTC");
+        assert_eq!(
+            local_timestamp(at, Tz::Europe__Berlin),
+            "2026-07-01 14:30:00 CEST"
+        );
+        assert_eq!(
+            local_timestamp(at, Tz::America__New_York),
+            "2026-07-01 08:30:00 EDT"
+        );
+    }
+
+    #[test]
+    fn test_expand_template_placeholders() {
+        let mut post = thread_post("did:plc:op", "3kabc", vec![]).post;
+        post.author.display_name = Some("Op Author".to_string());
+        post.record.created_at = "2026-03-04T05:06:07Z".parse().unwrap();
+
+        assert_eq!(
+            expand_template("@{handle}", &post, DEFAULT_POST_URL_TEMPLATE),
+            "@3kabc.bsky.social"
+        );
+        assert_eq!(
+            expand_template("by {display_name}", &post, DEFAULT_POST_URL_TEMPLATE),
+            "by Op Author"
+        );
+        assert_eq!(
+            expand_template("on {date}", &post, DEFAULT_POST_URL_TEMPLATE),
+            "on 2026-03-04"
+        );
+        assert_eq!(
+            expand_template("{url}", &post, DEFAULT_POST_URL_TEMPLATE),
+            "https://bsky.app/profile/3kabc.bsky.social/post/3kabc"
+        );
+        assert_eq!(
+            expand_template("\"{text}\"", &post, DEFAULT_POST_URL_TEMPLATE),
+            "\"Post 3kabc\""
+        );
+    }
+
+    #[test]
+    fn test_custom_frontend_post_urls() {
+        let post = thread_post("did:plc:op", "3kabc", vec![]).post;
+        let templates = HighlightTemplates {
+            post_url: "https://deer.social/profile/{handle}/post/{rkey}".to_string(),
+            ..Default::default()
+        };
+
+        let highlight = format_post_as_highlight(&post, None, false, &templates);
+        assert_eq!(
+            highlight.source_url.as_deref(),
+            Some("https://deer.social/profile/3kabc.bsky.social/post/3kabc")
+        );
+        // Dedup keys stay on bsky.app, so changing the template doesn't re-save
+        assert_eq!(
+            highlight.highlight_url.as_deref(),
+            Some("https://bsky.app/profile/did:plc:op/post/3kabc")
+        );
+        assert_eq!(
+            format_post_as_document(&post, &templates).url,
+            "https://bsky.app/profile/did:plc:op/post/3kabc"
+        );
+    }
+
+    #[test]
+    fn test_expand_template_keeps_unknown_placeholders() {
+        let post = thread_post("did:plc:op", "3kabc", vec![]).post;
+
+        assert_eq!(
+            expand_template("{handle} {likes} {", &post, DEFAULT_POST_URL_TEMPLATE),
+            "3kabc.bsky.social {likes} {"
+        );
+    }
+
+    #[test]
+    fn test_document_titles_follow_custom_title_template() {
+        let thread = thread_post(
+            "did:plc:op",
+            "one",
+            vec![thread_post("did:plc:op", "two", vec![])],
+        );
+        let quoted = [thread_post("did:plc:other", "q", vec![])];
+        let limits = ThreadLimits::default();
+        let titles = |templates: &HighlightTemplates| {
+            [
+                format_post_as_document(&thread.post, templates).title,
+                format_thread_as_document(&thread, false, &limits, Tz::UTC, templates, None).title,
+                format_quote_as_document(
+                    &thread,
+                    &quoted,
+                    false,
+                    &limits,
+                    Tz::UTC,
+                    templates,
+                    None,
+                )
+                .title,
+            ]
+            .map(Option::unwrap)
+        };
+
+        assert_eq!(
+            titles(&HighlightTemplates::default()),
+            [
+                "Post by @one.bsky.social",
+                "Thread by @one.bsky.social",
+                "Quote by @one.bsky.social",
+            ]
+        );
+        let custom = HighlightTemplates {
+            title: "{display_name} on Bluesky".to_string(),
+            ..Default::default()
+        };
+        assert_eq!(
+            titles(&custom),
+            ["one.bsky.social on Bluesky"; 3].map(String::from)
+        );
+    }
+
+    #[test]
+    fn test_note_template_used_without_user_note() {
+        let post = thread_post("did:plc:op", "3kabc", vec![]).post;
+        let templates = HighlightTemplates {
+            title: "Saved from {handle}".to_string(),
+            note: Some("via {url}".to_string()),
+            ..Default::default()
+        };
+
+        let highlight = format_post_as_highlight(&post, None, false, &templates);
+        assert_eq!(
+            highlight.title.as_deref(),
+            Some("Saved from 3kabc.bsky.social")
+        );
+        assert_eq!(
+            highlight.note.as_deref(),
+            Some("via https://bsky.app/profile/3kabc.bsky.social/post/3kabc")
+        );
+
+        let highlight = format_post_as_highlight(&post, Some("mine"), false, &templates);
+        assert_eq!(highlight.note.as_deref(), Some("mine"));
+    }
+
+    #[test]
+    fn test_highlight_category_override_is_serialized() {
+        let post = thread_post("did:plc:op", "3kabc", vec![]).post;
+
+        let highlight = format_post_as_highlight(&post, None, false, &Default::default());
+        assert_eq!(highlight.category.as_deref(), Some("tweets"));
+
+        let templates = HighlightTemplates {
+            category: "articles".to_string(),
+            ..Default::default()
+        };
+        let highlight = format_post_as_highlight(&post, None, false, &templates);
+        let json = serde_json::to_value(&highlight).unwrap();
+        assert_eq!(json["category"], "articles");
+    }
 
     #[test]
     fn test_extract_rkey() {
@@ -154,4 +1328,43 @@ mod tests {
         assert_eq!(html_escape("<script>"), "&lt;script&gt;");
         assert_eq!(html_escape("a & b"), "a &amp; b");
     }
+
+    #[test]
+    fn test_alt_text_html_neutralized() {
+        let mut thread = thread_post("did:plc:op", "pic", vec![]);
+        thread.post.record.embed = Some(Embed::Images {
+            images: vec![image("bafyimg", "<img src=x onerror=alert(1)>")],
+        });
+
+        let html = format_posts_as_html(
+            &[&thread],
+            false,
+            Tz::UTC,
+            ThreadLimits::default().max_bytes,
+            None,
+        );
+
+        assert!(!html.contains("<img"));
+        assert!(html.contains("Image: &lt;img src=x onerror=alert(1)&gt;"));
+    }
+
+    #[test]
+    fn test_handle_cannot_close_markup() {
+        let mut thread = thread_post("did:plc:op", "root", vec![]);
+        thread.post.author.handle = "evil</div><script>x</script>".to_string();
+        thread.post.author.display_name = Some("\"Quoted\" & <b>bold</b>".to_string());
+
+        let html = format_posts_as_html(
+            &[&thread],
+            false,
+            Tz::UTC,
+            ThreadLimits::default().max_bytes,
+            None,
+        );
+
+        assert_eq!(html.matches("</div>").count(), 1);
+        assert!(!html.contains("<script>"));
+        assert!(!html.contains("<b>"));
+        assert!(html.contains("@evil&lt;/div&gt;&lt;script&gt;"));
+    }
 }
@@ -0,0 +1,82 @@
+//! HTML output for Reader documents
+//!
+//! Everything a user wrote (post text, names, handles, alt text) reaches
+//! the HTML through `Html::text`, which escapes it. Raw markup can only be
+//! a `'static` literal from our own code, so user content can't slip in
+//! unescaped by way of a `format!`.
+
+/// HTML being built from trusted markup and escaped text
+#[derive(Debug, Default)]
+pub struct Html(String);
+
+impl Html {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Append markup written in this codebase
+    pub fn markup(&mut self, markup: &'static str) -> &mut Self {
+        self.0.push_str(markup);
+        self
+    }
+
+    /// Append user-derived text, escaped
+    pub fn text(&mut self, text: &str) -> &mut Self {
+        self.0.push_str(&html_escape(text));
+        self
+    }
+
+    /// Append HTML built elsewhere
+    pub fn append(&mut self, other: Html) -> &mut Self {
+        self.0.push_str(&other.0);
+        self
+    }
+
+    /// Length in bytes
+    pub fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    /// Whether nothing has been appended yet
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    /// The finished HTML
+    pub fn into_string(self) -> String {
+        self.0
+    }
+}
+
+/// Escape text for use in HTML content or a quoted attribute value
+pub fn html_escape(s: &str) -> String {
+    let mut escaped = String::with_capacity(s.len());
+    for c in s.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&#39;"),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_text_escaped_markup_kept() {
+        let mut html = Html::new();
+        html.markup("<p title=\"x\">")
+            .text("<b onclick='go()'>hi</b> & bye")
+            .markup("</p>");
+        assert_eq!(
+            html.into_string(),
+            "<p title=\"x\">&lt;b onclick=&#39;go()&#39;&gt;hi&lt;/b&gt; &amp; bye</p>"
+        );
+    }
+}
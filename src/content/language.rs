@@ -0,0 +1,135 @@
+//! Post language detection, for `lang:xx` tags
+//!
+//! Backed by `whatlang` when built with the `language-detection` feature;
+//! without it nothing is ever detected.
+
+/// Whether this build can detect languages
+pub const AVAILABLE: bool = cfg!(feature = "language-detection");
+
+/// Shortest text (in characters) worth detecting; shorter guesses are noise
+const MIN_DETECT_CHARS: usize = 20;
+
+/// `lang:xx` tag (ISO 639-1) for the text's language, if detection is reliable
+pub fn language_tag(text: &str) -> Option<String> {
+    if text.trim().chars().count() < MIN_DETECT_CHARS {
+        return None;
+    }
+    detect(text).map(|code| format!("lang:{}", code))
+}
+
+#[cfg(feature = "language-detection")]
+fn detect(text: &str) -> Option<&'static str> {
+    let info = whatlang::detect(text)?;
+    if !info.is_reliable() {
+        return None;
+    }
+    Some(iso_639_1(info.lang()))
+}
+
+#[cfg(not(feature = "language-detection"))]
+fn detect(_text: &str) -> Option<&'static str> {
+    None
+}
+
+/// Two-letter code for a detected language
+#[cfg(feature = "language-detection")]
+fn iso_639_1(lang: whatlang::Lang) -> &'static str {
+    use whatlang::Lang::*;
+
+    match lang {
+        Afr => "af",
+        Aka => "ak",
+        Amh => "am",
+        Ara => "ar",
+        Aze => "az",
+        Bel => "be",
+        Ben => "bn",
+        Bul => "bg",
+        Cat => "ca",
+        Ces => "cs",
+        Cmn => "zh",
+        Dan => "da",
+        Deu => "de",
+        Ell => "el",
+        Eng => "en",
+        Epo => "eo",
+        Est => "et",
+        Fin => "fi",
+        Fra => "fr",
+        Guj => "gu",
+        Heb => "he",
+        Hin => "hi",
+        Hrv => "hr",
+        Hun => "hu",
+        Hye => "hy",
+        Ind => "id",
+        Ita => "it",
+        Jav => "jv",
+        Jpn => "ja",
+        Kan => "kn",
+        Kat => "ka",
+        Khm => "km",
+        Kor => "ko",
+        Lat => "la",
+        Lav => "lv",
+        Lit => "lt",
+        Mal => "ml",
+        Mar => "mr",
+        Mkd => "mk",
+        Mya => "my",
+        Nep => "ne",
+        Nld => "nl",
+        Nob => "nb",
+        Ori => "or",
+        Pan => "pa",
+        Pes => "fa",
+        Pol => "pl",
+        Por => "pt",
+        Ron => "ro",
+        Rus => "ru",
+        Sin => "si",
+        Slk => "sk",
+        Slv => "sl",
+        Sna => "sn",
+        Spa => "es",
+        Srp => "sr",
+        Swe => "sv",
+        Tam => "ta",
+        Tel => "te",
+        Tgl => "tl",
+        Tha => "th",
+        Tuk => "tk",
+        Tur => "tr",
+        Ukr => "uk",
+        Urd => "ur",
+        Uzb => "uz",
+        Vie => "vi",
+        Yid => "yi",
+        Zul => "zu",
+    }
+}
+
+#[cfg(all(test, feature = "language-detection"))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_english_and_german_posts_tagged() {
+        assert_eq!(
+            language_tag("This is a long post about the things I read this week and loved."),
+            Some("lang:en".to_string())
+        );
+        assert_eq!(
+            language_tag(
+                "Das ist ein langer Beitrag über die Bücher, die ich diese Woche gelesen habe."
+            ),
+            Some("lang:de".to_string())
+        );
+    }
+
+    #[test]
+    fn test_short_text_not_detected() {
+        assert_eq!(language_tag("so good"), None);
+        assert_eq!(language_tag("   "), None);
+    }
+}
@@ -1,14 +1,127 @@
 //! Link extraction from posts
 
+use url::Url;
+
 use crate::bluesky::{Facet, FacetFeature, PostRecord};
 
-/// Extract all links from a post's facets
-pub fn extract_links(record: &PostRecord) -> Vec<String> {
+/// Query parameters that only track where a click came from
+///
+/// A trailing `*` matches any parameter with that prefix.
+pub const DEFAULT_STRIP_QUERY_PARAMS: &[&str] = &[
+    "utm_*", "fbclid", "gclid", "dclid", "msclkid", "igshid", "mc_cid", "mc_eid", "ref", "ref_src",
+    "_ga",
+];
+
+/// What a link points at, for picking its Reader category
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LinkKind {
+    /// A web page; Reader's default
+    Article,
+    Pdf,
+    /// A YouTube video
+    Video,
+}
+
+impl LinkKind {
+    /// Reader category to save the link under (None lets Reader decide)
+    pub fn category(self) -> Option<&'static str> {
+        match self {
+            Self::Article => None,
+            Self::Pdf => Some("pdf"),
+            Self::Video => Some("video"),
+        }
+    }
+}
+
+/// Classify a link by its host and path
+pub fn link_kind(link: &str) -> LinkKind {
+    let Ok(url) = Url::parse(link) else {
+        return LinkKind::Article;
+    };
+    let host = url.host_str().unwrap_or_default();
+    let host = host.strip_prefix("www.").unwrap_or(host);
+    let path = url.path();
+
+    let youtube_video = match host {
+        "youtu.be" => path.len() > 1,
+        "youtube.com" | "m.youtube.com" => {
+            path == "/watch" || path.starts_with("/shorts/") || path.starts_with("/live/")
+        }
+        _ => false,
+    };
+
+    if youtube_video {
+        LinkKind::Video
+    } else if path.to_ascii_lowercase().ends_with(".pdf") {
+        LinkKind::Pdf
+    } else {
+        LinkKind::Article
+    }
+}
+
+/// The default tracking parameters as owned strings
+pub fn default_strip_query_params() -> Vec<String> {
+    DEFAULT_STRIP_QUERY_PARAMS
+        .iter()
+        .map(|param| param.to_string())
+        .collect()
+}
+
+/// Extract all links from a post's facets, normalized
+///
+/// `strip_params` lists the query parameters to drop (see `normalize_url`).
+pub fn extract_links(record: &PostRecord, strip_params: &[String]) -> Vec<String> {
     let Some(facets) = &record.facets else {
         return Vec::new();
     };
 
-    facets.iter().flat_map(extract_links_from_facet).collect()
+    facets
+        .iter()
+        .flat_map(extract_links_from_facet)
+        .map(|link| normalize_url(&link, strip_params))
+        .collect()
+}
+
+/// Canonical form of a link for saving and duplicate checks
+///
+/// Lowercases the host, drops the listed query parameters, the fragment, and
+/// any trailing slash; other query parameters are kept in order.
+/// Unparseable input comes back trimmed but otherwise as-is.
+pub fn normalize_url(link: &str, strip_params: &[String]) -> String {
+    let Ok(mut url) = Url::parse(link.trim()) else {
+        return link.trim().to_string();
+    };
+
+    let kept: Vec<(String, String)> = url
+        .query_pairs()
+        .filter(|(key, _)| !strip_params.iter().any(|param| param_matches(param, key)))
+        .map(|(key, value)| (key.into_owned(), value.into_owned()))
+        .collect();
+    if kept.is_empty() {
+        url.set_query(None);
+    } else {
+        url.query_pairs_mut().clear().extend_pairs(kept);
+    }
+    url.set_fragment(None);
+
+    let path = url.path().trim_end_matches('/').to_string();
+    url.set_path(&path);
+
+    let mut normalized = url.to_string();
+    if url.query().is_none() && normalized.ends_with('/') {
+        normalized.pop();
+    }
+    normalized
+}
+
+/// Whether a query key matches a strip pattern (case-insensitive)
+fn param_matches(pattern: &str, key: &str) -> bool {
+    let key = key.to_ascii_lowercase();
+    let pattern = pattern.trim().to_ascii_lowercase();
+    match pattern.strip_suffix('*') {
+        Some(prefix) => key.starts_with(prefix),
+        None => key == pattern,
+    }
 }
 
 fn extract_links_from_facet(facet: &Facet) -> Vec<String> {
@@ -35,8 +148,9 @@ mod tests {
             created_at: Utc::now(),
             reply: None,
             facets: None,
+            embed: None,
         };
-        assert!(extract_links(&record).is_empty());
+        assert!(extract_links(&record, &default_strip_query_params()).is_empty());
     }
 
     #[test]
@@ -54,9 +168,79 @@ mod tests {
                     uri: "https://example.com".to_string(),
                 }],
             }]),
+            embed: None,
         };
-        let links = extract_links(&record);
+        let links = extract_links(&record, &default_strip_query_params());
         assert_eq!(links.len(), 1);
         assert_eq!(links[0], "https://example.com");
     }
+
+    /// `normalize_url` with the default tracking parameters
+    fn normalize(link: &str) -> String {
+        normalize_url(link, &default_strip_query_params())
+    }
+
+    #[test]
+    fn test_normalize_url_strips_tracking() {
+        assert_eq!(
+            normalize("https://Example.com/article/?utm_source=bsky&utm_medium=social&id=7#top"),
+            "https://example.com/article?id=7"
+        );
+        assert_eq!(
+            normalize("https://example.com/a/?fbclid=abc"),
+            "https://example.com/a"
+        );
+        assert_eq!(normalize("https://example.com/"), "https://example.com");
+        assert_eq!(normalize(" not a url "), "not a url");
+    }
+
+    #[test]
+    fn test_normalize_real_world_tracking_urls() {
+        assert_eq!(
+            normalize("https://www.NYTimes.com/2026/01/02/tech/ai.html?smid=nytcore&utm_campaign=share&utm_content=bsky"),
+            "https://www.nytimes.com/2026/01/02/tech/ai.html?smid=nytcore"
+        );
+        assert_eq!(
+            normalize("https://blog.example.dev/post/?ref=hackernews#comments"),
+            "https://blog.example.dev/post"
+        );
+        assert_eq!(
+            normalize("https://shop.example.com/item?id=42&gclid=EAIaIQ&mc_cid=9"),
+            "https://shop.example.com/item?id=42"
+        );
+        assert_eq!(
+            normalize("https://news.ycombinator.com/item?id=39000000"),
+            "https://news.ycombinator.com/item?id=39000000"
+        );
+    }
+
+    #[test]
+    fn test_link_kind() {
+        assert_eq!(
+            link_kind("https://arxiv.org/pdf/2401.00001v1.PDF"),
+            LinkKind::Pdf
+        );
+        assert_eq!(LinkKind::Pdf.category(), Some("pdf"));
+        assert_eq!(
+            link_kind("https://www.youtube.com/watch?v=dQw4w9WgXcQ"),
+            LinkKind::Video
+        );
+        assert_eq!(link_kind("https://youtu.be/dQw4w9WgXcQ"), LinkKind::Video);
+        assert_eq!(LinkKind::Video.category(), Some("video"));
+        assert_eq!(
+            link_kind("https://www.youtube.com/@channel"),
+            LinkKind::Article
+        );
+        assert_eq!(link_kind("https://example.com/pdf-tips"), LinkKind::Article);
+        assert_eq!(LinkKind::Article.category(), None);
+    }
+
+    #[test]
+    fn test_configured_params_replace_defaults() {
+        let strip = vec!["smid".to_string(), "utm_*".to_string()];
+        assert_eq!(
+            normalize_url("https://example.com/a?smid=x&ref=feed&utm_source=y", &strip),
+            "https://example.com/a?ref=feed"
+        );
+    }
 }
@@ -3,6 +3,9 @@
 //! Converts Bluesky posts/threads into Readwise-compatible formats.
 
 pub mod formatter;
+pub mod html;
+pub mod language;
 pub mod links;
+pub mod oembed;
 
 pub use formatter::*;
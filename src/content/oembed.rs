@@ -0,0 +1,126 @@
+//! Video titles from YouTube's oEmbed endpoint
+//!
+//! Used to title saved video links. Lookups are best-effort: callers save
+//! the bare URL when this fails.
+
+use std::time::Duration;
+
+use anyhow::Result;
+use async_trait::async_trait;
+use serde::Deserialize;
+
+/// YouTube's public oEmbed endpoint
+const YOUTUBE_OEMBED_URL: &str = "https://www.youtube.com/oembed";
+
+/// Longest an oEmbed lookup may hold up a save
+const OEMBED_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Looks up a video's title
+#[async_trait]
+pub trait OEmbedClient: Send + Sync {
+    async fn video_title(&self, url: &str) -> Result<Option<String>>;
+}
+
+/// oEmbed client for YouTube
+pub struct YouTubeOEmbed {
+    http: reqwest::Client,
+    endpoint: String,
+}
+
+impl YouTubeOEmbed {
+    /// Client for the public endpoint on a shared `reqwest::Client`
+    pub fn new(http: reqwest::Client) -> Self {
+        Self {
+            http,
+            endpoint: YOUTUBE_OEMBED_URL.to_string(),
+        }
+    }
+
+    /// Use a different endpoint (e.g., a mock server)
+    pub fn with_endpoint(mut self, endpoint: &str) -> Self {
+        self.endpoint = endpoint.to_string();
+        self
+    }
+}
+
+#[async_trait]
+impl OEmbedClient for YouTubeOEmbed {
+    async fn video_title(&self, url: &str) -> Result<Option<String>> {
+        #[derive(Deserialize)]
+        struct OEmbedResponse {
+            title: Option<String>,
+        }
+
+        let response = self
+            .http
+            .get(&self.endpoint)
+            .query(&[("url", url), ("format", "json")])
+            .timeout(OEMBED_TIMEOUT)
+            .send()
+            .await?;
+
+        if !response.status().is_success() {
+            anyhow::bail!("oEmbed error {}", response.status());
+        }
+
+        let oembed: OEmbedResponse = response.json().await?;
+        Ok(oembed.title.filter(|title| !title.trim().is_empty()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::extract::Query;
+    use axum::http::StatusCode;
+    use axum::response::IntoResponse;
+    use axum::routing::get;
+    use axum::{Json, Router};
+    use serde_json::json;
+    use std::collections::HashMap;
+
+    /// Local stand-in for YouTube's oEmbed endpoint
+    async fn mock_youtube() -> String {
+        let app = Router::new().route(
+            "/oembed",
+            get(|Query(params): Query<HashMap<String, String>>| async move {
+                assert_eq!(params["format"], "json");
+                match params["url"].as_str() {
+                    "https://youtu.be/titled" => {
+                        Json(json!({ "title": "A talk worth watching", "type": "video" }))
+                            .into_response()
+                    }
+                    "https://youtu.be/untitled" => Json(json!({ "title": "  " })).into_response(),
+                    _ => StatusCode::NOT_FOUND.into_response(),
+                }
+            }),
+        );
+
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+        format!("http://{}/oembed", addr)
+    }
+
+    #[tokio::test]
+    async fn test_video_title_from_oembed() {
+        let oembed =
+            YouTubeOEmbed::new(reqwest::Client::new()).with_endpoint(&mock_youtube().await);
+
+        assert_eq!(
+            oembed.video_title("https://youtu.be/titled").await.unwrap(),
+            Some("A talk worth watching".to_string())
+        );
+        assert_eq!(
+            oembed
+                .video_title("https://youtu.be/untitled")
+                .await
+                .unwrap(),
+            None
+        );
+        assert!(oembed
+            .video_title("https://youtu.be/private")
+            .await
+            .is_err());
+    }
+}
@@ -0,0 +1,140 @@
+//! Encryption for secrets stored at rest
+//!
+//! Values are sealed with AES-256-GCM and stored as base64 of
+//! `version (1 byte) || nonce (12 bytes) || ciphertext+tag`.
+//! The version byte leaves room for key or algorithm rotation.
+
+use aes_gcm::aead::{Aead, AeadCore, KeyInit, OsRng};
+use aes_gcm::{Aes256Gcm, Key, Nonce};
+use base64::{engine::general_purpose::STANDARD, Engine};
+use thiserror::Error;
+
+/// Current ciphertext format version
+const VERSION: u8 = 1;
+
+/// AES-GCM nonce length in bytes
+const NONCE_LEN: usize = 12;
+
+/// Errors from encrypting or decrypting a value
+#[derive(Error, Debug)]
+pub enum CryptoError {
+    #[error("Encryption key must be base64 encoding 32 bytes")]
+    InvalidKey,
+
+    #[error("Ciphertext is malformed")]
+    Malformed,
+
+    #[error("Unsupported ciphertext version: {0}")]
+    UnsupportedVersion(u8),
+
+    #[error("Encryption failed")]
+    EncryptionFailed,
+
+    #[error("Decryption failed")]
+    DecryptionFailed,
+}
+
+/// A 256-bit key used to seal secrets
+#[derive(Clone)]
+pub struct EncryptionKey(Key<Aes256Gcm>);
+
+impl EncryptionKey {
+    /// Parse a key from its base64 representation
+    pub fn from_base64(encoded: &str) -> Result<Self, CryptoError> {
+        let bytes = STANDARD
+            .decode(encoded.trim())
+            .map_err(|_| CryptoError::InvalidKey)?;
+        let bytes: [u8; 32] = bytes.try_into().map_err(|_| CryptoError::InvalidKey)?;
+        Ok(Self(Key::<Aes256Gcm>::from(bytes)))
+    }
+
+    /// Fixed key for tests
+    #[cfg(test)]
+    pub fn test_key() -> Self {
+        Self(Key::<Aes256Gcm>::from([7u8; 32]))
+    }
+}
+
+impl std::fmt::Debug for EncryptionKey {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("EncryptionKey([REDACTED])")
+    }
+}
+
+/// Encrypt a plaintext value with a fresh random nonce
+pub fn encrypt(plaintext: &str, key: &EncryptionKey) -> Result<String, CryptoError> {
+    let cipher = Aes256Gcm::new(&key.0);
+    let nonce = Aes256Gcm::generate_nonce(&mut OsRng);
+    let ciphertext = cipher
+        .encrypt(&nonce, plaintext.as_bytes())
+        .map_err(|_| CryptoError::EncryptionFailed)?;
+
+    let mut sealed = Vec::with_capacity(1 + NONCE_LEN + ciphertext.len());
+    sealed.push(VERSION);
+    sealed.extend_from_slice(&nonce);
+    sealed.extend_from_slice(&ciphertext);
+
+    Ok(STANDARD.encode(sealed))
+}
+
+/// Decrypt a value produced by [`encrypt`]
+pub fn decrypt(ciphertext: &str, key: &EncryptionKey) -> Result<String, CryptoError> {
+    let sealed = STANDARD
+        .decode(ciphertext)
+        .map_err(|_| CryptoError::Malformed)?;
+
+    let (&version, rest) = sealed.split_first().ok_or(CryptoError::Malformed)?;
+    if version != VERSION {
+        return Err(CryptoError::UnsupportedVersion(version));
+    }
+    if rest.len() < NONCE_LEN {
+        return Err(CryptoError::Malformed);
+    }
+
+    let (nonce, body) = rest.split_at(NONCE_LEN);
+    let nonce: [u8; NONCE_LEN] = nonce.try_into().map_err(|_| CryptoError::Malformed)?;
+    let cipher = Aes256Gcm::new(&key.0);
+    let plaintext = cipher
+        .decrypt(&Nonce::from(nonce), body)
+        .map_err(|_| CryptoError::DecryptionFailed)?;
+
+    String::from_utf8(plaintext).map_err(|_| CryptoError::Malformed)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_round_trip() {
+        let key = EncryptionKey::test_key();
+        let sealed = encrypt("readwise-token", &key).unwrap();
+        assert_ne!(sealed, "readwise-token");
+        assert_eq!(decrypt(&sealed, &key).unwrap(), "readwise-token");
+    }
+
+    #[test]
+    fn test_nonce_per_value() {
+        let key = EncryptionKey::test_key();
+        assert_ne!(
+            encrypt("same", &key).unwrap(),
+            encrypt("same", &key).unwrap()
+        );
+    }
+
+    #[test]
+    fn test_tampered_ciphertext_fails() {
+        let key = EncryptionKey::test_key();
+        let mut bytes = STANDARD.decode(encrypt("secret", &key).unwrap()).unwrap();
+        let last = bytes.len() - 1;
+        bytes[last] ^= 0x01;
+
+        let result = decrypt(&STANDARD.encode(bytes), &key);
+        assert!(matches!(result, Err(CryptoError::DecryptionFailed)));
+    }
+
+    #[test]
+    fn test_invalid_key_length() {
+        assert!(EncryptionKey::from_base64(&STANDARD.encode([0u8; 16])).is_err());
+    }
+}
@@ -3,6 +3,8 @@
 //! Handles user data, OAuth tokens, settings, and processed items.
 
 pub mod models;
+pub mod pagination;
 pub mod queries;
+pub mod session_store;
 
 pub use models::*;
@@ -1,9 +1,13 @@
 //! Database models
 
 use chrono::{DateTime, Utc};
+use chrono_tz::Tz;
 use serde::{Deserialize, Serialize};
 use uuid::Uuid;
 
+use crate::bluesky::ADULT_CONTENT_LABELS;
+use crate::services::post_class::{PostClassRules, SaveTarget};
+
 /// A registered user
 #[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow)]
 pub struct User {
@@ -11,9 +15,29 @@ pub struct User {
     pub bluesky_did: String,
     pub bluesky_handle: String,
     pub created_at: DateTime<Utc>,
+    /// Tokens were rejected; bookmark sync is off until they log in again
+    pub needs_reauth: bool,
 }
 
-/// OAuth tokens for a user (encrypted at rest)
+/// A user with activity totals, for operators (no tokens)
+#[derive(Debug, Clone, Serialize, sqlx::FromRow)]
+pub struct UserWithStats {
+    pub id: Uuid,
+    pub bluesky_did: String,
+    pub bluesky_handle: String,
+    pub created_at: DateTime<Utc>,
+    pub needs_reauth: bool,
+    /// None until the user has saved settings
+    pub bookmark_sync_enabled: Option<bool>,
+    /// Latest processed bookmark or DM
+    pub last_activity_at: Option<DateTime<Utc>>,
+    pub bookmarks_processed: i64,
+    pub dms_processed: i64,
+    /// Saves still queued for retry
+    pub failed_saves_pending: i64,
+}
+
+/// OAuth tokens for a user (encrypted at rest, decrypted on read)
 #[derive(Debug, Clone, sqlx::FromRow)]
 pub struct UserToken {
     pub user_id: Uuid,
@@ -21,6 +45,8 @@ pub struct UserToken {
     pub refresh_token: Option<String>,
     pub expires_at: Option<DateTime<Utc>>,
     pub updated_at: DateTime<Utc>,
+    /// Private DPoP key the tokens are bound to (multibase)
+    pub dpop_key: Option<String>,
 }
 
 /// User settings
@@ -32,6 +58,105 @@ pub struct UserSettings {
     pub extract_links: bool,
     pub last_bookmark_cursor: Option<String>,
     pub updated_at: DateTime<Utc>,
+    /// Only auto-save bookmarks by these authors (handles or DIDs); empty allows all
+    pub author_allowlist: Vec<String>,
+    /// Never auto-save bookmarks by these authors; wins over the allowlist
+    pub author_denylist: Vec<String>,
+    /// Skip bookmarked posts shorter than this many characters
+    pub min_post_length: i32,
+    /// Seconds between bookmark polls; None uses the server default
+    pub poll_interval_secs: Option<i32>,
+    /// Readwise category for saved highlights; None uses the server default
+    pub highlight_category: Option<String>,
+    /// Also save each image's alt text as its own highlight
+    pub save_image_alt_text: bool,
+    /// DM a recap of the last day's saves once a day
+    pub daily_digest: bool,
+    /// IANA timezone (e.g. `Europe/Berlin`) for saved posts and digests; None is UTC
+    pub timezone: Option<String>,
+    /// When the last digest went out (or was skipped for having no saves)
+    pub last_digest_at: Option<DateTime<Utc>>,
+    /// The one-time import of existing bookmarks has finished
+    pub backfilled: bool,
+    /// Bookmarks found by the running import; None before it starts
+    pub backfill_total: Option<i32>,
+    /// Bookmarks the running import has worked through
+    pub backfill_done: i32,
+    /// Per-post-class save overrides, as `class=target[:category]` entries
+    pub post_class_rules: Vec<String>,
+    /// Where single posts are saved (`highlight` or `document`); None is highlights
+    pub single_post_target: Option<String>,
+    /// Don't auto-save bookmarks carrying any of `skip_labels`
+    pub skip_labeled: bool,
+    /// Labels to skip; empty uses the built-in adult/graphic content labels
+    pub skip_labels: Vec<String>,
+    /// Add the post's like/repost/reply counts to saves
+    pub include_engagement: bool,
+}
+
+/// A timezone name, falling back to UTC if unset or unknown
+fn timezone_or_utc(name: Option<&str>) -> Tz {
+    name.and_then(|name| name.parse().ok()).unwrap_or(Tz::UTC)
+}
+
+impl UserSettings {
+    /// The user's timezone, falling back to UTC if unset or unknown
+    pub fn local_timezone(&self) -> Tz {
+        timezone_or_utc(self.timezone.as_deref())
+    }
+
+    /// The user's per-post-class save overrides
+    pub fn class_rules(&self) -> PostClassRules {
+        PostClassRules::from_entries(&self.post_class_rules)
+    }
+
+    /// Where the user wants single posts saved, if they chose
+    pub fn single_post_target(&self) -> Option<SaveTarget> {
+        self.single_post_target
+            .as_deref()
+            .and_then(SaveTarget::parse)
+    }
+
+    /// Labels whose bookmarks shouldn't be auto-saved (empty when not skipping)
+    pub fn labels_to_skip(&self) -> Vec<String> {
+        if !self.skip_labeled {
+            Vec::new()
+        } else if self.skip_labels.is_empty() {
+            ADULT_CONTENT_LABELS.iter().map(|l| l.to_string()).collect()
+        } else {
+            self.skip_labels.clone()
+        }
+    }
+
+    /// `(done, total)` while the bookmark import is running
+    pub fn backfill_progress(&self) -> Option<(i32, i32)> {
+        match self.backfill_total {
+            Some(total) if !self.backfilled => Some((self.backfill_done, total)),
+            _ => None,
+        }
+    }
+}
+
+/// Changes to a user's settings; `None` fields keep their current value
+///
+/// Nullable columns take `Some(None)` to clear them.
+#[derive(Debug, Clone, Default)]
+pub struct SettingsUpdate {
+    pub readwise_token: Option<String>,
+    pub bookmark_sync_enabled: Option<bool>,
+    pub extract_links: Option<bool>,
+    pub author_allowlist: Option<Vec<String>>,
+    pub author_denylist: Option<Vec<String>>,
+    pub min_post_length: Option<i32>,
+    pub poll_interval_secs: Option<Option<i32>>,
+    pub highlight_category: Option<Option<String>>,
+    pub save_image_alt_text: Option<bool>,
+    pub include_engagement: Option<bool>,
+    pub daily_digest: Option<bool>,
+    pub timezone: Option<Option<String>>,
+    pub post_class_rules: Option<Vec<String>>,
+    pub skip_labeled: Option<bool>,
+    pub skip_labels: Option<Vec<String>>,
 }
 
 /// A processed bookmark (for deduplication)
@@ -43,11 +168,75 @@ pub struct ProcessedBookmark {
     pub processed_at: DateTime<Utc>,
 }
 
+/// A bookmark save that failed and is queued for retry
+#[derive(Debug, Clone, sqlx::FromRow)]
+pub struct FailedSave {
+    pub id: Uuid,
+    pub user_id: Uuid,
+    pub post_uri: String,
+    /// Most recent error
+    pub error: String,
+    pub attempts: i32,
+    pub next_attempt_at: DateTime<Utc>,
+    /// Retries exhausted; kept for inspection
+    pub permanently_failed: bool,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+/// One save attempt from the `save_events` audit trail
+#[derive(Debug, Clone, Serialize, sqlx::FromRow)]
+pub struct SaveEvent {
+    pub id: Uuid,
+    #[serde(skip)]
+    pub user_id: Uuid,
+    pub post_uri: String,
+    /// What was saved (`highlight`, `document`, `link`, `alt_text`, ...);
+    /// None when saving the post itself failed
+    pub kind: Option<String>,
+    /// `saved`, `skipped`, or `failed`
+    pub status: String,
+    pub readwise_id: Option<String>,
+    pub error: Option<String>,
+    /// The link a `link` event saved
+    pub url: Option<String>,
+    pub at: DateTime<Utc>,
+}
+
+/// A save attempt to append to `save_events`
+#[derive(Debug, Clone, Copy, Default)]
+pub struct NewSaveEvent<'a> {
+    pub post_uri: &'a str,
+    pub kind: Option<&'a str>,
+    pub status: &'a str,
+    pub readwise_id: Option<&'a str>,
+    pub error: Option<&'a str>,
+    pub url: Option<&'a str>,
+}
+
+/// A user opted in to the daily digest, with what scheduling it needs
+#[derive(Debug, Clone, sqlx::FromRow)]
+pub struct DigestSubscriber {
+    pub user_id: Uuid,
+    pub bluesky_did: String,
+    pub timezone: Option<String>,
+    pub last_digest_at: Option<DateTime<Utc>>,
+    /// Set after a failed send; no retry before then
+    pub digest_retry_at: Option<DateTime<Utc>>,
+}
+
+impl DigestSubscriber {
+    /// The user's timezone, falling back to UTC if unset or unknown
+    pub fn local_timezone(&self) -> Tz {
+        timezone_or_utc(self.timezone.as_deref())
+    }
+}
+
 /// A processed DM
 #[derive(Debug, Clone, sqlx::FromRow)]
 pub struct ProcessedDm {
     pub id: Uuid,
-    pub user_id: Uuid,
+    pub user_id: Option<Uuid>,
     pub message_id: String,
     pub post_uri: Option<String>,
     pub status: String,
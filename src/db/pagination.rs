@@ -0,0 +1,131 @@
+//! Keyset pagination
+//!
+//! History queries page on a `(timestamp, id)` key rather than OFFSET, so
+//! new rows don't shift pages. Cursors are opaque URL-safe base64 strings.
+
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine};
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use thiserror::Error;
+use uuid::Uuid;
+
+/// Largest page a caller may ask for
+pub const MAX_PAGE_SIZE: i64 = 100;
+
+/// Cursor decoding errors
+#[derive(Error, Debug, PartialEq, Eq)]
+pub enum CursorError {
+    #[error("Invalid page cursor")]
+    Invalid,
+}
+
+/// Position of a row in `(timestamp, id)` order
+///
+/// `processed_at` holds whichever timestamp the query orders by.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Cursor {
+    pub processed_at: DateTime<Utc>,
+    pub id: Uuid,
+}
+
+/// Encode a cursor as an opaque string
+pub fn encode_cursor(cursor: &Cursor) -> String {
+    URL_SAFE_NO_PAD.encode(format!(
+        "{}:{}",
+        cursor.processed_at.timestamp_micros(),
+        cursor.id
+    ))
+}
+
+/// Decode a cursor produced by `encode_cursor`
+pub fn decode_cursor(encoded: &str) -> Result<Cursor, CursorError> {
+    let bytes = URL_SAFE_NO_PAD
+        .decode(encoded.trim())
+        .map_err(|_| CursorError::Invalid)?;
+    let decoded = String::from_utf8(bytes).map_err(|_| CursorError::Invalid)?;
+    let (micros, id) = decoded.split_once(':').ok_or(CursorError::Invalid)?;
+
+    let micros: i64 = micros.parse().map_err(|_| CursorError::Invalid)?;
+    Ok(Cursor {
+        processed_at: DateTime::from_timestamp_micros(micros).ok_or(CursorError::Invalid)?,
+        id: id.parse().map_err(|_| CursorError::Invalid)?,
+    })
+}
+
+/// Which page of newest-first results to fetch
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PageRequest {
+    /// The newest rows
+    First,
+    /// Rows older than the cursor
+    Before(Cursor),
+    /// Rows newer than the cursor
+    After(Cursor),
+}
+
+/// One page of newest-first results
+#[derive(Debug, Clone, Serialize)]
+pub struct Page<T> {
+    pub items: Vec<T>,
+    /// Cursor for the next (older) page, if there is one
+    pub before: Option<String>,
+    /// Cursor for the previous (newer) page, if there is one
+    pub after: Option<String>,
+}
+
+/// Turn rows fetched for `page` into a `Page`
+///
+/// `rows` holds up to `limit + 1` rows in fetch order: newest first, or
+/// oldest first for `After`. The extra row only signals another page.
+pub fn into_page<T>(
+    mut rows: Vec<T>,
+    page: PageRequest,
+    limit: i64,
+    cursor: impl Fn(&T) -> Cursor,
+) -> Page<T> {
+    let has_more = rows.len() as i64 > limit;
+    rows.truncate(limit as usize);
+    if matches!(page, PageRequest::After(_)) {
+        rows.reverse();
+    }
+
+    let (older, newer) = match page {
+        PageRequest::First => (has_more, false),
+        PageRequest::Before(_) => (has_more, true),
+        PageRequest::After(_) => (true, has_more),
+    };
+    let encode = |row: Option<&T>| row.map(|row| encode_cursor(&cursor(row)));
+
+    Page {
+        before: encode(rows.last().filter(|_| older)),
+        after: encode(rows.first().filter(|_| newer)),
+        items: rows,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_cursor_round_trip() {
+        let cursor = Cursor {
+            processed_at: DateTime::from_timestamp_micros(1_700_000_000_123_456).unwrap(),
+            id: Uuid::new_v4(),
+        };
+        assert_eq!(decode_cursor(&encode_cursor(&cursor)), Ok(cursor));
+    }
+
+    #[test]
+    fn test_invalid_cursor_rejected() {
+        assert_eq!(decode_cursor("not base64!"), Err(CursorError::Invalid));
+        assert_eq!(
+            decode_cursor(&URL_SAFE_NO_PAD.encode("123:not-a-uuid")),
+            Err(CursorError::Invalid)
+        );
+        assert_eq!(
+            decode_cursor(&URL_SAFE_NO_PAD.encode("nocolon")),
+            Err(CursorError::Invalid)
+        );
+    }
+}
@@ -1,23 +1,105 @@
 //! Database queries
-//!
-//! TODO: Implement database operations
 
-use anyhow::Result;
-use sqlx::PgPool;
+use std::time::Duration;
+
+use anyhow::{Context, Result};
+use chrono::{DateTime, Utc};
+use sqlx::postgres::{PgConnectOptions, PgPoolOptions};
+use sqlx::{PgPool, Postgres, QueryBuilder};
 use uuid::Uuid;
 
 use super::models::*;
+use super::pagination::{into_page, Cursor, Page, PageRequest, MAX_PAGE_SIZE};
+use crate::crypto::{self, EncryptionKey};
+use crate::services::post_class::SaveTarget;
+
+/// Connection pool settings
+#[derive(Debug, Clone)]
+pub struct PoolConfig {
+    /// Maximum number of open connections
+    pub max_connections: u32,
+    /// How long to wait for a free connection
+    pub acquire_timeout: Duration,
+    /// How long an idle connection is kept before closing
+    pub idle_timeout: Duration,
+}
+
+impl Default for PoolConfig {
+    fn default() -> Self {
+        Self {
+            max_connections: 10,
+            acquire_timeout: Duration::from_secs(5),
+            idle_timeout: Duration::from_secs(600),
+        }
+    }
+}
+
+impl PoolConfig {
+    fn pool_options(&self) -> PgPoolOptions {
+        PgPoolOptions::new()
+            .max_connections(self.max_connections)
+            .acquire_timeout(self.acquire_timeout)
+            .idle_timeout(self.idle_timeout)
+    }
+}
 
 /// Database operations
+#[derive(Clone)]
 pub struct Database {
     pool: PgPool,
+    /// Key for tokens encrypted at rest
+    key: EncryptionKey,
 }
 
 impl Database {
     /// Create a new database connection pool
-    pub async fn connect(database_url: &str) -> Result<Self> {
-        let pool = PgPool::connect(database_url).await?;
-        Ok(Self { pool })
+    pub async fn connect(
+        database_url: &str,
+        key: EncryptionKey,
+        pool_config: &PoolConfig,
+    ) -> Result<Self> {
+        let options: PgConnectOptions = database_url.parse().context("Invalid database URL")?;
+        Self::connect_with(options, key, pool_config).await
+    }
+
+    /// Create a new database connection pool from parsed connect options
+    ///
+    /// Runs a health probe so a bad URL fails at startup rather than on first use.
+    pub async fn connect_with(
+        options: PgConnectOptions,
+        key: EncryptionKey,
+        pool_config: &PoolConfig,
+    ) -> Result<Self> {
+        let pool = pool_config.pool_options().connect_with(options).await?;
+        let db = Self::new(pool, key);
+        db.ping().await.context("Database health check failed")?;
+        Ok(db)
+    }
+
+    /// Check the database is reachable
+    pub async fn ping(&self) -> Result<()> {
+        sqlx::query("SELECT 1").execute(&self.pool).await?;
+        Ok(())
+    }
+
+    /// Wrap an existing connection pool
+    pub fn new(pool: PgPool, key: EncryptionKey) -> Self {
+        Self { pool, key }
+    }
+
+    /// Underlying connection pool (shared with the session store)
+    pub fn pool(&self) -> &PgPool {
+        &self.pool
+    }
+
+    /// Encrypt a secret before writing it
+    fn seal(&self, plaintext: &str) -> Result<String> {
+        Ok(crypto::encrypt(plaintext, &self.key)?)
+    }
+
+    /// Decrypt a secret after reading it
+    fn open(&self, ciphertext: &str) -> Result<String> {
+        Ok(crypto::decrypt(ciphertext, &self.key)?)
     }
 
     /// Run database migrations
@@ -26,6 +108,53 @@ impl Database {
         Ok(())
     }
 
+    /// Create a new user
+    pub async fn create_user(&self, did: &str, handle: &str) -> Result<User> {
+        let user = sqlx::query_as::<_, User>(
+            "INSERT INTO users (bluesky_did, bluesky_handle) VALUES ($1, $2) RETURNING *",
+        )
+        .bind(did)
+        .bind(handle)
+        .fetch_one(&self.pool)
+        .await?;
+        Ok(user)
+    }
+
+    /// Create a user, or update the handle of an existing user with this DID
+    pub async fn upsert_user(&self, did: &str, handle: &str) -> Result<User> {
+        let user = sqlx::query_as::<_, User>(
+            r#"
+            INSERT INTO users (bluesky_did, bluesky_handle) VALUES ($1, $2)
+            ON CONFLICT (bluesky_did) DO UPDATE SET bluesky_handle = EXCLUDED.bluesky_handle
+            RETURNING *
+            "#,
+        )
+        .bind(did)
+        .bind(handle)
+        .fetch_one(&self.pool)
+        .await?;
+        Ok(user)
+    }
+
+    /// Store a user's new handle (the DID never changes)
+    pub async fn update_user_handle(&self, user_id: Uuid, handle: &str) -> Result<()> {
+        sqlx::query("UPDATE users SET bluesky_handle = $1 WHERE id = $2")
+            .bind(handle)
+            .bind(user_id)
+            .execute(&self.pool)
+            .await?;
+        Ok(())
+    }
+
+    /// Get a user by their ID
+    pub async fn get_user_by_id(&self, id: Uuid) -> Result<Option<User>> {
+        let user = sqlx::query_as::<_, User>("SELECT * FROM users WHERE id = $1")
+            .bind(id)
+            .fetch_optional(&self.pool)
+            .await?;
+        Ok(user)
+    }
+
     /// Get a user by their Bluesky DID
     pub async fn get_user_by_did(&self, did: &str) -> Result<Option<User>> {
         let user = sqlx::query_as::<_, User>("SELECT * FROM users WHERE bluesky_did = $1")
@@ -42,9 +171,331 @@ impl Database {
                 .bind(user_id)
                 .fetch_optional(&self.pool)
                 .await?;
+
+        settings
+            .map(|mut s| {
+                s.readwise_token = self.open(&s.readwise_token)?;
+                Ok(s)
+            })
+            .transpose()
+    }
+
+    /// Create settings for a user, replacing any existing settings
+    pub async fn create_user_settings(
+        &self,
+        user_id: Uuid,
+        readwise_token: &str,
+        bookmark_sync_enabled: bool,
+        extract_links: bool,
+    ) -> Result<UserSettings> {
+        let mut settings = sqlx::query_as::<_, UserSettings>(
+            r#"
+            INSERT INTO user_settings (user_id, readwise_token, bookmark_sync_enabled, extract_links)
+            VALUES ($1, $2, $3, $4)
+            ON CONFLICT (user_id) DO UPDATE SET
+                readwise_token = EXCLUDED.readwise_token,
+                bookmark_sync_enabled = EXCLUDED.bookmark_sync_enabled,
+                extract_links = EXCLUDED.extract_links,
+                updated_at = NOW()
+            RETURNING *
+            "#,
+        )
+        .bind(user_id)
+        .bind(self.seal(readwise_token)?)
+        .bind(bookmark_sync_enabled)
+        .bind(extract_links)
+        .fetch_one(&self.pool)
+        .await?;
+
+        settings.readwise_token = readwise_token.to_string();
         Ok(settings)
     }
 
+    /// Replace a user's Readwise token, leaving every other setting alone
+    ///
+    /// Returns false if the user has no settings yet.
+    pub async fn set_readwise_token(&self, user_id: Uuid, readwise_token: &str) -> Result<bool> {
+        let result = sqlx::query(
+            "UPDATE user_settings SET readwise_token = $2, updated_at = NOW() WHERE user_id = $1",
+        )
+        .bind(user_id)
+        .bind(self.seal(readwise_token)?)
+        .execute(&self.pool)
+        .await?;
+
+        Ok(result.rows_affected() > 0)
+    }
+
+    /// Set (or clear, with None) where a user's single posts are saved
+    pub async fn set_single_post_target(
+        &self,
+        user_id: Uuid,
+        target: Option<SaveTarget>,
+    ) -> Result<()> {
+        sqlx::query(
+            "UPDATE user_settings SET single_post_target = $2, updated_at = NOW() WHERE user_id = $1",
+        )
+        .bind(user_id)
+        .bind(target.map(SaveTarget::as_str))
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    /// Users opted in to the daily digest
+    pub async fn digest_subscribers(&self) -> Result<Vec<DigestSubscriber>> {
+        let subscribers = sqlx::query_as::<_, DigestSubscriber>(
+            r#"
+            SELECT s.user_id, u.bluesky_did, s.timezone, s.last_digest_at, s.digest_retry_at
+            FROM user_settings s
+            JOIN users u ON u.id = s.user_id
+            WHERE s.daily_digest
+            ORDER BY s.user_id
+            "#,
+        )
+        .fetch_all(&self.pool)
+        .await?;
+        Ok(subscribers)
+    }
+
+    /// Record that a user's digest for today has been handled
+    pub async fn mark_digest_sent(&self, user_id: Uuid, at: DateTime<Utc>) -> Result<()> {
+        sqlx::query(
+            "UPDATE user_settings SET last_digest_at = $2, digest_retry_at = NULL WHERE user_id = $1",
+        )
+        .bind(user_id)
+        .bind(at)
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    /// Hold off a user's failed digest until `retry_at`
+    pub async fn defer_digest(&self, user_id: Uuid, retry_at: DateTime<Utc>) -> Result<()> {
+        sqlx::query("UPDATE user_settings SET digest_retry_at = $2 WHERE user_id = $1")
+            .bind(user_id)
+            .bind(retry_at)
+            .execute(&self.pool)
+            .await?;
+
+        Ok(())
+    }
+
+    /// Start a user's bookmark import of `total` bookmarks
+    pub async fn start_backfill(&self, user_id: Uuid, total: i32) -> Result<()> {
+        sqlx::query(
+            "UPDATE user_settings SET backfill_total = $2, backfill_done = 0 WHERE user_id = $1",
+        )
+        .bind(user_id)
+        .bind(total)
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    /// Record how many bookmarks the running import has worked through
+    pub async fn set_backfill_progress(&self, user_id: Uuid, done: i32) -> Result<()> {
+        sqlx::query("UPDATE user_settings SET backfill_done = $2 WHERE user_id = $1")
+            .bind(user_id)
+            .bind(done)
+            .execute(&self.pool)
+            .await?;
+
+        Ok(())
+    }
+
+    /// Mark a user's bookmark import finished so it never runs again
+    pub async fn finish_backfill(&self, user_id: Uuid) -> Result<()> {
+        sqlx::query("UPDATE user_settings SET backfilled = TRUE WHERE user_id = $1")
+            .bind(user_id)
+            .execute(&self.pool)
+            .await?;
+
+        Ok(())
+    }
+
+    /// Apply the fields set in `update` in one statement, leaving every
+    /// other column (the bookmark cursor included) as it is
+    ///
+    /// Returns `None` if the user has no settings yet.
+    pub async fn update_user_settings(
+        &self,
+        user_id: Uuid,
+        update: &SettingsUpdate,
+    ) -> Result<Option<UserSettings>> {
+        let mut query =
+            QueryBuilder::<Postgres>::new("UPDATE user_settings SET updated_at = NOW()");
+        if let Some(token) = &update.readwise_token {
+            query
+                .push(", readwise_token = ")
+                .push_bind(self.seal(token)?);
+        }
+        if let Some(enabled) = update.bookmark_sync_enabled {
+            query.push(", bookmark_sync_enabled = ").push_bind(enabled);
+        }
+        if let Some(enabled) = update.extract_links {
+            query.push(", extract_links = ").push_bind(enabled);
+        }
+        if let Some(allowlist) = &update.author_allowlist {
+            query.push(", author_allowlist = ").push_bind(allowlist);
+        }
+        if let Some(denylist) = &update.author_denylist {
+            query.push(", author_denylist = ").push_bind(denylist);
+        }
+        if let Some(min_post_length) = update.min_post_length {
+            query
+                .push(", min_post_length = ")
+                .push_bind(min_post_length);
+        }
+        if let Some(poll_interval_secs) = update.poll_interval_secs {
+            query
+                .push(", poll_interval_secs = ")
+                .push_bind(poll_interval_secs);
+        }
+        if let Some(category) = &update.highlight_category {
+            query.push(", highlight_category = ").push_bind(category);
+        }
+        if let Some(enabled) = update.save_image_alt_text {
+            query.push(", save_image_alt_text = ").push_bind(enabled);
+        }
+        if let Some(enabled) = update.include_engagement {
+            query.push(", include_engagement = ").push_bind(enabled);
+        }
+        if let Some(enabled) = update.daily_digest {
+            query.push(", daily_digest = ").push_bind(enabled);
+        }
+        if let Some(timezone) = &update.timezone {
+            query.push(", timezone = ").push_bind(timezone);
+        }
+        if let Some(rules) = &update.post_class_rules {
+            query.push(", post_class_rules = ").push_bind(rules);
+        }
+        if let Some(enabled) = update.skip_labeled {
+            query.push(", skip_labeled = ").push_bind(enabled);
+        }
+        if let Some(labels) = &update.skip_labels {
+            query.push(", skip_labels = ").push_bind(labels);
+        }
+        query
+            .push(" WHERE user_id = ")
+            .push_bind(user_id)
+            .push(" RETURNING *");
+
+        let settings = query
+            .build_query_as::<UserSettings>()
+            .fetch_optional(&self.pool)
+            .await?;
+
+        settings
+            .map(|mut s| {
+                s.readwise_token = self.open(&s.readwise_token)?;
+                Ok(s)
+            })
+            .transpose()
+    }
+
+    /// Store OAuth tokens for a user, replacing any existing tokens
+    ///
+    /// `dpop_key` is the key the tokens are bound to, kept for refreshes.
+    pub async fn store_tokens(
+        &self,
+        user_id: Uuid,
+        access_token: &str,
+        refresh_token: Option<&str>,
+        expires_at: Option<DateTime<Utc>>,
+        dpop_key: Option<&str>,
+    ) -> Result<()> {
+        let refresh_token = refresh_token.map(|t| self.seal(t)).transpose()?;
+        let dpop_key = dpop_key.map(|k| self.seal(k)).transpose()?;
+
+        sqlx::query(
+            r#"
+            INSERT INTO user_tokens (user_id, access_token, refresh_token, expires_at, dpop_key)
+            VALUES ($1, $2, $3, $4, $5)
+            ON CONFLICT (user_id) DO UPDATE SET
+                access_token = EXCLUDED.access_token,
+                refresh_token = EXCLUDED.refresh_token,
+                expires_at = EXCLUDED.expires_at,
+                dpop_key = EXCLUDED.dpop_key,
+                updated_at = NOW()
+            "#,
+        )
+        .bind(user_id)
+        .bind(self.seal(access_token)?)
+        .bind(refresh_token)
+        .bind(expires_at)
+        .bind(dpop_key)
+        .execute(&self.pool)
+        .await?;
+        Ok(())
+    }
+
+    /// Flag a user whose tokens were rejected and turn off their bookmark sync
+    pub async fn mark_needs_reauth(&self, user_id: Uuid) -> Result<()> {
+        let mut tx = self.pool.begin().await?;
+
+        sqlx::query("UPDATE users SET needs_reauth = TRUE WHERE id = $1")
+            .bind(user_id)
+            .execute(&mut *tx)
+            .await?;
+        sqlx::query(
+            "UPDATE user_settings SET bookmark_sync_enabled = FALSE, updated_at = NOW() WHERE user_id = $1",
+        )
+        .bind(user_id)
+        .execute(&mut *tx)
+        .await?;
+
+        tx.commit().await?;
+        Ok(())
+    }
+
+    /// Clear the reconnect flag after a successful login
+    pub async fn clear_needs_reauth(&self, user_id: Uuid) -> Result<()> {
+        sqlx::query("UPDATE users SET needs_reauth = FALSE WHERE id = $1")
+            .bind(user_id)
+            .execute(&self.pool)
+            .await?;
+        Ok(())
+    }
+
+    /// Get a user's OAuth tokens
+    pub async fn get_tokens(&self, user_id: Uuid) -> Result<Option<UserToken>> {
+        let tokens = sqlx::query_as::<_, UserToken>("SELECT * FROM user_tokens WHERE user_id = $1")
+            .bind(user_id)
+            .fetch_optional(&self.pool)
+            .await?;
+
+        tokens
+            .map(|mut t| {
+                t.access_token = self.open(&t.access_token)?;
+                t.refresh_token = t.refresh_token.map(|r| self.open(&r)).transpose()?;
+                t.dpop_key = t.dpop_key.map(|k| self.open(&k)).transpose()?;
+                Ok(t)
+            })
+            .transpose()
+    }
+
+    /// Update a user's access token (e.g., after refresh)
+    pub async fn update_access_token(
+        &self,
+        user_id: Uuid,
+        access_token: &str,
+        expires_at: Option<DateTime<Utc>>,
+    ) -> Result<()> {
+        sqlx::query(
+            "UPDATE user_tokens SET access_token = $2, expires_at = $3, updated_at = NOW() WHERE user_id = $1",
+        )
+        .bind(user_id)
+        .bind(self.seal(access_token)?)
+        .bind(expires_at)
+        .execute(&self.pool)
+        .await?;
+        Ok(())
+    }
+
     /// Check if a bookmark has been processed
     pub async fn is_bookmark_processed(&self, user_id: Uuid, post_uri: &str) -> Result<bool> {
         let result = sqlx::query_scalar::<_, bool>(
@@ -56,4 +507,1100 @@ impl Database {
         .await?;
         Ok(result)
     }
+
+    /// Record a bookmark as processed (no-op if already recorded)
+    ///
+    /// Returns true only for the call that inserted the row, so concurrent
+    /// polls of the same bookmark agree on a single winner.
+    pub async fn mark_bookmark_processed(&self, user_id: Uuid, post_uri: &str) -> Result<bool> {
+        let inserted = sqlx::query_scalar::<_, Uuid>(
+            r#"
+            INSERT INTO processed_bookmarks (user_id, post_uri)
+            VALUES ($1, $2)
+            ON CONFLICT (user_id, post_uri) DO NOTHING
+            RETURNING id
+            "#,
+        )
+        .bind(user_id)
+        .bind(post_uri)
+        .fetch_optional(&self.pool)
+        .await?;
+        Ok(inserted.is_some())
+    }
+
+    /// Forget that a bookmark was processed, so it's saved again; returns
+    /// whether there was a record
+    pub async fn unmark_bookmark_processed(&self, user_id: Uuid, post_uri: &str) -> Result<bool> {
+        let result =
+            sqlx::query("DELETE FROM processed_bookmarks WHERE user_id = $1 AND post_uri = $2")
+                .bind(user_id)
+                .bind(post_uri)
+                .execute(&self.pool)
+                .await?;
+        Ok(result.rows_affected() > 0)
+    }
+
+    /// Count all bookmarks processed for a user
+    pub async fn processed_bookmark_count(&self, user_id: Uuid) -> Result<i64> {
+        let count = sqlx::query_scalar::<_, i64>(
+            "SELECT COUNT(*) FROM processed_bookmarks WHERE user_id = $1",
+        )
+        .bind(user_id)
+        .fetch_one(&self.pool)
+        .await?;
+        Ok(count)
+    }
+
+    /// Every user with sync status and activity totals, oldest first
+    pub async fn list_users_with_stats(&self) -> Result<Vec<UserWithStats>> {
+        let users = sqlx::query_as::<_, UserWithStats>(
+            r#"
+            SELECT u.id, u.bluesky_did, u.bluesky_handle, u.created_at, u.needs_reauth,
+                   s.bookmark_sync_enabled,
+                   GREATEST(b.last_at, d.last_at) AS last_activity_at,
+                   COALESCE(b.total, 0) AS bookmarks_processed,
+                   COALESCE(d.total, 0) AS dms_processed,
+                   COALESCE(f.total, 0) AS failed_saves_pending
+            FROM users u
+            LEFT JOIN user_settings s ON s.user_id = u.id
+            LEFT JOIN (
+                SELECT user_id, COUNT(*) AS total, MAX(processed_at) AS last_at
+                FROM processed_bookmarks GROUP BY user_id
+            ) b ON b.user_id = u.id
+            LEFT JOIN (
+                SELECT user_id, COUNT(*) AS total, MAX(processed_at) AS last_at
+                FROM processed_dms WHERE user_id IS NOT NULL GROUP BY user_id
+            ) d ON d.user_id = u.id
+            LEFT JOIN (
+                SELECT user_id, COUNT(*) AS total
+                FROM failed_saves WHERE NOT permanently_failed GROUP BY user_id
+            ) f ON f.user_id = u.id
+            ORDER BY u.created_at, u.id
+            "#,
+        )
+        .fetch_all(&self.pool)
+        .await?;
+        Ok(users)
+    }
+
+    /// Count bookmarks saved for a user since midnight UTC
+    pub async fn saves_today(&self, user_id: Uuid) -> Result<i64> {
+        let midnight = Utc::now()
+            .date_naive()
+            .and_time(chrono::NaiveTime::MIN)
+            .and_utc();
+        self.saves_since(user_id, midnight).await
+    }
+
+    /// Count bookmarks saved for a user since `since`
+    pub async fn saves_since(&self, user_id: Uuid, since: DateTime<Utc>) -> Result<i64> {
+        let count = sqlx::query_scalar::<_, i64>(
+            "SELECT COUNT(*) FROM processed_bookmarks WHERE user_id = $1 AND processed_at >= $2",
+        )
+        .bind(user_id)
+        .bind(since)
+        .fetch_one(&self.pool)
+        .await?;
+        Ok(count)
+    }
+
+    /// When the user's most recent bookmark was processed, if ever
+    pub async fn last_bookmark_processed_at(&self, user_id: Uuid) -> Result<Option<DateTime<Utc>>> {
+        let last = sqlx::query_scalar::<_, Option<DateTime<Utc>>>(
+            "SELECT MAX(processed_at) FROM processed_bookmarks WHERE user_id = $1",
+        )
+        .bind(user_id)
+        .fetch_one(&self.pool)
+        .await?;
+        Ok(last)
+    }
+
+    /// A page of a user's processed bookmarks, newest first
+    pub async fn recent_saves(
+        &self,
+        user_id: Uuid,
+        page: PageRequest,
+        limit: i64,
+    ) -> Result<Page<ProcessedBookmark>> {
+        let limit = limit.clamp(1, MAX_PAGE_SIZE);
+        // One extra row tells us whether there's another page
+        let fetch = limit + 1;
+
+        let rows = match page {
+            PageRequest::First => {
+                sqlx::query_as::<_, ProcessedBookmark>(
+                    r#"
+                    SELECT * FROM processed_bookmarks
+                    WHERE user_id = $1
+                    ORDER BY processed_at DESC, id DESC
+                    LIMIT $2
+                    "#,
+                )
+                .bind(user_id)
+                .bind(fetch)
+                .fetch_all(&self.pool)
+                .await?
+            }
+            PageRequest::Before(cursor) => {
+                sqlx::query_as::<_, ProcessedBookmark>(
+                    r#"
+                    SELECT * FROM processed_bookmarks
+                    WHERE user_id = $1 AND (processed_at, id) < ($2, $3)
+                    ORDER BY processed_at DESC, id DESC
+                    LIMIT $4
+                    "#,
+                )
+                .bind(user_id)
+                .bind(cursor.processed_at)
+                .bind(cursor.id)
+                .bind(fetch)
+                .fetch_all(&self.pool)
+                .await?
+            }
+            PageRequest::After(cursor) => {
+                sqlx::query_as::<_, ProcessedBookmark>(
+                    r#"
+                    SELECT * FROM processed_bookmarks
+                    WHERE user_id = $1 AND (processed_at, id) > ($2, $3)
+                    ORDER BY processed_at ASC, id ASC
+                    LIMIT $4
+                    "#,
+                )
+                .bind(user_id)
+                .bind(cursor.processed_at)
+                .bind(cursor.id)
+                .bind(fetch)
+                .fetch_all(&self.pool)
+                .await?
+            }
+        };
+
+        Ok(into_page(rows, page, limit, |row| Cursor {
+            processed_at: row.processed_at,
+            id: row.id,
+        }))
+    }
+
+    /// Queue a failed bookmark save for retry (no-op if already queued)
+    pub async fn record_failed_save(
+        &self,
+        user_id: Uuid,
+        post_uri: &str,
+        error: &str,
+        next_attempt_at: DateTime<Utc>,
+    ) -> Result<()> {
+        sqlx::query(
+            r#"
+            INSERT INTO failed_saves (user_id, post_uri, error, next_attempt_at)
+            VALUES ($1, $2, $3, $4)
+            ON CONFLICT (user_id, post_uri) DO NOTHING
+            "#,
+        )
+        .bind(user_id)
+        .bind(post_uri)
+        .bind(error)
+        .bind(next_attempt_at)
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    /// Check if a bookmark save is queued for retry or has given up
+    pub async fn has_failed_save(&self, user_id: Uuid, post_uri: &str) -> Result<bool> {
+        let exists = sqlx::query_scalar::<_, bool>(
+            "SELECT EXISTS(SELECT 1 FROM failed_saves WHERE user_id = $1 AND post_uri = $2)",
+        )
+        .bind(user_id)
+        .bind(post_uri)
+        .fetch_one(&self.pool)
+        .await?;
+
+        Ok(exists)
+    }
+
+    /// Failed saves whose next attempt is due, oldest first
+    ///
+    /// Users who've turned bookmark sync off are left out until they turn it
+    /// back on.
+    pub async fn due_failed_saves(&self, limit: i64) -> Result<Vec<FailedSave>> {
+        let saves = sqlx::query_as::<_, FailedSave>(
+            r#"
+            SELECT f.* FROM failed_saves f
+            JOIN user_settings s ON s.user_id = f.user_id
+            WHERE NOT f.permanently_failed
+              AND f.next_attempt_at <= NOW()
+              AND s.bookmark_sync_enabled
+            ORDER BY f.next_attempt_at
+            LIMIT $1
+            "#,
+        )
+        .bind(limit)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(saves)
+    }
+
+    /// Record another failed attempt and when to try next
+    pub async fn reschedule_failed_save(
+        &self,
+        id: Uuid,
+        error: &str,
+        next_attempt_at: DateTime<Utc>,
+    ) -> Result<()> {
+        sqlx::query(
+            r#"
+            UPDATE failed_saves SET
+                error = $2,
+                attempts = attempts + 1,
+                next_attempt_at = $3,
+                updated_at = NOW()
+            WHERE id = $1
+            "#,
+        )
+        .bind(id)
+        .bind(error)
+        .bind(next_attempt_at)
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    /// Record a final failed attempt and stop retrying
+    pub async fn give_up_failed_save(&self, id: Uuid, error: &str) -> Result<()> {
+        sqlx::query(
+            r#"
+            UPDATE failed_saves SET
+                error = $2,
+                attempts = attempts + 1,
+                permanently_failed = TRUE,
+                updated_at = NOW()
+            WHERE id = $1
+            "#,
+        )
+        .bind(id)
+        .bind(error)
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    /// Drop a failed save once it has gone through
+    pub async fn delete_failed_save(&self, id: Uuid) -> Result<()> {
+        sqlx::query("DELETE FROM failed_saves WHERE id = $1")
+            .bind(id)
+            .execute(&self.pool)
+            .await?;
+
+        Ok(())
+    }
+
+    /// Append a save attempt to the audit trail
+    pub async fn record_save_event(&self, user_id: Uuid, event: NewSaveEvent<'_>) -> Result<()> {
+        sqlx::query(
+            r#"
+            INSERT INTO save_events (user_id, post_uri, kind, status, readwise_id, error, url)
+            VALUES ($1, $2, $3, $4, $5, $6, $7)
+            "#,
+        )
+        .bind(user_id)
+        .bind(event.post_uri)
+        .bind(event.kind)
+        .bind(event.status)
+        .bind(event.readwise_id)
+        .bind(event.error)
+        .bind(event.url)
+        .execute(&self.pool)
+        .await?;
+        Ok(())
+    }
+
+    /// Distinct posts saved for a user since `since`, by bookmark or DM
+    ///
+    /// Links and alt text saved alongside a post don't count separately.
+    pub async fn saved_posts_since(&self, user_id: Uuid, since: DateTime<Utc>) -> Result<i64> {
+        let count = sqlx::query_scalar::<_, i64>(
+            r#"
+            SELECT COUNT(DISTINCT post_uri) FROM save_events
+            WHERE user_id = $1 AND status = 'saved' AND at >= $2
+                AND kind NOT IN ('link', 'alt_text')
+            "#,
+        )
+        .bind(user_id)
+        .bind(since)
+        .fetch_one(&self.pool)
+        .await?;
+        Ok(count)
+    }
+
+    /// The latest save of each post saved since `since`, newest first
+    pub async fn latest_saves_since(
+        &self,
+        user_id: Uuid,
+        since: DateTime<Utc>,
+        limit: i64,
+    ) -> Result<Vec<SaveEvent>> {
+        let events = sqlx::query_as::<_, SaveEvent>(
+            r#"
+            SELECT * FROM (
+                SELECT DISTINCT ON (post_uri) * FROM save_events
+                WHERE user_id = $1 AND status = 'saved' AND at >= $2
+                    AND kind NOT IN ('link', 'alt_text')
+                ORDER BY post_uri, at DESC
+            ) latest
+            ORDER BY at DESC, id DESC
+            LIMIT $3
+            "#,
+        )
+        .bind(user_id)
+        .bind(since)
+        .bind(limit.clamp(1, MAX_PAGE_SIZE))
+        .fetch_all(&self.pool)
+        .await?;
+        Ok(events)
+    }
+
+    /// A page of a user's save attempts, newest first
+    pub async fn save_events_page(
+        &self,
+        user_id: Uuid,
+        page: PageRequest,
+        limit: i64,
+    ) -> Result<Page<SaveEvent>> {
+        let limit = limit.clamp(1, MAX_PAGE_SIZE);
+        let fetch = limit + 1;
+
+        let rows = match page {
+            PageRequest::First => {
+                sqlx::query_as::<_, SaveEvent>(
+                    r#"
+                    SELECT * FROM save_events
+                    WHERE user_id = $1
+                    ORDER BY at DESC, id DESC
+                    LIMIT $2
+                    "#,
+                )
+                .bind(user_id)
+                .bind(fetch)
+                .fetch_all(&self.pool)
+                .await?
+            }
+            PageRequest::Before(cursor) => {
+                sqlx::query_as::<_, SaveEvent>(
+                    r#"
+                    SELECT * FROM save_events
+                    WHERE user_id = $1 AND (at, id) < ($2, $3)
+                    ORDER BY at DESC, id DESC
+                    LIMIT $4
+                    "#,
+                )
+                .bind(user_id)
+                .bind(cursor.processed_at)
+                .bind(cursor.id)
+                .bind(fetch)
+                .fetch_all(&self.pool)
+                .await?
+            }
+            PageRequest::After(cursor) => {
+                sqlx::query_as::<_, SaveEvent>(
+                    r#"
+                    SELECT * FROM save_events
+                    WHERE user_id = $1 AND (at, id) > ($2, $3)
+                    ORDER BY at ASC, id ASC
+                    LIMIT $4
+                    "#,
+                )
+                .bind(user_id)
+                .bind(cursor.processed_at)
+                .bind(cursor.id)
+                .bind(fetch)
+                .fetch_all(&self.pool)
+                .await?
+            }
+        };
+
+        Ok(into_page(rows, page, limit, |event| Cursor {
+            processed_at: event.at,
+            id: event.id,
+        }))
+    }
+
+    /// A user's most recent save attempts, newest first
+    pub async fn recent_save_events(&self, user_id: Uuid, limit: i64) -> Result<Vec<SaveEvent>> {
+        let events = sqlx::query_as::<_, SaveEvent>(
+            r#"
+            SELECT * FROM save_events
+            WHERE user_id = $1
+            ORDER BY at DESC, id DESC
+            LIMIT $2
+            "#,
+        )
+        .bind(user_id)
+        .bind(limit.clamp(1, MAX_PAGE_SIZE))
+        .fetch_all(&self.pool)
+        .await?;
+        Ok(events)
+    }
+
+    /// Feature flags an operator has set, by name
+    pub async fn feature_flags(&self) -> Result<Vec<(String, bool)>> {
+        let flags = sqlx::query_as::<_, (String, bool)>(
+            "SELECT name, enabled FROM feature_flags ORDER BY name",
+        )
+        .fetch_all(&self.pool)
+        .await?;
+        Ok(flags)
+    }
+
+    /// Turn a feature on or off for everyone
+    pub async fn set_feature(&self, name: &str, enabled: bool) -> Result<()> {
+        sqlx::query(
+            r#"
+            INSERT INTO feature_flags (name, enabled)
+            VALUES ($1, $2)
+            ON CONFLICT (name) DO UPDATE SET enabled = $2, updated_at = NOW()
+            "#,
+        )
+        .bind(name)
+        .bind(enabled)
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    /// Reader document previously created for a user's post, if any
+    pub async fn get_saved_document(
+        &self,
+        user_id: Uuid,
+        post_uri: &str,
+    ) -> Result<Option<String>> {
+        let id = sqlx::query_scalar::<_, String>(
+            "SELECT readwise_id FROM saved_documents WHERE user_id = $1 AND post_uri = $2",
+        )
+        .bind(user_id)
+        .bind(post_uri)
+        .fetch_optional(&self.pool)
+        .await?;
+        Ok(id)
+    }
+
+    /// Remember the Reader document created for a user's post
+    pub async fn record_saved_document(
+        &self,
+        user_id: Uuid,
+        post_uri: &str,
+        readwise_id: &str,
+    ) -> Result<()> {
+        sqlx::query(
+            r#"
+            INSERT INTO saved_documents (user_id, post_uri, readwise_id)
+            VALUES ($1, $2, $3)
+            ON CONFLICT (user_id, post_uri)
+            DO UPDATE SET readwise_id = EXCLUDED.readwise_id, saved_at = NOW()
+            "#,
+        )
+        .bind(user_id)
+        .bind(post_uri)
+        .bind(readwise_id)
+        .execute(&self.pool)
+        .await?;
+        Ok(())
+    }
+
+    /// Whether a user already saved this (normalized) link to Reader
+    pub async fn is_link_saved(&self, user_id: Uuid, url: &str) -> Result<bool> {
+        let saved = sqlx::query_scalar::<_, bool>(
+            "SELECT EXISTS(SELECT 1 FROM saved_links WHERE user_id = $1 AND url = $2)",
+        )
+        .bind(user_id)
+        .bind(url)
+        .fetch_one(&self.pool)
+        .await?;
+        Ok(saved)
+    }
+
+    /// Remember the Reader document created for a link
+    pub async fn record_saved_link(
+        &self,
+        user_id: Uuid,
+        url: &str,
+        readwise_id: &str,
+    ) -> Result<()> {
+        sqlx::query(
+            r#"
+            INSERT INTO saved_links (user_id, url, readwise_id)
+            VALUES ($1, $2, $3)
+            ON CONFLICT (user_id, url)
+            DO UPDATE SET readwise_id = EXCLUDED.readwise_id, saved_at = NOW()
+            "#,
+        )
+        .bind(user_id)
+        .bind(url)
+        .bind(readwise_id)
+        .execute(&self.pool)
+        .await?;
+        Ok(())
+    }
+
+    /// Check if a DM has been processed
+    pub async fn is_dm_processed(&self, message_id: &str) -> Result<bool> {
+        let result = sqlx::query_scalar::<_, bool>(
+            "SELECT EXISTS(SELECT 1 FROM processed_dms WHERE message_id = $1)",
+        )
+        .bind(message_id)
+        .fetch_one(&self.pool)
+        .await?;
+        Ok(result)
+    }
+
+    /// Record a DM as processed by the bot account `bot_did` (no-op if
+    /// already recorded)
+    pub async fn mark_dm_processed(
+        &self,
+        user_id: Option<Uuid>,
+        message_id: &str,
+        status: &str,
+        bot_did: &str,
+    ) -> Result<()> {
+        sqlx::query(
+            r#"
+            INSERT INTO processed_dms (user_id, message_id, status, bot_account)
+            VALUES ($1, $2, $3, $4)
+            ON CONFLICT (message_id) DO NOTHING
+            "#,
+        )
+        .bind(user_id)
+        .bind(message_id)
+        .bind(status)
+        .bind(bot_did)
+        .execute(&self.pool)
+        .await?;
+        Ok(())
+    }
+
+    /// Create a single-use magic-link token for a DID, valid for `ttl`
+    pub async fn create_login_token(&self, did: &str, ttl: chrono::Duration) -> Result<String> {
+        let token = format!("{}{}", Uuid::new_v4().simple(), Uuid::new_v4().simple());
+        sqlx::query(
+            "INSERT INTO login_tokens (token, bluesky_did, expires_at) VALUES ($1, $2, $3)",
+        )
+        .bind(&token)
+        .bind(did)
+        .bind(Utc::now() + ttl)
+        .execute(&self.pool)
+        .await?;
+        Ok(token)
+    }
+
+    /// Consume a magic-link token, returning its DID if it was valid and unexpired
+    pub async fn consume_login_token(&self, token: &str) -> Result<Option<String>> {
+        let did = sqlx::query_scalar::<_, String>(
+            "DELETE FROM login_tokens WHERE token = $1 AND expires_at > NOW() RETURNING bluesky_did",
+        )
+        .bind(token)
+        .fetch_optional(&self.pool)
+        .await?;
+        Ok(did)
+    }
+
+    /// Delete expired magic-link tokens, returning how many were removed
+    pub async fn delete_expired_login_tokens(&self) -> Result<u64> {
+        let result = sqlx::query("DELETE FROM login_tokens WHERE expires_at <= NOW()")
+            .execute(&self.pool)
+            .await?;
+        Ok(result.rows_affected())
+    }
+
+    /// Delete processed bookmark and DM rows older than `cutoff`
+    ///
+    /// Returns how many rows were removed.
+    pub async fn delete_processed_before(&self, cutoff: DateTime<Utc>) -> Result<u64> {
+        let mut tx = self.pool.begin().await?;
+        let bookmarks = sqlx::query("DELETE FROM processed_bookmarks WHERE processed_at < $1")
+            .bind(cutoff)
+            .execute(&mut *tx)
+            .await?;
+        let dms = sqlx::query("DELETE FROM processed_dms WHERE processed_at < $1")
+            .bind(cutoff)
+            .execute(&mut *tx)
+            .await?;
+        tx.commit().await?;
+        Ok(bookmarks.rows_affected() + dms.rows_affected())
+    }
+
+    /// Delete a user and all of their data (tokens, settings, processed items)
+    ///
+    /// Returns true if the user existed. Safe to call more than once.
+    pub async fn delete_user(&self, user_id: Uuid) -> Result<bool> {
+        let mut tx = self.pool.begin().await?;
+
+        sqlx::query("DELETE FROM processed_dms WHERE user_id = $1")
+            .bind(user_id)
+            .execute(&mut *tx)
+            .await?;
+        sqlx::query("DELETE FROM processed_bookmarks WHERE user_id = $1")
+            .bind(user_id)
+            .execute(&mut *tx)
+            .await?;
+        sqlx::query("DELETE FROM user_settings WHERE user_id = $1")
+            .bind(user_id)
+            .execute(&mut *tx)
+            .await?;
+        sqlx::query("DELETE FROM user_tokens WHERE user_id = $1")
+            .bind(user_id)
+            .execute(&mut *tx)
+            .await?;
+        let deleted = sqlx::query("DELETE FROM users WHERE id = $1")
+            .bind(user_id)
+            .execute(&mut *tx)
+            .await?
+            .rows_affected();
+
+        tx.commit().await?;
+        Ok(deleted > 0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn test_db(pool: PgPool) -> Database {
+        Database::new(pool, EncryptionKey::test_key())
+    }
+
+    async fn count(pool: &PgPool, table: &str, user_id: Uuid) -> i64 {
+        let column = if table == "users" { "id" } else { "user_id" };
+        sqlx::query_scalar(&format!(
+            "SELECT COUNT(*) FROM {} WHERE {} = $1",
+            table, column
+        ))
+        .bind(user_id)
+        .fetch_one(pool)
+        .await
+        .unwrap()
+    }
+
+    #[sqlx::test]
+    async fn test_delete_user_removes_all_data(pool: PgPool) {
+        let user_id: Uuid = sqlx::query_scalar(
+            "INSERT INTO users (bluesky_did, bluesky_handle) VALUES ($1, $2) RETURNING id",
+        )
+        .bind("did:plc:test")
+        .bind("test.bsky.social")
+        .fetch_one(&pool)
+        .await
+        .unwrap();
+
+        sqlx::query("INSERT INTO user_tokens (user_id, access_token) VALUES ($1, 'access')")
+            .bind(user_id)
+            .execute(&pool)
+            .await
+            .unwrap();
+        sqlx::query("INSERT INTO user_settings (user_id, readwise_token) VALUES ($1, 'rw')")
+            .bind(user_id)
+            .execute(&pool)
+            .await
+            .unwrap();
+        sqlx::query("INSERT INTO processed_bookmarks (user_id, post_uri) VALUES ($1, 'at://x')")
+            .bind(user_id)
+            .execute(&pool)
+            .await
+            .unwrap();
+        sqlx::query(
+            "INSERT INTO processed_dms (user_id, message_id, status) VALUES ($1, 'msg1', 'ok')",
+        )
+        .bind(user_id)
+        .execute(&pool)
+        .await
+        .unwrap();
+
+        let db = test_db(pool.clone());
+        assert!(db.delete_user(user_id).await.unwrap());
+
+        for table in [
+            "users",
+            "user_tokens",
+            "user_settings",
+            "processed_bookmarks",
+        ] {
+            assert_eq!(
+                count(&pool, table, user_id).await,
+                0,
+                "{} not purged",
+                table
+            );
+        }
+        // processed_dms uses ON DELETE SET NULL, so check the row itself is gone
+        let dms: i64 =
+            sqlx::query_scalar("SELECT COUNT(*) FROM processed_dms WHERE message_id = 'msg1'")
+                .fetch_one(&pool)
+                .await
+                .unwrap();
+        assert_eq!(dms, 0);
+
+        // Deleting again is a no-op
+        assert!(!db.delete_user(user_id).await.unwrap());
+    }
+
+    #[sqlx::test]
+    async fn test_small_pool_serves_sequential_queries(
+        _: PgPoolOptions,
+        options: PgConnectOptions,
+    ) {
+        let pool_config = PoolConfig {
+            max_connections: 1,
+            acquire_timeout: Duration::from_secs(2),
+            ..PoolConfig::default()
+        };
+        let db = Database::connect_with(options, EncryptionKey::test_key(), &pool_config)
+            .await
+            .unwrap();
+
+        for _ in 0..5 {
+            db.ping().await.unwrap();
+            assert!(db.get_user_by_did("did:plc:none").await.unwrap().is_none());
+        }
+    }
+
+    #[sqlx::test]
+    async fn test_concurrent_mark_processed_has_one_winner(pool: PgPool) {
+        let db = test_db(pool);
+        let user = db
+            .create_user("did:plc:test", "test.bsky.social")
+            .await
+            .unwrap();
+        let uri = "at://did:plc:x/app.bsky.feed.post/1";
+
+        let (first, second) = tokio::join!(
+            db.mark_bookmark_processed(user.id, uri),
+            db.mark_bookmark_processed(user.id, uri)
+        );
+
+        assert!(first.unwrap() ^ second.unwrap());
+        assert_eq!(db.processed_bookmark_count(user.id).await.unwrap(), 1);
+        assert!(!db.mark_bookmark_processed(user.id, uri).await.unwrap());
+    }
+
+    #[sqlx::test]
+    async fn test_create_and_get_user(pool: PgPool) {
+        let db = test_db(pool);
+        let user = db
+            .create_user("did:plc:test", "test.bsky.social")
+            .await
+            .unwrap();
+
+        let by_id = db.get_user_by_id(user.id).await.unwrap().unwrap();
+        assert_eq!(by_id.bluesky_did, "did:plc:test");
+
+        let by_did = db.get_user_by_did("did:plc:test").await.unwrap().unwrap();
+        assert_eq!(by_did.id, user.id);
+        assert_eq!(by_did.bluesky_handle, "test.bsky.social");
+    }
+
+    #[sqlx::test]
+    async fn test_upsert_user_updates_handle(pool: PgPool) {
+        let db = test_db(pool);
+        let created = db
+            .upsert_user("did:plc:test", "old.bsky.social")
+            .await
+            .unwrap();
+        let updated = db
+            .upsert_user("did:plc:test", "new.bsky.social")
+            .await
+            .unwrap();
+
+        assert_eq!(created.id, updated.id);
+        assert_eq!(updated.bluesky_handle, "new.bsky.social");
+    }
+
+    #[sqlx::test]
+    async fn test_create_and_update_settings(pool: PgPool) {
+        let db = test_db(pool);
+        let user = db
+            .create_user("did:plc:test", "test.bsky.social")
+            .await
+            .unwrap();
+
+        let created = db
+            .create_user_settings(user.id, "rw-token", true, false)
+            .await
+            .unwrap();
+        assert_eq!(created.readwise_token, "rw-token");
+
+        let updated = db
+            .update_user_settings(
+                user.id,
+                &SettingsUpdate {
+                    readwise_token: Some("rw-token-2".to_string()),
+                    bookmark_sync_enabled: Some(false),
+                    extract_links: Some(true),
+                    ..Default::default()
+                },
+            )
+            .await
+            .unwrap()
+            .unwrap();
+        assert!(!updated.bookmark_sync_enabled);
+
+        let read = db.get_user_settings(user.id).await.unwrap().unwrap();
+        assert_eq!(read.readwise_token, "rw-token-2");
+        assert!(!read.bookmark_sync_enabled);
+        assert!(read.extract_links);
+
+        // Upsert replaces existing settings
+        db.create_user_settings(user.id, "rw-token-3", true, false)
+            .await
+            .unwrap();
+        let read = db.get_user_settings(user.id).await.unwrap().unwrap();
+        assert_eq!(read.readwise_token, "rw-token-3");
+    }
+
+    #[sqlx::test]
+    async fn test_update_author_filters_and_min_length(pool: PgPool) {
+        let db = test_db(pool);
+        let user = db
+            .create_user("did:plc:test", "test.bsky.social")
+            .await
+            .unwrap();
+        let created = db
+            .create_user_settings(user.id, "rw-token", true, false)
+            .await
+            .unwrap();
+        assert!(created.author_allowlist.is_empty());
+        assert!(created.author_denylist.is_empty());
+        assert_eq!(created.min_post_length, 0);
+
+        db.update_user_settings(
+            user.id,
+            &SettingsUpdate {
+                author_allowlist: Some(vec!["alice.bsky.social".to_string()]),
+                author_denylist: Some(vec!["did:plc:spam".to_string()]),
+                min_post_length: Some(20),
+                ..Default::default()
+            },
+        )
+        .await
+        .unwrap();
+
+        let read = db.get_user_settings(user.id).await.unwrap().unwrap();
+        assert_eq!(read.min_post_length, 20);
+        assert_eq!(read.author_allowlist, vec!["alice.bsky.social"]);
+        assert_eq!(read.author_denylist, vec!["did:plc:spam"]);
+    }
+
+    #[sqlx::test]
+    async fn test_recent_saves_pages_forward_and_back(pool: PgPool) {
+        use crate::db::pagination::decode_cursor;
+
+        let db = test_db(pool.clone());
+        let user = db
+            .create_user("did:plc:test", "test.bsky.social")
+            .await
+            .unwrap();
+        for i in 1..=5 {
+            let uri = format!("at://did:plc:x/app.bsky.feed.post/{}", i);
+            db.mark_bookmark_processed(user.id, &uri).await.unwrap();
+            sqlx::query(
+                "UPDATE processed_bookmarks SET processed_at = NOW() - make_interval(mins => $1) \
+                 WHERE post_uri = $2",
+            )
+            .bind(10 - i)
+            .bind(&uri)
+            .execute(&pool)
+            .await
+            .unwrap();
+        }
+        let uris = |page: &Page<ProcessedBookmark>| -> Vec<String> {
+            page.items
+                .iter()
+                .map(|row| row.post_uri.rsplit('/').next().unwrap().to_string())
+                .collect()
+        };
+        let cursor = |encoded: &Option<String>| decode_cursor(encoded.as_ref().unwrap()).unwrap();
+
+        let first = db
+            .recent_saves(user.id, PageRequest::First, 2)
+            .await
+            .unwrap();
+        assert_eq!(uris(&first), vec!["5", "4"]);
+        assert!(first.after.is_none());
+
+        let second = db
+            .recent_saves(user.id, PageRequest::Before(cursor(&first.before)), 2)
+            .await
+            .unwrap();
+        assert_eq!(uris(&second), vec!["3", "2"]);
+
+        let last = db
+            .recent_saves(user.id, PageRequest::Before(cursor(&second.before)), 2)
+            .await
+            .unwrap();
+        assert_eq!(uris(&last), vec!["1"]);
+        assert!(last.before.is_none());
+
+        // Paging back lands on the same rows
+        let back = db
+            .recent_saves(user.id, PageRequest::After(cursor(&last.after)), 2)
+            .await
+            .unwrap();
+        assert_eq!(uris(&back), vec!["3", "2"]);
+        let back = db
+            .recent_saves(user.id, PageRequest::After(cursor(&back.after)), 2)
+            .await
+            .unwrap();
+        assert_eq!(uris(&back), vec!["5", "4"]);
+        assert!(back.after.is_none());
+    }
+
+    #[sqlx::test]
+    async fn test_save_events_page_forward_and_back(pool: PgPool) {
+        use crate::db::pagination::decode_cursor;
+
+        let db = test_db(pool.clone());
+        let user = db
+            .create_user("did:plc:test", "test.bsky.social")
+            .await
+            .unwrap();
+        for i in 1..=3 {
+            let uri = format!("at://did:plc:x/app.bsky.feed.post/{}", i);
+            db.record_save_event(
+                user.id,
+                NewSaveEvent {
+                    post_uri: &uri,
+                    kind: Some("highlight"),
+                    status: "saved",
+                    ..Default::default()
+                },
+            )
+            .await
+            .unwrap();
+            sqlx::query(
+                "UPDATE save_events SET at = NOW() - make_interval(mins => $1) WHERE post_uri = $2",
+            )
+            .bind(10 - i)
+            .bind(&uri)
+            .execute(&pool)
+            .await
+            .unwrap();
+        }
+        let uris = |page: &Page<SaveEvent>| -> Vec<String> {
+            page.items
+                .iter()
+                .map(|event| event.post_uri.rsplit('/').next().unwrap().to_string())
+                .collect()
+        };
+        let cursor = |encoded: &Option<String>| decode_cursor(encoded.as_ref().unwrap()).unwrap();
+
+        let first = db
+            .save_events_page(user.id, PageRequest::First, 2)
+            .await
+            .unwrap();
+        assert_eq!(uris(&first), vec!["3", "2"]);
+        assert!(first.after.is_none());
+
+        let last = db
+            .save_events_page(user.id, PageRequest::Before(cursor(&first.before)), 2)
+            .await
+            .unwrap();
+        assert_eq!(uris(&last), vec!["1"]);
+        assert!(last.before.is_none());
+
+        let back = db
+            .save_events_page(user.id, PageRequest::After(cursor(&last.after)), 2)
+            .await
+            .unwrap();
+        assert_eq!(uris(&back), vec!["3", "2"]);
+        assert!(back.after.is_none());
+    }
+
+    #[sqlx::test]
+    async fn test_update_settings_without_row(pool: PgPool) {
+        let db = test_db(pool);
+        let result = db
+            .update_user_settings(Uuid::new_v4(), &SettingsUpdate::default())
+            .await
+            .unwrap();
+        assert!(result.is_none());
+    }
+
+    #[sqlx::test]
+    async fn test_store_and_get_tokens(pool: PgPool) {
+        let db = test_db(pool);
+        let user = db
+            .create_user("did:plc:test", "test.bsky.social")
+            .await
+            .unwrap();
+
+        db.store_tokens(user.id, "access-1", Some("refresh-1"), None, Some("z-dpop"))
+            .await
+            .unwrap();
+        let tokens = db.get_tokens(user.id).await.unwrap().unwrap();
+        assert_eq!(tokens.access_token, "access-1");
+        assert_eq!(tokens.refresh_token.as_deref(), Some("refresh-1"));
+        assert_eq!(tokens.dpop_key.as_deref(), Some("z-dpop"));
+
+        let expires_at = Utc::now();
+        db.update_access_token(user.id, "access-2", Some(expires_at))
+            .await
+            .unwrap();
+        let tokens = db.get_tokens(user.id).await.unwrap().unwrap();
+        assert_eq!(tokens.access_token, "access-2");
+        assert_eq!(tokens.refresh_token.as_deref(), Some("refresh-1"));
+        assert!(tokens.expires_at.is_some());
+    }
+
+    #[sqlx::test]
+    async fn test_readwise_token_decrypted_on_read(pool: PgPool) {
+        let db = test_db(pool.clone());
+        let user = db
+            .create_user("did:plc:test", "test.bsky.social")
+            .await
+            .unwrap();
+        let user_id = user.id;
+        db.create_user_settings(user_id, "rw-token", true, false)
+            .await
+            .unwrap();
+
+        let stored: String =
+            sqlx::query_scalar("SELECT readwise_token FROM user_settings WHERE user_id = $1")
+                .bind(user_id)
+                .fetch_one(&pool)
+                .await
+                .unwrap();
+        assert_ne!(stored, "rw-token");
+
+        let settings = db.get_user_settings(user_id).await.unwrap().unwrap();
+        assert_eq!(settings.readwise_token, "rw-token");
+    }
+
+    #[sqlx::test]
+    async fn test_login_token_is_single_use(pool: PgPool) {
+        let db = test_db(pool);
+        let token = db
+            .create_login_token("did:plc:test", chrono::Duration::minutes(15))
+            .await
+            .unwrap();
+
+        assert_eq!(
+            db.consume_login_token(&token).await.unwrap().as_deref(),
+            Some("did:plc:test")
+        );
+        assert!(db.consume_login_token(&token).await.unwrap().is_none());
+    }
+
+    #[sqlx::test]
+    async fn test_expired_login_token_rejected(pool: PgPool) {
+        let db = test_db(pool);
+        let token = db
+            .create_login_token("did:plc:test", chrono::Duration::minutes(-1))
+            .await
+            .unwrap();
+
+        assert!(db.consume_login_token(&token).await.unwrap().is_none());
+    }
 }
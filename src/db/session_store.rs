@@ -0,0 +1,163 @@
+//! Postgres-backed session store
+//!
+//! Persists tower-sessions records so logins survive restarts and are
+//! shared between instances.
+
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use sqlx::PgPool;
+use tower_sessions::session::{Id, Record};
+use tower_sessions::session_store::{self, ExpiredDeletion, SessionStore};
+
+/// Session store backed by the `sessions` table
+#[derive(Debug, Clone)]
+pub struct PostgresSessionStore {
+    pool: PgPool,
+}
+
+impl PostgresSessionStore {
+    /// Create a store using an existing connection pool
+    pub fn new(pool: PgPool) -> Self {
+        Self { pool }
+    }
+}
+
+/// Map a database error into a session store error
+fn backend(e: sqlx::Error) -> session_store::Error {
+    session_store::Error::Backend(e.to_string())
+}
+
+/// Serialize a record and its expiry for storage
+fn encode(record: &Record) -> session_store::Result<(String, DateTime<Utc>)> {
+    let data =
+        serde_json::to_string(record).map_err(|e| session_store::Error::Encode(e.to_string()))?;
+    let expiry = DateTime::from_timestamp(
+        record.expiry_date.unix_timestamp(),
+        record.expiry_date.nanosecond(),
+    )
+    .ok_or_else(|| session_store::Error::Encode("Session expiry out of range".to_string()))?;
+    Ok((data, expiry))
+}
+
+#[async_trait]
+impl SessionStore for PostgresSessionStore {
+    async fn create(&self, record: &mut Record) -> session_store::Result<()> {
+        loop {
+            let (data, expiry) = encode(record)?;
+            let inserted = sqlx::query(
+                "INSERT INTO sessions (id, data, expiry_date) VALUES ($1, $2, $3) ON CONFLICT (id) DO NOTHING",
+            )
+            .bind(record.id.to_string())
+            .bind(data)
+            .bind(expiry)
+            .execute(&self.pool)
+            .await
+            .map_err(backend)?
+            .rows_affected();
+
+            if inserted == 1 {
+                return Ok(());
+            }
+            // Session ID collision mitigation
+            record.id = Id::default();
+        }
+    }
+
+    async fn save(&self, record: &Record) -> session_store::Result<()> {
+        let (data, expiry) = encode(record)?;
+        sqlx::query(
+            r#"
+            INSERT INTO sessions (id, data, expiry_date)
+            VALUES ($1, $2, $3)
+            ON CONFLICT (id) DO UPDATE SET
+                data = EXCLUDED.data,
+                expiry_date = EXCLUDED.expiry_date
+            "#,
+        )
+        .bind(record.id.to_string())
+        .bind(data)
+        .bind(expiry)
+        .execute(&self.pool)
+        .await
+        .map_err(backend)?;
+        Ok(())
+    }
+
+    async fn load(&self, session_id: &Id) -> session_store::Result<Option<Record>> {
+        let data = sqlx::query_scalar::<_, String>(
+            "SELECT data FROM sessions WHERE id = $1 AND expiry_date > NOW()",
+        )
+        .bind(session_id.to_string())
+        .fetch_optional(&self.pool)
+        .await
+        .map_err(backend)?;
+
+        data.map(|data| {
+            serde_json::from_str(&data).map_err(|e| session_store::Error::Decode(e.to_string()))
+        })
+        .transpose()
+    }
+
+    async fn delete(&self, session_id: &Id) -> session_store::Result<()> {
+        sqlx::query("DELETE FROM sessions WHERE id = $1")
+            .bind(session_id.to_string())
+            .execute(&self.pool)
+            .await
+            .map_err(backend)?;
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl ExpiredDeletion for PostgresSessionStore {
+    async fn delete_expired(&self) -> session_store::Result<()> {
+        sqlx::query("DELETE FROM sessions WHERE expiry_date <= NOW()")
+            .execute(&self.pool)
+            .await
+            .map_err(backend)?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tower_sessions::cookie::time::{Duration, OffsetDateTime};
+
+    fn record(expires_in: Duration) -> Record {
+        let mut data = std::collections::HashMap::new();
+        data.insert("user_id".to_string(), serde_json::json!("abc"));
+        Record {
+            id: Id::default(),
+            data,
+            expiry_date: OffsetDateTime::now_utc() + expires_in,
+        }
+    }
+
+    #[sqlx::test]
+    async fn test_session_survives_store_reconstruction(pool: PgPool) {
+        let mut session = record(Duration::hours(1));
+        PostgresSessionStore::new(pool.clone())
+            .create(&mut session)
+            .await
+            .unwrap();
+
+        // A fresh store (e.g., after a restart) sees the same session
+        let loaded = PostgresSessionStore::new(pool)
+            .load(&session.id)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(loaded.data["user_id"], "abc");
+    }
+
+    #[sqlx::test]
+    async fn test_expired_session_not_loaded(pool: PgPool) {
+        let store = PostgresSessionStore::new(pool);
+        let mut session = record(Duration::minutes(-1));
+        store.create(&mut session).await.unwrap();
+
+        assert!(store.load(&session.id).await.unwrap().is_none());
+        store.delete_expired().await.unwrap();
+    }
+}
@@ -0,0 +1,250 @@
+//! Feature flags operators can flip without redeploying
+//!
+//! Each flag resolves, highest precedence first, from `feature_overrides`
+//! in the config, then the `feature_flags` table (set from `/admin/features`),
+//! then the configured default (`expand_quotes`, `detect_language`; digests
+//! default on). Table reads are cached for `feature_cache_ttl_secs`, so a
+//! flip reaches every service within that long.
+
+use std::sync::RwLock;
+use std::time::{Duration, Instant};
+
+use anyhow::{bail, Result};
+use serde::Serialize;
+use tracing::warn;
+
+use crate::db::queries::Database;
+
+/// A feature that can be toggled at runtime
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Feature {
+    /// Daily digest DMs
+    Digests,
+    /// Saving quote posts with the thread they quote
+    ExpandQuotes,
+    /// `lang:xx` tags on saves
+    DetectLanguage,
+}
+
+impl Feature {
+    pub const ALL: [Feature; 3] = [
+        Feature::Digests,
+        Feature::ExpandQuotes,
+        Feature::DetectLanguage,
+    ];
+
+    /// Name used in the table, overrides, and admin API
+    pub fn name(self) -> &'static str {
+        match self {
+            Feature::Digests => "digests",
+            Feature::ExpandQuotes => "expand_quotes",
+            Feature::DetectLanguage => "detect_language",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|feature| feature.name() == name)
+    }
+}
+
+/// Whether each feature is on
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub struct Features {
+    pub digests: bool,
+    pub expand_quotes: bool,
+    pub detect_language: bool,
+}
+
+impl Features {
+    pub fn get(&self, feature: Feature) -> bool {
+        match feature {
+            Feature::Digests => self.digests,
+            Feature::ExpandQuotes => self.expand_quotes,
+            Feature::DetectLanguage => self.detect_language,
+        }
+    }
+
+    pub fn set(&mut self, feature: Feature, enabled: bool) {
+        match feature {
+            Feature::Digests => self.digests = enabled,
+            Feature::ExpandQuotes => self.expand_quotes = enabled,
+            Feature::DetectLanguage => self.detect_language = enabled,
+        }
+    }
+
+    /// Apply stored flags, then overrides, on top of these defaults
+    ///
+    /// Stored names this build doesn't know are ignored.
+    pub fn resolve(mut self, stored: &[(String, bool)], overrides: &[(Feature, bool)]) -> Self {
+        for (name, enabled) in stored {
+            if let Some(feature) = Feature::from_name(name) {
+                self.set(feature, *enabled);
+            }
+        }
+        for &(feature, enabled) in overrides {
+            self.set(feature, enabled);
+        }
+        self
+    }
+}
+
+/// Parse `feature_overrides`, e.g. `digests=off,expand_quotes=on`
+pub fn parse_overrides(value: &str) -> Result<Vec<(Feature, bool)>> {
+    let mut overrides = Vec::new();
+    for entry in value.split(',').map(str::trim).filter(|e| !e.is_empty()) {
+        let Some((name, state)) = entry.split_once('=') else {
+            bail!("feature override must be name=on|off: {}", entry);
+        };
+        let Some(feature) = Feature::from_name(name.trim()) else {
+            bail!("unknown feature in overrides: {}", name.trim());
+        };
+        let enabled = match state.trim() {
+            "on" | "true" => true,
+            "off" | "false" => false,
+            other => bail!("feature {} must be on or off (got {})", name.trim(), other),
+        };
+        overrides.push((feature, enabled));
+    }
+    Ok(overrides)
+}
+
+/// Current feature flags, cached for a short TTL
+pub struct FeatureFlags {
+    db: Database,
+    defaults: Features,
+    overrides: Vec<(Feature, bool)>,
+    ttl: Duration,
+    cached: RwLock<Option<(Features, Instant)>>,
+}
+
+impl FeatureFlags {
+    pub fn new(
+        db: Database,
+        defaults: Features,
+        overrides: Vec<(Feature, bool)>,
+        ttl: Duration,
+    ) -> Self {
+        Self {
+            db,
+            defaults,
+            overrides,
+            ttl,
+            cached: RwLock::new(None),
+        }
+    }
+
+    /// Flags as of at most one TTL ago
+    ///
+    /// If the table can't be read, the last loaded flags (or, before the
+    /// first load, the defaults with overrides) are used until it can.
+    pub async fn current(&self) -> Features {
+        let cached = *self.cached.read().unwrap_or_else(|e| e.into_inner());
+        if let Some((features, loaded_at)) = cached {
+            if loaded_at.elapsed() < self.ttl {
+                return features;
+            }
+        }
+
+        match self.db.feature_flags().await {
+            Ok(stored) => {
+                let features = self.defaults.resolve(&stored, &self.overrides);
+                *self.cached.write().unwrap_or_else(|e| e.into_inner()) =
+                    Some((features, Instant::now()));
+                features
+            }
+            Err(e) => {
+                warn!("Failed to load feature flags: {}", e);
+                cached
+                    .map(|(features, _)| features)
+                    .unwrap_or_else(|| self.defaults.resolve(&[], &self.overrides))
+            }
+        }
+    }
+
+    /// Whether one feature is on
+    pub async fn enabled(&self, feature: Feature) -> bool {
+        self.current().await.get(feature)
+    }
+
+    /// Whether a config override pins this feature, so setting it has no effect
+    pub fn overridden(&self, feature: Feature) -> bool {
+        self.overrides.iter().any(|&(f, _)| f == feature)
+    }
+
+    /// Store a flag and drop the cache so this process sees it at once
+    ///
+    /// Other processes pick it up when their cache expires.
+    pub async fn set(&self, feature: Feature, enabled: bool) -> Result<()> {
+        self.db.set_feature(feature.name(), enabled).await?;
+        *self.cached.write().unwrap_or_else(|e| e.into_inner()) = None;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::crypto::EncryptionKey;
+    use sqlx::PgPool;
+
+    const DEFAULTS: Features = Features {
+        digests: true,
+        expand_quotes: true,
+        detect_language: false,
+    };
+
+    #[test]
+    fn test_overrides_beat_stored_flags_which_beat_defaults() {
+        let stored = vec![
+            ("expand_quotes".to_string(), false),
+            ("detect_language".to_string(), true),
+            ("retired_feature".to_string(), true),
+        ];
+        let overrides = vec![(Feature::DetectLanguage, false)];
+
+        let features = DEFAULTS.resolve(&stored, &overrides);
+
+        assert!(features.digests);
+        assert!(!features.expand_quotes);
+        assert!(!features.detect_language);
+        assert_eq!(DEFAULTS.resolve(&[], &[]), DEFAULTS);
+    }
+
+    #[test]
+    fn test_parse_overrides() {
+        assert_eq!(
+            parse_overrides(" digests=off, expand_quotes = true ,").unwrap(),
+            vec![(Feature::Digests, false), (Feature::ExpandQuotes, true)]
+        );
+        assert!(parse_overrides("").unwrap().is_empty());
+        assert!(parse_overrides("digests").is_err());
+        assert!(parse_overrides("sparkles=on").is_err());
+        assert!(parse_overrides("digests=maybe").is_err());
+    }
+
+    #[sqlx::test]
+    async fn test_set_flags_are_cached_and_overrides_win(pool: PgPool) {
+        let db = Database::new(pool, EncryptionKey::test_key());
+        let flags = FeatureFlags::new(
+            db.clone(),
+            DEFAULTS,
+            vec![(Feature::Digests, true)],
+            Duration::from_secs(3600),
+        );
+        assert_eq!(flags.current().await, DEFAULTS);
+
+        // Another process's change waits for the cache to expire...
+        db.set_feature("expand_quotes", false).await.unwrap();
+        assert!(flags.enabled(Feature::ExpandQuotes).await);
+
+        // ...while this process's own changes apply at once
+        flags.set(Feature::DetectLanguage, true).await.unwrap();
+        flags.set(Feature::Digests, false).await.unwrap();
+        let features = flags.current().await;
+        assert!(!features.expand_quotes);
+        assert!(features.detect_language);
+        assert!(features.digests, "the override pins digests on");
+        assert!(flags.overridden(Feature::Digests));
+        assert!(!flags.overridden(Feature::ExpandQuotes));
+    }
+}
@@ -0,0 +1,90 @@
+//! Outbound HTTP client
+//!
+//! One `reqwest::Client` is built at startup and cloned into every API
+//! client, so they share a connection pool and the same timeouts.
+
+use std::time::Duration;
+
+use anyhow::{Context, Result};
+use reqwest::{Client, ClientBuilder};
+
+use crate::config::Config;
+use crate::request_id::{self, REQUEST_ID_HEADER};
+
+/// User agent sent with every outbound request
+pub const USER_AGENT: &str = concat!("readwise-autosave/", env!("CARGO_PKG_VERSION"));
+
+/// How long an idle pooled connection is kept
+const POOL_IDLE_TIMEOUT: Duration = Duration::from_secs(90);
+
+/// Idle connections kept per host
+const POOL_MAX_IDLE_PER_HOST: usize = 16;
+
+/// Builder with our user agent, connect timeout, and pool settings
+///
+/// For clients that need a different request timeout (e.g., OAuth).
+pub fn client_builder(config: &Config) -> ClientBuilder {
+    Client::builder()
+        .user_agent(USER_AGENT)
+        .connect_timeout(Duration::from_secs(config.http_connect_timeout_secs))
+        .pool_idle_timeout(POOL_IDLE_TIMEOUT)
+        .pool_max_idle_per_host(POOL_MAX_IDLE_PER_HOST)
+}
+
+/// Build the client shared by the Bluesky and Readwise clients
+pub fn shared_http_client(config: &Config) -> Result<Client> {
+    client_builder(config)
+        .timeout(Duration::from_secs(config.http_timeout_secs))
+        .build()
+        .context("Failed to build HTTP client")
+}
+
+/// Sends the running operation's request ID with an outbound request
+pub trait WithRequestId {
+    fn with_request_id(self) -> Self;
+}
+
+impl WithRequestId for reqwest::RequestBuilder {
+    fn with_request_id(self) -> Self {
+        match request_id::current() {
+            Some(id) => self.header(REQUEST_ID_HEADER, id.to_string()),
+            None => self,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::{http::HeaderMap, routing::get, Router};
+
+    #[tokio::test]
+    async fn test_user_agent_is_set() {
+        let app = Router::new().route(
+            "/",
+            get(|headers: HeaderMap| async move {
+                headers
+                    .get("user-agent")
+                    .and_then(|value| value.to_str().ok())
+                    .unwrap_or_default()
+                    .to_string()
+            }),
+        );
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+
+        let client = shared_http_client(&Config::test_default()).unwrap();
+        let user_agent = client
+            .get(format!("http://{}/", addr))
+            .send()
+            .await
+            .unwrap()
+            .text()
+            .await
+            .unwrap();
+
+        assert_eq!(user_agent, USER_AGENT);
+        assert!(user_agent.starts_with("readwise-autosave/"));
+    }
+}
@@ -0,0 +1,118 @@
+//! Log output setup
+//!
+//! `pretty` is the human-readable default. `json` writes one object per
+//! line for log aggregators, with `timestamp`, `level`, `target`, `fields`
+//! (including `message`), the current `span`, and the enclosing `spans`
+//! (e.g. `user_did`, `request_id`, `post_uri`).
+
+use serde::Deserialize;
+use tracing::Subscriber;
+use tracing_subscriber::fmt::MakeWriter;
+use tracing_subscriber::{layer::SubscriberExt, util::SubscriberInitExt, EnvFilter};
+
+/// Filter used when `RUST_LOG` is unset
+const DEFAULT_FILTER: &str = "readwise_autosave=debug,tower_http=debug";
+
+/// Log line format
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum LogFormat {
+    #[default]
+    Pretty,
+    Json,
+}
+
+/// Install the global subscriber, writing to stdout
+pub fn init(format: LogFormat) {
+    subscriber(format, std::io::stdout).init();
+}
+
+/// Subscriber with the `RUST_LOG` filter and the chosen format
+fn subscriber<W>(format: LogFormat, writer: W) -> Box<dyn Subscriber + Send + Sync>
+where
+    W: for<'w> MakeWriter<'w> + Send + Sync + 'static,
+{
+    let filter = EnvFilter::try_from_default_env().unwrap_or_else(|_| DEFAULT_FILTER.into());
+    let registry = tracing_subscriber::registry().with(filter);
+
+    match format {
+        LogFormat::Pretty => {
+            Box::new(registry.with(tracing_subscriber::fmt::layer().with_writer(writer)))
+        }
+        LogFormat::Json => Box::new(
+            registry.with(
+                tracing_subscriber::fmt::layer()
+                    .json()
+                    .with_current_span(true)
+                    .with_span_list(true)
+                    .with_writer(writer),
+            ),
+        ),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Write;
+    use std::sync::{Arc, Mutex};
+
+    /// Collects everything written by the subscriber
+    #[derive(Clone, Default)]
+    struct Buffer(Arc<Mutex<Vec<u8>>>);
+
+    impl Write for Buffer {
+        fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+            self.0.lock().unwrap().extend_from_slice(buf);
+            Ok(buf.len())
+        }
+
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+
+    impl<'w> MakeWriter<'w> for Buffer {
+        type Writer = Buffer;
+
+        fn make_writer(&'w self) -> Self::Writer {
+            self.clone()
+        }
+    }
+
+    fn log_line(format: LogFormat) -> String {
+        let buffer = Buffer::default();
+        tracing::subscriber::with_default(subscriber(format, buffer.clone()), || {
+            let span = tracing::info_span!("poll", user_did = "did:plc:abc");
+            let _entered = span.enter();
+            tracing::info!("hello");
+        });
+        let bytes = buffer.0.lock().unwrap().clone();
+        String::from_utf8(bytes).unwrap()
+    }
+
+    #[test]
+    fn test_log_format_parsed() {
+        assert_eq!(
+            serde_json::from_str::<LogFormat>("\"json\"").unwrap(),
+            LogFormat::Json
+        );
+        assert_eq!(
+            serde_json::from_str::<LogFormat>("\"pretty\"").unwrap(),
+            LogFormat::Pretty
+        );
+        assert!(serde_json::from_str::<LogFormat>("\"xml\"").is_err());
+    }
+
+    #[test]
+    fn test_json_format_writes_json() {
+        let line = log_line(LogFormat::Json);
+        let json: serde_json::Value = serde_json::from_str(line.trim()).unwrap();
+        assert_eq!(json["fields"]["message"], "hello");
+        assert_eq!(json["span"]["user_did"], "did:plc:abc");
+
+        let line = log_line(LogFormat::Pretty);
+        assert!(serde_json::from_str::<serde_json::Value>(line.trim()).is_err());
+        assert!(line.contains("hello"));
+    }
+}
@@ -3,57 +3,296 @@
 #![allow(unused_imports)]
 #![allow(unused_variables)]
 
-use anyhow::Result;
+use anyhow::{Context, Result};
 use std::sync::Arc;
+use std::time::Duration;
 use tokio::net::TcpListener;
 use tower_http::trace::TraceLayer;
-use tracing_subscriber::{layer::SubscriberExt, util::SubscriberInitExt};
+use tower_sessions::cookie::{time, SameSite};
+use tower_sessions::{ExpiredDeletion, Expiry, SessionManagerLayer};
 
 mod bluesky;
+mod cli;
 mod config;
 mod content;
+mod crypto;
 mod db;
+mod features;
+mod http_client;
+mod logging;
+mod metrics;
 mod readwise;
+mod request_id;
 mod services;
+#[cfg(test)]
+mod test_support;
 mod web;
 
+/// Log users out after this many days without a visit
+const SESSION_INACTIVITY_DAYS: i64 = 7;
+
+/// Outbound timeout for OAuth requests (PAR, token exchange)
+///
+/// Shorter than the inbound timeout on the auth routes, so a slow
+/// authorization server fails the request cleanly instead of being cut off.
+const OAUTH_HTTP_TIMEOUT: Duration = Duration::from_secs(20);
+
+/// How often expired sessions are purged
+const SESSION_CLEANUP_INTERVAL: Duration = Duration::from_secs(60 * 60);
+
 /// Shared application state
 pub struct AppState {
     pub config: config::Config,
-    // TODO: Add database pool
+    pub db: db::queries::Database,
+    /// OAuth login flow (None when OAuth isn't configured)
+    pub oauth: Option<Arc<dyn bluesky::OAuthService>>,
+    /// Running per-user bookmark sync tasks
+    pub sync_tasks: Arc<services::sync_tasks::SyncTasks>,
+    /// Outbound HTTP client shared by the API clients
+    pub http: reqwest::Client,
+    /// Readwise API, for token checks from web handlers
+    pub readwise: Arc<dyn readwise::client::ReadwiseClient>,
+    /// Bounds Readwise saves in flight across all services
+    pub save_limiter: Arc<tokio::sync::Semaphore>,
+    /// Bookmarks waiting for the save workers (None saves while polling)
+    pub save_queue: Option<services::save_queue::SaveQueue>,
+    /// Shared handle → DID cache
+    pub handles: Arc<bluesky::HandleCache>,
+    /// Runtime feature toggles
+    pub features: Arc<features::FeatureFlags>,
+    /// Recent Readwise token checks for `/api/status`
+    pub token_checks: Arc<services::readwise_token::TokenChecks>,
     // TODO: Add OAuth client
 }
 
+impl AppState {
+    /// Unauthenticated Bluesky client on the configured API servers
+    pub fn bluesky_client(&self) -> bluesky::HttpBlueskyClient {
+        bluesky::HttpBlueskyClient::with_client(self.http.clone())
+            .with_base_url(&self.config.bsky_api_base)
+            .with_public_url(&self.config.bsky_public_api_base)
+            .with_thread_depth(self.config.max_thread_depth)
+    }
+
+    /// Readwise client on the configured API server
+    pub fn readwise_client(&self) -> readwise::client::HttpReadwiseClient {
+        readwise::client::HttpReadwiseClient::with_client(self.http.clone())
+            .with_base_url(&self.config.readwise_api_base)
+    }
+}
+
+#[cfg(test)]
+impl AppState {
+    /// State with test config, no OAuth, and the given database
+    pub fn test(db: db::queries::Database) -> Self {
+        let config = config::Config::test_default();
+        Self {
+            features: Arc::new(features::FeatureFlags::new(
+                db.clone(),
+                config.default_features(),
+                Vec::new(),
+                Duration::from_secs(config.feature_cache_ttl_secs),
+            )),
+            config,
+            db,
+            oauth: None,
+            sync_tasks: Arc::new(services::sync_tasks::SyncTasks::new()),
+            http: reqwest::Client::new(),
+            readwise: Arc::new(readwise::client::HttpReadwiseClient::new()),
+            save_limiter: Arc::new(tokio::sync::Semaphore::new(1)),
+            save_queue: None,
+            handles: Arc::new(bluesky::HandleCache::new(
+                Arc::new(bluesky::handles::IdentityHandleResolver::new(
+                    reqwest::Client::new(),
+                )),
+                Duration::from_secs(3600),
+            )),
+            token_checks: Arc::new(services::readwise_token::TokenChecks::new(
+                services::readwise_token::TOKEN_CHECK_TTL,
+            )),
+        }
+    }
+}
+
 #[tokio::main]
 async fn main() -> Result<()> {
-    // Initialize tracing
-    tracing_subscriber::registry()
-        .with(
-            tracing_subscriber::EnvFilter::try_from_default_env()
-                .unwrap_or_else(|_| "readwise_autosave=debug,tower_http=debug".into()),
-        )
-        .with(tracing_subscriber::fmt::layer())
-        .init();
+    let command = cli::parse_command(std::env::args().skip(1))?;
+
+    // Load configuration (before logging, which it configures)
+    let config = config::Config::load()?;
 
+    // Initialize tracing
+    logging::init(config.log_format);
     tracing::info!("Starting readwise-autosave");
 
-    // Load configuration
-    let config = config::Config::load()?;
-    tracing::info!("Configuration loaded");
+    metrics::init()?;
+
+    // Connect to the database
+    let encryption_key = crypto::EncryptionKey::from_base64(&config.encryption_key)?;
+    let pool_config = db::queries::PoolConfig {
+        max_connections: config.db_max_connections,
+        acquire_timeout: Duration::from_secs(config.db_acquire_timeout_secs),
+        idle_timeout: Duration::from_secs(config.db_idle_timeout_secs),
+    };
+    let db = db::queries::Database::connect(&config.database_url, encryption_key, &pool_config)
+        .await
+        .context("Failed to connect to the database; check DATABASE_URL")?;
+    db.migrate()
+        .await
+        .context("Failed to run database migrations")?;
+    tracing::info!("Database connected and migrated");
+    if command == cli::Command::Migrate {
+        return Ok(());
+    }
+
+    let http = http_client::shared_http_client(&config)?;
+    let handles = Arc::new(bluesky::HandleCache::new(
+        Arc::new(bluesky::handles::IdentityHandleResolver::new(http.clone())),
+        Duration::from_secs(config.handle_cache_ttl_secs),
+    ));
+
+    // OAuth login
+    let oauth = match (&config.oauth_client_id, &config.oauth_signing_key) {
+        (Some(client_id), Some(signing_key)) => {
+            let service = bluesky::oauth::AtprotoOAuthService::new(bluesky::oauth::OAuthConfig {
+                client_id: client_id.clone(),
+                redirect_uri: config.redirect_uri(),
+                signing_key: signing_key.clone(),
+                http: http_client::client_builder(&config)
+                    .timeout(OAUTH_HTTP_TIMEOUT)
+                    .build()
+                    .context("Failed to build OAuth HTTP client")?,
+                handles: handles.clone(),
+                scope: config.oauth_scope()?,
+            })?;
+            Some(Arc::new(service) as Arc<dyn bluesky::OAuthService>)
+        }
+        _ => {
+            tracing::warn!("OAuth is not configured; web login is disabled");
+            None
+        }
+    };
+
+    let features = Arc::new(features::FeatureFlags::new(
+        db.clone(),
+        config.default_features(),
+        config.feature_overrides()?,
+        Duration::from_secs(config.feature_cache_ttl_secs),
+    ));
+
+    let (save_queue, save_jobs) = services::save_queue::save_queue(config.save_queue_capacity);
 
     // Create shared state
     let state = Arc::new(AppState {
         config: config.clone(),
+        db,
+        oauth,
+        sync_tasks: Arc::new(services::sync_tasks::SyncTasks::new()),
+        readwise: Arc::new(
+            readwise::client::HttpReadwiseClient::with_client(http.clone())
+                .with_base_url(&config.readwise_api_base),
+        ),
+        http,
+        save_limiter: Arc::new(tokio::sync::Semaphore::new(config.max_concurrent_saves)),
+        save_queue: Some(save_queue),
+        handles,
+        features,
+        token_checks: Arc::new(services::readwise_token::TokenChecks::new(
+            services::readwise_token::TOKEN_CHECK_TTL,
+        )),
+    });
+
+    // One-shot check of credentials and connectivity
+    if command == cli::Command::Selftest {
+        let report = services::selftest::selftest(&state).await;
+        println!("{}", report);
+        std::process::exit(if report.passed() { 0 } else { 1 });
+    }
+
+    // DM bot (app-password login)
+    services::spawn_dm_bot(state.clone());
+
+    // Workers saving bookmarks queued by the sync loops
+    services::spawn_save_workers(state.clone(), save_jobs);
+
+    // Retries for bookmark saves that failed
+    services::spawn_save_retries(state.clone());
+
+    // Purge expired logins and old processed rows
+    services::spawn_cleanup_loop(state.clone());
+
+    // Session storage (Postgres, so logins survive restarts)
+    let session_store = db::session_store::PostgresSessionStore::new(state.db.pool().clone());
+    tokio::spawn({
+        let session_store = session_store.clone();
+        async move {
+            let mut ticker = tokio::time::interval(SESSION_CLEANUP_INTERVAL);
+            loop {
+                ticker.tick().await;
+                if let Err(e) = session_store.delete_expired().await {
+                    tracing::warn!("Failed to delete expired sessions: {}", e);
+                }
+            }
+        }
     });
+    let session_layer = SessionManagerLayer::new(session_store)
+        .with_secure(config.base_url().starts_with("https://"))
+        .with_http_only(true)
+        .with_same_site(SameSite::Lax)
+        .with_expiry(Expiry::OnInactivity(time::Duration::days(
+            SESSION_INACTIVITY_DAYS,
+        )));
 
     // Create router with state
-    let app = web::routes::create_router(state).layer(TraceLayer::new_for_http());
+    let app = web::routes::create_router(state)
+        .layer(session_layer)
+        .layer(TraceLayer::new_for_http());
 
     // Start the server
     let addr = &config.server_address;
     tracing::info!("Server listening on {}", addr);
     let listener = TcpListener::bind(addr).await?;
-    axum::serve(listener, app).await?;
+    // Peer addresses feed the operator IP allowlist
+    axum::serve(
+        listener,
+        app.into_make_service_with_connect_info::<std::net::SocketAddr>(),
+    )
+    .await?;
 
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::bluesky::BlueskyClient;
+    use crate::readwise::client::ReadwiseClient;
+    use crate::test_support::{self, MockBluesky, MockReadwise};
+    use sqlx::PgPool;
+
+    #[sqlx::test]
+    async fn test_clients_use_configured_base_urls(pool: PgPool) {
+        let post = test_support::post("abc").build();
+        let bluesky = MockBluesky::new().with_bookmark(&post).start().await;
+        let readwise = MockReadwise::start().await;
+        let state = AppState {
+            config: config::Config {
+                bsky_api_base: bluesky.url.clone(),
+                bsky_public_api_base: bluesky.url.clone(),
+                readwise_api_base: readwise.url.clone(),
+                ..config::Config::test_default()
+            },
+            ..AppState::test(db::queries::Database::new(
+                pool,
+                crypto::EncryptionKey::test_key(),
+            ))
+        };
+
+        let client = state
+            .bluesky_client()
+            .authenticated("access".to_string(), "did:plc:reader".to_string());
+        assert_eq!(client.get_bookmarks(None).await.unwrap().bookmarks.len(), 1);
+        client.get_post_thread(&post.uri).await.unwrap();
+        assert!(state.readwise_client().verify_token("token").await.unwrap());
+    }
+}
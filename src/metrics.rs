@@ -0,0 +1,107 @@
+//! Prometheus metrics
+//!
+//! Metric names live here so call sites and dashboards agree on them;
+//! the rest of the app records through the helpers below. The recorder is
+//! process-global; `init` installs it once.
+
+use std::sync::Mutex;
+use std::time::Duration;
+
+use anyhow::{Context, Result};
+use metrics_exporter_prometheus::{Matcher, PrometheusBuilder, PrometheusHandle};
+
+/// Bookmarks saved to Readwise by the sync loop
+const BOOKMARKS_PROCESSED: &str = "bookmarks_processed_total";
+
+/// DMs handled by the bot
+const DMS_PROCESSED: &str = "dms_processed_total";
+
+/// Readwise saves, labelled by `kind` and `result` ("success" / "failure")
+const READWISE_SAVES: &str = "readwise_saves_total";
+
+/// Time spent in each Readwise save call
+const READWISE_SAVE_DURATION: &str = "readwise_save_duration_seconds";
+
+/// OAuth logins that failed to complete
+const OAUTH_ERRORS: &str = "oauth_errors_total";
+
+/// Registered per-user bookmark sync tasks
+const SYNC_TASKS_ACTIVE: &str = "sync_tasks_active";
+
+/// Histogram buckets for Readwise save latency (seconds)
+const SAVE_DURATION_BUCKETS: &[f64] = &[0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0, 10.0, 30.0];
+
+static HANDLE: Mutex<Option<PrometheusHandle>> = Mutex::new(None);
+
+/// Install the Prometheus recorder, returning a handle for rendering
+///
+/// Safe to call more than once; later calls return the existing handle.
+pub fn init() -> Result<PrometheusHandle> {
+    let mut installed = HANDLE.lock().unwrap_or_else(|e| e.into_inner());
+    if let Some(handle) = installed.as_ref() {
+        return Ok(handle.clone());
+    }
+
+    let handle = PrometheusBuilder::new()
+        .set_buckets_for_metric(
+            Matcher::Full(READWISE_SAVE_DURATION.to_string()),
+            SAVE_DURATION_BUCKETS,
+        )
+        .context("Invalid histogram buckets")?
+        .install_recorder()
+        .context("Failed to install metrics recorder")?;
+    describe();
+
+    *installed = Some(handle.clone());
+    Ok(handle)
+}
+
+/// Render all metrics in the Prometheus text format
+pub fn render() -> String {
+    let installed = HANDLE.lock().unwrap_or_else(|e| e.into_inner());
+    installed
+        .as_ref()
+        .map(PrometheusHandle::render)
+        .unwrap_or_default()
+}
+
+/// Register help text for each metric
+fn describe() {
+    metrics::describe_counter!(BOOKMARKS_PROCESSED, "Bookmarks saved to Readwise");
+    metrics::describe_counter!(DMS_PROCESSED, "DMs handled by the bot");
+    metrics::describe_counter!(READWISE_SAVES, "Readwise save attempts by kind and result");
+    metrics::describe_histogram!(
+        READWISE_SAVE_DURATION,
+        metrics::Unit::Seconds,
+        "Time spent saving to Readwise"
+    );
+    metrics::describe_counter!(OAUTH_ERRORS, "OAuth logins that failed to complete");
+    metrics::describe_gauge!(SYNC_TASKS_ACTIVE, "Registered bookmark sync tasks");
+}
+
+/// Count a bookmark saved by the sync loop
+pub fn bookmark_processed() {
+    metrics::counter!(BOOKMARKS_PROCESSED).increment(1);
+}
+
+/// Count a DM handled by the bot
+pub fn dm_processed() {
+    metrics::counter!(DMS_PROCESSED).increment(1);
+}
+
+/// Record a Readwise save call and how long it took
+pub fn readwise_save(kind: &'static str, elapsed: Duration, success: bool) {
+    let result = if success { "success" } else { "failure" };
+    metrics::histogram!(READWISE_SAVE_DURATION, "kind" => kind).record(elapsed.as_secs_f64());
+    metrics::counter!(READWISE_SAVES, "kind" => kind, "result" => result).increment(1);
+}
+
+/// Count an OAuth login that failed to complete
+pub fn oauth_error() {
+    metrics::counter!(OAUTH_ERRORS).increment(1);
+}
+
+/// Set the number of registered sync tasks
+pub fn set_sync_tasks_active(count: usize) {
+    metrics::gauge!(SYNC_TASKS_ACTIVE).set(count as f64);
+}
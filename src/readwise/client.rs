@@ -1,11 +1,92 @@
 //! Readwise API client
 
+use std::time::Duration;
+
 use anyhow::Result;
 use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use reqwest::{header, StatusCode};
 use serde::{Deserialize, Serialize};
+use thiserror::Error;
+use tracing::warn;
+
+use crate::http_client::WithRequestId;
+
+/// Categories the v2 highlights API accepts
+pub const HIGHLIGHT_CATEGORIES: &[&str] = &["books", "articles", "tweets", "podcasts"];
+
+/// Category Bluesky posts are saved under unless configured otherwise
+pub const DEFAULT_HIGHLIGHT_CATEGORY: &str = "tweets";
+
+/// Source Reader shows for documents this app saves (`saved_using`)
+pub const SAVED_USING: &str = "Readwise Autosave";
+
+/// Whether Readwise accepts this highlight category
+pub fn is_highlight_category(category: &str) -> bool {
+    HIGHLIGHT_CATEGORIES.contains(&category)
+}
+
+/// Categories Reader accepts for saved documents
+pub const READER_CATEGORIES: &[&str] = &[
+    "article",
+    "email",
+    "rss",
+    "highlight",
+    "note",
+    "pdf",
+    "epub",
+    "tweet",
+    "video",
+];
+
+/// Whether Reader accepts this document category
+pub fn is_reader_category(category: &str) -> bool {
+    READER_CATEGORIES.contains(&category)
+}
+
+/// A Readwise API call came back with an error status
+#[derive(Debug, Error)]
+#[error("{api} error {status}: {body}")]
+pub struct ReadwiseApiError {
+    pub api: &'static str,
+    pub status: StatusCode,
+    pub body: String,
+}
+
+/// Most Reader list pages read in one call, in case a cursor never ends
+const MAX_LIST_PAGES: usize = 100;
+
+/// Times a rate-limited Reader list page is requested again
+const LIST_RATE_LIMIT_RETRIES: u32 = 3;
+
+/// Longest `Retry-After` honored, and the wait when the header is missing
+const MAX_RETRY_AFTER: Duration = Duration::from_secs(60);
+
+/// How long a 429 response asks us to wait, at most `MAX_RETRY_AFTER`
+fn retry_after(response: &reqwest::Response) -> Duration {
+    response
+        .headers()
+        .get(header::RETRY_AFTER)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.trim().parse().ok())
+        .map_or(MAX_RETRY_AFTER, |secs| {
+            Duration::from_secs(secs).min(MAX_RETRY_AFTER)
+        })
+}
+
+/// Longest highlight text the v2 API accepts, in characters
+pub const MAX_HIGHLIGHT_CHARS: usize = 8191;
+
+/// Ends highlight text cut down to `MAX_HIGHLIGHT_CHARS`
+const TRUNCATED_MARKER: &str = "…[truncated]";
+
+/// Error for a failed Readwise call
+fn api_error(api: &'static str, status: StatusCode, body: String) -> anyhow::Error {
+    ReadwiseApiError { api, status, body }.into()
+}
 
 /// Highlight to save (v2 API)
-#[derive(Debug, Clone, Serialize)]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
 pub struct Highlight {
     pub text: String,
     #[serde(skip_serializing_if = "Option::is_none")]
@@ -18,10 +99,32 @@ pub struct Highlight {
     pub category: Option<String>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub note: Option<String>,
+    /// Unique link to this highlight; Readwise treats a repeat as the same highlight
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub highlight_url: Option<String>,
+}
+
+impl Highlight {
+    /// Cut the text down to `MAX_HIGHLIGHT_CHARS`, ending it with a marker
+    ///
+    /// Returns whether the text was cut.
+    pub fn truncate_text(&mut self) -> bool {
+        if self.text.chars().count() <= MAX_HIGHLIGHT_CHARS {
+            return false;
+        }
+        let keep = MAX_HIGHLIGHT_CHARS - TRUNCATED_MARKER.chars().count();
+        if let Some((end, _)) = self.text.char_indices().nth(keep) {
+            self.text.truncate(end);
+        }
+        self.text.push_str(TRUNCATED_MARKER);
+        true
+    }
 }
 
 /// Document to save (v3 API / Reader)
-#[derive(Debug, Clone, Serialize)]
+///
+/// Reader dedups on `url`, so saving the same URL twice yields one document.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
 pub struct Document {
     pub url: String,
     #[serde(skip_serializing_if = "Option::is_none")]
@@ -32,6 +135,23 @@ pub struct Document {
     pub author: Option<String>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub tags: Option<Vec<String>>,
+    /// Reader category (e.g., "pdf", "video"); Reader guesses when unset
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub category: Option<String>,
+    /// Shown under the title in Reader's document list
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub summary: Option<String>,
+    /// Note attached to the whole document
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub notes: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub published_date: Option<DateTime<Utc>>,
+    /// Cover image
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub image_url: Option<String>,
+    /// Where the document was saved from, shown as its source
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub saved_using: Option<String>,
 }
 
 /// Response from save operations
@@ -40,20 +160,49 @@ pub struct SaveResponse {
     pub id: Option<String>,
 }
 
+/// A document already in Reader (v3 list API)
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+pub struct ReaderDocument {
+    pub id: String,
+    /// Reader's own URL for the document
+    pub url: String,
+    /// The URL the document was saved from
+    pub source_url: Option<String>,
+    pub title: Option<String>,
+    /// Reader location (e.g., "new", "later", "archive")
+    pub location: Option<String>,
+    pub created_at: DateTime<Utc>,
+}
+
 /// Trait for Readwise operations (for testability)
 #[async_trait]
 pub trait ReadwiseClient: Send + Sync {
-    /// Save a highlight (v2 API)
-    async fn save_highlight(&self, token: &str, highlight: Highlight) -> Result<()>;
+    /// Save a highlight (v2 API), returning its ID if Readwise reports one
+    async fn save_highlight(&self, token: &str, highlight: Highlight) -> Result<Option<String>>;
+
+    /// Save a document to Reader (v3 API), returning its ID if Readwise reports one
+    async fn save_document(&self, token: &str, document: Document) -> Result<Option<String>>;
 
-    /// Save a document to Reader (v3 API)
-    async fn save_document(&self, token: &str, document: Document) -> Result<()>;
+    /// Delete a Reader document (v3 API); one that's already gone is not an error
+    async fn delete_document(&self, token: &str, id: &str) -> Result<()>;
 
     /// Verify a token is valid
     async fn verify_token(&self, token: &str) -> Result<bool>;
+
+    /// List Reader documents (v3 API), following every page
+    ///
+    /// Narrowed to documents updated after `updated_after` and in
+    /// `location` when given.
+    async fn list_documents(
+        &self,
+        token: &str,
+        updated_after: Option<DateTime<Utc>>,
+        location: Option<&str>,
+    ) -> Result<Vec<ReaderDocument>>;
 }
 
 /// HTTP-based Readwise client
+#[derive(Clone)]
 pub struct HttpReadwiseClient {
     client: reqwest::Client,
     base_url: String,
@@ -61,11 +210,22 @@ pub struct HttpReadwiseClient {
 
 impl HttpReadwiseClient {
     pub fn new() -> Self {
+        Self::with_client(reqwest::Client::new())
+    }
+
+    /// Create a client on a shared `reqwest::Client`
+    pub fn with_client(client: reqwest::Client) -> Self {
         Self {
-            client: reqwest::Client::new(),
+            client,
             base_url: "https://readwise.io/api".to_string(),
         }
     }
+
+    /// Use a different API base URL (e.g., a mock server)
+    pub fn with_base_url(mut self, base_url: &str) -> Self {
+        self.base_url = base_url.trim_end_matches('/').to_string();
+        self
+    }
 }
 
 impl Default for HttpReadwiseClient {
@@ -76,12 +236,19 @@ impl Default for HttpReadwiseClient {
 
 #[async_trait]
 impl ReadwiseClient for HttpReadwiseClient {
-    async fn save_highlight(&self, token: &str, highlight: Highlight) -> Result<()> {
+    async fn save_highlight(&self, token: &str, highlight: Highlight) -> Result<Option<String>> {
         #[derive(Serialize)]
         struct HighlightsPayload {
             highlights: Vec<Highlight>,
         }
 
+        /// One entry per book the highlights were added to
+        #[derive(Deserialize)]
+        struct BookResponse {
+            #[serde(default)]
+            modified_highlights: Vec<u64>,
+        }
+
         let payload = HighlightsPayload {
             highlights: vec![highlight],
         };
@@ -91,31 +258,57 @@ impl ReadwiseClient for HttpReadwiseClient {
             .post(format!("{}/v2/highlights/", self.base_url))
             .header("Authorization", format!("Token {}", token))
             .json(&payload)
+            .with_request_id()
             .send()
             .await?;
 
         if !response.status().is_success() {
             let status = response.status();
             let text = response.text().await.unwrap_or_default();
-            anyhow::bail!("Readwise API error {}: {}", status, text);
+            return Err(api_error("Readwise API", status, text));
         }
 
-        Ok(())
+        let books: Vec<BookResponse> = response.json().await.unwrap_or_default();
+        Ok(books
+            .iter()
+            .flat_map(|book| &book.modified_highlights)
+            .next()
+            .map(|id| id.to_string()))
     }
 
-    async fn save_document(&self, token: &str, document: Document) -> Result<()> {
+    async fn save_document(&self, token: &str, document: Document) -> Result<Option<String>> {
         let response = self
             .client
             .post(format!("{}/v3/save/", self.base_url))
             .header("Authorization", format!("Token {}", token))
             .json(&document)
+            .with_request_id()
             .send()
             .await?;
 
         if !response.status().is_success() {
             let status = response.status();
             let text = response.text().await.unwrap_or_default();
-            anyhow::bail!("Readwise Reader API error {}: {}", status, text);
+            return Err(api_error("Readwise Reader API", status, text));
+        }
+
+        let saved: Option<SaveResponse> = response.json().await.ok();
+        Ok(saved.and_then(|saved| saved.id))
+    }
+
+    async fn delete_document(&self, token: &str, id: &str) -> Result<()> {
+        let response = self
+            .client
+            .delete(format!("{}/v3/delete/{}/", self.base_url, id))
+            .header("Authorization", format!("Token {}", token))
+            .with_request_id()
+            .send()
+            .await?;
+
+        if !response.status().is_success() && response.status() != StatusCode::NOT_FOUND {
+            let status = response.status();
+            let text = response.text().await.unwrap_or_default();
+            return Err(api_error("Readwise Reader API", status, text));
         }
 
         Ok(())
@@ -126,16 +319,228 @@ impl ReadwiseClient for HttpReadwiseClient {
             .client
             .get(format!("{}/v2/auth/", self.base_url))
             .header("Authorization", format!("Token {}", token))
+            .with_request_id()
             .send()
             .await?;
 
         Ok(response.status().as_u16() == 204)
     }
+
+    async fn list_documents(
+        &self,
+        token: &str,
+        updated_after: Option<DateTime<Utc>>,
+        location: Option<&str>,
+    ) -> Result<Vec<ReaderDocument>> {
+        #[derive(Deserialize)]
+        #[serde(rename_all = "camelCase")]
+        struct ListResponse {
+            results: Vec<ReaderDocument>,
+            next_page_cursor: Option<String>,
+        }
+
+        let mut documents = Vec::new();
+        let mut cursor: Option<String> = None;
+        let mut pages = 0;
+        let mut rate_limited = 0;
+
+        loop {
+            let mut query: Vec<(&str, String)> = Vec::new();
+            if let Some(after) = updated_after {
+                query.push(("updatedAfter", after.to_rfc3339()));
+            }
+            if let Some(location) = location {
+                query.push(("location", location.to_string()));
+            }
+            if let Some(cursor) = &cursor {
+                query.push(("pageCursor", cursor.clone()));
+            }
+
+            let response = self
+                .client
+                .get(format!("{}/v3/list/", self.base_url))
+                .header("Authorization", format!("Token {}", token))
+                .query(&query)
+                .with_request_id()
+                .send()
+                .await?;
+
+            let status = response.status();
+            if status == StatusCode::TOO_MANY_REQUESTS && rate_limited < LIST_RATE_LIMIT_RETRIES {
+                rate_limited += 1;
+                let wait = retry_after(&response);
+                warn!("Reader list rate limited, retrying in {:?}", wait);
+                tokio::time::sleep(wait).await;
+                continue;
+            }
+            if !status.is_success() {
+                let text = response.text().await.unwrap_or_default();
+                return Err(api_error("Readwise Reader API", status, text));
+            }
+
+            let page: ListResponse = response.json().await?;
+            documents.extend(page.results);
+            pages += 1;
+            match page.next_page_cursor.filter(|next| !next.is_empty()) {
+                Some(_) if pages == MAX_LIST_PAGES => {
+                    warn!("Reader list stopped after {} pages", pages);
+                    return Ok(documents);
+                }
+                Some(next) => cursor = Some(next),
+                None => return Ok(documents),
+            }
+        }
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use axum::extract::Query;
+    use axum::http::HeaderMap;
+    use axum::response::IntoResponse;
+    use axum::routing::get;
+    use axum::{Json, Router};
+    use serde_json::{json, Value};
+    use std::collections::HashMap;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+
+    #[test]
+    fn test_over_limit_highlight_truncated() {
+        let mut highlight = Highlight {
+            text: "é".repeat(MAX_HIGHLIGHT_CHARS + 100),
+            title: None,
+            author: None,
+            source_url: None,
+            category: None,
+            note: None,
+            highlight_url: None,
+        };
+        assert!(highlight.truncate_text());
+        assert_eq!(highlight.text.chars().count(), MAX_HIGHLIGHT_CHARS);
+        assert!(highlight.text.ends_with("é…[truncated]"));
+
+        // Already within the limit: left alone
+        assert!(!highlight.truncate_text());
+        highlight.text = "short".to_string();
+        assert!(!highlight.truncate_text());
+        assert_eq!(highlight.text, "short");
+    }
+
+    /// Local stand-in for the Reader list endpoint, serving two pages
+    async fn mock_reader() -> String {
+        let app = Router::new().route(
+            "/v3/list/",
+            get(
+                |headers: HeaderMap, Query(params): Query<HashMap<String, String>>| async move {
+                    assert_eq!(headers["authorization"], "Token rw-token");
+                    assert_eq!(params["location"], "later");
+                    assert_eq!(params["updatedAfter"], "2026-01-01T00:00:00+00:00");
+
+                    let page: Value = match params.get("pageCursor").map(String::as_str) {
+                        None => json!({
+                            "count": 3,
+                            "nextPageCursor": "page-2",
+                            "results": [
+                                document("a", "https://example.com/a"),
+                                document("b", "https://example.com/b"),
+                            ]
+                        }),
+                        Some("page-2") => json!({
+                            "count": 3,
+                            "nextPageCursor": null,
+                            "results": [document("c", "https://example.com/c")]
+                        }),
+                        Some(other) => panic!("Unexpected cursor {}", other),
+                    };
+                    Json(page)
+                },
+            ),
+        );
+
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+        format!("http://{}", addr)
+    }
+
+    fn document(id: &str, source_url: &str) -> Value {
+        json!({
+            "id": id,
+            "url": format!("https://read.readwise.io/read/{}", id),
+            "source_url": source_url,
+            "title": format!("Document {}", id),
+            "author": "someone",
+            "location": "later",
+            "created_at": "2026-01-02T03:04:05.000000+00:00",
+            "updated_at": "2026-01-02T03:04:05.000000+00:00"
+        })
+    }
+
+    #[tokio::test]
+    async fn test_list_documents_follows_pages() {
+        let client = HttpReadwiseClient::new().with_base_url(&mock_reader().await);
+        let updated_after = "2026-01-01T00:00:00Z".parse().unwrap();
+
+        let documents = client
+            .list_documents("rw-token", Some(updated_after), Some("later"))
+            .await
+            .unwrap();
+
+        let ids: Vec<&str> = documents.iter().map(|doc| doc.id.as_str()).collect();
+        assert_eq!(ids, vec!["a", "b", "c"]);
+        assert_eq!(
+            documents[2].source_url.as_deref(),
+            Some("https://example.com/c")
+        );
+        assert_eq!(documents[0].location.as_deref(), Some("later"));
+    }
+
+    /// Reader list endpoint that rate limits the first request (or every
+    /// one, if `always_limited`) and otherwise never runs out of pages
+    async fn mock_busy_reader(always_limited: bool) -> String {
+        let requests = Arc::new(AtomicUsize::new(0));
+        let app = Router::new().route(
+            "/v3/list/",
+            get(move || async move {
+                let n = requests.fetch_add(1, Ordering::SeqCst);
+                if always_limited || n == 0 {
+                    return (
+                        StatusCode::TOO_MANY_REQUESTS,
+                        [(header::RETRY_AFTER, "0")],
+                        Json(json!({ "detail": "Request was throttled." })),
+                    )
+                        .into_response();
+                }
+                Json(json!({
+                    "nextPageCursor": "more",
+                    "results": [document(&n.to_string(), "https://example.com")]
+                }))
+                .into_response()
+            }),
+        );
+
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+        format!("http://{}", addr)
+    }
+
+    #[tokio::test]
+    async fn test_list_documents_waits_out_rate_limits_and_caps_pages() {
+        let client = HttpReadwiseClient::new().with_base_url(&mock_busy_reader(false).await);
+        let documents = client.list_documents("rw-token", None, None).await.unwrap();
+        assert_eq!(documents.len(), MAX_LIST_PAGES);
+
+        let client = HttpReadwiseClient::new().with_base_url(&mock_busy_reader(true).await);
+        let error = client
+            .list_documents("rw-token", None, None)
+            .await
+            .unwrap_err();
+        let error = error.downcast_ref::<ReadwiseApiError>().unwrap();
+        assert_eq!(error.status, StatusCode::TOO_MANY_REQUESTS);
+    }
 
     #[test]
     fn test_highlight_serialization() {
@@ -146,6 +551,7 @@ mod tests {
             source_url: Some("https://example.com".to_string()),
             category: Some("tweets".to_string()),
             note: None,
+            highlight_url: None,
         };
 
         let json = serde_json::to_string(&highlight).unwrap();
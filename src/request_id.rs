@@ -0,0 +1,100 @@
+//! Per-operation request IDs
+//!
+//! Each bookmark poll, DM poll, failed-save retry pass, and inbound web
+//! request runs under its own ID; a bookmark save queued by a poll runs
+//! under the poll's. It's recorded on a `request_id` span, so every log line of the
+//! operation carries it, and sent as `x-request-id` on outbound Bluesky and
+//! Readwise requests made while it runs (see `http_client::WithRequestId`).
+
+use std::future::Future;
+
+use axum::{extract::Request, http::HeaderValue, middleware::Next, response::Response};
+use tracing::{info_span, Instrument};
+use uuid::Uuid;
+
+/// Header carrying the request ID
+pub const REQUEST_ID_HEADER: &str = "x-request-id";
+
+tokio::task_local! {
+    static REQUEST_ID: Uuid;
+}
+
+/// The ID of the operation running on this task, if any
+pub fn current() -> Option<Uuid> {
+    REQUEST_ID.try_with(|id| *id).ok()
+}
+
+/// Run an operation under a fresh request ID
+pub async fn traced<F: Future>(operation: F) -> F::Output {
+    traced_as(Uuid::new_v4(), operation).await
+}
+
+/// Run an operation under an earlier operation's ID (e.g. a queued job's),
+/// or under a fresh one without it
+pub async fn continued<F: Future>(id: Option<Uuid>, operation: F) -> F::Output {
+    traced_as(id.unwrap_or_else(Uuid::new_v4), operation).await
+}
+
+/// Run an operation under the given request ID
+async fn traced_as<F: Future>(id: Uuid, operation: F) -> F::Output {
+    let span = info_span!("operation", request_id = %id);
+    REQUEST_ID.scope(id, operation.instrument(span)).await
+}
+
+/// Middleware running each request under a request ID, echoed in the response
+///
+/// A caller's `x-request-id` is kept if it's a UUID; otherwise a new one is made.
+pub async fn assign_request_id(request: Request, next: Next) -> Response {
+    let id = request
+        .headers()
+        .get(REQUEST_ID_HEADER)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.parse().ok())
+        .unwrap_or_else(Uuid::new_v4);
+
+    let mut response = traced_as(id, next.run(request)).await;
+    if let Ok(value) = HeaderValue::from_str(&id.to_string()) {
+        response.headers_mut().insert(REQUEST_ID_HEADER, value);
+    }
+    response
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::readwise::client::{HttpReadwiseClient, ReadwiseClient};
+    use crate::test_support::MockReadwise;
+
+    #[tokio::test]
+    async fn test_outbound_request_carries_request_id() {
+        let server = MockReadwise::start().await;
+        let client = HttpReadwiseClient::new().with_base_url(&server.url);
+
+        let id = traced(async {
+            client.verify_token("token").await.unwrap();
+            current().unwrap()
+        })
+        .await;
+
+        let received = server.received("/v2/auth/");
+        assert_eq!(received[0]["request_id"], id.to_string());
+    }
+
+    #[tokio::test]
+    async fn test_continued_operation_keeps_its_id() {
+        let id = Uuid::new_v4();
+        assert_eq!(continued(Some(id), async { current() }).await, Some(id));
+        assert!(continued(None, async { current() }).await.is_some());
+    }
+
+    #[tokio::test]
+    async fn test_no_request_id_outside_operation() {
+        assert_eq!(current(), None);
+        let server = MockReadwise::start().await;
+        let client = HttpReadwiseClient::new().with_base_url(&server.url);
+
+        client.verify_token("token").await.unwrap();
+
+        assert!(server.received("/v2/auth/")[0]["request_id"].is_null());
+    }
+}
@@ -0,0 +1,146 @@
+//! Per-user author allowlist/denylist for bookmark auto-saves
+//!
+//! Entries are handles or DIDs. Handles are resolved to DIDs through the
+//! shared cache, so a handle change doesn't let a denied author slip through.
+
+use tracing::warn;
+
+use crate::bluesky::{Author, HandleCache};
+
+/// Split a comma- or whitespace-separated list of handles/DIDs
+pub fn parse_author_list(input: &str) -> Vec<String> {
+    let mut entries: Vec<String> = Vec::new();
+    for entry in input.split(|c: char| c == ',' || c.is_whitespace()) {
+        let entry = normalize_entry(entry);
+        if !entry.is_empty() && !entries.contains(&entry) {
+            entries.push(entry);
+        }
+    }
+    entries
+}
+
+/// Trim, drop a leading `@`, and lowercase handles (DIDs are case-sensitive)
+fn normalize_entry(entry: &str) -> String {
+    let entry = entry.trim().trim_start_matches('@');
+    if entry.starts_with("did:") {
+        entry.to_string()
+    } else {
+        entry.to_ascii_lowercase()
+    }
+}
+
+/// Resolved allowlist and denylist for one user
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct AuthorFilter {
+    allow: Vec<String>,
+    deny: Vec<String>,
+}
+
+impl AuthorFilter {
+    /// Build a filter from already-resolved entries
+    pub fn new(allow: Vec<String>, deny: Vec<String>) -> Self {
+        Self { allow, deny }
+    }
+
+    /// Whether a post by this author should be saved
+    ///
+    /// The denylist wins; a non-empty allowlist must match.
+    pub fn allows(&self, author: &Author) -> bool {
+        let matches =
+            |entry: &String| *entry == author.did || entry.eq_ignore_ascii_case(&author.handle);
+        if self.deny.iter().any(matches) {
+            return false;
+        }
+        self.allow.is_empty() || self.allow.iter().any(matches)
+    }
+}
+
+/// Build a filter for a user's lists, resolving handles to DIDs
+pub async fn resolve_filter(
+    handles: &HandleCache,
+    allowlist: &[String],
+    denylist: &[String],
+) -> AuthorFilter {
+    AuthorFilter::new(
+        resolve_all(handles, allowlist).await,
+        resolve_all(handles, denylist).await,
+    )
+}
+
+async fn resolve_all(handles: &HandleCache, entries: &[String]) -> Vec<String> {
+    let mut resolved = Vec::with_capacity(entries.len());
+    for entry in entries {
+        resolved.push(resolve(handles, entry).await);
+    }
+    resolved
+}
+
+/// DID for an entry, or the handle itself if it can't be resolved
+async fn resolve(handles: &HandleCache, entry: &str) -> String {
+    let entry = normalize_entry(entry);
+    if entry.starts_with("did:") {
+        return entry;
+    }
+
+    match handles.resolve_cached(&entry).await {
+        Ok(did) => did,
+        Err(e) => {
+            // Fall back to matching the handle
+            warn!("Author filter: {}", e);
+            entry
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn author(did: &str, handle: &str) -> Author {
+        Author {
+            did: did.to_string(),
+            handle: handle.to_string(),
+            display_name: None,
+            labels: Vec::new(),
+        }
+    }
+
+    fn list(entries: &[&str]) -> Vec<String> {
+        entries.iter().map(|e| e.to_string()).collect()
+    }
+
+    #[test]
+    fn test_allowlist_only() {
+        let filter = AuthorFilter::new(list(&["did:plc:alice", "bob.bsky.social"]), vec![]);
+        assert!(filter.allows(&author("did:plc:alice", "alice.bsky.social")));
+        assert!(filter.allows(&author("did:plc:bob", "Bob.bsky.social")));
+        assert!(!filter.allows(&author("did:plc:carol", "carol.bsky.social")));
+    }
+
+    #[test]
+    fn test_denylist_only() {
+        let filter = AuthorFilter::new(vec![], list(&["did:plc:spam"]));
+        assert!(!filter.allows(&author("did:plc:spam", "spam.bsky.social")));
+        assert!(filter.allows(&author("did:plc:alice", "alice.bsky.social")));
+    }
+
+    #[test]
+    fn test_denylist_wins_over_allowlist() {
+        let filter = AuthorFilter::new(
+            list(&["did:plc:alice", "did:plc:bob"]),
+            list(&["did:plc:bob"]),
+        );
+        assert!(filter.allows(&author("did:plc:alice", "alice.bsky.social")));
+        assert!(!filter.allows(&author("did:plc:bob", "bob.bsky.social")));
+        assert!(!filter.allows(&author("did:plc:carol", "carol.bsky.social")));
+    }
+
+    #[test]
+    fn test_parse_author_list() {
+        assert_eq!(
+            parse_author_list("@Alice.bsky.social, did:plc:AbC\nalice.bsky.social  "),
+            vec!["alice.bsky.social", "did:plc:AbC"]
+        );
+        assert!(parse_author_list(" , ").is_empty());
+    }
+}
@@ -1,62 +1,280 @@
 //! Bookmark sync service
 //!
-//! Polls user bookmarks and saves new ones to Readwise.
+//! Polls user bookmarks and saves new ones to Readwise. With a save queue,
+//! polls only enqueue new bookmarks and save workers do the saving.
 
-use anyhow::Result;
+use anyhow::{anyhow, Result};
+use async_trait::async_trait;
+use chrono::Utc;
+use std::sync::Arc;
 use std::time::Duration;
-use tokio::time::interval;
-use tracing::{debug, error, info, warn};
+use tokio::sync::Semaphore;
+use tokio::time::{interval, interval_at, sleep, Instant, Interval};
+use tracing::{debug, error, info, instrument, warn};
+use uuid::Uuid;
 
-use crate::bluesky::BlueskyClient;
+use crate::bluesky::handles::ClientHandleResolver;
+use crate::bluesky::{
+    is_token_rejected, BlueskyClient, BookmarkItem, BookmarkView, Embed, HandleCache, PostView,
+};
+use crate::content::oembed::OEmbedClient;
+use crate::content::{text_length, HighlightTemplates, ThreadLimits};
 use crate::db::models::{User, UserSettings};
+use crate::db::queries::Database;
+use crate::features::FeatureFlags;
 use crate::readwise::client::ReadwiseClient;
-use crate::services::processor::{PostProcessor, ProcessOptions};
+use crate::request_id;
+use crate::services::author_filter::{resolve_filter, AuthorFilter};
+use crate::services::cleanup::past_retention;
+use crate::services::processor::{
+    OutcomeKind, PostProcessor, ProcessError, ProcessOptions, ProcessOutcome,
+};
+use crate::services::save_queue::{SaveJob, SaveJobs, SaveQueue};
+
+/// Failed saves are retried this many times in total before giving up
+pub const MAX_SAVE_ATTEMPTS: i32 = 5;
+
+/// Delay before the first retry; doubles with each attempt
+const RETRY_BASE_DELAY: Duration = Duration::from_secs(60);
+
+/// Longest wait between retries
+const RETRY_MAX_DELAY: Duration = Duration::from_secs(6 * 60 * 60);
+
+/// How often the retry loop looks for due saves
+const RETRY_POLL_INTERVAL: Duration = Duration::from_secs(60);
+
+/// Failed saves retried per pass
+const RETRY_BATCH_SIZE: i64 = 50;
+
+/// Shortest poll interval a user may set
+pub const MIN_POLL_INTERVAL: Duration = Duration::from_secs(10);
+
+/// Most bookmark pages a backfill reads, in case a cursor never ends
+const MAX_BACKFILL_PAGES: usize = 1000;
+
+/// Wait after Readwise rate-limits a backfill save before trying it again
+const BACKFILL_RATE_LIMIT_PAUSE: Duration = Duration::from_secs(60);
+
+/// Rate-limited retries of one backfill save before it goes to the retry loop
+const BACKFILL_RATE_LIMIT_RETRIES: u32 = 3;
+
+/// How often polling checks whether the user has changed their handle
+const HANDLE_CHECK_INTERVAL: Duration = Duration::from_secs(60 * 60);
+
+/// Per-user poll timer that follows the stored interval
+struct PollSchedule {
+    period: Duration,
+    ticker: Interval,
+}
+
+impl PollSchedule {
+    /// First tick fires immediately
+    fn new(period: Duration) -> Self {
+        Self {
+            period,
+            ticker: interval(period),
+        }
+    }
+
+    /// Switch to a new period, restarting the timer; returns whether it changed
+    fn set_period(&mut self, period: Duration) -> bool {
+        if period == self.period {
+            return false;
+        }
+        self.period = period;
+        self.ticker = interval_at(Instant::now() + period, period);
+        true
+    }
+}
 
 /// Bookmark sync service configuration
 pub struct BookmarkSyncConfig {
     /// Polling interval
     pub poll_interval: Duration,
+    /// Pause between saves while backfilling, to go easy on Readwise
+    pub backfill_pause: Duration,
+    /// Days processed rows are kept; polls skip bookmarks older than that
+    pub retention_days: Option<u32>,
 }
 
 impl Default for BookmarkSyncConfig {
     fn default() -> Self {
         Self {
             poll_interval: Duration::from_secs(30),
+            backfill_pause: Duration::from_secs(1),
+            retention_days: None,
         }
     }
 }
 
+/// Gets a fresh authenticated client for a user whose token was rejected
+#[async_trait]
+pub trait SessionRefresher<B>: Send + Sync {
+    async fn refresh(&self, user: &User) -> Result<B>;
+}
+
 /// Bookmark sync service
 pub struct BookmarkSyncService<B: BlueskyClient, R: ReadwiseClient> {
     processor: PostProcessor<B, R>,
+    db: Database,
     config: BookmarkSyncConfig,
+    /// Resolves handles in users' author filters
+    handles: Arc<HandleCache>,
+    /// Without one, a rejected token stops sync straight away
+    refresher: Option<Arc<dyn SessionRefresher<B>>>,
+    /// Hand new bookmarks to save workers instead of saving while polling
+    save_queue: Option<SaveQueue>,
+}
+
+/// What a poll did with one bookmark
+enum BookmarkOutcome {
+    Saved(Box<ProcessOutcome>),
+    /// Handed to the save workers
+    Queued,
+    Skipped,
 }
 
-impl<B: BlueskyClient + Clone, R: ReadwiseClient + Clone> BookmarkSyncService<B, R> {
+/// Handle cache TTL when none is shared in
+const DEFAULT_HANDLE_CACHE_TTL: Duration = Duration::from_secs(3600);
+
+impl<B: BlueskyClient + Clone + 'static, R: ReadwiseClient + Clone> BookmarkSyncService<B, R> {
     /// Create a new bookmark sync service
-    pub fn new(bluesky: B, readwise: R, config: BookmarkSyncConfig) -> Self {
+    pub fn new(bluesky: B, readwise: R, db: Database, config: BookmarkSyncConfig) -> Self {
         Self {
-            processor: PostProcessor::new(bluesky, readwise),
+            handles: Arc::new(HandleCache::new(
+                Arc::new(ClientHandleResolver(bluesky.clone())),
+                DEFAULT_HANDLE_CACHE_TTL,
+            )),
+            processor: PostProcessor::new(bluesky, readwise)
+                .with_link_dedup(db.clone())
+                .with_save_audit(db.clone()),
+            db,
             config,
+            refresher: None,
+            save_queue: None,
         }
     }
 
+    /// Share a Readwise save limiter with other services
+    pub fn with_save_limiter(mut self, limiter: Arc<Semaphore>) -> Self {
+        self.processor = self.processor.with_save_limiter(limiter);
+        self
+    }
+
+    /// Use custom highlight title and note templates
+    pub fn with_highlight_templates(mut self, templates: HighlightTemplates) -> Self {
+        self.processor = self.processor.with_highlight_templates(templates);
+        self
+    }
+
+    /// Title saved video links from oEmbed
+    pub fn with_oembed(mut self, oembed: Arc<dyn OEmbedClient>) -> Self {
+        self.processor = self.processor.with_oembed(oembed);
+        self
+    }
+
+    /// Drop these query parameters from extracted links
+    pub fn with_strip_query_params(mut self, params: Vec<String>) -> Self {
+        self.processor = self.processor.with_strip_query_params(params);
+        self
+    }
+
+    /// Read the quote and language flags for each bookmark as it's saved
+    pub fn with_features(mut self, features: Arc<FeatureFlags>) -> Self {
+        self.processor = self.processor.with_features(features);
+        self
+    }
+
+    /// Cap how much of a thread goes into a saved document
+    pub fn with_thread_limits(mut self, limits: ThreadLimits) -> Self {
+        self.processor = self.processor.with_thread_limits(limits);
+        self
+    }
+
+    /// Share a handle → DID cache with other services
+    pub fn with_handle_cache(mut self, handles: Arc<HandleCache>) -> Self {
+        self.handles = handles;
+        self
+    }
+
+    /// Enqueue new bookmarks for save workers rather than saving inline
+    pub fn with_save_queue(mut self, queue: SaveQueue) -> Self {
+        self.save_queue = Some(queue);
+        self
+    }
+
+    /// Refresh user sessions when their access token is rejected
+    pub fn with_session_refresher(mut self, refresher: Arc<dyn SessionRefresher<B>>) -> Self {
+        self.refresher = Some(refresher);
+        self
+    }
+
     /// Start the bookmark sync loop for a user
     /// This should be spawned as a tokio task
+    ///
+    /// A first run also backfills existing bookmarks alongside polling, so
+    /// new bookmarks aren't held up behind the import.
+    ///
+    /// Returns once the user turns sync off (or their settings are deleted),
+    /// or once their tokens are rejected and can't be refreshed; sync is then
+    /// disabled until they log in again.
+    #[instrument(skip_all, fields(user_did = %user.bluesky_did))]
     pub async fn run_for_user(
         &self,
-        _user: User,
-        _settings: UserSettings,
+        user: User,
+        settings: UserSettings,
         bluesky_client: B,
     ) -> Result<()> {
-        let mut ticker = interval(self.config.poll_interval);
-
         info!("Starting bookmark sync");
 
+        let backfill = async {
+            if settings.backfilled {
+                return;
+            }
+            match self.backfill(&bluesky_client, &user, &settings).await {
+                Ok(count) => info!("Backfill saved {} existing bookmarks", count),
+                // Left unfinished, so it runs again next time sync starts
+                Err(e) => warn!("Backfill failed: {}", e),
+            }
+        };
+        let polling = self.poll_loop(user.clone(), settings.clone(), bluesky_client.clone());
+        tokio::pin!(polling);
+
+        tokio::select! {
+            result = &mut polling => return result,
+            () = backfill => {}
+        }
+        polling.await
+    }
+
+    /// Poll for new bookmarks until sync is turned off or access is revoked
+    async fn poll_loop(
+        &self,
+        mut user: User,
+        mut settings: UserSettings,
+        mut bluesky_client: B,
+    ) -> Result<()> {
+        let mut schedule = PollSchedule::new(self.poll_interval(&settings));
+        let mut handle_checked: Option<Instant> = None;
+
         loop {
-            ticker.tick().await;
+            schedule.ticker.tick().await;
+            if !self
+                .reload_settings(user.id, &mut settings, &mut schedule)
+                .await
+            {
+                info!("Bookmark sync turned off, stopping");
+                return Ok(());
+            }
+            if handle_checked.is_none_or(|at| at.elapsed() >= HANDLE_CHECK_INTERVAL) {
+                self.sync_handle(&bluesky_client, &mut user).await;
+                handle_checked = Some(Instant::now());
+            }
 
-            match self.poll_bookmarks(&bluesky_client, &_settings).await {
+            let result = self
+                .poll_with_refresh(&mut bluesky_client, &user, &settings)
+                .await;
+            match result {
                 Ok(count) => {
                     if count > 0 {
                         info!("Processed {} new bookmarks", count);
@@ -64,6 +282,14 @@ impl<B: BlueskyClient + Clone, R: ReadwiseClient + Clone> BookmarkSyncService<B,
                         debug!("No new bookmarks");
                     }
                 }
+                Err(e) if is_token_rejected(&e) => {
+                    warn!(
+                        "Access revoked, pausing sync until the user logs in again: {}",
+                        e
+                    );
+                    self.db.mark_needs_reauth(user.id).await?;
+                    return Ok(());
+                }
                 Err(e) => {
                     error!("Error polling bookmarks: {}", e);
                 }
@@ -71,36 +297,170 @@ impl<B: BlueskyClient + Clone, R: ReadwiseClient + Clone> BookmarkSyncService<B,
         }
     }
 
+    /// Poll, and if the token is rejected, refresh the session and poll once more
+    ///
+    /// Still-rejected tokens (or a failed refresh) come back as `TokenRejected`.
+    async fn poll_with_refresh(
+        &self,
+        bluesky: &mut B,
+        user: &User,
+        settings: &UserSettings,
+    ) -> Result<usize> {
+        match request_id::traced(self.poll_bookmarks(bluesky, user, settings)).await {
+            Err(e) if is_token_rejected(&e) => {
+                let Some(refresher) = &self.refresher else {
+                    return Err(e);
+                };
+                match refresher.refresh(user).await {
+                    Ok(refreshed) => *bluesky = refreshed,
+                    Err(refresh_error) => {
+                        warn!("Session refresh failed: {}", refresh_error);
+                        return Err(e);
+                    }
+                }
+                info!("Session refreshed after a rejected token");
+                request_id::traced(self.poll_bookmarks(bluesky, user, settings)).await
+            }
+            result => result,
+        }
+    }
+
+    /// Poll interval for a user: their override or the default, clamped
+    fn poll_interval(&self, settings: &UserSettings) -> Duration {
+        settings
+            .poll_interval_secs
+            .and_then(|secs| u64::try_from(secs).ok())
+            .map(Duration::from_secs)
+            .unwrap_or(self.config.poll_interval)
+            .max(MIN_POLL_INTERVAL)
+    }
+
+    /// Pick up settings changes made since the last cycle, returning whether
+    /// sync should keep running
+    ///
+    /// A new poll interval restarts the timer; on a read error the previous
+    /// settings are kept. Sync stops once it's turned off or the settings
+    /// are gone.
+    async fn reload_settings(
+        &self,
+        user_id: Uuid,
+        settings: &mut UserSettings,
+        schedule: &mut PollSchedule,
+    ) -> bool {
+        match self.db.get_user_settings(user_id).await {
+            Ok(Some(current)) => *settings = current,
+            Ok(None) => return false,
+            Err(e) => {
+                warn!("Failed to reload settings, using previous: {}", e);
+                return true;
+            }
+        }
+        if !settings.bookmark_sync_enabled {
+            return false;
+        }
+
+        let period = self.poll_interval(settings);
+        if schedule.set_period(period) {
+            info!("Poll interval changed to {}s", period.as_secs());
+        }
+        true
+    }
+
+    /// Store the user's current handle if they've changed it
+    ///
+    /// The DID stays the key; a failed profile lookup keeps the stored handle.
+    async fn sync_handle(&self, bluesky: &B, user: &mut User) {
+        let handle = match bluesky.get_profile(&user.bluesky_did).await {
+            Ok(profile) => profile.handle,
+            Err(e) => {
+                debug!("Couldn't fetch profile to check handle: {}", e);
+                return;
+            }
+        };
+        if handle == user.bluesky_handle {
+            return;
+        }
+
+        match self.db.update_user_handle(user.id, &handle).await {
+            Ok(()) => {
+                info!("Handle changed from {} to {}", user.bluesky_handle, handle);
+                user.bluesky_handle = handle;
+            }
+            Err(e) => warn!("Failed to store new handle {}: {}", handle, e),
+        }
+    }
+
     /// Poll bookmarks and process new ones
-    async fn poll_bookmarks(&self, bluesky: &B, settings: &UserSettings) -> Result<usize> {
+    ///
+    /// Run it under `request_id::traced` so one cycle's logs can be grouped.
+    #[instrument(skip_all, fields(user_did = %user.bluesky_did))]
+    async fn poll_bookmarks(
+        &self,
+        bluesky: &B,
+        user: &User,
+        settings: &UserSettings,
+    ) -> Result<usize> {
         // Get bookmarks starting from the last cursor
         let cursor = settings.last_bookmark_cursor.as_deref();
         let response = bluesky.get_bookmarks(cursor).await?;
+        debug!("Fetched {} bookmarks", response.bookmarks.len());
 
+        let filter = resolve_filter(
+            &self.handles,
+            &settings.author_allowlist,
+            &settings.author_denylist,
+        )
+        .await;
         let mut processed_count = 0;
 
         for bookmark in &response.bookmarks {
-            let post_uri = &bookmark.subject.uri;
-
-            // TODO: Check if already processed in database
-            // For now, just process all bookmarks in the response
+            // Its processed row may have been purged; saving it again would
+            // replace the user's Reader document
+            if past_retention(self.config.retention_days, bookmark.created_at) {
+                debug!(
+                    "Skipping bookmark {} older than the retention",
+                    bookmark.subject.uri
+                );
+                continue;
+            }
 
-            let options = ProcessOptions {
-                extract_links: settings.extract_links,
-                note: None,
-            };
+            // Not marked processed, so loosening the filter picks these up later
+            if let BookmarkItem::Post(post) = &bookmark.item {
+                if !filter.allows(&post.author) {
+                    debug!(
+                        "Skipping bookmark {} by filtered author {}",
+                        bookmark.subject.uri, post.author.handle
+                    );
+                    continue;
+                }
+            }
 
+            let bookmarked = bookmarked_post(bookmark);
             match self
-                .processor
-                .process_post(post_uri, &settings.readwise_token, options)
+                .process_bookmark(
+                    user,
+                    settings,
+                    bookmark,
+                    &bookmarked,
+                    self.save_queue.as_ref(),
+                )
                 .await
             {
-                Ok(_) => {
+                Ok(BookmarkOutcome::Skipped) => {}
+                Ok(BookmarkOutcome::Queued) => {
+                    processed_count += 1;
+                    debug!("Queued bookmark {}", bookmarked.uri);
+                }
+                Ok(BookmarkOutcome::Saved(outcome)) => {
                     processed_count += 1;
-                    // TODO: Mark as processed in database
+                    debug!(
+                        "Saved bookmark {} as {:?} ({} links, id {:?})",
+                        bookmarked.uri, outcome.kind, outcome.links_saved, outcome.readwise_id
+                    );
                 }
                 Err(e) => {
-                    warn!("Failed to process bookmark {}: {}", post_uri, e);
+                    warn!("Failed to process bookmark {}: {}", bookmarked.uri, e);
+                    self.dead_letter(user.id, bookmarked.uri, &e).await;
                 }
             }
         }
@@ -112,11 +472,1413 @@ impl<B: BlueskyClient + Clone, R: ReadwiseClient + Clone> BookmarkSyncService<B,
 
         Ok(processed_count)
     }
+
+    /// Import all of a user's existing bookmarks, oldest first
+    ///
+    /// Runs once, when sync is first enabled. Saves go one at a time with a
+    /// pause between them (skipping the save queue), wait out Readwise rate
+    /// limits, and report progress through the user's settings. Settings are
+    /// re-read before each save; if sync is turned off the backfill stops,
+    /// unfinished, and picks up again when sync next starts.
+    pub async fn backfill(
+        &self,
+        bluesky: &B,
+        user: &User,
+        settings: &UserSettings,
+    ) -> Result<usize> {
+        let mut bookmarks = Vec::new();
+        let mut cursor: Option<String> = None;
+        for page in 1.. {
+            let response = bluesky.get_bookmarks(cursor.as_deref()).await?;
+            let empty = response.bookmarks.is_empty();
+            bookmarks.extend(response.bookmarks);
+            match response.cursor {
+                Some(next) if !empty && cursor.as_ref() != Some(&next) => cursor = Some(next),
+                _ => break,
+            }
+            if page == MAX_BACKFILL_PAGES {
+                warn!("Backfill stopped after {} bookmark pages", page);
+                break;
+            }
+        }
+        // Pages list the newest bookmarks first
+        bookmarks.reverse();
+
+        let total = i32::try_from(bookmarks.len()).unwrap_or(i32::MAX);
+        info!("Backfilling {} bookmarks", total);
+        self.db.start_backfill(user.id, total).await?;
+
+        let filter = resolve_filter(
+            &self.handles,
+            &settings.author_allowlist,
+            &settings.author_denylist,
+        )
+        .await;
+        let mut settings = settings.clone();
+        let mut saved = 0;
+        for (done, bookmark) in (1..).zip(&bookmarks) {
+            match self.db.get_user_settings(user.id).await {
+                Ok(Some(current)) if current.bookmark_sync_enabled => settings = current,
+                Ok(_) => {
+                    info!("Bookmark sync turned off, stopping backfill");
+                    return Ok(saved);
+                }
+                Err(e) => warn!("Failed to reload settings, using previous: {}", e),
+            }
+            if self
+                .backfill_bookmark(user, &settings, &filter, bookmark)
+                .await
+            {
+                saved += 1;
+            }
+            self.db.set_backfill_progress(user.id, done).await?;
+        }
+
+        self.db.finish_backfill(user.id).await?;
+        Ok(saved)
+    }
+
+    /// Save one backfilled bookmark inline, returning whether it was saved
+    ///
+    /// Rate-limited saves are retried after a pause; other failures (and
+    /// saves still rate-limited) go to the retry loop.
+    async fn backfill_bookmark(
+        &self,
+        user: &User,
+        settings: &UserSettings,
+        filter: &AuthorFilter,
+        bookmark: &BookmarkView,
+    ) -> bool {
+        if let BookmarkItem::Post(post) = &bookmark.item {
+            if !filter.allows(&post.author) {
+                return false;
+            }
+        }
+
+        let bookmarked = bookmarked_post(bookmark);
+        let mut rate_limited = 0;
+        loop {
+            match self
+                .process_bookmark(user, settings, bookmark, &bookmarked, None)
+                .await
+            {
+                Ok(BookmarkOutcome::Saved(_)) => {
+                    sleep(self.config.backfill_pause).await;
+                    return true;
+                }
+                Ok(_) => return false,
+                Err(e) if is_rate_limited(&e) && rate_limited < BACKFILL_RATE_LIMIT_RETRIES => {
+                    rate_limited += 1;
+                    warn!(
+                        "Readwise rate-limited the backfill, pausing {}s",
+                        BACKFILL_RATE_LIMIT_PAUSE.as_secs()
+                    );
+                    sleep(BACKFILL_RATE_LIMIT_PAUSE).await;
+                }
+                Err(e) => {
+                    warn!("Failed to backfill bookmark {}: {}", bookmarked.uri, e);
+                    self.dead_letter(user.id, bookmarked.uri, &e).await;
+                    return false;
+                }
+            }
+        }
+    }
+
+    /// Save (or queue, given a queue) one bookmark unless it's already processed or unavailable
+    async fn process_bookmark(
+        &self,
+        user: &User,
+        settings: &UserSettings,
+        bookmark: &BookmarkView,
+        bookmarked: &BookmarkedPost<'_>,
+        queue: Option<&SaveQueue>,
+    ) -> Result<BookmarkOutcome> {
+        let post_uri = bookmarked.uri;
+
+        if self.db.is_bookmark_processed(user.id, post_uri).await? {
+            return Ok(BookmarkOutcome::Skipped);
+        }
+        if self.db.has_failed_save(user.id, post_uri).await? {
+            // The retry loop owns it now
+            return Ok(BookmarkOutcome::Skipped);
+        }
+
+        let skip_labels = settings.labels_to_skip();
+
+        match &bookmark.item {
+            BookmarkItem::Post(post) if post.has_label(&skip_labels) => {
+                debug!("Skipping labeled bookmark {}", post_uri);
+                self.db.mark_bookmark_processed(user.id, post_uri).await?;
+                return Ok(BookmarkOutcome::Skipped);
+            }
+            // A bare quote is saved as the quoted post, so its own length doesn't matter
+            BookmarkItem::Post(post)
+                if bookmarked.shared_by.is_none() && too_short(post, settings.min_post_length) =>
+            {
+                // Won't get longer; don't recheck it every poll
+                debug!("Skipping short bookmark {}", post_uri);
+                self.db.mark_bookmark_processed(user.id, post_uri).await?;
+                return Ok(BookmarkOutcome::Skipped);
+            }
+            BookmarkItem::Post(_) | BookmarkItem::Repost(_) => {}
+            BookmarkItem::NotFound { .. } | BookmarkItem::Blocked { .. } => {
+                // Deleted or blocked posts will never load; don't retry them every poll
+                debug!("Skipping unavailable bookmark {}", post_uri);
+                self.db.mark_bookmark_processed(user.id, post_uri).await?;
+                return Ok(BookmarkOutcome::Skipped);
+            }
+            BookmarkItem::Unknown => {
+                warn!("Skipping bookmark {} with unknown item type", post_uri);
+                return Ok(BookmarkOutcome::Skipped);
+            }
+        }
+
+        if let Some(queue) = queue {
+            let job = SaveJob {
+                user_id: user.id,
+                post_uri: post_uri.to_string(),
+                note: bookmarked.note(),
+                request_id: request_id::current(),
+            };
+            // A full queue sends the bookmark to the retry loop rather than blocking polls
+            queue.enqueue(job).await?;
+            return Ok(BookmarkOutcome::Queued);
+        }
+
+        let outcome = self
+            .save_bookmark(user.id, settings, post_uri, bookmarked.note())
+            .await?;
+        Ok(match outcome.kind {
+            OutcomeKind::Skipped => BookmarkOutcome::Skipped,
+            _ => BookmarkOutcome::Saved(Box::new(outcome)),
+        })
+    }
+
+    /// Mark a bookmarked post processed and save it to Readwise
+    ///
+    /// The processed row is claimed first, so when two polls race on one
+    /// bookmark only the one that inserted it saves. A failed save releases
+    /// the claim for the caller to dead-letter, unless Readwise already has
+    /// the post; that keeps the claim and counts as skipped.
+    async fn save_bookmark(
+        &self,
+        user_id: Uuid,
+        settings: &UserSettings,
+        post_uri: &str,
+        note: Option<String>,
+    ) -> Result<ProcessOutcome> {
+        if !self.db.mark_bookmark_processed(user_id, post_uri).await? {
+            debug!("Bookmark {} was claimed by a concurrent poll", post_uri);
+            return Ok(ProcessOutcome::skipped());
+        }
+        match self.save_claimed(user_id, settings, post_uri, note).await {
+            Ok(outcome) => {
+                crate::metrics::bookmark_processed();
+                Ok(outcome)
+            }
+            Err(e) if is_duplicate(&e) => {
+                info!("Readwise already has {}, marking it processed", post_uri);
+                Ok(ProcessOutcome::skipped())
+            }
+            Err(e) => {
+                if let Err(unmark_error) =
+                    self.db.unmark_bookmark_processed(user_id, post_uri).await
+                {
+                    error!(
+                        "Failed to release bookmark {} after a failed save: {}",
+                        post_uri, unmark_error
+                    );
+                }
+                Err(e)
+            }
+        }
+    }
+
+    /// Save a bookmark whose processed row this call holds
+    async fn save_claimed(
+        &self,
+        user_id: Uuid,
+        settings: &UserSettings,
+        post_uri: &str,
+        note: Option<String>,
+    ) -> Result<ProcessOutcome> {
+        let options = ProcessOptions {
+            note,
+            existing_document_id: self.db.get_saved_document(user_id, post_uri).await?,
+            user_id: Some(user_id),
+            ..ProcessOptions::from_settings(settings)
+        };
+
+        let outcome = self
+            .processor
+            .process_post(post_uri, &settings.readwise_token, options)
+            .await?;
+        if let Some(id) = outcome.new_document_id() {
+            self.db.record_saved_document(user_id, post_uri, id).await?;
+        }
+        Ok(outcome)
+    }
+
+    /// Queue a failed bookmark for the retry loop
+    async fn dead_letter(&self, user_id: Uuid, post_uri: &str, error: &anyhow::Error) {
+        let next_attempt_at = Utc::now() + retry_delay(1);
+        if let Err(e) = self
+            .db
+            .record_failed_save(user_id, post_uri, &error.to_string(), next_attempt_at)
+            .await
+        {
+            error!("Failed to queue bookmark {} for retry: {}", post_uri, e);
+        }
+    }
+
+    /// Retry due failed saves forever
+    /// This should be spawned as a tokio task
+    pub async fn run_retries(&self) {
+        let mut ticker = interval(RETRY_POLL_INTERVAL);
+
+        info!("Starting failed save retries");
+
+        loop {
+            ticker.tick().await;
+
+            match request_id::traced(self.retry_failed_saves()).await {
+                Ok(0) => {}
+                Ok(count) => info!("Recovered {} failed saves", count),
+                Err(e) => error!("Error retrying failed saves: {}", e),
+            }
+        }
+    }
+
+    /// Retry every due failed save once, returning how many went through
+    async fn retry_failed_saves(&self) -> Result<usize> {
+        let mut recovered = 0;
+
+        for failed in self.db.due_failed_saves(RETRY_BATCH_SIZE).await? {
+            match self
+                .save_for_user(failed.user_id, &failed.post_uri, None)
+                .await
+            {
+                Ok(()) => {
+                    self.db.delete_failed_save(failed.id).await?;
+                    recovered += 1;
+                }
+                Err(e) if failed.attempts + 1 >= MAX_SAVE_ATTEMPTS => {
+                    warn!(
+                        "Giving up on {} after {} attempts: {}",
+                        failed.post_uri,
+                        failed.attempts + 1,
+                        e
+                    );
+                    self.db
+                        .give_up_failed_save(failed.id, &e.to_string())
+                        .await?;
+                }
+                Err(e) => {
+                    debug!("Retry of {} failed: {}", failed.post_uri, e);
+                    let next_attempt_at = Utc::now() + retry_delay(failed.attempts + 1);
+                    self.db
+                        .reschedule_failed_save(failed.id, &e.to_string(), next_attempt_at)
+                        .await?;
+                }
+            }
+        }
+
+        Ok(recovered)
+    }
+
+    /// Save a bookmark with the user's current settings, unless already
+    /// processed or the user has turned sync off
+    ///
+    /// A bookmark skipped because sync is off isn't marked processed, so the
+    /// first poll after sync is turned back on saves it.
+    async fn save_for_user(
+        &self,
+        user_id: Uuid,
+        post_uri: &str,
+        note: Option<String>,
+    ) -> Result<()> {
+        if self.db.is_bookmark_processed(user_id, post_uri).await? {
+            return Ok(());
+        }
+        let settings = self
+            .db
+            .get_user_settings(user_id)
+            .await?
+            .ok_or_else(|| anyhow!("User has no settings"))?;
+        if !settings.bookmark_sync_enabled {
+            debug!("Bookmark sync is off, not saving {}", post_uri);
+            return Ok(());
+        }
+
+        self.save_bookmark(user_id, &settings, post_uri, note)
+            .await?;
+        Ok(())
+    }
+
+    /// Forget that a post was processed and save it again with the user's
+    /// current settings
+    pub async fn reprocess(&self, user_id: Uuid, post_uri: &str) -> Result<ProcessOutcome> {
+        let settings = self
+            .db
+            .get_user_settings(user_id)
+            .await?
+            .ok_or_else(|| anyhow!("User has no settings"))?;
+        if self.db.unmark_bookmark_processed(user_id, post_uri).await? {
+            info!("Reprocessing {}", post_uri);
+        }
+        self.save_bookmark(user_id, &settings, post_uri, None).await
+    }
+
+    /// What saving a post would send to Readwise with the user's current
+    /// settings, without saving it
+    pub async fn preview(&self, user_id: Uuid, post_uri: &str) -> Result<ProcessOutcome> {
+        let settings = self.db.get_user_settings(user_id).await?;
+        let options = ProcessOptions {
+            user_id: Some(user_id),
+            dry_run: true,
+            ..settings
+                .as_ref()
+                .map(ProcessOptions::from_settings)
+                .unwrap_or_default()
+        };
+        // Nothing is written to Readwise, so no token is needed
+        Ok(self.processor.process_post(post_uri, "", options).await?)
+    }
+
+    /// Save one queued bookmark, dead-lettering it on failure
+    #[instrument(skip_all, fields(user_id = %job.user_id, post_uri = %job.post_uri))]
+    async fn run_save_job(&self, job: &SaveJob) {
+        if let Err(e) = self
+            .save_for_user(job.user_id, &job.post_uri, job.note.clone())
+            .await
+        {
+            warn!("Failed to save queued bookmark: {}", e);
+            self.dead_letter(job.user_id, &job.post_uri, &e).await;
+        }
+    }
+}
+
+impl<B, R> BookmarkSyncService<B, R>
+where
+    B: BlueskyClient + Clone + 'static,
+    R: ReadwiseClient + Clone + 'static,
+{
+    /// Drain the save queue with `workers` concurrent workers
+    /// This should be spawned as a tokio task
+    ///
+    /// Returns once every `SaveQueue` has been dropped and the queue is empty.
+    pub async fn run_save_workers(self: Arc<Self>, jobs: SaveJobs, workers: usize) {
+        let jobs = Arc::new(jobs);
+        let mut tasks = tokio::task::JoinSet::new();
+
+        info!("Starting {} save workers", workers.max(1));
+
+        for _ in 0..workers.max(1) {
+            let service = self.clone();
+            let jobs = jobs.clone();
+            tasks.spawn(async move {
+                while let Some(job) = jobs.next().await {
+                    request_id::continued(job.request_id, service.run_save_job(&job)).await;
+                }
+            });
+        }
+        while tasks.join_next().await.is_some() {}
+    }
+}
+
+/// Backoff before the next retry, after `attempts` failures
+pub fn retry_delay(attempts: i32) -> chrono::Duration {
+    let doublings = u32::try_from(attempts.saturating_sub(1))
+        .unwrap_or(0)
+        .min(16);
+    let delay = RETRY_BASE_DELAY
+        .saturating_mul(2u32.saturating_pow(doublings))
+        .min(RETRY_MAX_DELAY);
+    chrono::Duration::from_std(delay).unwrap_or(chrono::Duration::MAX)
+}
+
+/// The post a bookmark saves, and how it reached the user
+struct BookmarkedPost<'a> {
+    uri: &'a str,
+    /// Who reposted or quoted it, if the bookmark wasn't the post itself
+    shared_by: Option<(&'static str, &'a str)>,
+}
+
+impl BookmarkedPost<'_> {
+    /// Highlight note crediting the repost or quote
+    fn note(&self) -> Option<String> {
+        self.shared_by
+            .map(|(how, handle)| format!("{} by @{}", how, handle))
+    }
+}
+
+/// Follow a repost, or a quote with no text of its own, to the original post
+///
+/// Saving under the original's URI means bookmarking both the post and a
+/// repost of it saves it once.
+fn bookmarked_post(bookmark: &BookmarkView) -> BookmarkedPost<'_> {
+    match &bookmark.item {
+        BookmarkItem::Repost(repost) => BookmarkedPost {
+            uri: &repost.subject.uri,
+            shared_by: Some(("reposted", &repost.by.handle)),
+        },
+        BookmarkItem::Post(post) if post.record.text.trim().is_empty() => {
+            match &post.record.embed {
+                Some(Embed::Record { record }) => BookmarkedPost {
+                    uri: &record.uri,
+                    shared_by: Some(("quoted", &post.author.handle)),
+                },
+                _ => BookmarkedPost {
+                    uri: &bookmark.subject.uri,
+                    shared_by: None,
+                },
+            }
+        }
+        _ => BookmarkedPost {
+            uri: &bookmark.subject.uri,
+            shared_by: None,
+        },
+    }
+}
+
+/// Whether a save failed because Readwise is rate-limiting us
+fn is_rate_limited(error: &anyhow::Error) -> bool {
+    error
+        .downcast_ref::<ProcessError>()
+        .is_some_and(ProcessError::is_rate_limited)
+}
+
+/// Whether a save failed because Readwise already has the item
+fn is_duplicate(error: &anyhow::Error) -> bool {
+    matches!(
+        error.downcast_ref::<ProcessError>(),
+        Some(ProcessError::Duplicate)
+    )
+}
+
+/// Whether a post falls under the user's minimum length
+fn too_short(post: &PostView, min_post_length: i32) -> bool {
+    usize::try_from(min_post_length).is_ok_and(|min| text_length(&post.record) < min)
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::bluesky::types::*;
+    use crate::crypto::EncryptionKey;
+    use crate::db::models::SettingsUpdate;
+    use crate::readwise::client::{Document, Highlight, ReaderDocument};
+    use async_trait::async_trait;
+    use chrono::Utc;
+    use tracing_test::traced_test;
+
+    /// Bookmarks a single post; saves succeed unless `fail_saves` is set
+    #[derive(Clone)]
+    struct MockClient {
+        post: PostView,
+        fail_saves: bool,
+        /// Answer highlight saves with 409 Conflict, as if Readwise had them
+        already_saved: bool,
+        /// Reject the access token on every bookmark fetch
+        token_rejected: bool,
+        /// Bookmark a repost of `post` by this account instead of the post
+        reposted_by: Option<Author>,
+        /// Thread URIs fetched
+        fetched: Arc<std::sync::Mutex<Vec<String>>>,
+        /// Handle `get_profile` reports (None fails the lookup)
+        profile_handle: Option<String>,
+        /// Serve this many one-bookmark pages (newest first) instead of `post`
+        pages: usize,
+        /// How long ago `post` was bookmarked
+        bookmarked_days_ago: i64,
+    }
+
+    impl MockClient {
+        fn new(fail_saves: bool) -> Self {
+            Self {
+                post: post("A bookmarked post worth keeping"),
+                fail_saves,
+                already_saved: false,
+                token_rejected: false,
+                reposted_by: None,
+                fetched: Arc::default(),
+                profile_handle: None,
+                pages: 0,
+                bookmarked_days_ago: 0,
+            }
+        }
+    }
+
+    /// URI of the post bookmarked on page `page` of a paged `MockClient`
+    fn paged_uri(page: usize) -> String {
+        format!("at://did:plc:abc/app.bsky.feed.post/page{}", page)
+    }
+
+    /// Hands out clients whose tokens are still rejected
+    struct RevokedRefresher {
+        calls: std::sync::atomic::AtomicUsize,
+    }
+
+    #[async_trait]
+    impl SessionRefresher<MockClient> for RevokedRefresher {
+        async fn refresh(&self, _user: &User) -> Result<MockClient> {
+            self.calls.fetch_add(1, std::sync::atomic::Ordering::SeqCst);
+            Ok(MockClient {
+                token_rejected: true,
+                ..MockClient::new(false)
+            })
+        }
+    }
+
+    #[async_trait]
+    impl BlueskyClient for MockClient {
+        async fn get_bookmarks(&self, cursor: Option<&str>) -> Result<BookmarkResponse> {
+            if self.token_rejected {
+                return Err(crate::bluesky::TokenRejected("401 Unauthorized".to_string()).into());
+            }
+            if self.pages > 0 {
+                let page: usize = cursor.map_or(Ok(0), str::parse)?;
+                let mut post = self.post.clone();
+                post.uri = paged_uri(page);
+                return Ok(BookmarkResponse {
+                    cursor: (page + 1 < self.pages).then(|| (page + 1).to_string()),
+                    bookmarks: vec![BookmarkView {
+                        subject: StrongRef {
+                            uri: post.uri.clone(),
+                            cid: post.cid.clone(),
+                        },
+                        created_at: Utc::now(),
+                        item: BookmarkItem::Post(Box::new(post)),
+                    }],
+                });
+            }
+            let post_ref = StrongRef {
+                uri: self.post.uri.clone(),
+                cid: self.post.cid.clone(),
+            };
+            let (subject, item) = match &self.reposted_by {
+                Some(by) => {
+                    let repost_ref = StrongRef {
+                        uri: format!("at://{}/app.bsky.feed.repost/r1", by.did),
+                        cid: "bafyrepost".to_string(),
+                    };
+                    let item = BookmarkItem::Repost(Box::new(RepostView {
+                        uri: repost_ref.uri.clone(),
+                        cid: repost_ref.cid.clone(),
+                        by: by.clone(),
+                        subject: post_ref,
+                    }));
+                    (repost_ref, item)
+                }
+                None => (post_ref, BookmarkItem::Post(Box::new(self.post.clone()))),
+            };
+            Ok(BookmarkResponse {
+                cursor: None,
+                bookmarks: vec![BookmarkView {
+                    subject,
+                    created_at: Utc::now() - chrono::Duration::days(self.bookmarked_days_ago),
+                    item,
+                }],
+            })
+        }
+
+        async fn get_post_thread(&self, uri: &str) -> Result<ThreadResponse> {
+            self.fetched.lock().unwrap().push(uri.to_string());
+            Ok(ThreadResponse {
+                thread: ThreadViewPost {
+                    post: self.post.clone(),
+                    parent: None,
+                    replies: None,
+                },
+            })
+        }
+
+        async fn get_post_record(&self, _did: &str, _rkey: &str) -> Result<PostView> {
+            Err(anyhow!("unused"))
+        }
+
+        async fn send_dm(&self, _convo_id: &str, _text: &str) -> Result<()> {
+            Ok(())
+        }
+
+        async fn get_convo_for_member(&self, _did: &str) -> Result<ConvoView> {
+            Err(anyhow!("unused"))
+        }
+
+        async fn list_convos(&self) -> Result<ConvoListResponse> {
+            Ok(ConvoListResponse {
+                cursor: None,
+                convos: vec![],
+            })
+        }
+
+        async fn get_messages(&self, _convo_id: &str) -> Result<MessagesResponse> {
+            Ok(MessagesResponse {
+                cursor: None,
+                messages: vec![],
+            })
+        }
+
+        async fn mark_convo_read(&self, _convo_id: &str) -> Result<()> {
+            Ok(())
+        }
+
+        async fn resolve_handle(&self, handle: &str) -> Result<String> {
+            Ok(format!("did:plc:{}", handle))
+        }
+
+        async fn get_profile(&self, actor: &str) -> Result<Author> {
+            let handle = self
+                .profile_handle
+                .clone()
+                .ok_or_else(|| anyhow!("Profile not found"))?;
+            Ok(Author {
+                did: actor.to_string(),
+                handle,
+                display_name: None,
+                labels: Vec::new(),
+            })
+        }
+    }
+
+    #[async_trait]
+    impl ReadwiseClient for MockClient {
+        async fn save_highlight(
+            &self,
+            _token: &str,
+            _highlight: Highlight,
+        ) -> Result<Option<String>> {
+            if self.fail_saves {
+                return Err(anyhow!("Readwise API error 500"));
+            }
+            if self.already_saved {
+                return Err(crate::readwise::client::ReadwiseApiError {
+                    api: "Readwise API",
+                    status: reqwest::StatusCode::CONFLICT,
+                    body: String::new(),
+                }
+                .into());
+            }
+            Ok(Some("hl-1".to_string()))
+        }
+
+        async fn save_document(&self, _token: &str, _document: Document) -> Result<Option<String>> {
+            Ok(Some("doc-1".to_string()))
+        }
+
+        async fn delete_document(&self, _token: &str, _id: &str) -> Result<()> {
+            Ok(())
+        }
+
+        async fn verify_token(&self, _token: &str) -> Result<bool> {
+            Ok(true)
+        }
+
+        async fn list_documents(
+            &self,
+            _token: &str,
+            _updated_after: Option<chrono::DateTime<Utc>>,
+            _location: Option<&str>,
+        ) -> Result<Vec<ReaderDocument>> {
+            Ok(vec![])
+        }
+    }
+
+    fn test_service(
+        db: Database,
+        client: MockClient,
+    ) -> BookmarkSyncService<MockClient, MockClient> {
+        BookmarkSyncService::new(client.clone(), client, db, BookmarkSyncConfig::default())
+    }
+
+    async fn test_user(db: &Database) -> (User, UserSettings) {
+        let user = db
+            .create_user("did:plc:reader", "reader.bsky.social")
+            .await
+            .unwrap();
+        let settings = db
+            .create_user_settings(user.id, "rw-secret-token", true, false)
+            .await
+            .unwrap();
+        (user, settings)
+    }
+
+    const POST_URI: &str = "at://did:plc:abc/app.bsky.feed.post/1";
+
+    #[sqlx::test]
+    async fn test_backfill_walks_all_pages_oldest_first(pool: sqlx::PgPool) {
+        let db = Database::new(pool, EncryptionKey::test_key());
+        let (user, settings) = test_user(&db).await;
+        assert!(!settings.backfilled);
+        let client = MockClient {
+            pages: 3,
+            ..MockClient::new(false)
+        };
+        let service = BookmarkSyncService::new(
+            client.clone(),
+            client.clone(),
+            db.clone(),
+            BookmarkSyncConfig {
+                backfill_pause: Duration::ZERO,
+                ..Default::default()
+            },
+        );
+
+        let saved = service.backfill(&client, &user, &settings).await.unwrap();
+
+        assert_eq!(saved, 3);
+        assert_eq!(
+            *client.fetched.lock().unwrap(),
+            vec![paged_uri(2), paged_uri(1), paged_uri(0)]
+        );
+        for page in 0..3 {
+            assert!(db
+                .is_bookmark_processed(user.id, &paged_uri(page))
+                .await
+                .unwrap());
+        }
+        let settings = db.get_user_settings(user.id).await.unwrap().unwrap();
+        assert!(settings.backfilled);
+        assert_eq!(settings.backfill_done, 3);
+        assert_eq!(settings.backfill_progress(), None);
+    }
+
+    #[sqlx::test]
+    async fn test_backfill_stops_when_sync_is_turned_off(pool: sqlx::PgPool) {
+        let db = Database::new(pool, EncryptionKey::test_key());
+        let (user, settings) = test_user(&db).await;
+        db.update_user_settings(
+            user.id,
+            &SettingsUpdate {
+                bookmark_sync_enabled: Some(false),
+                ..Default::default()
+            },
+        )
+        .await
+        .unwrap();
+        let client = MockClient {
+            pages: 3,
+            ..MockClient::new(false)
+        };
+        let service = test_service(db.clone(), client.clone());
+
+        let saved = service.backfill(&client, &user, &settings).await.unwrap();
+
+        assert_eq!(saved, 0);
+        assert!(!db
+            .is_bookmark_processed(user.id, &paged_uri(2))
+            .await
+            .unwrap());
+        // Left unfinished, to resume when sync is back on
+        let settings = db.get_user_settings(user.id).await.unwrap().unwrap();
+        assert!(!settings.backfilled);
+    }
+
+    #[sqlx::test]
+    async fn test_new_bookmarks_save_while_backfilling(pool: sqlx::PgPool) {
+        let db = Database::new(pool, EncryptionKey::test_key());
+        let (user, settings) = test_user(&db).await;
+        let client = MockClient {
+            pages: 3,
+            ..MockClient::new(false)
+        };
+        let service = BookmarkSyncService::new(
+            client.clone(),
+            client.clone(),
+            db.clone(),
+            BookmarkSyncConfig {
+                backfill_pause: Duration::from_secs(3600),
+                ..Default::default()
+            },
+        );
+
+        // The backfill saves the oldest bookmark, then pauses for an hour
+        let _ = tokio::time::timeout(
+            Duration::from_secs(2),
+            service.run_for_user(user.clone(), settings, client.clone()),
+        )
+        .await;
+
+        assert!(db
+            .is_bookmark_processed(user.id, &paged_uri(2))
+            .await
+            .unwrap());
+        assert!(!db
+            .is_bookmark_processed(user.id, &paged_uri(1))
+            .await
+            .unwrap());
+        // ...while polling has already saved the newest
+        assert!(db
+            .is_bookmark_processed(user.id, &paged_uri(0))
+            .await
+            .unwrap());
+    }
+
+    #[sqlx::test]
+    async fn test_bookmark_readwise_already_has_stays_processed(pool: sqlx::PgPool) {
+        let db = Database::new(pool, EncryptionKey::test_key());
+        let (user, _) = test_user(&db).await;
+        let client = MockClient {
+            already_saved: true,
+            ..MockClient::new(false)
+        };
+        let service = test_service(db.clone(), client);
+
+        service
+            .save_for_user(user.id, POST_URI, None)
+            .await
+            .unwrap();
+
+        assert!(db.is_bookmark_processed(user.id, POST_URI).await.unwrap());
+        assert!(!db.has_failed_save(user.id, POST_URI).await.unwrap());
+    }
+
+    #[sqlx::test]
+    async fn test_racing_saves_of_one_bookmark_save_once(pool: sqlx::PgPool) {
+        let db = Database::new(pool, EncryptionKey::test_key());
+        let (user, settings) = test_user(&db).await;
+        let client = MockClient::new(false);
+        let service = test_service(db.clone(), client.clone());
+
+        let (first, second) = tokio::join!(
+            service.save_bookmark(user.id, &settings, POST_URI, None),
+            service.save_bookmark(user.id, &settings, POST_URI, None),
+        );
+
+        let skipped = [first.unwrap().kind, second.unwrap().kind]
+            .into_iter()
+            .filter(|kind| *kind == OutcomeKind::Skipped)
+            .count();
+        assert_eq!(skipped, 1);
+        assert_eq!(client.fetched.lock().unwrap().len(), 1);
+    }
+
+    #[sqlx::test]
+    async fn test_failed_save_is_queued(pool: sqlx::PgPool) {
+        let db = Database::new(pool, EncryptionKey::test_key());
+        let (user, settings) = test_user(&db).await;
+        let client = MockClient::new(true);
+        let service = test_service(db.clone(), client.clone());
+
+        let count = service
+            .poll_bookmarks(&client, &user, &settings)
+            .await
+            .unwrap();
+
+        assert_eq!(count, 0);
+        assert!(db.has_failed_save(user.id, POST_URI).await.unwrap());
+        assert!(!db.is_bookmark_processed(user.id, POST_URI).await.unwrap());
+        // Not due until the backoff passes
+        assert!(db.due_failed_saves(10).await.unwrap().is_empty());
+    }
+
+    #[sqlx::test]
+    async fn test_purged_bookmarks_are_not_saved_again(pool: sqlx::PgPool) {
+        let db = Database::new(pool.clone(), EncryptionKey::test_key());
+        let (user, settings) = test_user(&db).await;
+        let client = MockClient {
+            bookmarked_days_ago: 40,
+            ..MockClient::new(false)
+        };
+        let config = BookmarkSyncConfig {
+            retention_days: Some(30),
+            ..BookmarkSyncConfig::default()
+        };
+        let service = BookmarkSyncService::new(client.clone(), client.clone(), db.clone(), config);
+        // Saved when it was bookmarked, 40 days ago
+        db.mark_bookmark_processed(user.id, POST_URI).await.unwrap();
+        sqlx::query("UPDATE processed_bookmarks SET processed_at = NOW() - INTERVAL '40 days'")
+            .execute(&pool)
+            .await
+            .unwrap();
+        let state = crate::AppState {
+            config: crate::config::Config {
+                retention_days: Some(30),
+                ..crate::config::Config::test_default()
+            },
+            ..crate::AppState::test(db.clone())
+        };
+
+        let report = crate::services::cleanup::run_cleanup(&state).await.unwrap();
+        assert_eq!(report.processed_rows, 1);
+
+        let count = service
+            .poll_bookmarks(&client, &user, &settings)
+            .await
+            .unwrap();
+        assert_eq!(count, 0);
+        assert!(client.fetched.lock().unwrap().is_empty());
+        assert!(!db.is_bookmark_processed(user.id, POST_URI).await.unwrap());
+    }
+
+    #[sqlx::test]
+    async fn test_poll_enqueues_and_defers_when_queue_full(pool: sqlx::PgPool) {
+        let db = Database::new(pool, EncryptionKey::test_key());
+        let (user, settings) = test_user(&db).await;
+        let client = MockClient::new(false);
+        let (queue, jobs) = crate::services::save_queue::save_queue(1);
+        let queue = queue.with_enqueue_timeout(Duration::from_millis(10));
+        let service = test_service(db.clone(), client.clone()).with_save_queue(queue.clone());
+
+        // Queued, not saved, and not queued again by the next poll
+        let (count, poll_id) = request_id::traced(async {
+            let count = service.poll_bookmarks(&client, &user, &settings).await;
+            (count.unwrap(), request_id::current())
+        })
+        .await;
+        assert_eq!(count, 1);
+        service
+            .poll_bookmarks(&client, &user, &settings)
+            .await
+            .unwrap();
+        assert_eq!(queue.len(), 1);
+        assert!(!db.is_bookmark_processed(user.id, POST_URI).await.unwrap());
+
+        // The save carries on under the poll's request ID
+        let job = jobs.next().await.unwrap();
+        assert_eq!(job.request_id, poll_id);
+        assert!(poll_id.is_some());
+        service.run_save_job(&job).await;
+        drop(job);
+        assert!(db.is_bookmark_processed(user.id, POST_URI).await.unwrap());
+
+        // With the queue full, the bookmark goes to the retry loop
+        db.delete_user(user.id).await.unwrap();
+        let (user, settings) = test_user(&db).await;
+        queue
+            .enqueue(SaveJob {
+                user_id: Uuid::new_v4(),
+                post_uri: "at://did:plc:other/app.bsky.feed.post/1".to_string(),
+                note: None,
+                request_id: None,
+            })
+            .await
+            .unwrap();
+        assert_eq!(
+            service
+                .poll_bookmarks(&client, &user, &settings)
+                .await
+                .unwrap(),
+            0
+        );
+        assert_eq!(queue.len(), 1);
+        assert!(db.has_failed_save(user.id, POST_URI).await.unwrap());
+    }
+
+    #[sqlx::test]
+    async fn test_changed_handle_is_stored(pool: sqlx::PgPool) {
+        let db = Database::new(pool, EncryptionKey::test_key());
+        let (mut user, _) = test_user(&db).await;
+        let client = MockClient {
+            profile_handle: Some("renamed.bsky.social".to_string()),
+            ..MockClient::new(false)
+        };
+        let service = test_service(db.clone(), client.clone());
+
+        service.sync_handle(&client, &mut user).await;
+
+        assert_eq!(user.bluesky_handle, "renamed.bsky.social");
+        let stored = db.get_user_by_did("did:plc:reader").await.unwrap().unwrap();
+        assert_eq!(stored.id, user.id);
+        assert_eq!(stored.bluesky_handle, "renamed.bsky.social");
+
+        // A failed lookup leaves the handle alone
+        service
+            .sync_handle(&MockClient::new(false), &mut user)
+            .await;
+        assert_eq!(user.bluesky_handle, "renamed.bsky.social");
+    }
+
+    #[sqlx::test]
+    async fn test_retry_success_clears_failed_save(pool: sqlx::PgPool) {
+        let db = Database::new(pool, EncryptionKey::test_key());
+        let (user, _) = test_user(&db).await;
+        db.record_failed_save(user.id, POST_URI, "Readwise API error 500", Utc::now())
+            .await
+            .unwrap();
+        let service = test_service(db.clone(), MockClient::new(false));
+
+        assert_eq!(service.retry_failed_saves().await.unwrap(), 1);
+        assert!(db.is_bookmark_processed(user.id, POST_URI).await.unwrap());
+        assert!(!db.has_failed_save(user.id, POST_URI).await.unwrap());
+    }
+
+    #[sqlx::test]
+    async fn test_retry_gives_up_after_max_attempts(pool: sqlx::PgPool) {
+        let db = Database::new(pool.clone(), EncryptionKey::test_key());
+        let (user, _) = test_user(&db).await;
+        db.record_failed_save(user.id, POST_URI, "Readwise API error 500", Utc::now())
+            .await
+            .unwrap();
+        let service = test_service(db.clone(), MockClient::new(true));
+
+        // A failure short of the limit reschedules with backoff
+        assert_eq!(service.retry_failed_saves().await.unwrap(), 0);
+        let attempts: i32 = sqlx::query_scalar("SELECT attempts FROM failed_saves")
+            .fetch_one(&pool)
+            .await
+            .unwrap();
+        assert_eq!(attempts, 2);
+        assert!(db.due_failed_saves(10).await.unwrap().is_empty());
+
+        sqlx::query("UPDATE failed_saves SET attempts = $1, next_attempt_at = NOW()")
+            .bind(MAX_SAVE_ATTEMPTS - 1)
+            .execute(&pool)
+            .await
+            .unwrap();
+        assert_eq!(service.retry_failed_saves().await.unwrap(), 0);
+
+        let (attempts, given_up): (i32, bool) =
+            sqlx::query_as("SELECT attempts, permanently_failed FROM failed_saves")
+                .fetch_one(&pool)
+                .await
+                .unwrap();
+        assert_eq!(attempts, MAX_SAVE_ATTEMPTS);
+        assert!(given_up);
+        assert!(db.due_failed_saves(10).await.unwrap().is_empty());
+    }
+
+    #[sqlx::test]
+    async fn test_repost_bookmark_saves_original_post(pool: sqlx::PgPool) {
+        let db = Database::new(pool, EncryptionKey::test_key());
+        let (user, settings) = test_user(&db).await;
+        let client = MockClient {
+            reposted_by: Some(Author {
+                did: "did:plc:bob".to_string(),
+                handle: "bob.bsky.social".to_string(),
+                display_name: None,
+                labels: Vec::new(),
+            }),
+            ..MockClient::new(false)
+        };
+        let service = test_service(db.clone(), client.clone());
+
+        let bookmarks = client.get_bookmarks(None).await.unwrap().bookmarks;
+        let bookmarked = bookmarked_post(&bookmarks[0]);
+        assert_eq!(bookmarked.uri, POST_URI);
+        assert_eq!(
+            bookmarked.note().as_deref(),
+            Some("reposted by @bob.bsky.social")
+        );
+
+        let count = service
+            .poll_bookmarks(&client, &user, &settings)
+            .await
+            .unwrap();
+
+        assert_eq!(count, 1);
+        assert_eq!(*client.fetched.lock().unwrap(), vec![POST_URI.to_string()]);
+        assert!(db.is_bookmark_processed(user.id, POST_URI).await.unwrap());
+    }
+
+    #[sqlx::test]
+    async fn test_labeled_bookmark_skipped_when_enabled(pool: sqlx::PgPool) {
+        let db = Database::new(pool, EncryptionKey::test_key());
+        let (user, _) = test_user(&db).await;
+        db.update_user_settings(
+            user.id,
+            &SettingsUpdate {
+                skip_labeled: Some(true),
+                ..Default::default()
+            },
+        )
+        .await
+        .unwrap();
+        let settings = db.get_user_settings(user.id).await.unwrap().unwrap();
+
+        let mut labeled = post("Not for work");
+        labeled.labels = vec![Label {
+            src: "did:plc:labeler".to_string(),
+            val: "graphic-media".to_string(),
+            neg: false,
+        }];
+        let client = MockClient {
+            post: labeled,
+            ..MockClient::new(false)
+        };
+        let service = test_service(db.clone(), client.clone());
+        let count = service
+            .poll_bookmarks(&client, &user, &settings)
+            .await
+            .unwrap();
+        assert_eq!(count, 0);
+        assert!(client.fetched.lock().unwrap().is_empty());
+        assert!(db.is_bookmark_processed(user.id, POST_URI).await.unwrap());
+
+        let client = MockClient {
+            post: PostView {
+                uri: "at://did:plc:abc/app.bsky.feed.post/2".to_string(),
+                ..post("Fine to save")
+            },
+            ..MockClient::new(false)
+        };
+        let count = service
+            .poll_bookmarks(&client, &user, &settings)
+            .await
+            .unwrap();
+        assert_eq!(count, 1);
+    }
+
+    #[sqlx::test]
+    async fn test_short_bookmark_processed_without_saving(pool: sqlx::PgPool) {
+        let db = Database::new(pool, EncryptionKey::test_key());
+        let (user, _) = test_user(&db).await;
+        db.update_user_settings(
+            user.id,
+            &SettingsUpdate {
+                min_post_length: Some(20),
+                ..Default::default()
+            },
+        )
+        .await
+        .unwrap();
+        let settings = db.get_user_settings(user.id).await.unwrap().unwrap();
+
+        let client = MockClient {
+            post: post("lol same"),
+            ..MockClient::new(false)
+        };
+        let service = test_service(db.clone(), client.clone());
+        let count = service
+            .poll_bookmarks(&client, &user, &settings)
+            .await
+            .unwrap();
+
+        assert_eq!(count, 0);
+        assert!(client.fetched.lock().unwrap().is_empty());
+        assert!(db.is_bookmark_processed(user.id, POST_URI).await.unwrap());
+    }
+
+    #[test]
+    fn test_retry_delay_backs_off() {
+        assert_eq!(retry_delay(1), chrono::Duration::seconds(60));
+        assert_eq!(retry_delay(2), chrono::Duration::seconds(120));
+        assert_eq!(retry_delay(4), chrono::Duration::seconds(480));
+        assert_eq!(retry_delay(100), chrono::Duration::hours(6));
+    }
+
+    #[sqlx::test]
+    #[traced_test]
+    async fn test_processing_span_carries_user_did(pool: sqlx::PgPool) {
+        let db = Database::new(pool, EncryptionKey::test_key());
+        let (user, settings) = test_user(&db).await;
+        let client = MockClient::new(false);
+        let service = test_service(db, client.clone());
+
+        let count = request_id::traced(service.poll_bookmarks(&client, &user, &settings))
+            .await
+            .unwrap();
+        assert_eq!(count, 1);
+
+        logs_assert(|lines: &[&str]| {
+            let processing = lines
+                .iter()
+                .find(|line| line.contains("Processing post"))
+                .ok_or("no processing log")?;
+            if !processing.contains("user_did=did:plc:reader")
+                || !processing.contains("request_id=")
+                || !processing.contains("post_uri=at://did:plc:abc/app.bsky.feed.post/1")
+            {
+                return Err(format!("missing span fields: {}", processing));
+            }
+            if lines.iter().any(|line| line.contains("rw-secret-token")) {
+                return Err("token logged".to_string());
+            }
+            Ok(())
+        });
+    }
+
+    fn post(text: &str) -> PostView {
+        PostView {
+            uri: "at://did:plc:abc/app.bsky.feed.post/1".to_string(),
+            cid: "cid".to_string(),
+            author: Author {
+                did: "did:plc:abc".to_string(),
+                handle: "abc.bsky.social".to_string(),
+                display_name: None,
+                labels: Vec::new(),
+            },
+            record: PostRecord {
+                text: text.to_string(),
+                created_at: Utc::now(),
+                reply: None,
+                facets: None,
+                embed: None,
+            },
+            indexed_at: Utc::now(),
+            labels: Vec::new(),
+            like_count: None,
+            repost_count: None,
+            reply_count: None,
+        }
+    }
+
+    #[test]
+    fn test_bare_quote_saves_quoted_post() {
+        let mut quote = post("");
+        quote.record.embed = Some(Embed::Record {
+            record: StrongRef {
+                uri: "at://did:plc:carol/app.bsky.feed.post/q".to_string(),
+                cid: "bafyq".to_string(),
+            },
+        });
+        let bookmark = BookmarkView {
+            subject: StrongRef {
+                uri: quote.uri.clone(),
+                cid: quote.cid.clone(),
+            },
+            created_at: Utc::now(),
+            item: BookmarkItem::Post(Box::new(quote.clone())),
+        };
+        let bookmarked = bookmarked_post(&bookmark);
+        assert_eq!(bookmarked.uri, "at://did:plc:carol/app.bsky.feed.post/q");
+        assert_eq!(
+            bookmarked.note().as_deref(),
+            Some("quoted by @abc.bsky.social")
+        );
+
+        // A quote with commentary is saved as itself
+        quote.record.text = "Worth reading".to_string();
+        let bookmark = BookmarkView {
+            item: BookmarkItem::Post(Box::new(quote)),
+            ..bookmark
+        };
+        assert_eq!(bookmarked_post(&bookmark).uri, POST_URI);
+    }
+
+    #[test]
+    fn test_short_post_skipped() {
+        assert!(too_short(&post("lol https://example.com/x"), 10));
+        assert!(too_short(&post("this 👆"), 10));
+    }
+
+    #[test]
+    fn test_long_enough_post_saved() {
+        assert!(!too_short(&post("A long post worth keeping"), 10));
+        assert!(!too_short(&post("lol"), 0));
+    }
+
+    #[sqlx::test]
+    async fn test_poll_interval_change_applies_next_cycle(pool: sqlx::PgPool) {
+        let db = Database::new(pool, EncryptionKey::test_key());
+        let (user, mut settings) = test_user(&db).await;
+        let service = test_service(db.clone(), MockClient::new(false));
+        let mut schedule = PollSchedule::new(service.poll_interval(&settings));
+        assert_eq!(schedule.period, Duration::from_secs(30));
+
+        db.update_user_settings(
+            user.id,
+            &SettingsUpdate {
+                poll_interval_secs: Some(Some(120)),
+                ..Default::default()
+            },
+        )
+        .await
+        .unwrap();
+        service
+            .reload_settings(user.id, &mut settings, &mut schedule)
+            .await;
+        assert_eq!(settings.poll_interval_secs, Some(120));
+        assert_eq!(schedule.period, Duration::from_secs(120));
+
+        // Too-short intervals are clamped
+        db.update_user_settings(
+            user.id,
+            &SettingsUpdate {
+                poll_interval_secs: Some(Some(1)),
+                ..Default::default()
+            },
+        )
+        .await
+        .unwrap();
+        service
+            .reload_settings(user.id, &mut settings, &mut schedule)
+            .await;
+        assert_eq!(schedule.period, MIN_POLL_INTERVAL);
+
+        db.update_user_settings(
+            user.id,
+            &SettingsUpdate {
+                poll_interval_secs: Some(None),
+                ..Default::default()
+            },
+        )
+        .await
+        .unwrap();
+        service
+            .reload_settings(user.id, &mut settings, &mut schedule)
+            .await;
+        assert_eq!(schedule.period, Duration::from_secs(30));
+    }
+
+    #[sqlx::test]
+    async fn test_rejected_token_disables_sync(pool: sqlx::PgPool) {
+        let db = Database::new(pool, EncryptionKey::test_key());
+        let (user, settings) = test_user(&db).await;
+        let refresher = Arc::new(RevokedRefresher {
+            calls: Default::default(),
+        });
+        let service = test_service(db.clone(), MockClient::new(false))
+            .with_session_refresher(refresher.clone());
+        let revoked = MockClient {
+            token_rejected: true,
+            ..MockClient::new(false)
+        };
+
+        tokio::time::timeout(
+            Duration::from_secs(5),
+            service.run_for_user(user.clone(), settings, revoked),
+        )
+        .await
+        .expect("sync loop should stop")
+        .unwrap();
+
+        assert_eq!(refresher.calls.load(std::sync::atomic::Ordering::SeqCst), 1);
+        let user = db.get_user_by_id(user.id).await.unwrap().unwrap();
+        assert!(user.needs_reauth);
+        let settings = db.get_user_settings(user.id).await.unwrap().unwrap();
+        assert!(!settings.bookmark_sync_enabled);
+
+        db.clear_needs_reauth(user.id).await.unwrap();
+        let user = db.get_user_by_id(user.id).await.unwrap().unwrap();
+        assert!(!user.needs_reauth);
+    }
+
+    #[sqlx::test]
+    async fn test_sync_stops_once_turned_off(pool: sqlx::PgPool) {
+        let db = Database::new(pool, EncryptionKey::test_key());
+        let (user, settings) = test_user(&db).await;
+        db.finish_backfill(user.id).await.unwrap();
+        db.record_failed_save(user.id, POST_URI, "Readwise API error 500", Utc::now())
+            .await
+            .unwrap();
+        db.update_user_settings(
+            user.id,
+            &SettingsUpdate {
+                bookmark_sync_enabled: Some(false),
+                ..Default::default()
+            },
+        )
+        .await
+        .unwrap();
+        let client = MockClient::new(false);
+        let service = test_service(db.clone(), client.clone());
+
+        // Started with the settings from before sync was turned off
+        tokio::time::timeout(
+            Duration::from_secs(5),
+            service.run_for_user(user.clone(), settings, client.clone()),
+        )
+        .await
+        .expect("sync loop should stop")
+        .unwrap();
+        assert!(client.fetched.lock().unwrap().is_empty());
+
+        // Its failed saves wait until sync is back on
+        assert_eq!(service.retry_failed_saves().await.unwrap(), 0);
+        assert!(db.has_failed_save(user.id, POST_URI).await.unwrap());
+        assert!(client.fetched.lock().unwrap().is_empty());
+    }
 
     #[test]
     fn test_default_config() {
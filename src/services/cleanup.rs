@@ -0,0 +1,141 @@
+//! Periodic purge of expired and old rows
+//!
+//! Drops expired pending OAuth logins and magic-link tokens, and, when
+//! `retention_days` is set, processed bookmark and DM rows older than that.
+//!
+//! Those rows are what stop a bookmark or DM being handled twice, so the
+//! pollers ignore anything older than the retention (`past_retention`).
+//! An item is processed after it was made, so a purged row's item is
+//! always past it.
+
+use anyhow::Result;
+use chrono::{DateTime, Duration, Utc};
+
+use crate::AppState;
+
+/// How many entries one cleanup pass removed
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct CleanupReport {
+    pub oauth_states: usize,
+    pub login_tokens: u64,
+    pub processed_rows: u64,
+}
+
+/// Extra margin on the pollers' cutoff, so clock skew between Bluesky and
+/// us can't let an item whose row was purged back in
+const RETENTION_LEEWAY: Duration = Duration::hours(1);
+
+/// Whether a bookmark or message made `at` is too old for the pollers to
+/// handle, because its processed row may already be purged
+pub fn past_retention(retention_days: Option<u32>, at: DateTime<Utc>) -> bool {
+    retention_days
+        .is_some_and(|days| at < Utc::now() - Duration::days(i64::from(days)) + RETENTION_LEEWAY)
+}
+
+/// Run one cleanup pass
+pub async fn run_cleanup(state: &AppState) -> Result<CleanupReport> {
+    let oauth_states = state
+        .oauth
+        .as_ref()
+        .map_or(0, |oauth| oauth.cleanup_expired());
+    let login_tokens = state.db.delete_expired_login_tokens().await?;
+    let processed_rows = match state.config.retention_days {
+        Some(days) => {
+            let cutoff = Utc::now() - Duration::days(i64::from(days));
+            state.db.delete_processed_before(cutoff).await?
+        }
+        None => 0,
+    };
+
+    Ok(CleanupReport {
+        oauth_states,
+        login_tokens,
+        processed_rows,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::Config;
+    use crate::crypto::EncryptionKey;
+    use crate::db::queries::Database;
+    use sqlx::PgPool;
+
+    #[sqlx::test]
+    async fn test_cleanup_removes_expired_and_keeps_fresh(pool: PgPool) {
+        let db = Database::new(pool.clone(), EncryptionKey::test_key());
+        let user = db
+            .create_user("did:plc:reader", "reader.bsky.social")
+            .await
+            .unwrap();
+        let expired = db
+            .create_login_token("did:plc:reader", Duration::minutes(-1))
+            .await
+            .unwrap();
+        let fresh = db
+            .create_login_token("did:plc:reader", Duration::minutes(15))
+            .await
+            .unwrap();
+        for uri in [
+            "at://did:plc:x/app.bsky.feed.post/old",
+            "at://did:plc:x/app.bsky.feed.post/new",
+        ] {
+            db.mark_bookmark_processed(user.id, uri).await.unwrap();
+        }
+        sqlx::query(
+            "UPDATE processed_bookmarks SET processed_at = NOW() - INTERVAL '40 days' \
+             WHERE post_uri LIKE '%/old'",
+        )
+        .execute(&pool)
+        .await
+        .unwrap();
+        let state = AppState {
+            config: Config {
+                retention_days: Some(30),
+                ..Config::test_default()
+            },
+            ..AppState::test(db.clone())
+        };
+
+        let report = run_cleanup(&state).await.unwrap();
+
+        assert_eq!(report.login_tokens, 1);
+        assert_eq!(report.processed_rows, 1);
+        let tokens: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM login_tokens WHERE token = $1")
+            .bind(&expired)
+            .fetch_one(&pool)
+            .await
+            .unwrap();
+        assert_eq!(tokens, 0);
+        assert!(db.consume_login_token(&fresh).await.unwrap().is_some());
+        assert!(!db
+            .is_bookmark_processed(user.id, "at://did:plc:x/app.bsky.feed.post/old")
+            .await
+            .unwrap());
+        assert!(db
+            .is_bookmark_processed(user.id, "at://did:plc:x/app.bsky.feed.post/new")
+            .await
+            .unwrap());
+    }
+
+    #[sqlx::test]
+    async fn test_processed_rows_kept_without_retention(pool: PgPool) {
+        let db = Database::new(pool.clone(), EncryptionKey::test_key());
+        let user = db
+            .create_user("did:plc:reader", "reader.bsky.social")
+            .await
+            .unwrap();
+        db.mark_bookmark_processed(user.id, "at://did:plc:x/app.bsky.feed.post/old")
+            .await
+            .unwrap();
+        sqlx::query("UPDATE processed_bookmarks SET processed_at = NOW() - INTERVAL '10 years'")
+            .execute(&pool)
+            .await
+            .unwrap();
+
+        let report = run_cleanup(&AppState::test(db)).await.unwrap();
+
+        assert_eq!(report, CleanupReport::default());
+    }
+}
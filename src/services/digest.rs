@@ -0,0 +1,365 @@
+//! Daily digest DMs
+//!
+//! Once a day, at `digest_hour` in each opted-in user's timezone, the bot
+//! DMs them how many posts were saved in the last 24 hours (by bookmark or
+//! DM) and the latest few. Days with no saves are skipped rather than
+//! sending an empty recap. A digest that fails to send is retried hourly
+//! for the rest of the day.
+
+use std::sync::Arc;
+use std::time::Duration;
+
+use anyhow::Result;
+use chrono::{DateTime, TimeDelta, Utc};
+use chrono_tz::Tz;
+use tokio::time::interval;
+use tracing::{debug, error, info, warn};
+
+use crate::bluesky::BlueskyClient;
+use crate::db::models::DigestSubscriber;
+use crate::db::queries::Database;
+use crate::features::{Feature, FeatureFlags};
+use crate::services::dm_bot::BotSession;
+
+/// How often to check whether anyone's digest is due
+const DIGEST_CHECK_INTERVAL: Duration = Duration::from_secs(5 * 60);
+
+/// How far back a digest looks
+const DIGEST_WINDOW: TimeDelta = TimeDelta::hours(24);
+
+/// Wait before retrying a digest that failed to send
+const DIGEST_RETRY_DELAY: TimeDelta = TimeDelta::hours(1);
+
+/// Saves listed by title in a digest
+const DIGEST_TOP_SAVES: i64 = 5;
+
+/// Characters of post text kept in a digest title
+const TITLE_TEXT_CHARS: usize = 60;
+
+/// Sends opted-in users their daily digest through the bot account
+pub struct DigestService<B: BlueskyClient> {
+    /// The DM bot's session, so a refresh by either is seen by both
+    bot: Arc<BotSession<B>>,
+    db: Database,
+    /// Local hour (0-23) digests go out
+    hour: u32,
+    /// Pauses digests while the `digests` flag is off
+    features: Option<Arc<FeatureFlags>>,
+}
+
+impl<B: BlueskyClient + Clone> DigestService<B> {
+    pub fn new(bot: Arc<BotSession<B>>, db: Database, hour: u32) -> Self {
+        Self {
+            bot,
+            db,
+            hour,
+            features: None,
+        }
+    }
+
+    /// Only send digests while the `digests` feature flag is on
+    pub fn with_features(mut self, features: Arc<FeatureFlags>) -> Self {
+        self.features = Some(features);
+        self
+    }
+
+    /// Send due digests forever
+    /// This should be spawned as a tokio task
+    pub async fn run(&self) {
+        let mut ticker = interval(DIGEST_CHECK_INTERVAL);
+
+        loop {
+            ticker.tick().await;
+
+            if let Some(flags) = &self.features {
+                if !flags.enabled(Feature::Digests).await {
+                    continue;
+                }
+            }
+
+            match self.send_due_digests(Utc::now()).await {
+                Ok(0) => {}
+                Ok(count) => info!("Handled {} daily digests", count),
+                Err(e) => error!("Error sending daily digests: {}", e),
+            }
+        }
+    }
+
+    /// Send every digest due at `now`, returning how many were handled
+    pub async fn send_due_digests(&self, now: DateTime<Utc>) -> Result<usize> {
+        let mut handled = 0;
+
+        for subscriber in self.db.digest_subscribers().await? {
+            let timezone = subscriber.local_timezone();
+            if !digest_due(now, timezone, self.hour, subscriber.last_digest_at)
+                || subscriber.digest_retry_at.is_some_and(|at| now < at)
+            {
+                continue;
+            }
+            match self.send_digest(&subscriber, timezone, now).await {
+                Ok(()) => handled += 1,
+                Err(e) => {
+                    warn!(
+                        "Daily digest for {} failed, retrying in an hour: {}",
+                        subscriber.bluesky_did, e
+                    );
+                    self.db
+                        .defer_digest(subscriber.user_id, now + DIGEST_RETRY_DELAY)
+                        .await?;
+                }
+            }
+        }
+
+        Ok(handled)
+    }
+
+    /// DM one user their digest (if they saved anything) and record it
+    async fn send_digest(
+        &self,
+        user: &DigestSubscriber,
+        timezone: Tz,
+        now: DateTime<Utc>,
+    ) -> Result<()> {
+        let since = now - DIGEST_WINDOW;
+        let total = self.db.saved_posts_since(user.user_id, since).await?;
+
+        if total > 0 {
+            let recent = self
+                .db
+                .latest_saves_since(user.user_id, since, DIGEST_TOP_SAVES)
+                .await?;
+            let mut items = Vec::new();
+            for save in &recent {
+                items.push(DigestItem {
+                    title: self.title(&save.post_uri).await,
+                    saved_at: save.at.with_timezone(&timezone),
+                });
+            }
+
+            // Through the bot's session, so an expired token is refreshed
+            let did = &user.bluesky_did;
+            let convo = self
+                .bot
+                .chat(|bluesky| async move { bluesky.get_convo_for_member(did).await })
+                .await?;
+            let convo = &convo;
+            let text = &digest_text(total, &items);
+            self.bot
+                .chat(|bluesky| async move { bluesky.send_dm(&convo.id, text).await })
+                .await?;
+        } else {
+            debug!("No saves for {}, skipping digest", user.bluesky_did);
+        }
+
+        self.db.mark_digest_sent(user.user_id, now).await
+    }
+
+    /// `@handle: text…` for a saved post, or its URI if it no longer loads
+    async fn title(&self, post_uri: &str) -> String {
+        match self.bot.client().get_post_thread(post_uri).await {
+            Ok(response) => {
+                let post = &response.thread.post;
+                format!(
+                    "@{}: {}",
+                    post.author.handle,
+                    snippet(&post.record.text, TITLE_TEXT_CHARS)
+                )
+            }
+            Err(e) => {
+                debug!("Couldn't load {} for digest: {}", post_uri, e);
+                post_uri.to_string()
+            }
+        }
+    }
+}
+
+/// Whether today's digest (at `hour` local time) is due and not yet handled
+///
+/// A digest missed at its hour (e.g., during a restart) still goes out
+/// later the same day. Days where `hour` doesn't exist (a DST gap) are skipped.
+pub fn digest_due(
+    now: DateTime<Utc>,
+    timezone: Tz,
+    hour: u32,
+    last_digest_at: Option<DateTime<Utc>>,
+) -> bool {
+    let Some(scheduled) = now
+        .with_timezone(&timezone)
+        .date_naive()
+        .and_hms_opt(hour, 0, 0)
+        .and_then(|local| local.and_local_timezone(timezone).earliest())
+    else {
+        return false;
+    };
+    let scheduled = scheduled.with_timezone(&Utc);
+
+    now >= scheduled && last_digest_at.is_none_or(|last| last < scheduled)
+}
+
+/// One save listed in a digest
+#[derive(Debug, Clone)]
+pub struct DigestItem {
+    pub title: String,
+    /// When it was saved, in the user's timezone
+    pub saved_at: DateTime<Tz>,
+}
+
+/// Digest DM text for `total` saves, listing the given latest ones
+pub fn digest_text(total: i64, items: &[DigestItem]) -> String {
+    let posts = if total == 1 { "post" } else { "posts" };
+    let mut text = format!(
+        "📚 Your daily recap: {} {} saved to Readwise in the last 24 hours.",
+        total, posts
+    );
+
+    if !items.is_empty() {
+        text.push_str("\n\nLatest:");
+        for item in items {
+            text.push_str(&format!(
+                "\n• {} ({})",
+                item.title,
+                item.saved_at.format("%H:%M %Z")
+            ));
+        }
+        let more = total - items.len() as i64;
+        if more > 0 {
+            text.push_str(&format!("\n…and {} more", more));
+        }
+    }
+
+    text
+}
+
+/// The first `max` characters of `text` on one line, with `…` if cut
+fn snippet(text: &str, max: usize) -> String {
+    let line = text.split_whitespace().collect::<Vec<_>>().join(" ");
+    match line.char_indices().nth(max) {
+        Some((end, _)) => format!("{}…", line[..end].trim_end()),
+        None => line,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::bluesky::HttpBlueskyClient;
+    use crate::crypto::EncryptionKey;
+    use crate::db::models::{NewSaveEvent, SettingsUpdate};
+    use crate::test_support::MockBluesky;
+    use sqlx::PgPool;
+
+    #[test]
+    fn test_digest_text_lists_latest_saves() {
+        let saved = |title: &str, at: &str| DigestItem {
+            title: title.to_string(),
+            saved_at: at
+                .parse::<DateTime<Utc>>()
+                .unwrap()
+                .with_timezone(&Tz::Europe__Berlin),
+        };
+        let items = vec![
+            saved(
+                "@alice.bsky.social: A thread about gardens",
+                "2026-07-01T16:05:00Z",
+            ),
+            saved(
+                "@bob.bsky.social: Long read on compilers",
+                "2026-07-01T09:30:00Z",
+            ),
+        ];
+
+        assert_eq!(
+            digest_text(5, &items),
+            "📚 Your daily recap: 5 posts saved to Readwise in the last 24 hours.\n\n\
+             Latest:\n\
+             • @alice.bsky.social: A thread about gardens (18:05 CEST)\n\
+             • @bob.bsky.social: Long read on compilers (11:30 CEST)\n\
+             …and 3 more"
+        );
+        assert_eq!(
+            digest_text(1, &items[..1]),
+            "📚 Your daily recap: 1 post saved to Readwise in the last 24 hours.\n\n\
+             Latest:\n\
+             • @alice.bsky.social: A thread about gardens (18:05 CEST)"
+        );
+        assert_eq!(snippet("Line one\nline   two", 20), "Line one line two");
+        assert_eq!(snippet("Line one\nline   two", 9), "Line one…");
+    }
+
+    #[test]
+    fn test_digest_due_at_local_hour_once_a_day() {
+        let at = |s: &str| s.parse::<DateTime<Utc>>().unwrap();
+        let berlin: Tz = "Europe/Berlin".parse().unwrap();
+
+        // 08:00 in Berlin (summer) is 06:00 UTC
+        assert!(!digest_due(at("2026-07-01T05:59:00Z"), berlin, 8, None));
+        assert!(digest_due(at("2026-07-01T06:00:00Z"), berlin, 8, None));
+        assert!(digest_due(at("2026-07-01T20:00:00Z"), berlin, 8, None));
+
+        // Already handled today, but due again tomorrow
+        let sent = Some(at("2026-07-01T06:05:00Z"));
+        assert!(!digest_due(at("2026-07-01T20:00:00Z"), berlin, 8, sent));
+        assert!(digest_due(at("2026-07-02T06:00:00Z"), berlin, 8, sent));
+
+        assert!(digest_due(at("2026-07-01T08:00:00Z"), Tz::UTC, 8, None));
+    }
+
+    #[sqlx::test]
+    async fn test_failed_digest_waits_an_hour_and_counts_dm_saves(pool: PgPool) {
+        let db = Database::new(pool, EncryptionKey::test_key());
+        let user = db
+            .create_user("did:plc:reader", "reader.bsky.social")
+            .await
+            .unwrap();
+        db.create_user_settings(user.id, "rw-token", true, false)
+            .await
+            .unwrap();
+        db.update_user_settings(
+            user.id,
+            &SettingsUpdate {
+                daily_digest: Some(true),
+                ..Default::default()
+            },
+        )
+        .await
+        .unwrap();
+        for (post, kind, status) in [
+            ("1", "highlight", "saved"),
+            ("1", "highlight", "saved"),
+            ("2", "highlight", "saved"),
+            ("3", "highlight", "failed"),
+            ("3", "link", "saved"),
+        ] {
+            let uri = format!("at://did:plc:author/app.bsky.feed.post/{}", post);
+            let event = NewSaveEvent {
+                post_uri: &uri,
+                kind: Some(kind),
+                status,
+                ..Default::default()
+            };
+            db.record_save_event(user.id, event).await.unwrap();
+        }
+        let now = Utc::now();
+
+        // Saves from DMs count, once per post; a link saved from a post
+        // that failed doesn't
+        let total = db
+            .saved_posts_since(user.id, now - DIGEST_WINDOW)
+            .await
+            .unwrap();
+        assert_eq!(total, 2);
+
+        // The mock has no DM endpoints, so sending fails
+        let bluesky = MockBluesky::new().start().await;
+        let client = HttpBlueskyClient::new()
+            .with_base_url(&bluesky.url)
+            .with_public_url(&bluesky.url);
+        let digests = DigestService::new(Arc::new(BotSession::new(client)), db.clone(), 0);
+        assert_eq!(digests.send_due_digests(now).await.unwrap(), 0);
+
+        let subscriber = &db.digest_subscribers().await.unwrap()[0];
+        assert_eq!(subscriber.last_digest_at, None);
+        let retry_at = subscriber.digest_retry_at.unwrap();
+        assert!(retry_at > now + TimeDelta::minutes(59));
+    }
+}
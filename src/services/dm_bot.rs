@@ -3,29 +3,64 @@
 //! Polls bot account DMs and processes save requests.
 
 use anyhow::{anyhow, Result};
+use async_trait::async_trait;
 use regex::Regex;
+use std::future::Future;
+use std::sync::{Arc, Mutex};
 use std::time::Duration;
+use tokio::sync::Semaphore;
 use tokio::time::interval;
-use tracing::{debug, error, info, warn};
+use tracing::{debug, error, info, instrument, warn};
+use unicode_segmentation::UnicodeSegmentation;
+use uuid::Uuid;
 
-use crate::bluesky::BlueskyClient;
+use crate::bluesky::aturi::POST_COLLECTION;
+use crate::bluesky::{
+    is_token_rejected, parse_at_uri, Author, BlueskyClient, ConvoView, HandleCache, MessageView,
+};
+use crate::content::oembed::OEmbedClient;
+use crate::content::{HighlightTemplates, ThreadLimits};
+use crate::db::models::SettingsUpdate;
+use crate::db::queries::Database;
+use crate::features::FeatureFlags;
 use crate::readwise::client::ReadwiseClient;
-use crate::services::processor::{PostProcessor, ProcessOptions};
+use crate::request_id;
+use crate::services::bookmark_sync::retry_delay;
+use crate::services::cleanup::past_retention;
+use crate::services::post_class::SaveTarget;
+use crate::services::processor::{
+    OutcomeKind, PostProcessor, ProcessError, ProcessOptions, ProcessOutcome,
+};
+use crate::services::readwise_token::{rotate_readwise_token, TokenRotation};
+use crate::services::sync_tasks::{SyncStarter, SyncTasks};
 
 /// DM bot configuration
 pub struct DmBotConfig {
     /// Polling interval
     pub poll_interval: Duration,
+    /// Base URL for links in replies
+    pub base_url: String,
+    /// Days processed rows are kept; older messages are never answered
+    pub retention_days: Option<u32>,
 }
 
 impl Default for DmBotConfig {
     fn default() -> Self {
         Self {
             poll_interval: Duration::from_secs(10),
+            base_url: "http://localhost:3000".to_string(),
+            retention_days: None,
         }
     }
 }
 
+/// How long a settings magic link stays valid
+const MAGIC_LINK_TTL_MINUTES: i64 = 15;
+
+/// The chat lexicon caps message text at 1000 graphemes and 10000 bytes
+const MAX_DM_GRAPHEMES: usize = 1000;
+const MAX_DM_BYTES: usize = 10_000;
+
 /// Parsed DM command
 #[derive(Debug, Clone, PartialEq)]
 pub enum DmCommand {
@@ -35,33 +70,235 @@ pub enum DmCommand {
         note: Option<String>,
         extract_links: bool,
     },
+    /// Save several posts, answered with one combined reply
+    SaveBatch {
+        post_urls: Vec<String>,
+        extract_links: bool,
+    },
+    /// Show what saving a post would do, without saving it
+    DryRun { post_url: String },
     /// Register with a Readwise token (DM-only registration)
     Register { readwise_token: String },
+    /// Replace a registered sender's Readwise token
+    RotateToken { readwise_token: String },
     /// Request help
     Help,
     /// Request settings link
     Settings,
+    /// Report the sender's sync state
+    Status,
+    /// Check that the sender's stored Readwise token still works
+    Verify,
+    /// Turn bookmark sync on or off
+    SetSync(bool),
+    /// Choose whether single posts become highlights or Reader documents
+    SetFormat(SaveTarget),
     /// Unknown command
     Unknown(String),
 }
 
+/// Reply sent when someone who hasn't registered tries to save a post
+const REGISTER_PROMPT: &str = "👋 I don't have a Readwise token for you yet. \
+Send \"register <token>\" (get one at https://readwise.io/access_token) and try again.";
+
+/// Reply sent when a Readwise token fails verification
+const INVALID_TOKEN_REPLY: &str = "❌ That Readwise token didn't work. \
+Double-check it at https://readwise.io/access_token and send \"register <token>\" again.";
+
+/// Reply when a replacement Readwise token fails verification
+const INVALID_ROTATION_REPLY: &str = "❌ That Readwise token didn't work, so I kept your old one. \
+Double-check it at https://readwise.io/access_token and send \"token <token>\" again.";
+
+/// Reply when the sender's stored Readwise token still works
+const TOKEN_VALID_REPLY: &str = "✅ Your Readwise token works.";
+
+/// Reply when the sender's stored Readwise token no longer works
+const STALE_TOKEN_REPLY: &str = "❌ Readwise no longer accepts your token. \
+Get a fresh one at https://readwise.io/access_token and send \"register <token>\".";
+
+/// Reply when the post to save doesn't exist or can't be seen
+const POST_NOT_FOUND_REPLY: &str = "❌ I couldn't save that: post not found. \
+It may have been deleted, or its author may block me.";
+
+/// Reply when Readwise refuses the sender's stored token
+const TOKEN_REJECTED_REPLY: &str = "❌ I couldn't save that: Readwise rejected your token. \
+Get a fresh one at https://readwise.io/access_token and send \"register <token>\".";
+
+/// Reply when Readwise already has the post (409 Conflict)
+const ALREADY_SAVED_REPLY: &str = "✅ That post is already saved in your Readwise.";
+
+/// Reply when Readwise rate limits a save (it's queued for retry)
+const RATE_LIMITED_REPLY: &str =
+    "⏳ Readwise is rate limiting saves right now, so I'll retry this one shortly.";
+
+/// Gets a fresh client for the bot account once its session has expired
+#[async_trait]
+pub trait BotSessionRefresher<B>: Send + Sync {
+    async fn refresh(&self) -> Result<B>;
+}
+
+/// The bot account's chat client, shared by the DM bot and daily digests so
+/// a session one of them refreshes is the one both use
+pub struct BotSession<B> {
+    /// Swapped for a refreshed client when the session expires mid-run
+    client: Mutex<B>,
+    /// Without one, an expired session fails chat calls until the bot restarts
+    refresher: Option<Arc<dyn BotSessionRefresher<B>>>,
+}
+
+impl<B: BlueskyClient + Clone> BotSession<B> {
+    pub fn new(client: B) -> Self {
+        Self {
+            client: Mutex::new(client),
+            refresher: None,
+        }
+    }
+
+    /// Refresh the session when a chat call's token is rejected
+    pub fn with_refresher(mut self, refresher: Arc<dyn BotSessionRefresher<B>>) -> Self {
+        self.refresher = Some(refresher);
+        self
+    }
+
+    /// The bot account's current client
+    pub fn client(&self) -> B {
+        self.client
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .clone()
+    }
+
+    /// Make a chat call, and if the token is rejected, refresh the session
+    /// and try once more
+    ///
+    /// Still-rejected tokens (or a failed refresh) come back as `TokenRejected`.
+    pub async fn chat<T, F, Fut>(&self, call: F) -> Result<T>
+    where
+        F: Fn(B) -> Fut,
+        Fut: Future<Output = Result<T>>,
+    {
+        match call(self.client()).await {
+            Err(e) if is_token_rejected(&e) => {
+                let Some(refresher) = &self.refresher else {
+                    return Err(e);
+                };
+                let refreshed = match refresher.refresh().await {
+                    Ok(refreshed) => refreshed,
+                    Err(refresh_error) => {
+                        warn!("Bot session refresh failed: {}", refresh_error);
+                        return Err(e);
+                    }
+                };
+                info!("Bot session refreshed after a rejected token");
+                *self.client.lock().unwrap_or_else(|e| e.into_inner()) = refreshed.clone();
+                call(refreshed).await
+            }
+            result => result,
+        }
+    }
+}
+
 /// DM bot service
 pub struct DmBotService<B: BlueskyClient, R: ReadwiseClient> {
     processor: PostProcessor<B, R>,
-    bluesky: B,
+    session: Arc<BotSession<B>>,
+    readwise: R,
+    db: Database,
+    /// DID of the bot account (its own messages are ignored)
+    bot_did: String,
     config: DmBotConfig,
+    /// Resolves handles in post URLs to DIDs (handles are kept when unset)
+    handles: Option<Arc<HandleCache>>,
+    /// Running sync loops, and how to start one (settings only change when unset)
+    sync: Option<(Arc<SyncTasks>, Arc<dyn SyncStarter>)>,
 }
 
 impl<B: BlueskyClient + Clone, R: ReadwiseClient + Clone> DmBotService<B, R> {
     /// Create a new DM bot service
-    pub fn new(bluesky: B, readwise: R, config: DmBotConfig) -> Self {
+    pub fn new(
+        bluesky: B,
+        readwise: R,
+        db: Database,
+        bot_did: String,
+        config: DmBotConfig,
+    ) -> Self {
         Self {
-            processor: PostProcessor::new(bluesky.clone(), readwise),
-            bluesky,
+            processor: PostProcessor::new(bluesky.clone(), readwise.clone())
+                .with_link_dedup(db.clone())
+                .with_save_audit(db.clone()),
+            session: Arc::new(BotSession::new(bluesky)),
+            readwise,
+            db,
+            bot_did,
             config,
+            handles: None,
+            sync: None,
         }
     }
 
+    /// Share a Readwise save limiter with other services
+    pub fn with_save_limiter(mut self, limiter: Arc<Semaphore>) -> Self {
+        self.processor = self.processor.with_save_limiter(limiter);
+        self
+    }
+
+    /// Use custom highlight title and note templates
+    pub fn with_highlight_templates(mut self, templates: HighlightTemplates) -> Self {
+        self.processor = self.processor.with_highlight_templates(templates);
+        self
+    }
+
+    /// Title saved video links from oEmbed
+    pub fn with_oembed(mut self, oembed: Arc<dyn OEmbedClient>) -> Self {
+        self.processor = self.processor.with_oembed(oembed);
+        self
+    }
+
+    /// Drop these query parameters from extracted links
+    pub fn with_strip_query_params(mut self, params: Vec<String>) -> Self {
+        self.processor = self.processor.with_strip_query_params(params);
+        self
+    }
+
+    /// Check `expand_quotes` and `detect_language` for every DM'd post
+    pub fn with_features(mut self, features: Arc<FeatureFlags>) -> Self {
+        self.processor = self.processor.with_features(features);
+        self
+    }
+
+    /// Cap how much of a thread goes into a saved document
+    pub fn with_thread_limits(mut self, limits: ThreadLimits) -> Self {
+        self.processor = self.processor.with_thread_limits(limits);
+        self
+    }
+
+    /// Resolve handles in post URLs through a shared cache
+    pub fn with_handle_cache(mut self, handles: Arc<HandleCache>) -> Self {
+        self.handles = Some(handles);
+        self
+    }
+
+    /// Start and stop users' sync loops on `sync on` / `sync off`
+    pub fn with_sync_control(
+        mut self,
+        tasks: Arc<SyncTasks>,
+        starter: Arc<dyn SyncStarter>,
+    ) -> Self {
+        self.sync = Some((tasks, starter));
+        self
+    }
+
+    /// Refresh the bot session when a chat call's token is rejected
+    pub fn with_session_refresher(mut self, refresher: Arc<dyn BotSessionRefresher<B>>) -> Self {
+        self.session = Arc::new(BotSession::new(self.session.client()).with_refresher(refresher));
+        self
+    }
+
+    /// The bot's chat session, for other services that DM as the bot
+    pub fn session(&self) -> Arc<BotSession<B>> {
+        self.session.clone()
+    }
+
     /// Start the DM polling loop
     pub async fn run(&self) -> Result<()> {
         let mut ticker = interval(self.config.poll_interval);
@@ -71,7 +308,7 @@ impl<B: BlueskyClient + Clone, R: ReadwiseClient + Clone> DmBotService<B, R> {
         loop {
             ticker.tick().await;
 
-            match self.poll_dms().await {
+            match request_id::traced(self.poll_dms()).await {
                 Ok(count) => {
                     if count > 0 {
                         info!("Processed {} DMs", count);
@@ -88,18 +325,124 @@ impl<B: BlueskyClient + Clone, R: ReadwiseClient + Clone> DmBotService<B, R> {
 
     /// Poll for new DMs and process them
     async fn poll_dms(&self) -> Result<usize> {
-        // TODO: Implement actual DM polling using chat.bsky.convo.listConvos
-        // TODO: Track processed message IDs to avoid duplicates
-        // For now, this is a stub
-        Ok(0)
+        let convos = self
+            .session
+            .chat(|bluesky| async move { bluesky.list_convos().await })
+            .await?;
+        let mut count = 0;
+
+        for convo in convos.convos.iter().filter(|c| c.unread_count > 0) {
+            let messages = self
+                .session
+                .chat(|bluesky| async move { bluesky.get_messages(&convo.id).await })
+                .await?;
+
+            // Messages come newest first; answer them in the order they were sent
+            for message in messages.messages.iter().rev() {
+                // Past the retention its processed row may be gone, and
+                // answering it again could re-run an old command
+                if message.sender.did == self.bot_did
+                    || past_retention(self.config.retention_days, message.sent_at)
+                    || self.db.is_dm_processed(&message.id).await?
+                {
+                    continue;
+                }
+                self.handle_message(convo, message).await?;
+                count += 1;
+            }
+
+            self.session
+                .chat(|bluesky| async move { bluesky.mark_convo_read(&convo.id).await })
+                .await?;
+        }
+
+        Ok(count)
+    }
+
+    /// Look up the sender, process their message, and reply
+    #[instrument(skip_all, fields(user_did = %message.sender.did, message_id = %message.id))]
+    async fn handle_message(&self, convo: &ConvoView, message: &MessageView) -> Result<()> {
+        let Some(text) = &message.text else {
+            // Deleted message
+            return self
+                .db
+                .mark_dm_processed(None, &message.id, "skipped", &self.bot_did)
+                .await;
+        };
+
+        // Messages carrying a Readwise token must never be handled twice
+        match Self::parse_message(text) {
+            DmCommand::Register { .. } => {
+                self.db
+                    .mark_dm_processed(None, &message.id, "register", &self.bot_did)
+                    .await?;
+            }
+            DmCommand::RotateToken { .. } => {
+                self.db
+                    .mark_dm_processed(None, &message.id, "token", &self.bot_did)
+                    .await?;
+            }
+            _ => {}
+        }
+
+        let sender = convo
+            .members
+            .iter()
+            .find(|member| member.did == message.sender.did)
+            .cloned()
+            .unwrap_or_else(|| Author {
+                did: message.sender.did.clone(),
+                handle: message.sender.did.clone(),
+                display_name: None,
+                labels: Vec::new(),
+            });
+
+        let user = self.db.get_user_by_did(&message.sender.did).await?;
+        let readwise_token = match &user {
+            Some(user) => self
+                .db
+                .get_user_settings(user.id)
+                .await?
+                .map(|settings| settings.readwise_token),
+            None => None,
+        };
+        if readwise_token.is_none() {
+            warn!("DM from unregistered user {}", message.sender.did);
+        }
+
+        let (reply, status) = match self
+            .process_message(&sender, text, readwise_token.as_deref())
+            .await
+        {
+            Ok(reply) => (reply, "ok"),
+            Err(e) => {
+                warn!("Failed to process DM {}: {}", message.id, e);
+                (format!("❌ Sorry, I couldn't save that: {}", e), "error")
+            }
+        };
+
+        // Record before replying so a failed send never causes a double save
+        self.db
+            .mark_dm_processed(user.map(|u| u.id), &message.id, status, &self.bot_did)
+            .await?;
+        crate::metrics::dm_processed();
+        for chunk in chunk_message(&reply, MAX_DM_GRAPHEMES, MAX_DM_BYTES) {
+            let chunk = &chunk;
+            self.session
+                .chat(|bluesky| async move { bluesky.send_dm(&convo.id, chunk).await })
+                .await?;
+        }
+        Ok(())
     }
 
     /// Process a single DM message
+    ///
+    /// `readwise_token` is None when the sender hasn't registered.
     pub async fn process_message(
         &self,
-        convo_id: &str,
+        sender: &Author,
         message_text: &str,
-        readwise_token: &str,
+        readwise_token: Option<&str>,
     ) -> Result<String> {
         let command = Self::parse_message(message_text);
 
@@ -109,29 +452,96 @@ impl<B: BlueskyClient + Clone, R: ReadwiseClient + Clone> DmBotService<B, R> {
                 note,
                 extract_links,
             } => {
-                // Convert URL to AT-URI
-                let post_uri = Self::url_to_at_uri(&post_url)?;
+                let Some(readwise_token) = readwise_token else {
+                    return Ok(REGISTER_PROMPT.to_string());
+                };
+
+                match self
+                    .save_post(sender, &post_url, note, extract_links, readwise_token)
+                    .await
+                {
+                    Ok(outcome) => Ok(format!(
+                        "✅ Saved to Readwise as {}!",
+                        Self::describe(&outcome)
+                    )),
+                    Err(ProcessError::PostUnavailable) => Ok(POST_NOT_FOUND_REPLY.to_string()),
+                    Err(ProcessError::Duplicate) => Ok(ALREADY_SAVED_REPLY.to_string()),
+                    Err(e) if e.is_token_rejected() => Ok(TOKEN_REJECTED_REPLY.to_string()),
+                    Err(e) if e.is_rate_limited() => Ok(RATE_LIMITED_REPLY.to_string()),
+                    Err(e) => Err(e.into()),
+                }
+            }
+            DmCommand::SaveBatch {
+                post_urls,
+                extract_links,
+            } => {
+                let Some(readwise_token) = readwise_token else {
+                    return Ok(REGISTER_PROMPT.to_string());
+                };
+
+                // One reply for the whole batch keeps us under the chat rate limit
+                let mut lines = Vec::with_capacity(post_urls.len());
+                let mut saved = 0;
+                for post_url in &post_urls {
+                    match self
+                        .save_post(sender, post_url, None, extract_links, readwise_token)
+                        .await
+                    {
+                        Ok(outcome) => {
+                            saved += 1;
+                            lines.push(format!("✅ {} → {}", post_url, Self::describe(&outcome)));
+                        }
+                        Err(ProcessError::Duplicate) => {
+                            saved += 1;
+                            lines.push(format!("✅ {} → already in Readwise", post_url));
+                        }
+                        Err(e) => {
+                            warn!("Failed to save {} from batch: {}", post_url, e);
+                            lines.push(format!("❌ {}: {}", post_url, e));
+                        }
+                    }
+                }
 
+                Ok(format!(
+                    "Saved {} of {} posts:\n{}",
+                    saved,
+                    post_urls.len(),
+                    lines.join("\n")
+                ))
+            }
+            DmCommand::DryRun { post_url } => {
+                let post_uri = self.post_uri(&post_url).await?;
                 let options = ProcessOptions {
-                    extract_links,
-                    note,
+                    dry_run: true,
+                    ..Default::default()
                 };
 
-                self.processor
-                    .process_post(&post_uri, readwise_token, options)
+                // Nothing is written to Readwise, so no token is needed
+                let outcome = self
+                    .processor
+                    .process_post(&post_uri, readwise_token.unwrap_or_default(), options)
                     .await?;
 
-                Ok("✅ Saved to Readwise!".to_string())
+                Ok(format!(
+                    "🧪 Dry run: this post would be saved as {}.",
+                    Self::describe(&outcome)
+                ))
             }
-            DmCommand::Register { readwise_token: _ } => {
-                // TODO: Save the Readwise token for this user
-                Ok("✅ Registered! You can now DM me post URLs to save them.".to_string())
+            DmCommand::Register { readwise_token } => self.register(sender, &readwise_token).await,
+            DmCommand::RotateToken { readwise_token } => {
+                self.rotate_token(sender, &readwise_token).await
             }
             DmCommand::Help => Ok(Self::help_message()),
-            DmCommand::Settings => {
-                // TODO: Return actual settings URL
-                Ok("⚙️ Visit https://your-domain.com/dashboard to manage settings".to_string())
+            DmCommand::Settings => self.settings_link(sender).await,
+            DmCommand::Status => self.status(sender).await,
+            DmCommand::Verify => {
+                let Some(readwise_token) = readwise_token else {
+                    return Ok(REGISTER_PROMPT.to_string());
+                };
+                self.verify(sender, readwise_token).await
             }
+            DmCommand::SetSync(enabled) => self.set_sync(sender, enabled).await,
+            DmCommand::SetFormat(target) => self.set_format(sender, target).await,
             DmCommand::Unknown(text) => {
                 warn!("Unknown command: {}", text);
                 Ok(format!(
@@ -143,6 +553,260 @@ impl<B: BlueskyClient + Clone, R: ReadwiseClient + Clone> DmBotService<B, R> {
         }
     }
 
+    /// Save one post for a registered sender
+    ///
+    /// A rate-limited save is handed to the failed-save retries.
+    async fn save_post(
+        &self,
+        sender: &Author,
+        post_url: &str,
+        note: Option<String>,
+        extract_links: bool,
+        readwise_token: &str,
+    ) -> Result<ProcessOutcome, ProcessError> {
+        // Convert URL to AT-URI
+        let post_uri = self.post_uri(post_url).await?;
+
+        // Re-saving a thread updates the document saved last time
+        let user = self.db.get_user_by_did(&sender.did).await?;
+        let (existing_document_id, settings) = match &user {
+            Some(user) => (
+                self.db.get_saved_document(user.id, &post_uri).await?,
+                self.db.get_user_settings(user.id).await?,
+            ),
+            None => (None, None),
+        };
+
+        let options = ProcessOptions {
+            extract_links,
+            note,
+            existing_document_id,
+            user_id: user.as_ref().map(|user| user.id),
+            ..settings
+                .as_ref()
+                .map(ProcessOptions::from_settings)
+                .unwrap_or_default()
+        };
+
+        let outcome = match self
+            .processor
+            .process_post(&post_uri, readwise_token, options)
+            .await
+        {
+            Ok(outcome) => outcome,
+            Err(e) if e.is_rate_limited() => {
+                if let Some(user) = &user {
+                    let next_attempt_at = chrono::Utc::now() + retry_delay(1);
+                    self.db
+                        .record_failed_save(user.id, &post_uri, &e.to_string(), next_attempt_at)
+                        .await?;
+                }
+                return Err(e);
+            }
+            Err(e) => return Err(e),
+        };
+        if let (Some(user), Some(id)) = (&user, outcome.new_document_id()) {
+            self.db
+                .record_saved_document(user.id, &post_uri, id)
+                .await?;
+        }
+        Ok(outcome)
+    }
+
+    /// Verify a Readwise token and save it for the sender
+    ///
+    /// Creates the user if they've never logged in on the web.
+    async fn register(&self, sender: &Author, readwise_token: &str) -> Result<String> {
+        if !self.readwise.verify_token(readwise_token).await? {
+            warn!("Invalid Readwise token from {}", sender.did);
+            return Ok(INVALID_TOKEN_REPLY.to_string());
+        }
+
+        let user = match self.db.get_user_by_did(&sender.did).await? {
+            Some(user) => user,
+            None => self.db.create_user(&sender.did, &sender.handle).await?,
+        };
+
+        // Keep any preferences already set on the dashboard
+        let existing = self.db.get_user_settings(user.id).await?;
+        self.db
+            .create_user_settings(
+                user.id,
+                readwise_token,
+                existing.as_ref().is_none_or(|s| s.bookmark_sync_enabled),
+                existing.as_ref().is_some_and(|s| s.extract_links),
+            )
+            .await?;
+
+        info!("Registered {} via DM", sender.did);
+        Ok("✅ Registered! You can now DM me post URLs to save them.".to_string())
+    }
+
+    /// Replace the sender's Readwise token, keeping their other settings
+    async fn rotate_token(&self, sender: &Author, readwise_token: &str) -> Result<String> {
+        let Some(user) = self.db.get_user_by_did(&sender.did).await? else {
+            return Ok(REGISTER_PROMPT.to_string());
+        };
+
+        match rotate_readwise_token(&self.db, &self.readwise, user.id, readwise_token).await? {
+            TokenRotation::Rotated => {
+                info!("Rotated Readwise token for {} via DM", sender.did);
+                Ok("🔑 Updated your Readwise token. Everything else is unchanged.".to_string())
+            }
+            TokenRotation::Rejected => {
+                warn!("Invalid replacement Readwise token from {}", sender.did);
+                Ok(INVALID_ROTATION_REPLY.to_string())
+            }
+            TokenRotation::NotRegistered => Ok(REGISTER_PROMPT.to_string()),
+        }
+    }
+
+    /// Create a single-use magic link that logs the sender into the dashboard
+    async fn settings_link(&self, sender: &Author) -> Result<String> {
+        if self.db.get_user_by_did(&sender.did).await?.is_none() {
+            return Ok(REGISTER_PROMPT.to_string());
+        }
+
+        let token = self
+            .db
+            .create_login_token(
+                &sender.did,
+                chrono::Duration::minutes(MAGIC_LINK_TTL_MINUTES),
+            )
+            .await?;
+
+        Ok(format!(
+            "⚙️ Manage your settings here (link expires in {} minutes):\n{}/auth/magic/{}",
+            MAGIC_LINK_TTL_MINUTES, self.config.base_url, token
+        ))
+    }
+
+    /// Turn the sender's bookmark sync on or off, starting or stopping its loop
+    async fn set_sync(&self, sender: &Author, enabled: bool) -> Result<String> {
+        let Some(user) = self.db.get_user_by_did(&sender.did).await? else {
+            return Ok(REGISTER_PROMPT.to_string());
+        };
+        if self.db.get_user_settings(user.id).await?.is_none() {
+            return Ok(REGISTER_PROMPT.to_string());
+        }
+        if enabled && user.needs_reauth {
+            return Ok(format!(
+                "🔑 Bluesky stopped accepting my access to your account. \
+                 Log in again at {} and then send \"sync on\".",
+                self.config.base_url
+            ));
+        }
+
+        self.db
+            .update_user_settings(
+                user.id,
+                &SettingsUpdate {
+                    bookmark_sync_enabled: Some(enabled),
+                    ..Default::default()
+                },
+            )
+            .await?;
+
+        if let Some((tasks, starter)) = &self.sync {
+            if !enabled {
+                tasks.cancel(user.id);
+            } else if !tasks.is_running(user.id) {
+                starter.start(&user).await?;
+            }
+        }
+
+        info!(
+            "Bookmark sync turned {} via DM",
+            if enabled { "on" } else { "off" }
+        );
+        Ok(if enabled {
+            "🔄 Bookmark sync is on. New bookmarks will be saved to Readwise.".to_string()
+        } else {
+            "⏸️ Bookmark sync is off. Send \"sync on\" to resume.".to_string()
+        })
+    }
+
+    /// Choose where the sender's single posts are saved
+    async fn set_format(&self, sender: &Author, target: SaveTarget) -> Result<String> {
+        let Some(user) = self.db.get_user_by_did(&sender.did).await? else {
+            return Ok(REGISTER_PROMPT.to_string());
+        };
+        if self.db.get_user_settings(user.id).await?.is_none() {
+            return Ok(REGISTER_PROMPT.to_string());
+        }
+
+        self.db
+            .set_single_post_target(user.id, Some(target))
+            .await?;
+
+        info!("Single post target set to {} via DM", target.as_str());
+        Ok(match target {
+            SaveTarget::Highlight => "✨ Single posts will be saved as highlights.",
+            SaveTarget::Document => "📄 Single posts will be saved to Reader.",
+        }
+        .to_string())
+    }
+
+    /// Summarize the sender's sync settings, recent activity, and token health
+    async fn status(&self, sender: &Author) -> Result<String> {
+        let Some(user) = self.db.get_user_by_did(&sender.did).await? else {
+            return Ok(REGISTER_PROMPT.to_string());
+        };
+        let Some(settings) = self.db.get_user_settings(user.id).await? else {
+            return Ok(REGISTER_PROMPT.to_string());
+        };
+
+        let sync = if settings.bookmark_sync_enabled {
+            "on"
+        } else {
+            "off"
+        };
+        let last_saved = match self.db.last_bookmark_processed_at(user.id).await? {
+            Some(at) => at.format("%Y-%m-%d %H:%M UTC").to_string(),
+            None => "never".to_string(),
+        };
+        let saves_today = self.db.saves_today(user.id).await?;
+        let token = match self.readwise.verify_token(&settings.readwise_token).await {
+            Ok(true) => "valid",
+            Ok(false) => "invalid (send \"register <token>\" to replace it)",
+            Err(e) => {
+                warn!("Couldn't verify Readwise token for {}: {}", sender.did, e);
+                "couldn't be checked right now"
+            }
+        };
+
+        let mut status = format!(
+            "📊 Your status\n\
+             • Bookmark sync: {}\n\
+             • Last bookmark saved: {}\n\
+             • Bookmarks saved today: {}\n\
+             • Readwise token: {}",
+            sync, last_saved, saves_today, token
+        );
+        if let Some((done, total)) = settings.backfill_progress() {
+            status.push_str(&format!(
+                "\n• Importing existing bookmarks: {} of {}",
+                done, total
+            ));
+        }
+        Ok(status)
+    }
+
+    /// Describe an outcome for a reply, e.g. "a highlight (plus 2 links)"
+    fn describe(outcome: &ProcessOutcome) -> String {
+        let target = match outcome.kind {
+            OutcomeKind::Highlight => "a highlight",
+            OutcomeKind::Document => "a Reader document",
+            OutcomeKind::DocumentReplaced => "a Reader document, replacing your earlier copy",
+            OutcomeKind::Skipped => "nothing",
+        };
+        match outcome.links_saved {
+            0 => target.to_string(),
+            1 => format!("{} (plus 1 link)", target),
+            n => format!("{} (plus {} links)", target, n),
+        }
+    }
+
     /// Parse a DM message into a command
     pub fn parse_message(text: &str) -> DmCommand {
         let text = text.trim();
@@ -157,6 +821,44 @@ impl<B: BlueskyClient + Clone, R: ReadwiseClient + Clone> DmBotService<B, R> {
             return DmCommand::Settings;
         }
 
+        // Check for status command
+        if text.eq_ignore_ascii_case("status") {
+            return DmCommand::Status;
+        }
+
+        // Check for verify command
+        if text.eq_ignore_ascii_case("verify") {
+            return DmCommand::Verify;
+        }
+
+        // Check for sync on/off commands
+        if text.eq_ignore_ascii_case("sync on") {
+            return DmCommand::SetSync(true);
+        }
+        if text.eq_ignore_ascii_case("sync off") {
+            return DmCommand::SetSync(false);
+        }
+
+        // Check for save format command
+        let lower = text.to_ascii_lowercase();
+        if let Some(format) = lower.strip_prefix("set format ") {
+            return match format.trim() {
+                "highlight" => DmCommand::SetFormat(SaveTarget::Highlight),
+                "reader" => DmCommand::SetFormat(SaveTarget::Document),
+                other => DmCommand::Unknown(format!(
+                    "Formats are \"reader\" and \"highlight\" (got \"{}\").",
+                    other
+                )),
+            };
+        }
+
+        // Check for token rotation command
+        if let Some(token) = text.strip_prefix("token ") {
+            return DmCommand::RotateToken {
+                readwise_token: token.trim().to_string(),
+            };
+        }
+
         // Check for register command
         if let Some(token) = text.strip_prefix("register ") {
             return DmCommand::Register {
@@ -164,19 +866,41 @@ impl<B: BlueskyClient + Clone, R: ReadwiseClient + Clone> DmBotService<B, R> {
             };
         }
 
-        // Try to extract a Bluesky post URL
         let url_pattern = Regex::new(r"https://bsky\.app/profile/[^/]+/post/[a-zA-Z0-9]+").unwrap();
 
+        // Check for dry-run command
+        if let Some(rest) = text.strip_prefix("dryrun ") {
+            if let Some(url_match) = url_pattern.find(rest) {
+                return DmCommand::DryRun {
+                    post_url: url_match.as_str().to_string(),
+                };
+            }
+        }
+
+        // Several post URLs save as a batch (notes only apply to a single post)
+        let mut post_urls: Vec<String> = Vec::new();
+        for url_match in url_pattern.find_iter(text) {
+            let url = url_match.as_str().to_string();
+            if !post_urls.contains(&url) {
+                post_urls.push(url);
+            }
+        }
+        if post_urls.len() > 1 {
+            return DmCommand::SaveBatch {
+                post_urls,
+                extract_links: text.split_whitespace().any(|word| word == "+links"),
+            };
+        }
+
+        // Try to extract a Bluesky post URL
         if let Some(url_match) = url_pattern.find(text) {
             let post_url = url_match.as_str().to_string();
 
             // Check for +links flag
-            let extract_links = text.contains("+links");
+            let extract_links = text.split_whitespace().any(|word| word == "+links");
 
-            // Extract note (text after URL, excluding flags)
-            let after_url = text[url_match.end()..].trim();
-            let note = after_url.replace("+links", "").trim().to_string();
-            let note = if note.is_empty() { None } else { Some(note) };
+            // Everything around the URL, minus flags, is the note
+            let note = Self::extract_note(&text[..url_match.start()], &text[url_match.end()..]);
 
             return DmCommand::SavePost {
                 post_url,
@@ -188,22 +912,87 @@ impl<B: BlueskyClient + Clone, R: ReadwiseClient + Clone> DmBotService<B, R> {
         DmCommand::Unknown(text.to_string())
     }
 
+    /// Build the note from the text before and after the post URL
+    ///
+    /// Recognized flags are removed; line and paragraph breaks are kept,
+    /// with runs of blank lines collapsed to one.
+    fn extract_note(before: &str, after: &str) -> Option<String> {
+        let mut lines: Vec<String> = Vec::new();
+        for line in before.lines().chain(after.lines()) {
+            let line = line
+                .split_whitespace()
+                .filter(|word| !Self::is_flag(word))
+                .collect::<Vec<_>>()
+                .join(" ");
+            let blank_run = line.is_empty() && lines.last().is_some_and(String::is_empty);
+            if !blank_run {
+                lines.push(line);
+            }
+        }
+
+        let note = lines.join("\n").trim().to_string();
+        if note.is_empty() {
+            None
+        } else {
+            Some(note)
+        }
+    }
+
+    /// Whether a word is a save flag rather than part of the note
+    fn is_flag(word: &str) -> bool {
+        word == "+links" || word == "to:later" || word.starts_with("tag:")
+    }
+
+    /// AT-URI for a post URL, with the handle resolved to a DID when possible
+    async fn post_uri(&self, url: &str) -> Result<String> {
+        let uri = parse_at_uri(&Self::url_to_at_uri(url)?)?;
+        let Some(handles) = &self.handles else {
+            return Ok(uri.to_string());
+        };
+        if uri.did_or_handle.starts_with("did:") {
+            return Ok(uri.to_string());
+        }
+
+        match handles.resolve_cached(&uri.did_or_handle).await {
+            Ok(did) => Ok(format!("at://{}/{}/{}", did, uri.collection, uri.rkey)),
+            Err(e) => {
+                // The public API also accepts handle URIs
+                warn!("{}; using the handle URI", e);
+                Ok(uri.to_string())
+            }
+        }
+    }
+
     /// Convert a bsky.app URL to an AT-URI
     fn url_to_at_uri(url: &str) -> Result<String> {
         // URL format: https://bsky.app/profile/{handle}/post/{rkey}
         let parts: Vec<&str> = url.split('/').collect();
 
-        if parts.len() < 6 {
+        if parts.len() < 7 {
             return Err(anyhow!("Invalid Bluesky URL format"));
         }
 
         let handle = parts[4];
         let rkey = parts[6];
 
-        // TODO: Resolve handle to DID using identity resolution
-        // For now, assume handle format for the AT-URI
-        // This should be: at://{did}/app.bsky.feed.post/{rkey}
-        Ok(format!("at://{}/app.bsky.feed.post/{}", handle, rkey))
+        // Handles are resolved to DIDs by `post_uri` when a cache is set
+        let uri = parse_at_uri(&format!("at://{}/{}/{}", handle, POST_COLLECTION, rkey))?;
+        Ok(uri.to_string())
+    }
+
+    /// Check the sender's stored Readwise token against Readwise
+    async fn verify(&self, sender: &Author, readwise_token: &str) -> Result<String> {
+        match self.readwise.verify_token(readwise_token).await {
+            Ok(true) => Ok(TOKEN_VALID_REPLY.to_string()),
+            Ok(false) => Ok(STALE_TOKEN_REPLY.to_string()),
+            Err(e) => {
+                warn!("Couldn't verify Readwise token for {}: {}", sender.did, e);
+                Ok(
+                    "⚠️ I couldn't reach Readwise to check your token. Try again in a bit."
+                        .to_string(),
+                )
+            }
+        }
     }
 
     /// Generate help message
@@ -214,8 +1003,15 @@ Commands:
 • Send a post URL to save it
 • URL +links - Also save linked content
 • URL Your note here - Add a note
+• Several URLs in one message - Save them all
+• dryrun URL - Show how a post would be saved, without saving
 • register <token> - Register with Readwise token
+• token <token> - Replace your Readwise token
 • settings - Get link to settings
+• status - Show your sync status
+• verify - Check that your Readwise token still works
+• sync on / sync off - Turn bookmark sync on or off
+• set format reader / set format highlight - Save single posts to Reader or as highlights
 • help - Show this message
 
 Examples:
@@ -226,6 +1022,54 @@ https://bsky.app/profile/user.bsky.social/post/abc123 Great thread!"#
     }
 }
 
+/// Split a reply into messages of at most `max_graphemes` graphemes and
+/// `max_bytes` bytes
+///
+/// Splits between lines where possible; a single line over either limit is
+/// split mid-line, but never inside a grapheme.
+pub fn chunk_message(text: &str, max_graphemes: usize, max_bytes: usize) -> Vec<String> {
+    let max_graphemes = max_graphemes.max(1);
+    let mut chunks = Vec::new();
+    let mut current = String::new();
+    let mut current_graphemes = 0;
+
+    for line in text.lines() {
+        let mut pieces = vec![(String::new(), 0)];
+        for grapheme in line.graphemes(true) {
+            let (piece, count) = pieces.last_mut().expect("pieces is never empty");
+            if *count > 0
+                && (*count + 1 > max_graphemes || piece.len() + grapheme.len() > max_bytes)
+            {
+                pieces.push((grapheme.to_string(), 1));
+            } else {
+                piece.push_str(grapheme);
+                *count += 1;
+            }
+        }
+
+        for (piece, count) in pieces {
+            if !current.is_empty()
+                && (current_graphemes + 1 + count > max_graphemes
+                    || current.len() + 1 + piece.len() > max_bytes)
+            {
+                chunks.push(std::mem::take(&mut current));
+                current_graphemes = 0;
+            }
+            if !current.is_empty() {
+                current.push('\n');
+                current_graphemes += 1;
+            }
+            current.push_str(&piece);
+            current_graphemes += count;
+        }
+    }
+
+    if !current.is_empty() || chunks.is_empty() {
+        chunks.push(current);
+    }
+    chunks
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -278,12 +1122,100 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_parse_note_above_url() {
+        let msg = "Worth rereading\nhttps://bsky.app/profile/test.bsky.social/post/abc123 +links";
+        let cmd = DmBotService::<MockClient, MockClient>::parse_message(msg);
+
+        match cmd {
+            DmCommand::SavePost {
+                note,
+                extract_links,
+                ..
+            } => {
+                assert_eq!(note, Some("Worth rereading".to_string()));
+                assert!(extract_links);
+            }
+            _ => panic!("Expected SavePost command"),
+        }
+    }
+
+    #[test]
+    fn test_parse_multi_paragraph_note() {
+        let msg = "https://bsky.app/profile/test.bsky.social/post/abc123\n\
+                   First point.\n\n\n  Second point, tag:ideas\nstill second. to:later ";
+        let cmd = DmBotService::<MockClient, MockClient>::parse_message(msg);
+
+        match cmd {
+            DmCommand::SavePost { note, .. } => {
+                assert_eq!(
+                    note,
+                    Some("First point.\n\nSecond point,\nstill second.".to_string())
+                );
+            }
+            _ => panic!("Expected SavePost command"),
+        }
+    }
+
     #[test]
     fn test_parse_help() {
         let cmd = DmBotService::<MockClient, MockClient>::parse_message("help");
         assert_eq!(cmd, DmCommand::Help);
     }
 
+    #[test]
+    fn test_parse_dry_run() {
+        let cmd = DmBotService::<MockClient, MockClient>::parse_message(
+            "dryrun https://bsky.app/profile/test.bsky.social/post/abc123",
+        );
+        assert_eq!(
+            cmd,
+            DmCommand::DryRun {
+                post_url: "https://bsky.app/profile/test.bsky.social/post/abc123".to_string()
+            }
+        );
+    }
+
+    #[test]
+    fn test_parse_status() {
+        let cmd = DmBotService::<MockClient, MockClient>::parse_message(" Status ");
+        assert_eq!(cmd, DmCommand::Status);
+    }
+
+    #[test]
+    fn test_parse_verify() {
+        let cmd = DmBotService::<MockClient, MockClient>::parse_message(" Verify ");
+        assert_eq!(cmd, DmCommand::Verify);
+    }
+
+    #[test]
+    fn test_parse_sync_toggle() {
+        assert_eq!(
+            DmBotService::<MockClient, MockClient>::parse_message("sync on"),
+            DmCommand::SetSync(true)
+        );
+        assert_eq!(
+            DmBotService::<MockClient, MockClient>::parse_message(" Sync OFF "),
+            DmCommand::SetSync(false)
+        );
+    }
+
+    #[test]
+    fn test_parse_set_format() {
+        assert_eq!(
+            DmBotService::<MockClient, MockClient>::parse_message("set format reader"),
+            DmCommand::SetFormat(SaveTarget::Document)
+        );
+        assert_eq!(
+            DmBotService::<MockClient, MockClient>::parse_message("Set Format Highlight"),
+            DmCommand::SetFormat(SaveTarget::Highlight)
+        );
+        assert!(matches!(
+            DmBotService::<MockClient, MockClient>::parse_message("set format pdf"),
+            DmCommand::Unknown(_)
+        ));
+    }
+
     #[test]
     fn test_parse_register() {
         let cmd = DmBotService::<MockClient, MockClient>::parse_message("register abc123token");
@@ -295,6 +1227,582 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_parse_rotate_token() {
+        assert_eq!(
+            DmBotService::<MockClient, MockClient>::parse_message("token  newtoken456 "),
+            DmCommand::RotateToken {
+                readwise_token: "newtoken456".to_string()
+            }
+        );
+    }
+
+    #[sqlx::test]
+    async fn test_unregistered_sender_prompted_to_register(pool: sqlx::PgPool) {
+        let db = Database::new(pool, crate::crypto::EncryptionKey::test_key());
+        let client = MockClient::default();
+        let bot = test_bot(db.clone(), client.clone());
+
+        assert_eq!(bot.poll_dms().await.unwrap(), 1);
+        assert_eq!(
+            client.sent.lock().unwrap().as_slice(),
+            [REGISTER_PROMPT.to_string()]
+        );
+        assert!(db.is_dm_processed("msg1").await.unwrap());
+
+        // Already handled, so nothing new on the next poll
+        assert_eq!(bot.poll_dms().await.unwrap(), 0);
+    }
+
+    #[sqlx::test]
+    async fn test_purged_messages_are_not_answered_again(pool: sqlx::PgPool) {
+        let db = Database::new(pool.clone(), crate::crypto::EncryptionKey::test_key());
+        let client = MockClient {
+            sent_days_ago: 40,
+            ..MockClient::default()
+        };
+        let config = DmBotConfig {
+            retention_days: Some(30),
+            ..DmBotConfig::default()
+        };
+        let bot = DmBotService::new(
+            client.clone(),
+            client.clone(),
+            db.clone(),
+            "did:plc:bot".to_string(),
+            config,
+        );
+        // Answered when it was sent, 40 days ago
+        db.mark_dm_processed(None, "msg1", "register", "did:plc:bot")
+            .await
+            .unwrap();
+        sqlx::query("UPDATE processed_dms SET processed_at = NOW() - INTERVAL '40 days'")
+            .execute(&pool)
+            .await
+            .unwrap();
+        let state = crate::AppState {
+            config: crate::config::Config {
+                retention_days: Some(30),
+                ..crate::config::Config::test_default()
+            },
+            ..crate::AppState::test(db.clone())
+        };
+
+        let report = crate::services::cleanup::run_cleanup(&state).await.unwrap();
+        assert_eq!(report.processed_rows, 1);
+        assert!(!db.is_dm_processed("msg1").await.unwrap());
+
+        assert_eq!(bot.poll_dms().await.unwrap(), 0);
+        assert!(client.sent.lock().unwrap().is_empty());
+    }
+
+    /// Hands out a client with a live session, counting refreshes
+    struct MockRefresher {
+        client: MockClient,
+        calls: std::sync::atomic::AtomicUsize,
+    }
+
+    #[async_trait]
+    impl BotSessionRefresher<MockClient> for MockRefresher {
+        async fn refresh(&self) -> Result<MockClient> {
+            self.calls.fetch_add(1, std::sync::atomic::Ordering::SeqCst);
+            Ok(MockClient {
+                expired_session: false,
+                ..self.client.clone()
+            })
+        }
+    }
+
+    #[sqlx::test]
+    async fn test_expired_session_refreshed_and_retried(pool: sqlx::PgPool) {
+        let db = Database::new(pool, crate::crypto::EncryptionKey::test_key());
+        let client = MockClient {
+            expired_session: true,
+            ..MockClient::default()
+        };
+        let refresher = Arc::new(MockRefresher {
+            client: client.clone(),
+            calls: std::sync::atomic::AtomicUsize::new(0),
+        });
+        let bot = test_bot(db.clone(), client.clone()).with_session_refresher(refresher.clone());
+        // As digests hold it
+        let session = bot.session();
+
+        assert_eq!(bot.poll_dms().await.unwrap(), 1);
+        assert_eq!(
+            client.sent.lock().unwrap().as_slice(),
+            [REGISTER_PROMPT.to_string()]
+        );
+
+        // The refreshed session is kept for later polls, and shared
+        assert_eq!(bot.poll_dms().await.unwrap(), 0);
+        assert_eq!(refresher.calls.load(std::sync::atomic::Ordering::SeqCst), 1);
+        assert!(!session.client().expired_session);
+    }
+
+    #[sqlx::test]
+    async fn test_expired_session_without_refresher_fails(pool: sqlx::PgPool) {
+        let db = Database::new(pool, crate::crypto::EncryptionKey::test_key());
+        let client = MockClient {
+            expired_session: true,
+            ..MockClient::default()
+        };
+
+        let error = test_bot(db, client).poll_dms().await.unwrap_err();
+        assert!(is_token_rejected(&error));
+    }
+
+    #[sqlx::test]
+    async fn test_each_bot_account_answers_its_own_dms(pool: sqlx::PgPool) {
+        let db = Database::new(pool, crate::crypto::EncryptionKey::test_key());
+        let first = MockClient::default();
+        let second = MockClient {
+            account: Some("did:plc:bot2"),
+            ..MockClient::default()
+        };
+
+        assert_eq!(
+            test_bot(db.clone(), first.clone())
+                .poll_dms()
+                .await
+                .unwrap(),
+            1
+        );
+        assert_eq!(
+            test_bot(db.clone(), second.clone())
+                .poll_dms()
+                .await
+                .unwrap(),
+            1
+        );
+
+        // Each reply goes out through the account the DM was sent to
+        assert_eq!(first.sent.lock().unwrap().len(), 1);
+        assert_eq!(second.sent.lock().unwrap().len(), 1);
+        let rows: Vec<(String, Option<String>)> =
+            sqlx::query_as("SELECT message_id, bot_account FROM processed_dms ORDER BY message_id")
+                .fetch_all(db.pool())
+                .await
+                .unwrap();
+        assert_eq!(
+            rows,
+            vec![
+                (
+                    "did:plc:bot2/msg1".to_string(),
+                    Some("did:plc:bot2".to_string())
+                ),
+                ("msg1".to_string(), Some("did:plc:bot".to_string())),
+            ]
+        );
+    }
+
+    #[sqlx::test]
+    async fn test_register_saves_valid_token(pool: sqlx::PgPool) {
+        let db = Database::new(pool, crate::crypto::EncryptionKey::test_key());
+        let bot = test_bot(db.clone(), MockClient::default());
+
+        let reply = bot
+            .process_message(&sender(), "register good-token", None)
+            .await
+            .unwrap();
+        assert!(reply.starts_with("✅"));
+
+        let user = db.get_user_by_did("did:plc:sender").await.unwrap().unwrap();
+        assert_eq!(user.bluesky_handle, "sender.bsky.social");
+        let settings = db.get_user_settings(user.id).await.unwrap().unwrap();
+        assert_eq!(settings.readwise_token, "good-token");
+    }
+
+    #[sqlx::test]
+    async fn test_register_rejects_invalid_token(pool: sqlx::PgPool) {
+        let db = Database::new(pool, crate::crypto::EncryptionKey::test_key());
+        let client = MockClient {
+            reject_tokens: true,
+            ..Default::default()
+        };
+        let bot = test_bot(db.clone(), client);
+
+        let reply = bot
+            .process_message(&sender(), "register bad-token", None)
+            .await
+            .unwrap();
+        assert_eq!(reply, INVALID_TOKEN_REPLY);
+        assert!(db
+            .get_user_by_did("did:plc:sender")
+            .await
+            .unwrap()
+            .is_none());
+    }
+
+    #[sqlx::test]
+    async fn test_status_reports_settings_and_saves(pool: sqlx::PgPool) {
+        let db = Database::new(pool, crate::crypto::EncryptionKey::test_key());
+        let user = db
+            .create_user("did:plc:sender", "sender.bsky.social")
+            .await
+            .unwrap();
+        db.create_user_settings(user.id, "good-token", true, false)
+            .await
+            .unwrap();
+        db.mark_bookmark_processed(user.id, "at://did:plc:a/app.bsky.feed.post/1")
+            .await
+            .unwrap();
+        db.mark_bookmark_processed(user.id, "at://did:plc:a/app.bsky.feed.post/2")
+            .await
+            .unwrap();
+        let bot = test_bot(db, MockClient::default());
+
+        let reply = bot
+            .process_message(&sender(), "status", Some("good-token"))
+            .await
+            .unwrap();
+
+        assert!(reply.contains("Bookmark sync: on"));
+        assert!(reply.contains("Bookmarks saved today: 2"));
+        assert!(reply.contains("Readwise token: valid"));
+        assert!(!reply.contains("never"));
+    }
+
+    #[sqlx::test]
+    async fn test_verify_reports_token_validity(pool: sqlx::PgPool) {
+        let db = Database::new(pool, crate::crypto::EncryptionKey::test_key());
+
+        let bot = test_bot(db.clone(), MockClient::default());
+        let reply = bot
+            .process_message(&sender(), "verify", Some("good-token"))
+            .await
+            .unwrap();
+        assert_eq!(reply, TOKEN_VALID_REPLY);
+
+        let rejecting = MockClient {
+            reject_tokens: true,
+            ..MockClient::default()
+        };
+        let bot = test_bot(db.clone(), rejecting);
+        let reply = bot
+            .process_message(&sender(), "verify", Some("stale-token"))
+            .await
+            .unwrap();
+        assert_eq!(reply, STALE_TOKEN_REPLY);
+
+        let reply = bot
+            .process_message(&sender(), "verify", None)
+            .await
+            .unwrap();
+        assert_eq!(reply, REGISTER_PROMPT);
+    }
+
+    #[sqlx::test]
+    async fn test_status_prompts_unregistered_sender(pool: sqlx::PgPool) {
+        let db = Database::new(pool, crate::crypto::EncryptionKey::test_key());
+        let bot = test_bot(db, MockClient::default());
+
+        let reply = bot
+            .process_message(&sender(), "status", None)
+            .await
+            .unwrap();
+        assert_eq!(reply, REGISTER_PROMPT);
+    }
+
+    /// Registers a never-ending task, like a real sync loop
+    struct PendingStarter(Arc<SyncTasks>);
+
+    #[async_trait]
+    impl SyncStarter for PendingStarter {
+        async fn start(&self, user: &crate::db::models::User) -> Result<()> {
+            let task = tokio::spawn(std::future::pending::<()>());
+            self.0.register(user.id, task.abort_handle());
+            Ok(())
+        }
+    }
+
+    #[sqlx::test]
+    async fn test_sync_toggle_updates_settings_and_task(pool: sqlx::PgPool) {
+        let db = Database::new(pool, crate::crypto::EncryptionKey::test_key());
+        let user = db
+            .create_user("did:plc:sender", "sender.bsky.social")
+            .await
+            .unwrap();
+        db.create_user_settings(user.id, "good-token", false, true)
+            .await
+            .unwrap();
+        let tasks = Arc::new(SyncTasks::new());
+        let bot = test_bot(db.clone(), MockClient::default())
+            .with_sync_control(tasks.clone(), Arc::new(PendingStarter(tasks.clone())));
+
+        let reply = bot
+            .process_message(&sender(), "sync on", Some("good-token"))
+            .await
+            .unwrap();
+        assert!(reply.contains("sync is on"));
+        let settings = db.get_user_settings(user.id).await.unwrap().unwrap();
+        assert!(settings.bookmark_sync_enabled);
+        assert!(settings.extract_links);
+        assert!(tasks.is_running(user.id));
+
+        let reply = bot
+            .process_message(&sender(), "sync off", Some("good-token"))
+            .await
+            .unwrap();
+        assert!(reply.contains("sync is off"));
+        let settings = db.get_user_settings(user.id).await.unwrap().unwrap();
+        assert!(!settings.bookmark_sync_enabled);
+        assert!(!tasks.is_running(user.id));
+
+        // Unregistered senders are asked to register
+        let stranger = Author {
+            did: "did:plc:stranger".to_string(),
+            ..sender()
+        };
+        let reply = bot
+            .process_message(&stranger, "sync on", None)
+            .await
+            .unwrap();
+        assert_eq!(reply, REGISTER_PROMPT);
+    }
+
+    #[test]
+    fn test_parse_save_batch() {
+        let msg = "https://bsky.app/profile/a.bsky.social/post/1\n\
+                   https://bsky.app/profile/b.bsky.social/post/2 +links\n\
+                   https://bsky.app/profile/a.bsky.social/post/1";
+        let cmd = DmBotService::<MockClient, MockClient>::parse_message(msg);
+
+        assert_eq!(
+            cmd,
+            DmCommand::SaveBatch {
+                post_urls: vec![
+                    "https://bsky.app/profile/a.bsky.social/post/1".to_string(),
+                    "https://bsky.app/profile/b.bsky.social/post/2".to_string(),
+                ],
+                extract_links: true,
+            }
+        );
+    }
+
+    #[test]
+    fn test_short_reply_is_one_message() {
+        let reply = "Saved 2 of 2 posts:\n✅ one\n✅ two";
+        assert_eq!(
+            chunk_message(reply, MAX_DM_GRAPHEMES, MAX_DM_BYTES),
+            vec![reply.to_string()]
+        );
+    }
+
+    #[test]
+    fn test_long_reply_is_chunked_on_lines() {
+        let lines: Vec<String> = (0..30).map(|i| format!("✅ post {:02}", i)).collect();
+        let reply = lines.join("\n");
+
+        let chunks = chunk_message(&reply, 50, MAX_DM_BYTES);
+
+        assert!(chunks.len() > 1);
+        assert!(chunks
+            .iter()
+            .all(|chunk| chunk.graphemes(true).count() <= 50));
+        // No line is split, and nothing is lost
+        assert_eq!(chunks.join("\n"), reply);
+
+        let long_line = "x".repeat(120);
+        let chunks = chunk_message(&long_line, 50, MAX_DM_BYTES);
+        assert_eq!(
+            chunks.iter().map(String::len).collect::<Vec<_>>(),
+            vec![50, 50, 20]
+        );
+    }
+
+    #[test]
+    fn test_long_reply_is_chunked_by_bytes() {
+        // Each ✅ is one grapheme but three bytes
+        let reply = "✅".repeat(25);
+        let chunks = chunk_message(&reply, MAX_DM_GRAPHEMES, 30);
+        assert_eq!(
+            chunks.iter().map(String::len).collect::<Vec<_>>(),
+            vec![30, 30, 15]
+        );
+
+        // A multi-codepoint emoji is never split
+        let family = "👨‍👩‍👧";
+        let chunks = chunk_message(&family.repeat(3), 2, MAX_DM_BYTES);
+        assert_eq!(chunks, vec![family.repeat(2), family.to_string()]);
+    }
+
+    #[sqlx::test]
+    async fn test_batch_gets_one_reply(pool: sqlx::PgPool) {
+        let db = Database::new(pool, crate::crypto::EncryptionKey::test_key());
+        let user = db
+            .create_user("did:plc:sender", "sender.bsky.social")
+            .await
+            .unwrap();
+        db.create_user_settings(user.id, "good-token", true, false)
+            .await
+            .unwrap();
+        let client = MockClient::default();
+        let bot = test_bot(db, client.clone());
+
+        let convo = ConvoView {
+            id: "convo1".to_string(),
+            members: vec![sender()],
+            unread_count: 1,
+        };
+        let message = MessageView {
+            id: "batch1".to_string(),
+            text: Some(
+                "https://bsky.app/profile/a.bsky.social/post/1 \
+                 https://bsky.app/profile/b.bsky.social/post/2 \
+                 https://bsky.app/profile/c.bsky.social/post/3"
+                    .to_string(),
+            ),
+            sender: crate::bluesky::MessageSender {
+                did: "did:plc:sender".to_string(),
+            },
+            sent_at: chrono::Utc::now(),
+        };
+
+        bot.handle_message(&convo, &message).await.unwrap();
+
+        let sent = client.sent.lock().unwrap();
+        assert_eq!(sent.len(), 1);
+        assert!(sent[0].starts_with("Saved 0 of 3 posts:"));
+        assert_eq!(sent[0].matches("❌").count(), 3);
+    }
+
+    #[sqlx::test]
+    async fn test_batch_reports_partial_success(pool: sqlx::PgPool) {
+        let db = Database::new(pool, crate::crypto::EncryptionKey::test_key());
+        let user = db
+            .create_user("did:plc:sender", "sender.bsky.social")
+            .await
+            .unwrap();
+        db.create_user_settings(user.id, "good-token", true, false)
+            .await
+            .unwrap();
+        let client = MockClient {
+            thread: Some(crate::test_support::thread(
+                crate::test_support::post("abc123").build(),
+            )),
+            missing_rkey: Some("2"),
+            ..Default::default()
+        };
+        let bot = test_bot(db, client);
+
+        let reply = bot
+            .process_message(
+                &sender(),
+                "https://bsky.app/profile/a.bsky.social/post/1 \
+                 https://bsky.app/profile/b.bsky.social/post/2 \
+                 https://bsky.app/profile/c.bsky.social/post/3",
+                Some("good-token"),
+            )
+            .await
+            .unwrap();
+
+        assert!(reply.starts_with("Saved 2 of 3 posts:"), "{}", reply);
+        assert_eq!(reply.matches("✅").count(), 2);
+        assert!(reply.contains("❌ https://bsky.app/profile/b.bsky.social/post/2"));
+    }
+
+    /// Register the test sender and have them save one post
+    async fn save_reply(db: Database, client: MockClient) -> String {
+        let user = db
+            .create_user("did:plc:sender", "sender.bsky.social")
+            .await
+            .unwrap();
+        db.create_user_settings(user.id, "good-token", true, false)
+            .await
+            .unwrap();
+        test_bot(db, client)
+            .process_message(
+                &sender(),
+                "https://bsky.app/profile/test.bsky.social/post/abc123",
+                Some("good-token"),
+            )
+            .await
+            .unwrap()
+    }
+
+    #[sqlx::test]
+    async fn test_set_format_reader_saves_single_posts_to_reader(pool: sqlx::PgPool) {
+        let db = Database::new(pool, crate::crypto::EncryptionKey::test_key());
+        let client = MockClient {
+            thread: Some(crate::test_support::thread(
+                crate::test_support::post("abc123")
+                    .text("Worth keeping")
+                    .build(),
+            )),
+            ..Default::default()
+        };
+        let user = db
+            .create_user("did:plc:sender", "sender.bsky.social")
+            .await
+            .unwrap();
+        db.create_user_settings(user.id, "good-token", true, false)
+            .await
+            .unwrap();
+        let bot = test_bot(db.clone(), client);
+
+        let reply = bot
+            .process_message(&sender(), "set format reader", Some("good-token"))
+            .await
+            .unwrap();
+        assert!(reply.contains("Reader"));
+        let settings = db.get_user_settings(user.id).await.unwrap().unwrap();
+        assert_eq!(settings.single_post_target(), Some(SaveTarget::Document));
+
+        let reply = bot
+            .process_message(
+                &sender(),
+                "https://bsky.app/profile/test.bsky.social/post/abc123",
+                Some("good-token"),
+            )
+            .await
+            .unwrap();
+        assert!(reply.contains("a Reader document"), "{}", reply);
+    }
+
+    #[sqlx::test]
+    async fn test_missing_post_reported(pool: sqlx::PgPool) {
+        let db = Database::new(pool, crate::crypto::EncryptionKey::test_key());
+        let client = MockClient {
+            missing_posts: true,
+            ..Default::default()
+        };
+
+        let reply = save_reply(db, client).await;
+        assert_eq!(reply, POST_NOT_FOUND_REPLY);
+        assert!(reply.contains("post not found"));
+    }
+
+    #[sqlx::test]
+    async fn test_rejected_token_reported(pool: sqlx::PgPool) {
+        let db = Database::new(pool, crate::crypto::EncryptionKey::test_key());
+        let client = MockClient {
+            thread: Some(crate::test_support::thread(
+                crate::test_support::post("abc123").build(),
+            )),
+            failed_saves: Some(reqwest::StatusCode::UNAUTHORIZED),
+            ..Default::default()
+        };
+
+        let reply = save_reply(db, client).await;
+        assert_eq!(reply, TOKEN_REJECTED_REPLY);
+    }
+
+    #[sqlx::test]
+    async fn test_post_readwise_already_has_reported(pool: sqlx::PgPool) {
+        let db = Database::new(pool, crate::crypto::EncryptionKey::test_key());
+        let client = MockClient {
+            thread: Some(crate::test_support::thread(
+                crate::test_support::post("abc123").build(),
+            )),
+            failed_saves: Some(reqwest::StatusCode::CONFLICT),
+            ..Default::default()
+        };
+
+        let reply = save_reply(db, client).await;
+        assert_eq!(reply, ALREADY_SAVED_REPLY);
+    }
+
     #[test]
     fn test_url_to_at_uri() {
         let url = "https://bsky.app/profile/test.bsky.social/post/abc123";
@@ -302,12 +1810,80 @@ mod tests {
         assert_eq!(uri, "at://test.bsky.social/app.bsky.feed.post/abc123");
     }
 
+    #[sqlx::test]
+    async fn test_post_uri_resolves_handle(pool: sqlx::PgPool) {
+        use crate::bluesky::handles::ClientHandleResolver;
+
+        let client = MockClient::default();
+        let handles = Arc::new(HandleCache::new(
+            Arc::new(ClientHandleResolver(client.clone())),
+            Duration::from_secs(3600),
+        ));
+        let db = Database::new(pool, crate::crypto::EncryptionKey::test_key());
+        let bot = test_bot(db, client).with_handle_cache(handles);
+
+        let uri = bot
+            .post_uri("https://bsky.app/profile/test.bsky.social/post/abc123")
+            .await
+            .unwrap();
+        assert_eq!(
+            uri,
+            "at://did:plc:test.bsky.social/app.bsky.feed.post/abc123"
+        );
+    }
+
     // Mock client for tests
-    use crate::bluesky::types::{BookmarkResponse, ThreadResponse};
+    use crate::bluesky::types::*;
     use async_trait::async_trait;
+    use std::sync::{Arc, Mutex};
 
-    #[derive(Clone)]
-    struct MockClient;
+    /// Has one unread conversation with a single post URL, plus the bot's own reply
+    #[derive(Clone, Default)]
+    struct MockClient {
+        sent: Arc<Mutex<Vec<String>>>,
+        reject_tokens: bool,
+        /// Served for every post; without it, threads fail to load
+        thread: Option<ThreadViewPost>,
+        missing_posts: bool,
+        /// Only the post with this rkey is missing
+        missing_rkey: Option<&'static str>,
+        /// Saves fail with this Readwise status
+        failed_saves: Option<reqwest::StatusCode>,
+        /// Bot account DID the client is logged in as; None is did:plc:bot
+        account: Option<&'static str>,
+        /// Chat calls fail with a rejected token until the session is refreshed
+        expired_session: bool,
+        /// How long ago the listed messages were sent
+        sent_days_ago: i64,
+    }
+
+    impl MockClient {
+        fn account(&self) -> &'static str {
+            self.account.unwrap_or("did:plc:bot")
+        }
+
+        /// Message IDs are unique across accounts' conversations
+        fn message_id(&self, id: &str) -> String {
+            match self.account {
+                Some(account) => format!("{}/{}", account, id),
+                None => id.to_string(),
+            }
+        }
+    }
+
+    fn test_bot(db: Database, client: MockClient) -> DmBotService<MockClient, MockClient> {
+        let bot_did = client.account().to_string();
+        DmBotService::new(client.clone(), client, db, bot_did, DmBotConfig::default())
+    }
+
+    fn sender() -> Author {
+        Author {
+            did: "did:plc:sender".to_string(),
+            handle: "sender.bsky.social".to_string(),
+            display_name: None,
+            labels: Vec::new(),
+        }
+    }
 
     #[async_trait]
     impl BlueskyClient for MockClient {
@@ -318,13 +1894,77 @@ mod tests {
             })
         }
 
-        async fn get_post_thread(&self, _uri: &str) -> Result<ThreadResponse> {
-            unimplemented!()
+        async fn get_post_thread(&self, uri: &str) -> Result<ThreadResponse> {
+            let missing_rkey = self
+                .missing_rkey
+                .is_some_and(|rkey| uri.ends_with(&format!("/{rkey}")));
+            if self.missing_posts || missing_rkey {
+                return Err(crate::bluesky::PostNotFound(uri.to_string()).into());
+            }
+            match &self.thread {
+                Some(thread) => Ok(ThreadResponse {
+                    thread: thread.clone(),
+                }),
+                None => Err(anyhow!("Post not found")),
+            }
+        }
+
+        async fn get_post_record(&self, _did: &str, _rkey: &str) -> Result<PostView> {
+            Err(anyhow!("unused"))
         }
 
-        async fn send_dm(&self, _convo_id: &str, _text: &str) -> Result<()> {
+        async fn send_dm(&self, _convo_id: &str, text: &str) -> Result<()> {
+            self.sent.lock().unwrap().push(text.to_string());
             Ok(())
         }
+
+        async fn get_convo_for_member(&self, _did: &str) -> Result<ConvoView> {
+            Err(anyhow!("unused"))
+        }
+
+        async fn list_convos(&self) -> Result<ConvoListResponse> {
+            if self.expired_session {
+                return Err(crate::bluesky::TokenRejected("401 ExpiredToken".to_string()).into());
+            }
+            Ok(ConvoListResponse {
+                cursor: None,
+                convos: vec![ConvoView {
+                    id: "convo1".to_string(),
+                    members: vec![],
+                    unread_count: 1,
+                }],
+            })
+        }
+
+        async fn get_messages(&self, _convo_id: &str) -> Result<MessagesResponse> {
+            let message = |id: &str, sender: &str, text: &str| MessageView {
+                id: self.message_id(id),
+                text: Some(text.to_string()),
+                sender: MessageSender {
+                    did: sender.to_string(),
+                },
+                sent_at: chrono::Utc::now() - chrono::Duration::days(self.sent_days_ago),
+            };
+            Ok(MessagesResponse {
+                cursor: None,
+                messages: vec![
+                    message("msg0", self.account(), "hello"),
+                    message(
+                        "msg1",
+                        "did:plc:stranger",
+                        "https://bsky.app/profile/test.bsky.social/post/abc123",
+                    ),
+                ],
+            })
+        }
+
+        async fn mark_convo_read(&self, _convo_id: &str) -> Result<()> {
+            Ok(())
+        }
+
+        async fn resolve_handle(&self, handle: &str) -> Result<String> {
+            Ok(format!("did:plc:{}", handle))
+        }
     }
 
     #[async_trait]
@@ -333,20 +1973,49 @@ mod tests {
             &self,
             _token: &str,
             _highlight: crate::readwise::client::Highlight,
-        ) -> Result<()> {
-            Ok(())
+        ) -> Result<Option<String>> {
+            if let Some(status) = self.failed_saves {
+                return Err(crate::readwise::client::ReadwiseApiError {
+                    api: "Readwise API",
+                    status,
+                    body: String::new(),
+                }
+                .into());
+            }
+            Ok(None)
         }
 
         async fn save_document(
             &self,
             _token: &str,
             _document: crate::readwise::client::Document,
-        ) -> Result<()> {
+        ) -> Result<Option<String>> {
+            if let Some(status) = self.failed_saves {
+                return Err(crate::readwise::client::ReadwiseApiError {
+                    api: "Readwise API",
+                    status,
+                    body: String::new(),
+                }
+                .into());
+            }
+            Ok(None)
+        }
+
+        async fn delete_document(&self, _token: &str, _id: &str) -> Result<()> {
             Ok(())
         }
 
         async fn verify_token(&self, _token: &str) -> Result<bool> {
-            Ok(true)
+            Ok(!self.reject_tokens)
+        }
+
+        async fn list_documents(
+            &self,
+            _token: &str,
+            _updated_after: Option<chrono::DateTime<chrono::Utc>>,
+            _location: Option<&str>,
+        ) -> Result<Vec<crate::readwise::client::ReaderDocument>> {
+            Ok(vec![])
         }
     }
 }
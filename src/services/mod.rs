@@ -2,7 +2,428 @@
 //!
 //! - Bookmark sync: polls user bookmarks
 //! - DM bot: polls bot account DMs
+//! - Daily digest: DMs opted-in users a recap of the day's saves
+//! - Save workers: save bookmarks queued by the sync loops
+//! - Save retries: re-attempts bookmark saves that failed
+//! - Self-test: one-shot check of credentials and connectivity
 
+pub mod author_filter;
 pub mod bookmark_sync;
+pub mod cleanup;
+pub mod digest;
 pub mod dm_bot;
+pub mod post_class;
 pub mod processor;
+pub mod readwise_token;
+pub mod save_queue;
+pub mod selftest;
+pub mod sync_tasks;
+
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use anyhow::{anyhow, Result};
+use async_trait::async_trait;
+use tokio::task::JoinHandle;
+use tokio::time::sleep;
+use tracing::{debug, error, info, warn};
+use uuid::Uuid;
+
+use crate::bluesky::{AtpSession, HttpBlueskyClient, OAuthService};
+use crate::config::BotAccount;
+use crate::content::oembed::YouTubeOEmbed;
+use crate::db::models::User;
+use crate::db::queries::Database;
+use crate::readwise::client::HttpReadwiseClient;
+use crate::AppState;
+use bookmark_sync::{BookmarkSyncConfig, BookmarkSyncService, SessionRefresher};
+use digest::DigestService;
+use dm_bot::{BotSessionRefresher, DmBotConfig, DmBotService};
+use processor::ProcessOutcome;
+use save_queue::SaveJobs;
+use sync_tasks::SyncStarter;
+
+/// Refresh the bot session well before its access JWT (~2 hours) expires
+const BOT_SESSION_REFRESH: Duration = Duration::from_secs(90 * 60);
+
+/// Wait before retrying a failed bot login
+const BOT_LOGIN_RETRY: Duration = Duration::from_secs(60);
+
+/// Bookmark sync service sharing the app's clients, limiter, and caches
+fn bookmark_sync_service(
+    state: &AppState,
+) -> BookmarkSyncService<HttpBlueskyClient, HttpReadwiseClient> {
+    let service = BookmarkSyncService::new(
+        state.bluesky_client(),
+        state.readwise_client(),
+        state.db.clone(),
+        BookmarkSyncConfig {
+            poll_interval: Duration::from_secs(state.config.bookmark_poll_interval_secs),
+            retention_days: state.config.retention_days,
+            ..Default::default()
+        },
+    )
+    .with_save_limiter(state.save_limiter.clone())
+    .with_highlight_templates(state.config.highlight_templates())
+    .with_thread_limits(state.config.thread_limits())
+    .with_features(state.features.clone())
+    .with_strip_query_params(state.config.strip_query_params())
+    .with_oembed(Arc::new(YouTubeOEmbed::new(state.http.clone())))
+    .with_handle_cache(state.handles.clone());
+    match &state.save_queue {
+        Some(queue) => service.with_save_queue(queue.clone()),
+        None => service,
+    }
+}
+
+/// Save a post for a user again, even if it was processed before
+pub async fn reprocess_post(
+    state: &AppState,
+    user_id: Uuid,
+    post_uri: &str,
+) -> Result<ProcessOutcome> {
+    bookmark_sync_service(state)
+        .reprocess(user_id, post_uri)
+        .await
+}
+
+/// What saving a post would send to Readwise for a user, without saving it
+pub async fn preview_post(
+    state: &AppState,
+    user_id: Uuid,
+    post_uri: &str,
+) -> Result<ProcessOutcome> {
+    bookmark_sync_service(state)
+        .preview(user_id, post_uri)
+        .await
+}
+
+/// Save bookmarks queued by the sync loops in the background
+pub fn spawn_save_workers(state: Arc<AppState>, jobs: SaveJobs) -> JoinHandle<()> {
+    let service = Arc::new(bookmark_sync_service(&state));
+    let workers = state.config.save_workers;
+    tokio::spawn(async move { service.run_save_workers(jobs, workers).await })
+}
+
+/// Purge expired logins and old processed rows every `cleanup_interval_secs`
+pub fn spawn_cleanup_loop(state: Arc<AppState>) -> JoinHandle<()> {
+    let period = Duration::from_secs(state.config.cleanup_interval_secs.max(1));
+    tokio::spawn(async move {
+        let mut ticker = tokio::time::interval(period);
+        loop {
+            ticker.tick().await;
+            match cleanup::run_cleanup(&state).await {
+                Ok(report) => debug!("Cleanup removed {:?}", report),
+                Err(e) => warn!("Cleanup failed: {}", e),
+            }
+        }
+    })
+}
+
+/// Retry dead-lettered bookmark saves in the background
+pub fn spawn_save_retries(state: Arc<AppState>) -> JoinHandle<()> {
+    let service = bookmark_sync_service(&state);
+    tokio::spawn(async move { service.run_retries().await })
+}
+
+/// Start a user's bookmark sync loop with their stored tokens
+pub async fn spawn_user_sync(state: &AppState, user: User) -> Result<()> {
+    let tokens = state
+        .db
+        .get_tokens(user.id)
+        .await?
+        .ok_or_else(|| anyhow!("No Bluesky tokens for {}", user.bluesky_did))?;
+    let settings = state
+        .db
+        .get_user_settings(user.id)
+        .await?
+        .ok_or_else(|| anyhow!("No settings for {}", user.bluesky_did))?;
+    let client = state
+        .bluesky_client()
+        .authenticated(tokens.access_token, user.bluesky_did.clone());
+
+    let mut service = bookmark_sync_service(state);
+    if let Some(oauth) = &state.oauth {
+        service = service.with_session_refresher(Arc::new(UserSessionRefresh {
+            oauth: oauth.clone(),
+            db: state.db.clone(),
+            client: client.clone(),
+        }));
+    }
+    let user_id = user.id;
+    let task = tokio::spawn(async move {
+        if let Err(e) = service.run_for_user(user, settings, client).await {
+            error!("Bookmark sync stopped: {}", e);
+        }
+    });
+    state.sync_tasks.register(user_id, task.abort_handle());
+    Ok(())
+}
+
+/// Refreshes a user's OAuth session when their access token expires
+struct UserSessionRefresh {
+    oauth: Arc<dyn OAuthService>,
+    db: Database,
+    /// The user's client, which refreshed copies take a new access token on
+    client: HttpBlueskyClient,
+}
+
+#[async_trait]
+impl SessionRefresher<HttpBlueskyClient> for UserSessionRefresh {
+    async fn refresh(&self, user: &User) -> Result<HttpBlueskyClient> {
+        let tokens = self
+            .db
+            .get_tokens(user.id)
+            .await?
+            .ok_or_else(|| anyhow!("No Bluesky tokens for {}", user.bluesky_did))?;
+        // Logins from before DPoP keys were stored can't be refreshed
+        let (Some(refresh_token), Some(dpop_key)) = (tokens.refresh_token, tokens.dpop_key) else {
+            return Err(anyhow!("No refreshable session for {}", user.bluesky_did));
+        };
+
+        let refreshed = self
+            .oauth
+            .refresh_tokens(&user.bluesky_did, &refresh_token, &dpop_key)
+            .await?;
+        self.db
+            .store_tokens(
+                user.id,
+                &refreshed.access_token,
+                Some(&refreshed.refresh_token),
+                refreshed.expires_at,
+                Some(&dpop_key),
+            )
+            .await?;
+
+        let mut client = self.client.clone();
+        client.set_access_token(refreshed.access_token);
+        Ok(client)
+    }
+}
+
+/// Starts sync loops from the shared app state
+struct StateSyncStarter(Arc<AppState>);
+
+#[async_trait]
+impl SyncStarter for StateSyncStarter {
+    async fn start(&self, user: &User) -> Result<()> {
+        spawn_user_sync(&self.0, user.clone()).await
+    }
+}
+
+/// Log each bot account in and run a DM bot per account in the background
+///
+/// Each account polls and answers its own DMs. Daily digests go out from the
+/// first account only, so nobody gets one per bot. Returns no handles (DMs
+/// disabled) when no bot account is configured.
+pub fn spawn_dm_bot(state: Arc<AppState>) -> Vec<JoinHandle<()>> {
+    let accounts = state.config.bot_accounts();
+    if accounts.is_empty() {
+        warn!("No bot account configured; DMs are disabled");
+    }
+
+    accounts
+        .into_iter()
+        .enumerate()
+        .map(|(i, account)| tokio::spawn(run_dm_bot(state.clone(), account, i == 0)))
+        .collect()
+}
+
+/// Run the DM bot for one account (and, if `send_digests`, daily digests)
+/// forever, restarting with a fresh session before expiry
+async fn run_dm_bot(state: Arc<AppState>, account: BotAccount, send_digests: bool) {
+    let auth = state.bluesky_client();
+    let mut refresh_jwt: Option<String> = None;
+
+    loop {
+        let session = match bot_session(
+            &auth,
+            refresh_jwt.take(),
+            &account.handle,
+            &account.password,
+        )
+        .await
+        {
+            Ok(session) => session,
+            Err(e) => {
+                error!("Bot account {} login failed: {}", account.handle, e);
+                sleep(BOT_LOGIN_RETRY).await;
+                continue;
+            }
+        };
+        info!("Bot account {} logged in", session.did);
+
+        let bot_client = state
+            .bluesky_client()
+            .authenticated(session.access_jwt.clone(), session.did.clone());
+        let refresher = Arc::new(BotSessionRefresh {
+            auth: auth.clone(),
+            client: bot_client.clone(),
+            refresh_jwt: Mutex::new(session.refresh_jwt.clone()),
+        });
+        let bot = DmBotService::new(
+            bot_client,
+            state.readwise_client(),
+            state.db.clone(),
+            session.did.clone(),
+            DmBotConfig {
+                poll_interval: Duration::from_secs(state.config.dm_poll_interval_secs),
+                base_url: state.config.base_url(),
+                retention_days: state.config.retention_days,
+            },
+        )
+        .with_save_limiter(state.save_limiter.clone())
+        .with_highlight_templates(state.config.highlight_templates())
+        .with_thread_limits(state.config.thread_limits())
+        .with_features(state.features.clone())
+        .with_strip_query_params(state.config.strip_query_params())
+        .with_oembed(Arc::new(YouTubeOEmbed::new(state.http.clone())))
+        .with_handle_cache(state.handles.clone())
+        .with_sync_control(
+            state.sync_tasks.clone(),
+            Arc::new(StateSyncStarter(state.clone())),
+        )
+        .with_session_refresher(refresher.clone());
+        // Digests DM through the bot's session, refreshed token and all
+        let digest = DigestService::new(bot.session(), state.db.clone(), state.config.digest_hour)
+            .with_features(state.features.clone());
+
+        tokio::select! {
+            result = bot.run() => {
+                if let Err(e) = result {
+                    error!("DM bot stopped: {}", e);
+                }
+            }
+            _ = digest.run(), if send_digests => {}
+            _ = sleep(BOT_SESSION_REFRESH) => debug!("Refreshing bot session"),
+        }
+
+        // A refresh mid-run rotates the refresh token
+        refresh_jwt = Some(refresher.refresh_jwt());
+    }
+}
+
+/// Refreshes a running bot's session, keeping the latest refresh token
+struct BotSessionRefresh {
+    auth: HttpBlueskyClient,
+    /// The bot's client, which refreshed copies take a new access token on
+    client: HttpBlueskyClient,
+    refresh_jwt: Mutex<String>,
+}
+
+impl BotSessionRefresh {
+    fn refresh_jwt(&self) -> String {
+        self.refresh_jwt
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .clone()
+    }
+}
+
+#[async_trait]
+impl BotSessionRefresher<HttpBlueskyClient> for BotSessionRefresh {
+    async fn refresh(&self) -> Result<HttpBlueskyClient> {
+        let session = self.auth.refresh_session(&self.refresh_jwt()).await?;
+        *self.refresh_jwt.lock().unwrap_or_else(|e| e.into_inner()) = session.refresh_jwt;
+        let mut client = self.client.clone();
+        client.set_access_token(session.access_jwt);
+        Ok(client)
+    }
+}
+
+/// Refresh the bot session, falling back to a full app-password login
+async fn bot_session(
+    auth: &HttpBlueskyClient,
+    refresh_jwt: Option<String>,
+    handle: &str,
+    password: &str,
+) -> Result<AtpSession> {
+    if let Some(refresh_jwt) = refresh_jwt {
+        match auth.refresh_session(&refresh_jwt).await {
+            Ok(session) => return Ok(session),
+            Err(e) => warn!("Bot session refresh failed, logging in again: {}", e),
+        }
+    }
+    auth.login_with_app_password(handle, password).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::bluesky::oauth::{CompletedLogin, OAuthError, RefreshedTokens};
+    use crate::crypto::EncryptionKey;
+    use sqlx::PgPool;
+
+    /// Hands out "access-2"/"refresh-2" for "refresh-1" bound to "z-dpop"
+    struct RotatingOAuth;
+
+    #[async_trait]
+    impl OAuthService for RotatingOAuth {
+        async fn initiate_login(
+            &self,
+            _handle: &str,
+            _pds_override: Option<&str>,
+        ) -> Result<String, OAuthError> {
+            Err(OAuthError::UnknownState)
+        }
+
+        async fn complete_login(
+            &self,
+            _code: &str,
+            _state: &str,
+            _iss: Option<&str>,
+        ) -> Result<CompletedLogin, OAuthError> {
+            Err(OAuthError::UnknownState)
+        }
+
+        async fn refresh_tokens(
+            &self,
+            _did: &str,
+            refresh_token: &str,
+            dpop_key: &str,
+        ) -> Result<RefreshedTokens, OAuthError> {
+            if refresh_token != "refresh-1" || dpop_key != "z-dpop" {
+                return Err(OAuthError::Request("invalid_grant".to_string()));
+            }
+            Ok(RefreshedTokens {
+                access_token: "access-2".to_string(),
+                refresh_token: "refresh-2".to_string(),
+                expires_at: None,
+            })
+        }
+
+        fn cleanup_expired(&self) -> usize {
+            0
+        }
+    }
+
+    #[sqlx::test]
+    async fn test_user_session_refresh_stores_rotated_tokens(pool: PgPool) {
+        let db = Database::new(pool, EncryptionKey::test_key());
+        let user = db
+            .create_user("did:plc:refresh", "refresh.bsky.social")
+            .await
+            .unwrap();
+        db.store_tokens(user.id, "access-1", Some("refresh-1"), None, Some("z-dpop"))
+            .await
+            .unwrap();
+        let refresher = UserSessionRefresh {
+            oauth: Arc::new(RotatingOAuth),
+            db: db.clone(),
+            client: HttpBlueskyClient::new(),
+        };
+
+        refresher.refresh(&user).await.unwrap();
+
+        let tokens = db.get_tokens(user.id).await.unwrap().unwrap();
+        assert_eq!(tokens.access_token, "access-2");
+        assert_eq!(tokens.refresh_token.as_deref(), Some("refresh-2"));
+        assert_eq!(tokens.dpop_key.as_deref(), Some("z-dpop"));
+
+        // Sessions stored without their DPoP key can't be refreshed
+        db.store_tokens(user.id, "access-2", Some("refresh-2"), None, None)
+            .await
+            .unwrap();
+        assert!(refresher.refresh(&user).await.is_err());
+    }
+}
@@ -0,0 +1,290 @@
+//! Per-post-type save rules
+//!
+//! Posts are classified as top-level posts, replies, self-threads, or quotes.
+//! Users can map each class to a highlight or a Reader document, optionally
+//! with a category. Rules are stored as `class=target[:category]` entries,
+//! e.g. `reply=highlight:books`.
+
+use crate::bluesky::{parse_at_uri, ThreadViewPost};
+use crate::readwise::client::{is_highlight_category, is_reader_category};
+
+/// What kind of post is being saved
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PostClass {
+    /// A standalone post
+    TopLevel,
+    /// A reply to someone else's post
+    Reply,
+    /// Part of the author's own thread
+    SelfThread,
+    /// A post quoting another post
+    Quote,
+}
+
+impl PostClass {
+    /// Name used in stored rules and on the dashboard
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::TopLevel => "top_level",
+            Self::Reply => "reply",
+            Self::SelfThread => "self_thread",
+            Self::Quote => "quote",
+        }
+    }
+
+    fn parse(name: &str) -> Option<Self> {
+        match name {
+            "top_level" => Some(Self::TopLevel),
+            "reply" => Some(Self::Reply),
+            "self_thread" => Some(Self::SelfThread),
+            "quote" => Some(Self::Quote),
+            _ => None,
+        }
+    }
+}
+
+/// Classify a post by its place in the thread
+///
+/// A quote wins over a self-thread, which wins over a reply.
+pub fn classify_post(thread: &ThreadViewPost) -> PostClass {
+    let quotes_post = thread
+        .post
+        .record
+        .quoted()
+        .is_some_and(|record| parse_at_uri(&record.uri).is_ok());
+
+    if quotes_post {
+        PostClass::Quote
+    } else if is_self_thread(thread) {
+        PostClass::SelfThread
+    } else if thread.post.record.reply.is_some() {
+        PostClass::Reply
+    } else {
+        PostClass::TopLevel
+    }
+}
+
+/// Whether a post is part of its author's own thread
+///
+/// Only the author's own posts count: a parent by the same author or a
+/// self-reply. Replies from other people don't make a post a thread.
+pub fn is_self_thread(thread: &ThreadViewPost) -> bool {
+    let author = &thread.post.author.did;
+
+    let parent_by_author = thread
+        .parent_post()
+        .is_some_and(|parent| &parent.post.author.did == author);
+    let has_self_reply = thread
+        .reply_posts()
+        .any(|reply| &reply.post.author.did == author);
+
+    parent_by_author || has_self_reply
+}
+
+/// Where a class of posts is saved
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SaveTarget {
+    Highlight,
+    Document,
+}
+
+impl SaveTarget {
+    /// Name used in stored settings
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Highlight => "highlight",
+            Self::Document => "document",
+        }
+    }
+
+    /// Parse a stored name
+    pub fn parse(name: &str) -> Option<Self> {
+        match name {
+            "highlight" => Some(Self::Highlight),
+            "document" => Some(Self::Document),
+            _ => None,
+        }
+    }
+}
+
+/// How one class of posts is saved
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PostClassRule {
+    pub target: SaveTarget,
+    /// Readwise (highlight) or Reader (document) category; None keeps the default
+    pub category: Option<String>,
+}
+
+/// A user's rules, at most one per class
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct PostClassRules {
+    rules: Vec<(PostClass, PostClassRule)>,
+}
+
+impl PostClassRules {
+    /// The rule for a class, if the user set one
+    pub fn get(&self, class: PostClass) -> Option<&PostClassRule> {
+        self.rules
+            .iter()
+            .find(|(ruled, _)| *ruled == class)
+            .map(|(_, rule)| rule)
+    }
+
+    /// Rules in their stored `class=target[:category]` form
+    pub fn to_entries(&self) -> Vec<String> {
+        self.rules
+            .iter()
+            .map(|(class, rule)| match &rule.category {
+                Some(category) => {
+                    format!("{}={}:{}", class.as_str(), rule.target.as_str(), category)
+                }
+                None => format!("{}={}", class.as_str(), rule.target.as_str()),
+            })
+            .collect()
+    }
+
+    /// Parse stored entries, skipping any that no longer parse
+    pub fn from_entries(entries: &[String]) -> Self {
+        let mut rules = Self::default();
+        for entry in entries {
+            match parse_entry(entry) {
+                Ok((class, rule)) => rules.set(class, rule),
+                Err(e) => tracing::warn!("Ignoring post class rule {:?}: {}", entry, e),
+            }
+        }
+        rules
+    }
+
+    fn set(&mut self, class: PostClass, rule: PostClassRule) {
+        self.rules.retain(|(ruled, _)| *ruled != class);
+        self.rules.push((class, rule));
+    }
+}
+
+/// Parse comma- or newline-separated `class=target[:category]` rules
+///
+/// A later rule for the same class replaces an earlier one.
+pub fn parse_post_class_rules(input: &str) -> Result<PostClassRules, String> {
+    let mut rules = PostClassRules::default();
+    for entry in input.split([',', '\n']) {
+        let entry = entry.trim();
+        if entry.is_empty() {
+            continue;
+        }
+        let (class, rule) = parse_entry(entry)?;
+        rules.set(class, rule);
+    }
+    Ok(rules)
+}
+
+fn parse_entry(entry: &str) -> Result<(PostClass, PostClassRule), String> {
+    let (class, rule) = entry
+        .split_once('=')
+        .ok_or_else(|| format!("{} should look like reply=highlight", entry))?;
+    let class = PostClass::parse(&class.trim().to_ascii_lowercase()).ok_or_else(|| {
+        format!(
+            "Unknown post type {} (use top_level, reply, self_thread, or quote)",
+            class.trim()
+        )
+    })?;
+
+    let (target, category) = match rule.split_once(':') {
+        Some((target, category)) => (target, Some(category.trim().to_ascii_lowercase())),
+        None => (rule, None),
+    };
+    let target = target.trim().to_ascii_lowercase();
+    let target = SaveTarget::parse(&target)
+        .ok_or_else(|| format!("Unknown save target {} (use highlight or document)", target))?;
+
+    let category = category.filter(|category| !category.is_empty());
+    if let Some(category) = &category {
+        let valid = match target {
+            SaveTarget::Highlight => is_highlight_category(category),
+            SaveTarget::Document => is_reader_category(category),
+        };
+        if !valid {
+            let kind = match target {
+                SaveTarget::Highlight => "highlight",
+                SaveTarget::Document => "Reader document",
+            };
+            return Err(format!("{} isn't a {} category", category, kind));
+        }
+    }
+
+    Ok((class, PostClassRule { target, category }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::bluesky::{Embed, StrongRef};
+    use crate::test_support::{post, thread, thread_under};
+
+    fn quote_of(uri: &str) -> Embed {
+        Embed::Record {
+            record: StrongRef {
+                uri: uri.to_string(),
+                cid: "bafyquoted".to_string(),
+            },
+        }
+    }
+
+    #[test]
+    fn test_classify_top_level_post() {
+        let top = thread(post("top").text("Just a post").build());
+        assert_eq!(classify_post(&top), PostClass::TopLevel);
+    }
+
+    #[test]
+    fn test_classify_reply_to_someone_else() {
+        let parent = post("parent")
+            .author("did:plc:other", "other.bsky.social")
+            .build();
+        let reply = post("reply").text("Agreed!").reply_to(&parent).build();
+        let reply = thread_under(reply, thread(parent));
+        assert_eq!(classify_post(&reply), PostClass::Reply);
+    }
+
+    #[test]
+    fn test_classify_self_thread() {
+        let first = post("first").text("1/").build();
+        let second = post("second").text("2/").reply_to(&first).build();
+        let second = thread_under(second, thread(first));
+        assert_eq!(classify_post(&second), PostClass::SelfThread);
+    }
+
+    #[test]
+    fn test_classify_quote() {
+        let quote = post("quote")
+            .embed(quote_of("at://did:plc:other/app.bsky.feed.post/quoted"))
+            .build();
+        assert_eq!(classify_post(&thread(quote)), PostClass::Quote);
+
+        // Quoting a list or feed isn't quoting a post
+        let list = post("list")
+            .embed(quote_of("at://did:plc:other/app.bsky.graph.list/mylist"))
+            .build();
+        assert_eq!(classify_post(&thread(list)), PostClass::TopLevel);
+    }
+
+    #[test]
+    fn test_parse_post_class_rules() {
+        let rules =
+            parse_post_class_rules("reply=highlight:books\n Quote = document, reply=document")
+                .unwrap();
+        assert_eq!(
+            rules.get(PostClass::Reply),
+            Some(&PostClassRule {
+                target: SaveTarget::Document,
+                category: None
+            })
+        );
+        assert_eq!(rules.get(PostClass::TopLevel), None);
+        assert_eq!(rules.to_entries(), vec!["quote=document", "reply=document"]);
+        assert_eq!(PostClassRules::from_entries(&rules.to_entries()), rules);
+
+        assert!(parse_post_class_rules("reply=pdf").is_err());
+        assert!(parse_post_class_rules("repost=highlight").is_err());
+        assert!(parse_post_class_rules("reply=highlight:pdf").is_err());
+    }
+}
@@ -2,13 +2,40 @@
 //!
 //! Fetches posts, detects threads, and saves to Readwise.
 
+use std::borrow::Cow;
+use std::collections::HashSet;
+use std::future::Future;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
 use anyhow::Result;
+use chrono_tz::Tz;
+use reqwest::StatusCode;
+use serde::Serialize;
+use thiserror::Error;
+use tokio::sync::Semaphore;
 use tracing::{debug, info, instrument, warn};
+use uuid::Uuid;
 
-use crate::bluesky::{BlueskyClient, PostView, ThreadViewPost};
-use crate::content::links::extract_links;
-use crate::content::{format_post_as_highlight, format_thread_as_document, is_thread};
-use crate::readwise::client::{Document, ReadwiseClient};
+use crate::bluesky::{
+    parse_at_uri, AtUriError, BlueskyApiError, BlueskyClient, PostNotFound, PostView,
+    ThreadResponse, ThreadViewPost,
+};
+use crate::content::language::language_tag;
+use crate::content::links::{default_strip_query_params, extract_links, link_kind, LinkKind};
+use crate::content::oembed::OEmbedClient;
+use crate::content::{
+    engagement_line, format_alt_text_highlights, format_post_as_document, format_post_as_highlight,
+    format_quote_as_document, format_thread_as_document, is_empty_post, HighlightTemplates,
+    ThreadLimits,
+};
+use crate::db::models::{NewSaveEvent, UserSettings};
+use crate::db::queries::Database;
+use crate::features::FeatureFlags;
+use crate::readwise::client::{
+    Document, Highlight, ReadwiseApiError, ReadwiseClient, MAX_HIGHLIGHT_CHARS, SAVED_USING,
+};
+use crate::services::post_class::{classify_post, is_self_thread, PostClassRules, SaveTarget};
 
 /// Options for processing a post
 #[derive(Debug, Clone, Default)]
@@ -17,117 +44,976 @@ pub struct ProcessOptions {
     pub extract_links: bool,
     /// Optional note to attach to the highlight
     pub note: Option<String>,
+    /// Keep replies from other people when saving a thread
+    pub include_other_replies: bool,
+    /// Build and log what would be saved without calling Readwise
+    pub dry_run: bool,
+    /// Reader document already saved for this post; threads update it
+    pub existing_document_id: Option<String>,
+    /// Whose already-saved links to skip (needs `with_link_dedup`)
+    pub user_id: Option<Uuid>,
+    /// Readwise category for a highlight, overriding the processor's default
+    pub highlight_category: Option<String>,
+    /// Also save each image's alt text as its own highlight
+    pub save_image_alt_text: bool,
+    /// Add the post's like/repost/reply counts below the saved content
+    pub include_engagement: bool,
+    /// Zone saved documents show post times in; None is UTC
+    pub timezone: Option<Tz>,
+    /// Per-post-class overrides of where and under what category to save
+    pub class_rules: PostClassRules,
+    /// Where single posts go when no class rule applies; None is a highlight
+    pub single_post_target: Option<SaveTarget>,
+}
+
+impl ProcessOptions {
+    /// Options a user's settings ask for; the caller fills in the rest
+    pub fn from_settings(settings: &UserSettings) -> Self {
+        Self {
+            extract_links: settings.extract_links,
+            highlight_category: settings.highlight_category.clone(),
+            save_image_alt_text: settings.save_image_alt_text,
+            include_engagement: settings.include_engagement,
+            timezone: Some(settings.local_timezone()),
+            class_rules: settings.class_rules(),
+            single_post_target: settings.single_post_target(),
+            ..Default::default()
+        }
+    }
+}
+
+/// What processing a post did
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum OutcomeKind {
+    /// Saved as a Readwise highlight
+    Highlight,
+    /// Saved as a Reader document (threads)
+    Document,
+    /// Deleted a Reader document saved earlier and saved the thread afresh,
+    /// losing the old copy's highlights and notes
+    DocumentReplaced,
+    /// Not saved (e.g., already processed or unavailable)
+    Skipped,
+}
+
+impl OutcomeKind {
+    /// Name stored in `save_events`, matching the serialized form
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Highlight => "highlight",
+            Self::Document => "document",
+            Self::DocumentReplaced => "document_replaced",
+            Self::Skipped => "skipped",
+        }
+    }
+}
+
+/// Result of processing a post
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct ProcessOutcome {
+    pub kind: OutcomeKind,
+    /// Extracted links saved to Reader
+    pub links_saved: usize,
+    /// Image alt texts saved as their own highlights
+    pub alt_texts_saved: usize,
+    /// ID of the saved highlight or document, if Readwise returned one
+    pub readwise_id: Option<String>,
+    /// Nothing was written (dry run); this describes what would have been
+    pub dry_run: bool,
+    /// What a dry run would have sent to Readwise
+    pub preview: Option<SavePayload>,
+}
+
+/// Why processing a post failed
+///
+/// Converts into `anyhow::Error` for callers that don't branch on the kind.
+#[derive(Debug, Error)]
+pub enum ProcessError {
+    /// Deleted, or hidden by a block
+    #[error("post not found")]
+    PostUnavailable,
+
+    #[error("Readwise error {status}: {body}")]
+    Readwise { status: StatusCode, body: String },
+
+    #[error("Bluesky error {status}")]
+    Bluesky { status: StatusCode },
+
+    #[error(transparent)]
+    InvalidUri(#[from] AtUriError),
+
+    /// Readwise already has this item (409 Conflict)
+    #[error("already saved to Readwise")]
+    Duplicate,
+
+    /// Anything else: network, database, or unexpected responses
+    #[error(transparent)]
+    Other(anyhow::Error),
+}
+
+impl ProcessError {
+    /// Readwise refused the access token (revoked or mistyped)
+    pub fn is_token_rejected(&self) -> bool {
+        matches!(
+            self,
+            Self::Readwise {
+                status: StatusCode::UNAUTHORIZED | StatusCode::FORBIDDEN,
+                ..
+            }
+        )
+    }
+
+    /// Readwise is rate limiting saves; worth retrying later
+    pub fn is_rate_limited(&self) -> bool {
+        matches!(
+            self,
+            Self::Readwise {
+                status: StatusCode::TOO_MANY_REQUESTS,
+                ..
+            }
+        )
+    }
+}
+
+impl From<anyhow::Error> for ProcessError {
+    fn from(error: anyhow::Error) -> Self {
+        if error.is::<PostNotFound>() {
+            return Self::PostUnavailable;
+        }
+        let error = match error.downcast::<ReadwiseApiError>() {
+            Ok(e) if e.status == StatusCode::CONFLICT => return Self::Duplicate,
+            Ok(e) => {
+                return Self::Readwise {
+                    status: e.status,
+                    body: e.body,
+                }
+            }
+            Err(error) => error,
+        };
+        let error = match error.downcast::<BlueskyApiError>() {
+            Ok(e) => return Self::Bluesky { status: e.status },
+            Err(error) => error,
+        };
+        match error.downcast::<AtUriError>() {
+            Ok(e) => Self::InvalidUri(e),
+            Err(error) => Self::Other(error),
+        }
+    }
+}
+
+/// Whether a failed Bluesky call may succeed if tried again later: a server
+/// error, rate limit, or network failure
+fn is_transient(error: &anyhow::Error) -> bool {
+    if let Some(e) = error.downcast_ref::<BlueskyApiError>() {
+        return e.status.is_server_error() || e.status == StatusCode::TOO_MANY_REQUESTS;
+    }
+    error
+        .downcast_ref::<reqwest::Error>()
+        .is_some_and(|e| e.is_timeout() || e.is_connect() || e.is_request())
+}
+
+/// What saving a post sends to Readwise
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(tag = "type", content = "payload", rename_all = "snake_case")]
+pub enum SavePayload {
+    Highlight(Highlight),
+    Document(Document),
+    /// New content for a document saved earlier: the old copy, with any
+    /// highlights and notes made in Reader, is deleted before this is saved
+    DocumentReplace {
+        id: String,
+        document: Document,
+    },
+}
+
+impl SavePayload {
+    /// Outcome of saving this payload
+    pub fn kind(&self) -> OutcomeKind {
+        match self {
+            Self::Highlight(_) => OutcomeKind::Highlight,
+            Self::Document(_) => OutcomeKind::Document,
+            Self::DocumentReplace { .. } => OutcomeKind::DocumentReplaced,
+        }
+    }
+
+    /// Tag the saved item; a highlight gets the tag as a `.tag` in its note
+    pub fn add_tag(&mut self, tag: &str) {
+        match self {
+            Self::Highlight(highlight) => {
+                let tag = format!(".{}", tag);
+                highlight.note = Some(match highlight.note.take() {
+                    Some(note) if !note.trim().is_empty() => format!("{} {}", note, tag),
+                    _ => tag,
+                });
+            }
+            Self::Document(document) | Self::DocumentReplace { document, .. } => {
+                document
+                    .tags
+                    .get_or_insert_with(Vec::new)
+                    .push(tag.to_string());
+            }
+        }
+    }
 }
 
+impl ProcessOutcome {
+    /// Outcome for a post that wasn't processed
+    pub fn skipped() -> Self {
+        Self {
+            kind: OutcomeKind::Skipped,
+            links_saved: 0,
+            alt_texts_saved: 0,
+            readwise_id: None,
+            dry_run: false,
+            preview: None,
+        }
+    }
+
+    /// ID of a Reader document this outcome created (or re-created), for
+    /// later updates
+    pub fn new_document_id(&self) -> Option<&str> {
+        match self.kind {
+            OutcomeKind::Document | OutcomeKind::DocumentReplaced if !self.dry_run => {
+                self.readwise_id.as_deref()
+            }
+            _ => None,
+        }
+    }
+}
+
+/// Saves in flight when no shared limiter is given
+const DEFAULT_MAX_CONCURRENT_SAVES: usize = 8;
+
+/// Wait before re-fetching a thread that came back with posts missing
+const PARTIAL_THREAD_RETRY_DELAY: Duration = Duration::from_secs(2);
+
+/// Most quoted threads followed from one post (quotes of quotes)
+const MAX_QUOTE_DEPTH: usize = 3;
+
 /// Post processor handles fetching posts and saving to Readwise
 pub struct PostProcessor<B: BlueskyClient, R: ReadwiseClient> {
     bluesky: B,
     readwise: R,
+    /// Bounds Readwise saves in flight; shared across processors
+    save_permits: Arc<Semaphore>,
+    templates: HighlightTemplates,
+    thread_limits: ThreadLimits,
+    /// Records saved links so a user's repeat links are skipped
+    saved_links: Option<Database>,
+    /// Query parameters dropped from extracted links
+    strip_params: Vec<String>,
+    /// Delay before re-fetching a partial thread (None saves it as-is)
+    partial_thread_retry: Option<Duration>,
+    /// Titles saved video links (left untitled when unset)
+    oembed: Option<Arc<dyn OEmbedClient>>,
+    /// Records every save attempt in `save_events`
+    audit: Option<Database>,
+    /// Runtime flags for quote expansion and language tags; both off without
+    features: Option<Arc<FeatureFlags>>,
 }
 
 impl<B: BlueskyClient, R: ReadwiseClient> PostProcessor<B, R> {
     /// Create a new post processor
     pub fn new(bluesky: B, readwise: R) -> Self {
-        Self { bluesky, readwise }
+        Self {
+            bluesky,
+            readwise,
+            save_permits: Arc::new(Semaphore::new(DEFAULT_MAX_CONCURRENT_SAVES)),
+            templates: HighlightTemplates::default(),
+            thread_limits: ThreadLimits::default(),
+            saved_links: None,
+            strip_params: default_strip_query_params(),
+            partial_thread_retry: Some(PARTIAL_THREAD_RETRY_DELAY),
+            oembed: None,
+            audit: None,
+            features: None,
+        }
+    }
+
+    /// Use custom highlight title and note templates
+    pub fn with_highlight_templates(mut self, templates: HighlightTemplates) -> Self {
+        self.templates = templates;
+        self
+    }
+
+    /// Cap how much of a thread goes into a saved document
+    pub fn with_thread_limits(mut self, limits: ThreadLimits) -> Self {
+        self.thread_limits = limits;
+        self
+    }
+
+    /// Drop these query parameters from extracted links
+    pub fn with_strip_query_params(mut self, params: Vec<String>) -> Self {
+        self.strip_params = params;
+        self
+    }
+
+    /// Skip links a user has already saved, tracked in `saved_links`
+    pub fn with_link_dedup(mut self, db: Database) -> Self {
+        self.saved_links = Some(db);
+        self
+    }
+
+    /// Re-fetch a thread once, after `delay`, when parts of it didn't load
+    ///
+    /// On by default; None saves whatever the first fetch returned.
+    pub fn with_partial_thread_retry(mut self, delay: Option<Duration>) -> Self {
+        self.partial_thread_retry = delay;
+        self
+    }
+
+    /// Title saved video links from oEmbed
+    pub fn with_oembed(mut self, oembed: Arc<dyn OEmbedClient>) -> Self {
+        self.oembed = Some(oembed);
+        self
+    }
+
+    /// Look up `expand_quotes` and `detect_language` on each save
+    pub fn with_features(mut self, features: Arc<FeatureFlags>) -> Self {
+        self.features = Some(features);
+        self
+    }
+
+    /// Record each attempt for a user (`options.user_id`) in `save_events`
+    pub fn with_save_audit(mut self, db: Database) -> Self {
+        self.audit = Some(db);
+        self
+    }
+
+    /// Share a save limiter with other processors
+    pub fn with_save_limiter(mut self, limiter: Arc<Semaphore>) -> Self {
+        self.save_permits = limiter;
+        self
     }
 
     /// Process a post URI and save to Readwise
-    #[instrument(skip(self, readwise_token))]
+    ///
+    /// The span carries the post URI only; the token and note stay out of logs.
+    #[instrument(skip_all, fields(post_uri = %post_uri, dry_run = options.dry_run))]
     pub async fn process_post(
         &self,
         post_uri: &str,
         readwise_token: &str,
         options: ProcessOptions,
-    ) -> Result<()> {
+    ) -> Result<ProcessOutcome, ProcessError> {
+        // Dry runs don't attempt a save, so there's nothing to audit
+        let audited_user = options.user_id.filter(|_| !options.dry_run);
+        let result = self.save_post(post_uri, readwise_token, options).await;
+        if let Some(user_id) = audited_user {
+            self.record_attempt(user_id, post_uri, &result).await;
+        }
+        result
+    }
+
+    /// Append an attempt to the audit trail; a failure here only logs
+    async fn record_attempt(
+        &self,
+        user_id: Uuid,
+        post_uri: &str,
+        result: &Result<ProcessOutcome, ProcessError>,
+    ) {
+        let Some(db) = &self.audit else {
+            return;
+        };
+        let error = result.as_ref().err().map(ToString::to_string);
+        let event = match result {
+            Ok(outcome) => NewSaveEvent {
+                post_uri,
+                kind: Some(outcome.kind.as_str()),
+                status: match outcome.kind {
+                    OutcomeKind::Skipped => "skipped",
+                    _ => "saved",
+                },
+                readwise_id: outcome.readwise_id.as_deref(),
+                ..Default::default()
+            },
+            Err(_) => NewSaveEvent {
+                post_uri,
+                status: "failed",
+                error: error.as_deref(),
+                ..Default::default()
+            },
+        };
+        if let Err(e) = db.record_save_event(user_id, event).await {
+            warn!("Failed to record save event for {}: {}", post_uri, e);
+        }
+    }
+
+    /// Audit a link or alt-text save made alongside a post; a failure here
+    /// only logs
+    async fn record_extra(
+        &self,
+        user_id: Option<Uuid>,
+        post_uri: &str,
+        kind: &str,
+        url: Option<&str>,
+        result: &Result<Option<String>>,
+    ) {
+        let (Some(db), Some(user_id)) = (&self.audit, user_id) else {
+            return;
+        };
+        let error = result.as_ref().err().map(ToString::to_string);
+        let event = NewSaveEvent {
+            post_uri,
+            kind: Some(kind),
+            status: if result.is_ok() { "saved" } else { "failed" },
+            readwise_id: result.as_ref().ok().and_then(Option::as_deref),
+            error: error.as_deref(),
+            url,
+        };
+        if let Err(e) = db.record_save_event(user_id, event).await {
+            warn!(
+                "Failed to record {} save event for {}: {}",
+                kind, post_uri, e
+            );
+        }
+    }
+
+    /// Fetch a post and save it to Readwise, without auditing
+    async fn save_post(
+        &self,
+        post_uri: &str,
+        readwise_token: &str,
+        options: ProcessOptions,
+    ) -> Result<ProcessOutcome, ProcessError> {
         info!("Processing post: {}", post_uri);
+        let post_uri = parse_at_uri(post_uri)?.to_string();
 
         // Fetch the full thread
-        let thread_response = self.bluesky.get_post_thread(post_uri).await?;
+        let thread_response = self.fetch_thread(&post_uri).await?;
         let thread = &thread_response.thread;
 
-        // Determine if this is a thread or single post
-        if self.is_part_of_thread(thread) {
-            debug!("Post is part of a thread, saving to Reader");
-            self.save_thread(thread, readwise_token).await?;
+        let (expand_quotes, detect_language) = match &self.features {
+            Some(flags) => {
+                let features = flags.current().await;
+                (features.expand_quotes, features.detect_language)
+            }
+            None => (false, false),
+        };
+
+        let quoted = if expand_quotes {
+            self.fetch_quoted_threads(&thread.post).await
         } else {
-            debug!("Single post, saving as highlight");
-            self.save_single_post(&thread.post, readwise_token, options.note.as_deref())
-                .await?;
+            Vec::new()
+        };
+
+        // Threads, quotes, and empty posts go to Reader; single posts become highlights
+        let mut payload = self.build_payload(thread, &quoted, &options);
+        if detect_language {
+            if let Some(tag) = language_tag(&thread.post.record.text) {
+                payload.add_tag(&tag);
+            }
         }
+        let kind = payload.kind();
+        let (readwise_id, preview) = if options.dry_run {
+            info!("Dry run: would save {:?}", payload);
+            (None, Some(payload))
+        } else {
+            (self.save_payload(payload, readwise_token).await?, None)
+        };
 
         // Optionally extract and save links
-        if options.extract_links {
-            self.process_links(&thread.post, readwise_token).await?;
+        let links_saved = if options.extract_links {
+            self.process_links(&thread.post, readwise_token, &options)
+                .await?
+        } else {
+            0
+        };
+
+        let alt_texts_saved = if options.save_image_alt_text {
+            self.process_alt_text(&thread.post, readwise_token, &options)
+                .await
+        } else {
+            0
+        };
+
+        Ok(ProcessOutcome {
+            kind,
+            links_saved,
+            alt_texts_saved,
+            readwise_id,
+            dry_run: options.dry_run,
+            preview,
+        })
+    }
+
+    /// Fetch a thread, trying once more if a post in the author's chain didn't
+    /// load
+    ///
+    /// Posts their author's PDS no longer has were deleted and won't load on
+    /// a second try, so those skip the retry. Falls back to the first response if the retry fails or is still partial,
+    /// and to the bare post record if the AppView won't serve the thread.
+    /// Transient failures are returned instead, so the save is retried later
+    /// with its thread rather than saved without one.
+    async fn fetch_thread(&self, post_uri: &str) -> Result<ThreadResponse> {
+        let response = match self.bluesky.get_post_thread(post_uri).await {
+            Ok(response) => response,
+            // Deleted or blocked; the record won't help
+            Err(e) if e.is::<PostNotFound>() => return Err(e),
+            Err(e) if is_transient(&e) => return Err(e),
+            Err(e) => return self.fetch_record_only(post_uri, e).await,
+        };
+        let Some(delay) = self.partial_thread_retry else {
+            return Ok(response);
+        };
+        let missing = response.thread.missing_posts();
+        if missing.is_empty() {
+            return Ok(response);
+        }
+        if self.all_deleted(&missing).await {
+            debug!("Missing thread posts were deleted; saving what loaded");
+            return Ok(response);
         }
 
-        Ok(())
+        debug!("Thread came back partial, fetching again in {:?}", delay);
+        tokio::time::sleep(delay).await;
+        match self.bluesky.get_post_thread(post_uri).await {
+            Ok(retry) if !retry.thread.is_partial() => Ok(retry),
+            Ok(_) => {
+                warn!("Thread still partial after retry; saving what loaded");
+                Ok(response)
+            }
+            Err(e) => {
+                warn!("Thread re-fetch failed, saving what loaded: {}", e);
+                Ok(response)
+            }
+        }
     }
 
-    /// Check if a post is part of a thread
-    fn is_part_of_thread(&self, thread: &ThreadViewPost) -> bool {
-        // Has parent posts or is the start of a multi-post thread
-        thread.parent.is_some()
-            || thread
-                .replies
-                .as_ref()
-                .map(|r| !r.is_empty())
-                .unwrap_or(false)
-            || is_thread(&thread.post)
+    /// Whether every one of these posts is gone from its author's repo
+    ///
+    /// The AppView's not-found placeholder doesn't say why a post is
+    /// missing; the PDS does. Any other answer counts as not deleted.
+    async fn all_deleted(&self, uris: &[&str]) -> bool {
+        for uri in uris {
+            let Ok(uri) = parse_at_uri(uri) else {
+                return false;
+            };
+            match self
+                .bluesky
+                .get_post_record(&uri.did_or_handle, &uri.rkey)
+                .await
+            {
+                Err(e) if e.is::<PostNotFound>() => {}
+                _ => return false,
+            }
+        }
+        true
     }
 
-    /// Save a single post as a Readwise highlight
-    async fn save_single_post(
+    /// Fetch just the post record from the author's PDS, as a thread of one
+    ///
+    /// Used when the AppView can't serve the thread; the post then saves
+    /// without thread context. Returns `thread_error` if this fails too.
+    async fn fetch_record_only(
         &self,
-        post: &PostView,
+        post_uri: &str,
+        thread_error: anyhow::Error,
+    ) -> Result<ThreadResponse> {
+        let uri = parse_at_uri(post_uri)?;
+        if !uri.did_or_handle.starts_with("did:") {
+            return Err(thread_error);
+        }
+
+        warn!(
+            "Thread fetch failed ({}), falling back to the post record",
+            thread_error
+        );
+        match self
+            .bluesky
+            .get_post_record(&uri.did_or_handle, &uri.rkey)
+            .await
+        {
+            Ok(post) => Ok(ThreadResponse {
+                thread: ThreadViewPost {
+                    post,
+                    parent: None,
+                    replies: None,
+                },
+            }),
+            Err(e) => {
+                warn!("Post record fetch failed too: {}", e);
+                Err(thread_error)
+            }
+        }
+    }
+
+    /// Fetch the threads a post quotes, following quotes of quotes
+    ///
+    /// Stops after `MAX_QUOTE_DEPTH` threads, at a quote of something other
+    /// than a post, on a cycle, or when a fetch fails (saving what loaded).
+    async fn fetch_quoted_threads(&self, post: &PostView) -> Vec<ThreadViewPost> {
+        let mut quoted: Vec<ThreadViewPost> = Vec::new();
+        let mut seen = HashSet::from([post.uri.clone()]);
+        let mut next = post.record.quoted().map(|record| record.uri.clone());
+
+        while let Some(uri) = next.take() {
+            if quoted.len() >= MAX_QUOTE_DEPTH {
+                debug!("Not following quotes past {}", MAX_QUOTE_DEPTH);
+                break;
+            }
+            if parse_at_uri(&uri).is_err() || !seen.insert(uri.clone()) {
+                break;
+            }
+            match self.bluesky.get_post_thread(&uri).await {
+                Ok(response) => {
+                    next = response
+                        .thread
+                        .post
+                        .record
+                        .quoted()
+                        .map(|record| record.uri.clone());
+                    quoted.push(response.thread);
+                }
+                Err(e) => {
+                    warn!("Couldn't fetch quoted post {}: {}", uri, e);
+                    break;
+                }
+            }
+        }
+        quoted
+    }
+
+    /// Build what saving this post sends to Readwise
+    ///
+    /// `quoted` holds the threads the post quotes, when quotes are expanded.
+    /// A rule for the post's class overrides where it goes and its category.
+    fn build_payload(
+        &self,
+        thread: &ThreadViewPost,
+        quoted: &[ThreadViewPost],
+        options: &ProcessOptions,
+    ) -> SavePayload {
+        // Engagement counts go below the saved content
+        let footer = if options.include_engagement {
+            engagement_line(&thread.post)
+        } else {
+            None
+        };
+        let class = classify_post(thread);
+        let rule = options.class_rules.get(class);
+        let category = rule.and_then(|rule| rule.category.clone());
+        let as_highlight = !is_empty_post(&thread.post)
+            && match rule.map(|rule| rule.target) {
+                Some(target) => {
+                    debug!("Saving {} post as {:?} by rule", class.as_str(), target);
+                    target == SaveTarget::Highlight
+                }
+                None => {
+                    quoted.is_empty()
+                        && !is_self_thread(thread)
+                        && options.single_post_target != Some(SaveTarget::Document)
+                }
+            };
+
+        if as_highlight {
+            debug!("Saving as highlight");
+            let templates = self.templates_for(options, category);
+            // A trailing link saved separately would just be noise in the highlight
+            let mut highlight = format_post_as_highlight(
+                &thread.post,
+                options.note.as_deref(),
+                options.extract_links,
+                &templates,
+            );
+            if highlight.truncate_text() {
+                warn!(
+                    "Highlight text over {} characters, truncated",
+                    MAX_HIGHLIGHT_CHARS
+                );
+            }
+            if let Some(line) = footer {
+                highlight.note = Some(match highlight.note.take() {
+                    Some(note) if !note.trim().is_empty() => format!("{}\n{}", note, line),
+                    _ => line,
+                });
+            }
+            return SavePayload::Highlight(highlight);
+        }
+
+        let saved_as_document = |mut document: Document| {
+            if category.is_some() {
+                document.category = category.clone();
+            }
+            match &options.existing_document_id {
+                Some(id) => {
+                    debug!("Already saved, replacing Reader document {}", id);
+                    SavePayload::DocumentReplace {
+                        id: id.clone(),
+                        document,
+                    }
+                }
+                None => SavePayload::Document(document),
+            }
+        };
+
+        if !quoted.is_empty() {
+            debug!("Post quotes another, saving both to Reader");
+            saved_as_document(format_quote_as_document(
+                thread,
+                quoted,
+                options.include_other_replies,
+                &self.thread_limits,
+                options.timezone.unwrap_or(Tz::UTC),
+                &self.templates,
+                footer.as_deref(),
+            ))
+        } else if is_self_thread(thread) {
+            debug!("Post is part of a thread, saving to Reader");
+            saved_as_document(format_thread_as_document(
+                thread,
+                options.include_other_replies,
+                &self.thread_limits,
+                options.timezone.unwrap_or(Tz::UTC),
+                &self.templates,
+                footer.as_deref(),
+            ))
+        } else {
+            debug!("Single post, saving to Reader");
+            // Reader fetches the page itself, so the footer goes in the notes
+            let mut document = format_post_as_document(&thread.post, &self.templates);
+            document.notes = footer;
+            saved_as_document(document)
+        }
+    }
+
+    /// Send a payload to Readwise, returning the saved item's ID
+    async fn save_payload(
+        &self,
+        payload: SavePayload,
         readwise_token: &str,
-        note: Option<&str>,
-    ) -> Result<()> {
-        let highlight = format_post_as_highlight(post, note);
-        self.readwise
-            .save_highlight(readwise_token, highlight)
-            .await?;
-        info!("Saved post as highlight");
-        Ok(())
-    }
-
-    /// Save a thread as a Readwise Reader document
-    async fn save_thread(&self, thread: &ThreadViewPost, readwise_token: &str) -> Result<()> {
-        let document = format_thread_as_document(thread);
-        self.readwise
-            .save_document(readwise_token, document)
-            .await?;
-        info!("Saved thread to Reader");
-        Ok(())
+    ) -> Result<Option<String>> {
+        match payload {
+            SavePayload::Highlight(highlight) => {
+                let id = self
+                    .limited_save(
+                        "highlight",
+                        self.readwise.save_highlight(readwise_token, highlight),
+                    )
+                    .await?;
+                info!("Saved post as highlight");
+                Ok(id)
+            }
+            SavePayload::Document(document) => {
+                let id = self
+                    .limited_save(
+                        "document",
+                        self.readwise.save_document(readwise_token, document),
+                    )
+                    .await?;
+                info!("Saved document to Reader");
+                Ok(id)
+            }
+            // Reader's update endpoint can't replace content, and saving the
+            // same URL again returns the old copy, so delete it first. If the
+            // save then fails the caller keeps the old ID; deleting it again
+            // next time is a no-op (404), so the retry just saves.
+            SavePayload::DocumentReplace {
+                id: old_id,
+                document,
+            } => {
+                self.limited_save(
+                    "document_delete",
+                    self.readwise.delete_document(readwise_token, &old_id),
+                )
+                .await?;
+                let id = self
+                    .limited_save(
+                        "document",
+                        self.readwise.save_document(readwise_token, document),
+                    )
+                    .await
+                    .inspect_err(|e| {
+                        warn!(
+                            "Deleted Reader document {} but saving its replacement failed: {}",
+                            old_id, e
+                        )
+                    })?;
+                info!("Replaced thread in Reader");
+                Ok(id)
+            }
+        }
+    }
+
+    /// Run a Readwise save under the shared limiter, recording metrics
+    ///
+    /// The permit is held only for the save itself.
+    async fn limited_save<T>(
+        &self,
+        kind: &'static str,
+        save: impl Future<Output = Result<T>>,
+    ) -> Result<T> {
+        let _permit = self.save_permits.acquire().await?;
+        let started = Instant::now();
+        let result = save.await;
+        crate::metrics::readwise_save(kind, started.elapsed(), result.is_ok());
+
+        result
     }
 
     /// Extract links from a post and save them to Reader
-    async fn process_links(&self, post: &PostView, readwise_token: &str) -> Result<()> {
-        let links = extract_links(&post.record);
+    ///
+    /// Links are normalized first; ones the user already saved are skipped.
+    /// Returns how many links were saved (or would be, in a dry run).
+    async fn process_links(
+        &self,
+        post: &PostView,
+        readwise_token: &str,
+        options: &ProcessOptions,
+    ) -> Result<usize> {
+        let mut seen = HashSet::new();
+        let mut links = Vec::new();
+        for link in extract_links(&post.record, &self.strip_params) {
+            if !seen.insert(link.clone()) {
+                continue;
+            }
+            if self.link_saved(options.user_id, &link).await? {
+                debug!("Link already saved: {}", link);
+                continue;
+            }
+            links.push(link);
+        }
 
         if links.is_empty() {
-            debug!("No links found in post");
-            return Ok(());
+            debug!("No new links found in post");
+            return Ok(0);
         }
 
         info!("Found {} links to save", links.len());
 
+        if options.dry_run {
+            info!("Dry run: would save links {:?}", links);
+            return Ok(links.len());
+        }
+
+        let mut saved = 0;
         for link in links {
-            // Save each link as a Reader document
+            // Save each link as a Reader document, filed as a PDF or video when it is one
+            let kind = link_kind(&link);
+            let title = match kind {
+                LinkKind::Video => self.video_title(&link).await,
+                LinkKind::Article | LinkKind::Pdf => None,
+            };
             let document = Document {
                 url: link.clone(),
                 html: None,
-                title: None,
+                title,
                 author: None,
                 tags: Some(vec!["bluesky".to_string(), "extracted-link".to_string()]),
+                category: kind.category().map(str::to_string),
+                saved_using: Some(SAVED_USING.to_string()),
+                ..Default::default()
             };
 
-            match self.readwise.save_document(readwise_token, document).await {
-                Ok(_) => debug!("Saved link: {}", link),
+            let result = self
+                .limited_save(
+                    "link",
+                    self.readwise.save_document(readwise_token, document),
+                )
+                .await;
+            self.record_extra(options.user_id, &post.uri, "link", Some(&link), &result)
+                .await;
+            match result {
+                Ok(id) => {
+                    saved += 1;
+                    debug!("Saved link: {}", link);
+                    self.record_link(options.user_id, &link, id.as_deref())
+                        .await;
+                }
                 Err(e) => warn!("Failed to save link {}: {}", link, e),
             }
         }
 
-        Ok(())
+        Ok(saved)
+    }
+
+    /// Save each image's alt text as a separate highlight, returning how many
+    async fn process_alt_text(
+        &self,
+        post: &PostView,
+        readwise_token: &str,
+        options: &ProcessOptions,
+    ) -> usize {
+        let templates = self.templates_for(options, None);
+        let highlights = format_alt_text_highlights(post, &templates);
+        if options.dry_run {
+            if !highlights.is_empty() {
+                info!("Dry run: would save alt text {:?}", highlights);
+            }
+            return highlights.len();
+        }
+
+        let mut saved = 0;
+        for highlight in highlights {
+            let result = self
+                .limited_save(
+                    "alt_text",
+                    self.readwise.save_highlight(readwise_token, highlight),
+                )
+                .await;
+            self.record_extra(options.user_id, &post.uri, "alt_text", None, &result)
+                .await;
+            match result {
+                Ok(_) => saved += 1,
+                Err(e) => warn!("Failed to save image alt text: {}", e),
+            }
+        }
+        if saved > 0 {
+            debug!("Saved {} image alt texts", saved);
+        }
+        saved
+    }
+
+    /// Highlight templates with a category override applied
+    ///
+    /// `category` (from a post class rule) wins over the options' category.
+    fn templates_for(
+        &self,
+        options: &ProcessOptions,
+        category: Option<String>,
+    ) -> Cow<'_, HighlightTemplates> {
+        match category.or_else(|| options.highlight_category.clone()) {
+            Some(category) => Cow::Owned(HighlightTemplates {
+                category,
+                ..self.templates.clone()
+            }),
+            None => Cow::Borrowed(&self.templates),
+        }
+    }
+
+    /// A video's title, if oEmbed is set up and answers
+    async fn video_title(&self, link: &str) -> Option<String> {
+        let oembed = self.oembed.as_ref()?;
+        match oembed.video_title(link).await {
+            Ok(title) => title,
+            Err(e) => {
+                debug!("No oEmbed title for {}: {}", link, e);
+                None
+            }
+        }
+    }
+
+    /// Whether the user already saved this (normalized) link
+    async fn link_saved(&self, user_id: Option<Uuid>, link: &str) -> Result<bool> {
+        match (&self.saved_links, user_id) {
+            (Some(db), Some(user_id)) => db.is_link_saved(user_id, link).await,
+            _ => Ok(false),
+        }
+    }
+
+    /// Remember a saved link; failing to is logged, not fatal
+    ///
+    /// Without an ID from Readwise there's nothing to record.
+    async fn record_link(&self, user_id: Option<Uuid>, link: &str, readwise_id: Option<&str>) {
+        let (Some(db), Some(user_id), Some(readwise_id)) =
+            (&self.saved_links, user_id, readwise_id)
+        else {
+            return;
+        };
+        if let Err(e) = db.record_saved_link(user_id, link, readwise_id).await {
+            warn!("Failed to record saved link {}: {}", link, e);
+        }
     }
 }
 
@@ -135,10 +1021,15 @@ impl<B: BlueskyClient, R: ReadwiseClient> PostProcessor<B, R> {
 mod tests {
     use super::*;
     use crate::bluesky::types::*;
-    use crate::readwise::client::Highlight;
+    use crate::features::{Feature, Features};
+    use crate::readwise::client::ReaderDocument;
+    use crate::services::post_class::parse_post_class_rules;
     use async_trait::async_trait;
     use chrono::Utc;
+    use std::collections::HashMap;
+    use std::sync::atomic::{AtomicUsize, Ordering};
     use std::sync::Mutex;
+    use std::time::Duration;
 
     // Mock Bluesky client
     struct MockBlueskyClient {
@@ -158,41 +1049,114 @@ mod tests {
             Ok(self.thread.clone())
         }
 
+        async fn get_post_record(&self, _did: &str, _rkey: &str) -> Result<PostView> {
+            Err(anyhow::anyhow!("unused"))
+        }
+
         async fn send_dm(&self, _convo_id: &str, _text: &str) -> Result<()> {
             Ok(())
         }
+
+        async fn get_convo_for_member(&self, _did: &str) -> Result<ConvoView> {
+            Err(anyhow::anyhow!("unused"))
+        }
+
+        async fn list_convos(&self) -> Result<ConvoListResponse> {
+            Ok(ConvoListResponse {
+                cursor: None,
+                convos: vec![],
+            })
+        }
+
+        async fn get_messages(&self, _convo_id: &str) -> Result<MessagesResponse> {
+            Ok(MessagesResponse {
+                cursor: None,
+                messages: vec![],
+            })
+        }
+
+        async fn mark_convo_read(&self, _convo_id: &str) -> Result<()> {
+            Ok(())
+        }
+
+        async fn resolve_handle(&self, handle: &str) -> Result<String> {
+            Ok(format!("did:plc:{}", handle))
+        }
     }
 
     // Mock Readwise client
     struct MockReadwiseClient {
         highlights: Mutex<Vec<Highlight>>,
-        documents: Mutex<Vec<Document>>,
+        /// Keyed by URL, like Reader's own dedup
+        documents: Mutex<HashMap<String, Document>>,
+        /// Every save_document call, including repeats of a URL
+        document_saves: AtomicUsize,
+        deletes: Mutex<Vec<String>>,
+        /// Fail this many document saves before accepting them
+        failing_document_saves: AtomicUsize,
+        in_flight: AtomicUsize,
+        max_in_flight: AtomicUsize,
     }
 
     impl MockReadwiseClient {
         fn new() -> Self {
             Self {
                 highlights: Mutex::new(vec![]),
-                documents: Mutex::new(vec![]),
+                documents: Mutex::new(HashMap::new()),
+                document_saves: AtomicUsize::new(0),
+                deletes: Mutex::new(vec![]),
+                failing_document_saves: AtomicUsize::new(0),
+                in_flight: AtomicUsize::new(0),
+                max_in_flight: AtomicUsize::new(0),
             }
         }
     }
 
     #[async_trait]
     impl ReadwiseClient for MockReadwiseClient {
-        async fn save_highlight(&self, _token: &str, highlight: Highlight) -> Result<()> {
+        async fn save_highlight(
+            &self,
+            _token: &str,
+            highlight: Highlight,
+        ) -> Result<Option<String>> {
+            let current = self.in_flight.fetch_add(1, Ordering::SeqCst) + 1;
+            self.max_in_flight.fetch_max(current, Ordering::SeqCst);
+            tokio::time::sleep(Duration::from_millis(20)).await;
+            self.in_flight.fetch_sub(1, Ordering::SeqCst);
             self.highlights.lock().unwrap().push(highlight);
-            Ok(())
+            Ok(Some("hl-1".to_string()))
         }
 
-        async fn save_document(&self, _token: &str, document: Document) -> Result<()> {
-            self.documents.lock().unwrap().push(document);
+        async fn save_document(&self, _token: &str, document: Document) -> Result<Option<String>> {
+            let failing = self.failing_document_saves.load(Ordering::SeqCst);
+            if failing > 0 {
+                self.failing_document_saves
+                    .store(failing - 1, Ordering::SeqCst);
+                anyhow::bail!("Readwise Reader API error 502");
+            }
+            let n = self.document_saves.fetch_add(1, Ordering::SeqCst) + 1;
+            let mut documents = self.documents.lock().unwrap();
+            documents.insert(document.url.clone(), document);
+            Ok(Some(format!("doc-{}", n)))
+        }
+
+        async fn delete_document(&self, _token: &str, id: &str) -> Result<()> {
+            self.deletes.lock().unwrap().push(id.to_string());
             Ok(())
         }
 
         async fn verify_token(&self, _token: &str) -> Result<bool> {
             Ok(true)
         }
+
+        async fn list_documents(
+            &self,
+            _token: &str,
+            _updated_after: Option<chrono::DateTime<Utc>>,
+            _location: Option<&str>,
+        ) -> Result<Vec<ReaderDocument>> {
+            Ok(vec![])
+        }
     }
 
     fn make_test_post() -> PostView {
@@ -203,17 +1167,112 @@ mod tests {
                 did: "did:plc:test".to_string(),
                 handle: "test.bsky.social".to_string(),
                 display_name: Some("Test User".to_string()),
+                labels: Vec::new(),
             },
             record: PostRecord {
                 text: "Hello, world!".to_string(),
                 created_at: Utc::now(),
                 reply: None,
                 facets: None,
+                embed: None,
             },
             indexed_at: Utc::now(),
+            labels: Vec::new(),
+            like_count: None,
+            repost_count: None,
+            reply_count: None,
         }
     }
 
+    #[tokio::test]
+    async fn test_partial_thread_is_fetched_again() {
+        use crate::bluesky::HttpBlueskyClient;
+        use crate::readwise::client::HttpReadwiseClient;
+        use crate::test_support::{post, thread, thread_under, MockBluesky, MockReadwise};
+
+        let parent = post("parent").text("The start of the thread").build();
+        let reply = post("reply").text("And the end").reply_to(&parent).build();
+        let partial = ThreadViewPost {
+            post: reply.clone(),
+            parent: Some(Box::new(ThreadNode::NotFound {
+                uri: parent.uri.clone(),
+            })),
+            replies: None,
+        };
+        // The parent's record is still there, so it's worth another fetch
+        let bluesky = MockBluesky::new()
+            .with_thread(thread_under(reply.clone(), thread(parent.clone())))
+            .with_partial_thread(partial)
+            .with_record(&parent)
+            .start()
+            .await;
+        let readwise = MockReadwise::start().await;
+        let processor = PostProcessor::new(
+            HttpBlueskyClient::new()
+                .with_public_url(&bluesky.url)
+                .with_plc_url(&bluesky.url),
+            HttpReadwiseClient::new().with_base_url(&readwise.url),
+        )
+        .with_partial_thread_retry(Some(Duration::ZERO));
+
+        let outcome = processor
+            .process_post(&reply.uri, "token", ProcessOptions::default())
+            .await
+            .unwrap();
+
+        assert_eq!(
+            bluesky.received("/xrpc/app.bsky.feed.getPostThread").len(),
+            2
+        );
+        assert_eq!(outcome.kind, OutcomeKind::Document);
+        let saved = readwise.received("/v3/save/");
+        assert!(saved[0]["html"]
+            .as_str()
+            .unwrap()
+            .contains("The start of the thread"));
+    }
+
+    #[tokio::test]
+    async fn test_deleted_parent_is_not_fetched_again() {
+        use crate::bluesky::HttpBlueskyClient;
+        use crate::readwise::client::HttpReadwiseClient;
+        use crate::test_support::{post, MockBluesky, MockReadwise};
+
+        let parent = post("parent").build();
+        let reply = post("reply").text("And the end").reply_to(&parent).build();
+        let partial = ThreadViewPost {
+            post: reply.clone(),
+            parent: Some(Box::new(ThreadNode::NotFound {
+                uri: parent.uri.clone(),
+            })),
+            replies: None,
+        };
+        // The author's PDS has the reply but not the parent
+        let bluesky = MockBluesky::new()
+            .with_thread(partial)
+            .with_record(&reply)
+            .start()
+            .await;
+        let readwise = MockReadwise::start().await;
+        let processor = PostProcessor::new(
+            HttpBlueskyClient::new()
+                .with_public_url(&bluesky.url)
+                .with_plc_url(&bluesky.url),
+            HttpReadwiseClient::new().with_base_url(&readwise.url),
+        )
+        .with_partial_thread_retry(Some(Duration::ZERO));
+
+        processor
+            .process_post(&reply.uri, "token", ProcessOptions::default())
+            .await
+            .unwrap();
+
+        assert_eq!(
+            bluesky.received("/xrpc/app.bsky.feed.getPostThread").len(),
+            1
+        );
+    }
+
     #[tokio::test]
     async fn test_process_single_post() {
         let post = make_test_post();
@@ -229,10 +1288,913 @@ mod tests {
         let readwise = MockReadwiseClient::new();
         let processor = PostProcessor::new(bluesky, readwise);
 
-        let result = processor
+        let outcome = processor
+            .process_post(&post.uri, "test_token", ProcessOptions::default())
+            .await
+            .unwrap();
+
+        assert_eq!(
+            outcome,
+            ProcessOutcome {
+                kind: OutcomeKind::Highlight,
+                links_saved: 0,
+                alt_texts_saved: 0,
+                readwise_id: Some("hl-1".to_string()),
+                dry_run: false,
+                preview: None,
+            }
+        );
+    }
+
+    /// Flags with only `feature` on, pinned by an override
+    fn flags_on(pool: sqlx::PgPool, feature: Feature) -> Arc<FeatureFlags> {
+        let all_off = Features {
+            digests: false,
+            expand_quotes: false,
+            detect_language: false,
+        };
+        Arc::new(FeatureFlags::new(
+            Database::new(pool, crate::crypto::EncryptionKey::test_key()),
+            all_off,
+            vec![(feature, true)],
+            Duration::from_secs(60),
+        ))
+    }
+
+    #[cfg(feature = "language-detection")]
+    #[sqlx::test]
+    async fn test_highlight_tagged_with_language(pool: sqlx::PgPool) {
+        let mut post = make_test_post();
+        post.record.text =
+            "Ceci est un long message sur les livres que j'ai lus cette semaine.".to_string();
+        let thread = ThreadResponse {
+            thread: ThreadViewPost {
+                post: post.clone(),
+                parent: None,
+                replies: None,
+            },
+        };
+        let processor = PostProcessor::new(MockBlueskyClient { thread }, MockReadwiseClient::new())
+            .with_features(flags_on(pool, Feature::DetectLanguage));
+
+        processor
+            .process_post(&post.uri, "test_token", ProcessOptions::default())
+            .await
+            .unwrap();
+
+        let highlights = processor.readwise.highlights.lock().unwrap();
+        assert_eq!(highlights[0].note.as_deref(), Some(".lang:fr"));
+    }
+
+    #[tokio::test]
+    async fn test_engagement_footer_only_when_enabled() {
+        let mut post = make_test_post();
+        post.like_count = Some(12);
+        post.repost_count = Some(1);
+        post.reply_count = Some(0);
+        let thread = ThreadResponse {
+            thread: ThreadViewPost {
+                post: post.clone(),
+                parent: None,
+                replies: None,
+            },
+        };
+        let processor = PostProcessor::new(MockBlueskyClient { thread }, MockReadwiseClient::new());
+
+        for include_engagement in [false, true] {
+            let options = ProcessOptions {
+                note: Some("Worth rereading".to_string()),
+                include_engagement,
+                ..Default::default()
+            };
+            processor
+                .process_post(&post.uri, "test_token", options)
+                .await
+                .unwrap();
+        }
+
+        let highlights = processor.readwise.highlights.lock().unwrap();
+        assert_eq!(highlights[0].note.as_deref(), Some("Worth rereading"));
+        assert_eq!(
+            highlights[1].note.as_deref(),
+            Some("Worth rereading\n12 likes · 1 repost · 0 replies")
+        );
+    }
+
+    fn reply_by(did: &str, rkey: &str) -> ThreadViewPost {
+        let mut post = make_test_post();
+        post.uri = format!("at://{}/app.bsky.feed.post/{}", did, rkey);
+        post.author.did = did.to_string();
+        ThreadViewPost {
+            post,
+            parent: None,
+            replies: None,
+        }
+    }
+
+    async fn process_with_replies(replies: Vec<ThreadViewPost>) -> (usize, usize) {
+        let post = make_test_post();
+        let thread = ThreadResponse {
+            thread: ThreadViewPost {
+                post: post.clone(),
+                parent: None,
+                replies: Some(replies.into_iter().map(ThreadNode::from).collect()),
+            },
+        };
+        let processor = PostProcessor::new(MockBlueskyClient { thread }, MockReadwiseClient::new());
+
+        processor
+            .process_post(&post.uri, "test_token", ProcessOptions::default())
+            .await
+            .unwrap();
+
+        let highlights = processor.readwise.highlights.lock().unwrap().len();
+        let documents = processor.readwise.documents.lock().unwrap().len();
+        (highlights, documents)
+    }
+
+    #[tokio::test]
+    async fn test_post_with_only_foreign_replies_is_highlight() {
+        let (highlights, documents) =
+            process_with_replies(vec![reply_by("did:plc:stranger", "r1")]).await;
+        assert_eq!((highlights, documents), (1, 0));
+    }
+
+    #[tokio::test]
+    async fn test_self_thread_is_document() {
+        let (highlights, documents) = process_with_replies(vec![
+            reply_by("did:plc:stranger", "r1"),
+            reply_by("did:plc:test", "r2"),
+        ])
+        .await;
+        assert_eq!((highlights, documents), (0, 1));
+    }
+
+    #[tokio::test]
+    async fn test_class_rule_overrides_save_target() {
+        let post = make_test_post();
+        let thread = ThreadResponse {
+            thread: ThreadViewPost {
+                post: post.clone(),
+                parent: None,
+                replies: None,
+            },
+        };
+        let processor = PostProcessor::new(MockBlueskyClient { thread }, MockReadwiseClient::new());
+        let options = ProcessOptions {
+            class_rules: parse_post_class_rules("top_level=document:note").unwrap(),
+            ..Default::default()
+        };
+
+        let outcome = processor
+            .process_post(&post.uri, "test_token", options)
+            .await
+            .unwrap();
+
+        assert_eq!(outcome.kind, OutcomeKind::Document);
+        assert!(processor.readwise.highlights.lock().unwrap().is_empty());
+        let documents = processor.readwise.documents.lock().unwrap();
+        assert_eq!(
+            documents.values().next().unwrap().category.as_deref(),
+            Some("note")
+        );
+    }
+
+    #[tokio::test]
+    async fn test_empty_post_saved_as_document() {
+        let mut post = make_test_post();
+        post.record.text = "  ".to_string();
+        let thread = ThreadResponse {
+            thread: ThreadViewPost {
+                post: post.clone(),
+                parent: None,
+                replies: None,
+            },
+        };
+        let processor = PostProcessor::new(MockBlueskyClient { thread }, MockReadwiseClient::new());
+
+        let outcome = processor
             .process_post(&post.uri, "test_token", ProcessOptions::default())
+            .await
+            .unwrap();
+
+        assert_eq!(outcome.kind, OutcomeKind::Document);
+        assert!(processor.readwise.highlights.lock().unwrap().is_empty());
+        assert_eq!(processor.readwise.documents.lock().unwrap().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_resave_after_handle_change_is_one_document() {
+        let mut post = make_test_post();
+        post.record.text = String::new();
+        let processor = PostProcessor::new(
+            MockBlueskyClient {
+                thread: ThreadResponse {
+                    thread: ThreadViewPost {
+                        post: post.clone(),
+                        parent: None,
+                        replies: None,
+                    },
+                },
+            },
+            MockReadwiseClient::new(),
+        );
+        let options = ProcessOptions::default();
+
+        let save = |post: &PostView| {
+            processor.save_payload(
+                SavePayload::Document(format_post_as_document(
+                    post,
+                    &HighlightTemplates::default(),
+                )),
+                "test_token",
+            )
+        };
+        save(&post).await.unwrap();
+        post.author.handle = "renamed.bsky.social".to_string();
+        save(&post).await.unwrap();
+
+        let documents = processor.readwise.documents.lock().unwrap();
+        assert_eq!(documents.len(), 1);
+        assert!(documents.contains_key("https://bsky.app/profile/did:plc:test/post/abc123"));
+    }
+
+    fn link_facet(uri: &str) -> Facet {
+        Facet {
+            index: ByteSlice {
+                byte_start: 0,
+                byte_end: 1,
+            },
+            features: vec![FacetFeature::Link {
+                uri: uri.to_string(),
+            }],
+        }
+    }
+
+    #[sqlx::test]
+    async fn test_links_already_saved_are_skipped(pool: sqlx::PgPool) {
+        let db = Database::new(pool, crate::crypto::EncryptionKey::test_key());
+        let user = db
+            .create_user("did:plc:reader", "reader.bsky.social")
+            .await
+            .unwrap();
+        let mut post = make_test_post();
+        post.record.facets = Some(vec![
+            link_facet("https://example.com/article/?utm_source=bsky"),
+            link_facet("https://example.com/article"),
+        ]);
+        let processor = PostProcessor::new(
+            MockBlueskyClient {
+                thread: ThreadResponse {
+                    thread: ThreadViewPost {
+                        post: post.clone(),
+                        parent: None,
+                        replies: None,
+                    },
+                },
+            },
+            MockReadwiseClient::new(),
+        )
+        .with_link_dedup(db.clone());
+        let options = ProcessOptions {
+            extract_links: true,
+            user_id: Some(user.id),
+            ..Default::default()
+        };
+
+        let first = processor
+            .process_post(&post.uri, "test_token", options.clone())
+            .await
+            .unwrap();
+        let second = processor
+            .process_post(&post.uri, "test_token", options)
+            .await
+            .unwrap();
+
+        assert_eq!((first.links_saved, second.links_saved), (1, 0));
+        assert_eq!(processor.readwise.document_saves.load(Ordering::SeqCst), 1);
+        assert!(db
+            .is_link_saved(user.id, "https://example.com/article")
+            .await
+            .unwrap());
+        // Links don't pose as post documents
+        assert_eq!(
+            db.get_saved_document(user.id, "https://example.com/article")
+                .await
+                .unwrap(),
+            None
+        );
+        assert!(processor
+            .readwise
+            .documents
+            .lock()
+            .unwrap()
+            .contains_key("https://example.com/article"));
+    }
+
+    #[sqlx::test]
+    async fn test_each_attempt_records_one_save_event(pool: sqlx::PgPool) {
+        let db = Database::new(pool, crate::crypto::EncryptionKey::test_key());
+        let user = db
+            .create_user("did:plc:reader", "reader.bsky.social")
+            .await
+            .unwrap();
+        let post = make_test_post();
+        let processor = PostProcessor::new(
+            MockBlueskyClient {
+                thread: ThreadResponse {
+                    thread: ThreadViewPost {
+                        post: post.clone(),
+                        parent: None,
+                        replies: None,
+                    },
+                },
+            },
+            MockReadwiseClient::new(),
+        )
+        .with_save_audit(db.clone());
+        let options = ProcessOptions {
+            user_id: Some(user.id),
+            ..Default::default()
+        };
+
+        processor
+            .process_post(&post.uri, "test_token", options.clone())
+            .await
+            .unwrap();
+        assert!(processor
+            .process_post("not-a-uri", "test_token", options.clone())
+            .await
+            .is_err());
+        processor
+            .process_post(
+                &post.uri,
+                "test_token",
+                ProcessOptions {
+                    dry_run: true,
+                    ..options
+                },
+            )
+            .await
+            .unwrap();
+
+        let events = db.recent_save_events(user.id, 10).await.unwrap();
+        assert_eq!(events.len(), 2);
+        let failed = &events[0];
+        assert_eq!(failed.post_uri, "not-a-uri");
+        assert_eq!(failed.status, "failed");
+        assert_eq!(failed.kind, None);
+        assert!(failed.error.is_some());
+        let saved = &events[1];
+        assert_eq!(saved.post_uri, post.uri);
+        assert_eq!(saved.status, "saved");
+        assert_eq!(saved.kind.as_deref(), Some("highlight"));
+        assert_eq!(saved.readwise_id.as_deref(), Some("hl-1"));
+        assert_eq!(saved.error, None);
+    }
+
+    #[sqlx::test]
+    async fn test_link_and_alt_text_saves_record_events(pool: sqlx::PgPool) {
+        let db = Database::new(pool, crate::crypto::EncryptionKey::test_key());
+        let user = db
+            .create_user("did:plc:reader", "reader.bsky.social")
+            .await
+            .unwrap();
+        let mut post = make_test_post();
+        post.record.facets = Some(vec![link_facet("https://example.com/article")]);
+        post.record.embed = Some(Embed::Images {
+            images: vec![EmbedImage {
+                image: Blob {
+                    reference: BlobLink {
+                        link: "cid1".to_string(),
+                    },
+                    mime_type: "image/jpeg".to_string(),
+                    size: 1024,
+                },
+                alt: "A heron on one leg".to_string(),
+                aspect_ratio: None,
+            }],
+        });
+        let processor = PostProcessor::new(
+            MockBlueskyClient {
+                thread: ThreadResponse {
+                    thread: ThreadViewPost {
+                        post: post.clone(),
+                        parent: None,
+                        replies: None,
+                    },
+                },
+            },
+            MockReadwiseClient::new(),
+        )
+        .with_save_audit(db.clone());
+
+        processor
+            .process_post(
+                &post.uri,
+                "test_token",
+                ProcessOptions {
+                    extract_links: true,
+                    save_image_alt_text: true,
+                    user_id: Some(user.id),
+                    ..Default::default()
+                },
+            )
+            .await
+            .unwrap();
+
+        let events = db.recent_save_events(user.id, 10).await.unwrap();
+        let kinds: Vec<(Option<&str>, Option<&str>)> = events
+            .iter()
+            .map(|event| (event.kind.as_deref(), event.url.as_deref()))
+            .collect();
+        assert_eq!(events.len(), 3);
+        assert!(kinds.contains(&(Some("highlight"), None)));
+        assert!(kinds.contains(&(Some("link"), Some("https://example.com/article"))));
+        assert!(kinds.contains(&(Some("alt_text"), None)));
+        assert!(events
+            .iter()
+            .all(|event| event.post_uri == post.uri && event.status == "saved"));
+    }
+
+    /// Knows one video title; fails for everything else
+    struct MockOEmbed;
+
+    #[async_trait]
+    impl OEmbedClient for MockOEmbed {
+        async fn video_title(&self, url: &str) -> Result<Option<String>> {
+            if url.contains("dQw4w9WgXcQ") {
+                Ok(Some("A classic".to_string()))
+            } else {
+                anyhow::bail!("oEmbed error 404 Not Found")
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn test_links_saved_with_reader_category() {
+        let mut post = make_test_post();
+        post.record.facets = Some(vec![
+            link_facet("https://example.com/paper.pdf"),
+            link_facet("https://www.youtube.com/watch?v=dQw4w9WgXcQ"),
+            link_facet("https://www.youtube.com/watch?v=private"),
+            link_facet("https://example.com/article"),
+        ]);
+        let processor = PostProcessor::new(
+            MockBlueskyClient {
+                thread: ThreadResponse {
+                    thread: ThreadViewPost {
+                        post: post.clone(),
+                        parent: None,
+                        replies: None,
+                    },
+                },
+            },
+            MockReadwiseClient::new(),
+        )
+        .with_oembed(Arc::new(MockOEmbed));
+        let options = ProcessOptions {
+            extract_links: true,
+            ..Default::default()
+        };
+
+        let outcome = processor
+            .process_post(&post.uri, "test_token", options)
+            .await
+            .unwrap();
+
+        assert_eq!(outcome.links_saved, 4);
+        let documents = processor.readwise.documents.lock().unwrap();
+        let saved = |url: &str| documents.get(url).unwrap().clone();
+        assert_eq!(
+            saved("https://example.com/paper.pdf").category.as_deref(),
+            Some("pdf")
+        );
+        let video = saved("https://www.youtube.com/watch?v=dQw4w9WgXcQ");
+        assert_eq!(video.category.as_deref(), Some("video"));
+        assert_eq!(video.title.as_deref(), Some("A classic"));
+        // A failed lookup still saves the video, untitled
+        let untitled = saved("https://www.youtube.com/watch?v=private");
+        assert_eq!(untitled.category.as_deref(), Some("video"));
+        assert!(untitled.title.is_none());
+        assert!(saved("https://example.com/article").category.is_none());
+    }
+
+    #[tokio::test]
+    async fn test_image_alt_text_saved_as_tagged_highlights() {
+        let image = |cid: &str, alt: &str| EmbedImage {
+            image: Blob {
+                reference: BlobLink {
+                    link: cid.to_string(),
+                },
+                mime_type: "image/jpeg".to_string(),
+                size: 1024,
+            },
+            alt: alt.to_string(),
+            aspect_ratio: None,
+        };
+        let mut post = make_test_post();
+        post.record.embed = Some(Embed::Images {
+            images: vec![image("cid1", "A heron on one leg"), image("cid2", "  ")],
+        });
+        let thread = ThreadResponse {
+            thread: ThreadViewPost {
+                post: post.clone(),
+                parent: None,
+                replies: None,
+            },
+        };
+        let processor = PostProcessor::new(MockBlueskyClient { thread }, MockReadwiseClient::new());
+
+        let outcome = processor
+            .process_post(
+                &post.uri,
+                "test_token",
+                ProcessOptions {
+                    save_image_alt_text: true,
+                    ..Default::default()
+                },
+            )
+            .await
+            .unwrap();
+
+        assert_eq!(outcome.alt_texts_saved, 1);
+        let highlights = processor.readwise.highlights.lock().unwrap();
+        let alt_texts: Vec<&Highlight> = highlights
+            .iter()
+            .filter(|h| h.note.as_deref() == Some(".alt-text"))
+            .collect();
+        assert_eq!(alt_texts.len(), 1);
+        assert_eq!(alt_texts[0].text, "A heron on one leg");
+        assert_ne!(alt_texts[0].highlight_url, highlights[0].highlight_url);
+    }
+
+    #[tokio::test]
+    async fn test_dry_run_skips_readwise() {
+        let post = make_test_post();
+        let thread = ThreadResponse {
+            thread: ThreadViewPost {
+                post: post.clone(),
+                parent: None,
+                replies: None,
+            },
+        };
+        let processor = PostProcessor::new(MockBlueskyClient { thread }, MockReadwiseClient::new());
+
+        let outcome = processor
+            .process_post(
+                &post.uri,
+                "test_token",
+                ProcessOptions {
+                    extract_links: true,
+                    dry_run: true,
+                    ..Default::default()
+                },
+            )
+            .await
+            .unwrap();
+
+        assert_eq!(
+            outcome,
+            ProcessOutcome {
+                kind: OutcomeKind::Highlight,
+                links_saved: 0,
+                alt_texts_saved: 0,
+                readwise_id: None,
+                dry_run: true,
+                preview: Some(SavePayload::Highlight(format_post_as_highlight(
+                    &post,
+                    None,
+                    true,
+                    &processor.templates,
+                ))),
+            }
+        );
+        assert!(processor.readwise.highlights.lock().unwrap().is_empty());
+        assert!(processor.readwise.documents.lock().unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_save_limiter_serializes_saves() {
+        let post = make_test_post();
+        let thread = ThreadResponse {
+            thread: ThreadViewPost {
+                post: post.clone(),
+                parent: None,
+                replies: None,
+            },
+        };
+        let processor = PostProcessor::new(MockBlueskyClient { thread }, MockReadwiseClient::new())
+            .with_save_limiter(Arc::new(Semaphore::new(1)));
+
+        let (first, second) = tokio::join!(
+            processor.process_post(&post.uri, "token-a", ProcessOptions::default()),
+            processor.process_post(&post.uri, "token-b", ProcessOptions::default()),
+        );
+        first.unwrap();
+        second.unwrap();
+
+        assert_eq!(processor.readwise.highlights.lock().unwrap().len(), 2);
+        assert_eq!(processor.readwise.max_in_flight.load(Ordering::SeqCst), 1);
+    }
+
+    #[tokio::test]
+    async fn test_resaved_thread_replaces_document() {
+        let post = make_test_post();
+        let thread = ThreadResponse {
+            thread: ThreadViewPost {
+                post: post.clone(),
+                parent: None,
+                replies: Some(vec![reply_by("did:plc:test", "r1").into()]),
+            },
+        };
+        let processor = PostProcessor::new(MockBlueskyClient { thread }, MockReadwiseClient::new());
+
+        let first = processor
+            .process_post(&post.uri, "test_token", ProcessOptions::default())
+            .await
+            .unwrap();
+        assert_eq!(first.new_document_id(), Some("doc-1"));
+
+        let second = processor
+            .process_post(
+                &post.uri,
+                "test_token",
+                ProcessOptions {
+                    existing_document_id: first.readwise_id.clone(),
+                    ..Default::default()
+                },
+            )
+            .await
+            .unwrap();
+
+        // The old copy is deleted and the thread saved afresh under a new ID
+        assert_eq!(second.kind, OutcomeKind::DocumentReplaced);
+        assert_eq!(second.new_document_id(), Some("doc-2"));
+        assert_eq!(
+            processor.readwise.deletes.lock().unwrap().as_slice(),
+            ["doc-1".to_string()]
+        );
+        assert_eq!(processor.readwise.document_saves.load(Ordering::SeqCst), 2);
+    }
+
+    #[tokio::test]
+    async fn test_failed_replacement_saves_again_next_time() {
+        let post = make_test_post();
+        let thread = ThreadResponse {
+            thread: ThreadViewPost {
+                post: post.clone(),
+                parent: None,
+                replies: Some(vec![reply_by("did:plc:test", "r1").into()]),
+            },
+        };
+        let processor = PostProcessor::new(MockBlueskyClient { thread }, MockReadwiseClient::new());
+        let options = ProcessOptions {
+            existing_document_id: Some("doc-0".to_string()),
+            ..Default::default()
+        };
+
+        processor
+            .readwise
+            .failing_document_saves
+            .store(1, Ordering::SeqCst);
+        assert!(processor
+            .process_post(&post.uri, "test_token", options.clone())
+            .await
+            .is_err());
+        assert_eq!(
+            processor.readwise.deletes.lock().unwrap().as_slice(),
+            ["doc-0".to_string()]
+        );
+
+        // The caller still holds the old ID; deleting it again is harmless
+        let retried = processor
+            .process_post(&post.uri, "test_token", options)
+            .await
+            .unwrap();
+        assert_eq!(retried.kind, OutcomeKind::DocumentReplaced);
+        assert_eq!(retried.new_document_id(), Some("doc-1"));
+        assert_eq!(processor.readwise.deletes.lock().unwrap().len(), 2);
+    }
+
+    fn quoting(post: PostView, quoted: &PostView) -> PostView {
+        let mut post = post;
+        post.record.embed = Some(Embed::Record {
+            record: StrongRef {
+                uri: quoted.uri.clone(),
+                cid: quoted.cid.clone(),
+            },
+        });
+        post
+    }
+
+    #[sqlx::test]
+    async fn test_quote_saved_with_quoted_post(pool: sqlx::PgPool) {
+        use crate::bluesky::HttpBlueskyClient;
+        use crate::readwise::client::HttpReadwiseClient;
+        use crate::test_support::{post, thread, MockBluesky, MockReadwise};
+
+        let original = post("original").text("The quoted take").build();
+        let commentary = quoting(post("quote").text("Strong agree").build(), &original);
+        let bluesky = MockBluesky::new()
+            .with_thread(thread(original))
+            .with_thread(thread(commentary.clone()))
+            .start()
             .await;
+        let readwise = MockReadwise::start().await;
+        let processor = PostProcessor::new(
+            HttpBlueskyClient::new().with_public_url(&bluesky.url),
+            HttpReadwiseClient::new().with_base_url(&readwise.url),
+        )
+        .with_features(flags_on(pool, Feature::ExpandQuotes));
+
+        let outcome = processor
+            .process_post(&commentary.uri, "token", ProcessOptions::default())
+            .await
+            .unwrap();
 
-        assert!(result.is_ok());
+        assert_eq!(outcome.kind, OutcomeKind::Document);
+        assert!(readwise.received("/v2/highlights/").is_empty());
+        let saved = readwise.received("/v3/save/");
+        assert_eq!(saved.len(), 1);
+        assert_eq!(saved[0]["title"], "Quote by @author.bsky.social");
+        let html = saved[0]["html"].as_str().unwrap();
+        let (commentary_html, quoted_html) = html.split_once("<blockquote").unwrap();
+        assert!(commentary_html.contains("Strong agree"));
+        assert!(quoted_html.contains("The quoted take"));
+    }
+
+    #[sqlx::test]
+    async fn test_quote_cycle_followed_once(pool: sqlx::PgPool) {
+        use crate::bluesky::HttpBlueskyClient;
+        use crate::readwise::client::HttpReadwiseClient;
+        use crate::test_support::{post, thread, MockBluesky, MockReadwise};
+
+        let first = post("a").text("First").build();
+        let second = quoting(post("b").text("Second").build(), &first);
+        let first = quoting(first, &second);
+        let bluesky = MockBluesky::new()
+            .with_thread(thread(first.clone()))
+            .with_thread(thread(second))
+            .start()
+            .await;
+        let readwise = MockReadwise::start().await;
+        let processor = PostProcessor::new(
+            HttpBlueskyClient::new().with_public_url(&bluesky.url),
+            HttpReadwiseClient::new().with_base_url(&readwise.url),
+        )
+        .with_features(flags_on(pool, Feature::ExpandQuotes));
+
+        processor
+            .process_post(&first.uri, "token", ProcessOptions::default())
+            .await
+            .unwrap();
+
+        let saved = readwise.received("/v3/save/");
+        let html = saved[0]["html"].as_str().unwrap();
+        assert_eq!(html.matches("<blockquote").count(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_failures_keep_their_kind() {
+        use crate::bluesky::HttpBlueskyClient;
+        use crate::readwise::client::HttpReadwiseClient;
+        use crate::test_support::{post, MockBluesky, MockReadwise};
+
+        let saved = post("saved").build();
+        let bluesky = MockBluesky::new().with_bookmark(&saved).start().await;
+        let processor = |readwise: &str| {
+            PostProcessor::new(
+                HttpBlueskyClient::new().with_public_url(&bluesky.url),
+                HttpReadwiseClient::new().with_base_url(readwise),
+            )
+        };
+        let readwise = MockReadwise::start().await;
+        let process = |uri| {
+            let processor = processor(&readwise.url);
+            async move {
+                processor
+                    .process_post(uri, "token", ProcessOptions::default())
+                    .await
+                    .unwrap_err()
+            }
+        };
+
+        assert!(matches!(
+            process("at://did:plc:author/app.bsky.feed.post/gone").await,
+            ProcessError::PostUnavailable
+        ));
+        assert!(matches!(
+            process("https://bsky.app/profile/author.bsky.social/post/saved").await,
+            ProcessError::InvalidUri(AtUriError::Scheme(_))
+        ));
+
+        let rejected = MockReadwise::failing(StatusCode::UNAUTHORIZED).await;
+        let error = processor(&rejected.url)
+            .process_post(&saved.uri, "token", ProcessOptions::default())
+            .await
+            .unwrap_err();
+        assert!(matches!(
+            error,
+            ProcessError::Readwise {
+                status: StatusCode::UNAUTHORIZED,
+                ..
+            }
+        ));
+        assert!(error.is_token_rejected());
+
+        let conflict = MockReadwise::failing(StatusCode::CONFLICT).await;
+        let error = processor(&conflict.url)
+            .process_post(&saved.uri, "token", ProcessOptions::default())
+            .await
+            .unwrap_err();
+        assert!(matches!(error, ProcessError::Duplicate));
+    }
+
+    #[test]
+    fn test_readwise_status_mapping() {
+        let error = |status| {
+            ProcessError::from(anyhow::Error::new(ReadwiseApiError {
+                api: "Readwise API",
+                status,
+                body: String::new(),
+            }))
+        };
+
+        assert!(error(StatusCode::UNAUTHORIZED).is_token_rejected());
+        assert!(error(StatusCode::FORBIDDEN).is_token_rejected());
+        assert!(error(StatusCode::TOO_MANY_REQUESTS).is_rate_limited());
+        assert!(matches!(
+            error(StatusCode::CONFLICT),
+            ProcessError::Duplicate
+        ));
+        let bad_gateway = error(StatusCode::BAD_GATEWAY);
+        assert!(!bad_gateway.is_token_rejected() && !bad_gateway.is_rate_limited());
+    }
+
+    #[tokio::test]
+    async fn test_refused_thread_falls_back_to_post_record() {
+        use crate::bluesky::HttpBlueskyClient;
+        use crate::readwise::client::HttpReadwiseClient;
+        use crate::test_support::{post, MockBluesky, MockReadwise};
+
+        let saved = post("abc").text("Still worth keeping").build();
+        let bluesky = MockBluesky::new()
+            .with_record(&saved)
+            .with_thread_error(StatusCode::BAD_REQUEST)
+            .start()
+            .await;
+        let readwise = MockReadwise::start().await;
+        let processor = PostProcessor::new(
+            HttpBlueskyClient::new()
+                .with_public_url(&bluesky.url)
+                .with_plc_url(&bluesky.url),
+            HttpReadwiseClient::new().with_base_url(&readwise.url),
+        );
+
+        let outcome = processor
+            .process_post(&saved.uri, "token", ProcessOptions::default())
+            .await
+            .unwrap();
+
+        assert_eq!(outcome.kind, OutcomeKind::Highlight);
+        let highlights = readwise.received("/v2/highlights/");
+        let highlight = &highlights[0]["highlights"][0];
+        assert_eq!(highlight["text"], "Still worth keeping");
+        assert_eq!(highlight["title"], "Post by @author.bsky.social");
+    }
+
+    #[tokio::test]
+    async fn test_thread_outage_fails_so_the_save_is_retried() {
+        use crate::bluesky::HttpBlueskyClient;
+        use crate::readwise::client::HttpReadwiseClient;
+        use crate::test_support::{post, MockBluesky, MockReadwise};
+
+        let saved = post("abc").text("Worth keeping with its thread").build();
+        let bluesky = MockBluesky::new()
+            .with_record(&saved)
+            .with_thread_error(StatusCode::BAD_GATEWAY)
+            .start()
+            .await;
+        let readwise = MockReadwise::start().await;
+        let processor = PostProcessor::new(
+            HttpBlueskyClient::new()
+                .with_public_url(&bluesky.url)
+                .with_plc_url(&bluesky.url),
+            HttpReadwiseClient::new().with_base_url(&readwise.url),
+        );
+
+        let error = processor
+            .process_post(&saved.uri, "token", ProcessOptions::default())
+            .await
+            .unwrap_err();
+
+        assert!(matches!(
+            error,
+            ProcessError::Bluesky {
+                status: StatusCode::BAD_GATEWAY
+            }
+        ));
+        assert!(readwise.received("/v2/highlights/").is_empty());
     }
 }
@@ -0,0 +1,112 @@
+//! Rotating a user's stored Readwise token, and caching token checks
+//!
+//! Shared by `POST /api/readwise-token` and the DM bot's `token` command, so
+//! both verify the new token the same way and touch nothing else.
+
+use std::collections::HashMap;
+use std::sync::RwLock;
+use std::time::{Duration, Instant};
+
+use anyhow::Result;
+use uuid::Uuid;
+
+use crate::db::queries::Database;
+use crate::readwise::client::ReadwiseClient;
+
+/// Result of trying to replace a user's Readwise token
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TokenRotation {
+    /// The new token was verified and stored
+    Rotated,
+    /// Readwise refused the new token; the old one is kept
+    Rejected,
+    /// The user has no settings (and so no token) to rotate
+    NotRegistered,
+}
+
+/// Verify `readwise_token` and store it as the user's token
+///
+/// Sync flags, filters and the bookmark cursor are left as they are.
+pub async fn rotate_readwise_token<R: ReadwiseClient + ?Sized>(
+    db: &Database,
+    readwise: &R,
+    user_id: Uuid,
+    readwise_token: &str,
+) -> Result<TokenRotation> {
+    if db.get_user_settings(user_id).await?.is_none() {
+        return Ok(TokenRotation::NotRegistered);
+    }
+
+    if !readwise.verify_token(readwise_token).await? {
+        return Ok(TokenRotation::Rejected);
+    }
+
+    if !db.set_readwise_token(user_id, readwise_token).await? {
+        return Ok(TokenRotation::NotRegistered);
+    }
+
+    Ok(TokenRotation::Rotated)
+}
+
+/// How long `/api/status` reuses a token check
+pub const TOKEN_CHECK_TTL: Duration = Duration::from_secs(300);
+
+/// A user's last token check
+struct TokenCheck {
+    token: String,
+    valid: bool,
+    checked_at: Instant,
+}
+
+/// Recent Readwise token checks, so dashboards polling `/api/status` don't
+/// call Readwise on every request
+///
+/// One entry per user; a rotated token misses the cache because the entry
+/// remembers which token it checked. Failed checks aren't cached.
+pub struct TokenChecks {
+    ttl: Duration,
+    entries: RwLock<HashMap<Uuid, TokenCheck>>,
+}
+
+impl TokenChecks {
+    pub fn new(ttl: Duration) -> Self {
+        Self {
+            ttl,
+            entries: RwLock::new(HashMap::new()),
+        }
+    }
+
+    /// The cached result for this user and token, if still fresh
+    pub fn cached(&self, user_id: Uuid, token: &str) -> Option<bool> {
+        let entries = self.entries.read().unwrap_or_else(|e| e.into_inner());
+        entries
+            .get(&user_id)
+            .filter(|check| check.token == token && check.checked_at.elapsed() < self.ttl)
+            .map(|check| check.valid)
+    }
+
+    /// Verify a token with Readwise unless a fresh result is cached
+    pub async fn verify<R: ReadwiseClient + ?Sized>(
+        &self,
+        readwise: &R,
+        user_id: Uuid,
+        token: &str,
+    ) -> Result<bool> {
+        if let Some(valid) = self.cached(user_id, token) {
+            return Ok(valid);
+        }
+
+        let valid = readwise.verify_token(token).await?;
+        let mut entries = self.entries.write().unwrap_or_else(|e| e.into_inner());
+        entries.retain(|_, check| check.checked_at.elapsed() < self.ttl);
+        entries.insert(
+            user_id,
+            TokenCheck {
+                token: token.to_string(),
+                valid,
+                checked_at: Instant::now(),
+            },
+        );
+        Ok(valid)
+    }
+}
@@ -0,0 +1,216 @@
+//! Bounded queue between bookmark polling and saving
+//!
+//! Pollers enqueue save jobs and go back to polling; save workers drain the
+//! queue under the shared save limiter. A full queue makes enqueueing wait
+//! (backpressure) for a while, then fail so the caller can hand the job to
+//! the retry loop instead of holding it in memory.
+
+use std::collections::HashSet;
+use std::ops::Deref;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use thiserror::Error;
+use tokio::sync::mpsc::{self, error::SendTimeoutError};
+use uuid::Uuid;
+
+/// How long enqueueing waits for room before giving up
+const DEFAULT_ENQUEUE_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// One bookmark to save for a user
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SaveJob {
+    pub user_id: Uuid,
+    pub post_uri: String,
+    /// Highlight note (e.g., who reposted it)
+    pub note: Option<String>,
+    /// Request ID of the poll that queued it, which the save runs under
+    pub request_id: Option<Uuid>,
+}
+
+/// The queue stayed full (or its workers stopped) while enqueueing
+#[derive(Error, Debug, PartialEq, Eq)]
+#[error("Save queue is full")]
+pub struct QueueFull;
+
+/// Jobs queued or being saved, so a re-poll doesn't queue them twice
+type Pending = Arc<Mutex<HashSet<(Uuid, String)>>>;
+
+/// Sending half of the save queue, shared by pollers
+#[derive(Clone)]
+pub struct SaveQueue {
+    tx: mpsc::Sender<SaveJob>,
+    pending: Pending,
+    enqueue_timeout: Duration,
+}
+
+/// A job a worker has taken
+///
+/// Dropping it marks the job finished (saved or dead-lettered) so it can be
+/// queued again; that includes a worker panicking mid-save.
+#[derive(Debug)]
+pub struct TakenJob {
+    job: SaveJob,
+    pending: Pending,
+}
+
+impl Deref for TakenJob {
+    type Target = SaveJob;
+
+    fn deref(&self) -> &SaveJob {
+        &self.job
+    }
+}
+
+impl Drop for TakenJob {
+    fn drop(&mut self) {
+        lock(&self.pending).remove(&(self.job.user_id, self.job.post_uri.clone()));
+    }
+}
+
+/// Receiving half of the save queue, shared by save workers
+pub struct SaveJobs {
+    rx: tokio::sync::Mutex<mpsc::Receiver<SaveJob>>,
+    pending: Pending,
+}
+
+/// Create a queue holding at most `capacity` jobs (at least one)
+pub fn save_queue(capacity: usize) -> (SaveQueue, SaveJobs) {
+    let (tx, rx) = mpsc::channel(capacity.max(1));
+    let pending = Pending::default();
+    (
+        SaveQueue {
+            tx,
+            pending: pending.clone(),
+            enqueue_timeout: DEFAULT_ENQUEUE_TIMEOUT,
+        },
+        SaveJobs {
+            rx: tokio::sync::Mutex::new(rx),
+            pending,
+        },
+    )
+}
+
+impl SaveQueue {
+    /// Wait this long for room before giving up
+    pub fn with_enqueue_timeout(mut self, timeout: Duration) -> Self {
+        self.enqueue_timeout = timeout;
+        self
+    }
+
+    /// Queue a job, waiting for room while the queue is full
+    ///
+    /// A job already queued or being saved is not queued again.
+    pub async fn enqueue(&self, job: SaveJob) -> Result<(), QueueFull> {
+        let key = (job.user_id, job.post_uri.clone());
+        if !lock(&self.pending).insert(key.clone()) {
+            return Ok(());
+        }
+
+        match self.tx.send_timeout(job, self.enqueue_timeout).await {
+            Ok(()) => Ok(()),
+            Err(SendTimeoutError::Timeout(_) | SendTimeoutError::Closed(_)) => {
+                lock(&self.pending).remove(&key);
+                Err(QueueFull)
+            }
+        }
+    }
+
+    /// Jobs waiting in the queue
+    pub fn len(&self) -> usize {
+        self.tx.max_capacity() - self.tx.capacity()
+    }
+
+    /// Whether no jobs are waiting
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+impl SaveJobs {
+    /// Next job, or None once every `SaveQueue` is dropped
+    pub async fn next(&self) -> Option<TakenJob> {
+        let job = self.rx.lock().await.recv().await?;
+        Some(TakenJob {
+            job,
+            pending: self.pending.clone(),
+        })
+    }
+}
+
+fn lock(pending: &Pending) -> std::sync::MutexGuard<'_, HashSet<(Uuid, String)>> {
+    pending.lock().unwrap_or_else(|e| e.into_inner())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn job(rkey: &str) -> SaveJob {
+        SaveJob {
+            user_id: Uuid::nil(),
+            post_uri: format!("at://did:plc:x/app.bsky.feed.post/{}", rkey),
+            note: None,
+            request_id: None,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_full_queue_applies_backpressure() {
+        let (queue, jobs) = save_queue(2);
+        let queue = queue.with_enqueue_timeout(Duration::from_millis(50));
+
+        queue.enqueue(job("1")).await.unwrap();
+        queue.enqueue(job("2")).await.unwrap();
+        // No room: the producer waits, then gives up instead of growing the queue
+        assert_eq!(queue.enqueue(job("3")).await, Err(QueueFull));
+        assert_eq!(queue.len(), 2);
+
+        // A blocked producer proceeds as soon as a worker makes room
+        let producer = tokio::spawn({
+            let queue = queue.clone().with_enqueue_timeout(Duration::from_secs(5));
+            async move { queue.enqueue(job("3")).await }
+        });
+        let first = jobs.next().await.unwrap();
+        assert_eq!(*first, job("1"));
+        producer.await.unwrap().unwrap();
+        assert_eq!(queue.len(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_pending_job_is_not_queued_twice() {
+        let (queue, jobs) = save_queue(10);
+
+        queue.enqueue(job("1")).await.unwrap();
+        queue.enqueue(job("1")).await.unwrap();
+        assert_eq!(queue.len(), 1);
+
+        // Still pending while a worker saves it
+        let taken = jobs.next().await.unwrap();
+        queue.enqueue(job("1")).await.unwrap();
+        assert!(queue.is_empty());
+
+        drop(taken);
+        queue.enqueue(job("1")).await.unwrap();
+        assert_eq!(queue.len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_job_released_when_worker_panics() {
+        let (queue, jobs) = save_queue(10);
+        queue.enqueue(job("1")).await.unwrap();
+
+        let jobs = Arc::new(jobs);
+        let worker = tokio::spawn({
+            let jobs = jobs.clone();
+            async move {
+                let _job = jobs.next().await.unwrap();
+                panic!("save blew up");
+            }
+        });
+        assert!(worker.await.unwrap_err().is_panic());
+
+        queue.enqueue(job("1")).await.unwrap();
+        assert_eq!(queue.len(), 1);
+    }
+}
@@ -0,0 +1,304 @@
+//! One-shot startup check of credentials and connectivity
+//!
+//! Run with `--selftest`: logs each bot account in, fetches a known post
+//! from the public API, and verifies a Readwise token. Checks without the
+//! config they need are skipped rather than failed.
+
+use std::fmt;
+use std::future::Future;
+use std::time::Duration;
+
+use anyhow::{Context, Result};
+
+use crate::bluesky::BlueskyClient;
+use crate::readwise::ReadwiseClient;
+use crate::AppState;
+
+/// Longest a single check may take
+const CHECK_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Result of one check
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CheckOutcome {
+    Passed,
+    Failed(String),
+    /// Not run, with the reason
+    Skipped(String),
+}
+
+/// A named check and how it went
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Check {
+    pub name: &'static str,
+    pub outcome: CheckOutcome,
+}
+
+/// Outcome of every check
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SelftestReport {
+    pub checks: Vec<Check>,
+}
+
+impl SelftestReport {
+    /// Whether no check failed (skipped checks don't count against it)
+    pub fn passed(&self) -> bool {
+        !self
+            .checks
+            .iter()
+            .any(|check| matches!(check.outcome, CheckOutcome::Failed(_)))
+    }
+
+    fn push(&mut self, name: &'static str, outcome: CheckOutcome) {
+        self.checks.push(Check { name, outcome });
+    }
+}
+
+impl fmt::Display for SelftestReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for check in &self.checks {
+            match &check.outcome {
+                CheckOutcome::Passed => writeln!(f, "ok       {}", check.name)?,
+                CheckOutcome::Failed(e) => writeln!(f, "FAILED   {}: {}", check.name, e)?,
+                CheckOutcome::Skipped(why) => writeln!(f, "skipped  {}: {}", check.name, why)?,
+            }
+        }
+        write!(
+            f,
+            "selftest {}",
+            if self.passed() { "passed" } else { "failed" }
+        )
+    }
+}
+
+/// Run every check against the live services in `state`'s config
+pub async fn selftest(state: &AppState) -> SelftestReport {
+    let config = &state.config;
+    let accounts = config.bot_accounts();
+    let bot_login = (!accounts.is_empty()).then_some(async move {
+        let client = state.bluesky_client();
+        for account in &accounts {
+            client
+                .login_with_app_password(&account.handle, &account.password)
+                .await
+                .with_context(|| format!("{} login", account.handle))?;
+        }
+        Ok(())
+    });
+    let public = state.bluesky_client();
+
+    run_checks(
+        bot_login,
+        &public,
+        config.selftest_post_uri.as_deref(),
+        &*state.readwise,
+        config.selftest_readwise_token.as_deref(),
+    )
+    .await
+}
+
+/// Run the checks with the given clients
+///
+/// `bot_login` is None when no bot account is configured.
+async fn run_checks<B, R>(
+    bot_login: Option<impl Future<Output = Result<()>>>,
+    bluesky: &B,
+    post_uri: Option<&str>,
+    readwise: &R,
+    readwise_token: Option<&str>,
+) -> SelftestReport
+where
+    B: BlueskyClient + ?Sized,
+    R: ReadwiseClient + ?Sized,
+{
+    let mut report = SelftestReport::default();
+
+    let outcome = match bot_login {
+        Some(login) => run(login).await,
+        None => CheckOutcome::Skipped("no bot account configured".to_string()),
+    };
+    report.push("bot_login", outcome);
+
+    let outcome = match post_uri {
+        Some(uri) => run(async { bluesky.get_post_thread(uri).await.map(|_| ()) }).await,
+        None => CheckOutcome::Skipped("APP_SELFTEST_POST_URI not set".to_string()),
+    };
+    report.push("public_api", outcome);
+
+    let outcome = match readwise_token {
+        Some(token) => {
+            run(async {
+                match readwise.verify_token(token).await? {
+                    true => Ok(()),
+                    false => Err(anyhow::anyhow!("token rejected")),
+                }
+            })
+            .await
+        }
+        None => CheckOutcome::Skipped("APP_SELFTEST_READWISE_TOKEN not set".to_string()),
+    };
+    report.push("readwise", outcome);
+
+    report
+}
+
+/// Run one check under the timeout
+async fn run(check: impl Future<Output = Result<()>>) -> CheckOutcome {
+    match tokio::time::timeout(CHECK_TIMEOUT, check).await {
+        Ok(Ok(())) => CheckOutcome::Passed,
+        Ok(Err(e)) => CheckOutcome::Failed(e.to_string()),
+        Err(_) => CheckOutcome::Failed("timed out".to_string()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::bluesky::types::*;
+    use crate::readwise::client::{Document, Highlight, ReaderDocument};
+    use anyhow::bail;
+    use async_trait::async_trait;
+
+    /// Public API that can't be reached
+    struct UnreachableBluesky;
+
+    #[async_trait]
+    impl BlueskyClient for UnreachableBluesky {
+        async fn get_bookmarks(&self, _cursor: Option<&str>) -> Result<BookmarkResponse> {
+            bail!("unused")
+        }
+
+        async fn get_post_thread(&self, _uri: &str) -> Result<ThreadResponse> {
+            bail!("connection refused")
+        }
+
+        async fn get_post_record(&self, _did: &str, _rkey: &str) -> Result<PostView> {
+            bail!("unused")
+        }
+
+        async fn send_dm(&self, _convo_id: &str, _text: &str) -> Result<()> {
+            bail!("unused")
+        }
+
+        async fn get_convo_for_member(&self, _did: &str) -> Result<ConvoView> {
+            bail!("unused")
+        }
+
+        async fn list_convos(&self) -> Result<ConvoListResponse> {
+            bail!("unused")
+        }
+
+        async fn get_messages(&self, _convo_id: &str) -> Result<MessagesResponse> {
+            bail!("unused")
+        }
+
+        async fn mark_convo_read(&self, _convo_id: &str) -> Result<()> {
+            bail!("unused")
+        }
+
+        async fn resolve_handle(&self, handle: &str) -> Result<String> {
+            Ok(format!("did:plc:{}", handle))
+        }
+    }
+
+    /// Readwise that accepts only "good-token"
+    struct MockReadwise;
+
+    #[async_trait]
+    impl ReadwiseClient for MockReadwise {
+        async fn save_highlight(
+            &self,
+            _token: &str,
+            _highlight: Highlight,
+        ) -> Result<Option<String>> {
+            bail!("unused")
+        }
+
+        async fn save_document(&self, _token: &str, _document: Document) -> Result<Option<String>> {
+            bail!("unused")
+        }
+
+        async fn delete_document(&self, _token: &str, _id: &str) -> Result<()> {
+            bail!("unused")
+        }
+
+        async fn verify_token(&self, token: &str) -> Result<bool> {
+            Ok(token == "good-token")
+        }
+
+        async fn list_documents(
+            &self,
+            _token: &str,
+            _updated_after: Option<chrono::DateTime<chrono::Utc>>,
+            _location: Option<&str>,
+        ) -> Result<Vec<ReaderDocument>> {
+            bail!("unused")
+        }
+    }
+
+    fn outcome(report: &SelftestReport, name: &str) -> CheckOutcome {
+        report
+            .checks
+            .iter()
+            .find(|check| check.name == name)
+            .map(|check| check.outcome.clone())
+            .unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_unconfigured_checks_are_skipped() {
+        let report = run_checks(
+            None::<std::future::Ready<Result<()>>>,
+            &UnreachableBluesky,
+            None,
+            &MockReadwise,
+            None,
+        )
+        .await;
+
+        assert_eq!(report.checks.len(), 3);
+        assert!(report
+            .checks
+            .iter()
+            .all(|check| matches!(check.outcome, CheckOutcome::Skipped(_))));
+        assert!(report.passed());
+    }
+
+    #[tokio::test]
+    async fn test_any_failure_fails_the_report() {
+        let report = run_checks(
+            Some(async { Ok(()) }),
+            &UnreachableBluesky,
+            Some("at://did:plc:x/app.bsky.feed.post/1"),
+            &MockReadwise,
+            Some("bad-token"),
+        )
+        .await;
+
+        assert_eq!(outcome(&report, "bot_login"), CheckOutcome::Passed);
+        assert_eq!(
+            outcome(&report, "public_api"),
+            CheckOutcome::Failed("connection refused".to_string())
+        );
+        assert_eq!(
+            outcome(&report, "readwise"),
+            CheckOutcome::Failed("token rejected".to_string())
+        );
+        assert!(!report.passed());
+        assert!(report.to_string().ends_with("selftest failed"));
+    }
+
+    #[tokio::test]
+    async fn test_passing_checks_pass_the_report() {
+        let report = run_checks(
+            Some(async { Ok(()) }),
+            &UnreachableBluesky,
+            None,
+            &MockReadwise,
+            Some("good-token"),
+        )
+        .await;
+
+        assert_eq!(outcome(&report, "readwise"), CheckOutcome::Passed);
+        assert!(report.passed());
+    }
+}
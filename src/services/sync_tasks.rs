@@ -0,0 +1,83 @@
+//! Registry of running per-user sync tasks
+//!
+//! Lets other parts of the app (e.g. account deletion, the DM bot) stop or
+//! start a user's background sync loop.
+
+use std::collections::HashMap;
+use std::sync::Mutex;
+
+use anyhow::Result;
+use async_trait::async_trait;
+use tokio::task::AbortHandle;
+use uuid::Uuid;
+
+use crate::db::models::User;
+
+/// Starts a user's bookmark sync loop and registers it in `SyncTasks`
+#[async_trait]
+pub trait SyncStarter: Send + Sync {
+    async fn start(&self, user: &User) -> Result<()>;
+}
+
+/// Tracks the bookmark sync task running for each user
+#[derive(Default)]
+pub struct SyncTasks {
+    tasks: Mutex<HashMap<Uuid, AbortHandle>>,
+}
+
+impl SyncTasks {
+    /// Create an empty registry
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Register a user's sync task, aborting any previous one
+    pub fn register(&self, user_id: Uuid, handle: AbortHandle) {
+        let mut tasks = self.tasks.lock().unwrap_or_else(|e| e.into_inner());
+        if let Some(previous) = tasks.insert(user_id, handle) {
+            previous.abort();
+        }
+        crate::metrics::set_sync_tasks_active(tasks.len());
+    }
+
+    /// Cancel a user's sync task. Returns true if one was running.
+    pub fn cancel(&self, user_id: Uuid) -> bool {
+        let mut tasks = self.tasks.lock().unwrap_or_else(|e| e.into_inner());
+        match tasks.remove(&user_id) {
+            Some(handle) => {
+                handle.abort();
+                crate::metrics::set_sync_tasks_active(tasks.len());
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Check whether a user has a registered sync task
+    pub fn is_running(&self, user_id: Uuid) -> bool {
+        let tasks = self.tasks.lock().unwrap_or_else(|e| e.into_inner());
+        tasks
+            .get(&user_id)
+            .map(|handle| !handle.is_finished())
+            .unwrap_or(false)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_cancel_aborts_task() {
+        let tasks = SyncTasks::new();
+        let user_id = Uuid::new_v4();
+        let handle = tokio::spawn(std::future::pending::<()>());
+
+        tasks.register(user_id, handle.abort_handle());
+        assert!(tasks.is_running(user_id));
+
+        assert!(tasks.cancel(user_id));
+        assert!(handle.await.unwrap_err().is_cancelled());
+        assert!(!tasks.cancel(user_id));
+    }
+}
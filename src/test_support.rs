@@ -0,0 +1,517 @@
+//! Shared test helpers: mock Bluesky and Readwise servers, post fixtures
+//!
+//! The mocks are real axum servers on an ephemeral port, so tests exercise
+//! the HTTP clients end to end. Point a client at `MockServer::url` with
+//! `with_base_url` (or `with_public_url` for Bluesky reads).
+
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+
+use axum::{
+    extract::{Path, Query, State},
+    http::{header, HeaderMap, StatusCode},
+    response::IntoResponse,
+    routing::{self, get},
+    Json, Router,
+};
+use chrono::{DateTime, Utc};
+use serde_json::{json, Value};
+
+use crate::bluesky::types::*;
+
+/// A request body a mock server received
+#[derive(Debug, Clone, PartialEq)]
+pub struct RecordedRequest {
+    pub path: String,
+    pub body: Value,
+}
+
+type Recorder = Arc<Mutex<Vec<RecordedRequest>>>;
+
+/// A running mock server
+pub struct MockServer {
+    pub url: String,
+    requests: Recorder,
+}
+
+impl MockServer {
+    /// Serve `app` on an ephemeral local port
+    async fn start(app: Router, requests: Recorder) -> Self {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+        Self {
+            url: format!("http://{}", addr),
+            requests,
+        }
+    }
+
+    /// Bodies of the requests received at `path`, oldest first
+    pub fn received(&self, path: &str) -> Vec<Value> {
+        self.requests
+            .lock()
+            .unwrap()
+            .iter()
+            .filter(|request| request.path == path)
+            .map(|request| request.body.clone())
+            .collect()
+    }
+}
+
+fn record(requests: &Recorder, path: &str, body: Value) -> usize {
+    let mut requests = requests.lock().unwrap();
+    requests.push(RecordedRequest {
+        path: path.to_string(),
+        body,
+    });
+    requests.len()
+}
+
+/// Canned Bluesky API: bookmarks, post threads, and DM sends
+///
+/// With records added, it also acts as the PLC directory and PDS for their
+/// authors (use its URL for `with_plc_url`).
+#[derive(Default)]
+pub struct MockBluesky {
+    bookmarks: Vec<BookmarkView>,
+    threads: HashMap<String, ThreadViewPost>,
+    /// Served once, before the entry in `threads`
+    partial_threads: HashMap<String, ThreadViewPost>,
+    records: HashMap<String, PostView>,
+    thread_error: Option<StatusCode>,
+}
+
+impl MockBluesky {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Return this post from getBookmarks (and its thread from getPostThread)
+    pub fn with_bookmark(mut self, post: &PostView) -> Self {
+        self.bookmarks.push(BookmarkView {
+            subject: StrongRef {
+                uri: post.uri.clone(),
+                cid: post.cid.clone(),
+            },
+            created_at: post.indexed_at,
+            item: BookmarkItem::Post(Box::new(post.clone())),
+        });
+        self.threads
+            .entry(post.uri.clone())
+            .or_insert_with(|| thread(post.clone()));
+        self
+    }
+
+    /// Return this thread from getPostThread for its post's URI
+    pub fn with_thread(mut self, thread: ThreadViewPost) -> Self {
+        self.threads.insert(thread.post.uri.clone(), thread);
+        self
+    }
+
+    /// Return `partial` from the first getPostThread for its post's URI, then
+    /// the thread added with `with_thread`, as if the AppView caught up
+    pub fn with_partial_thread(mut self, partial: ThreadViewPost) -> Self {
+        self.partial_threads
+            .insert(partial.post.uri.clone(), partial);
+        self
+    }
+
+    /// Serve this post's record from getRecord, and its author's DID document
+    pub fn with_record(mut self, post: &PostView) -> Self {
+        self.records.insert(post.uri.clone(), post.clone());
+        self
+    }
+
+    /// Fail every getPostThread with this status (502 as if the AppView were
+    /// down, 400 as if it refused the thread)
+    pub fn with_thread_error(mut self, status: StatusCode) -> Self {
+        self.thread_error = Some(status);
+        self
+    }
+
+    pub async fn start(self) -> MockServer {
+        let requests = Recorder::default();
+        let bookmarks = json!({ "cursor": null, "bookmarks": self.bookmarks });
+        let threads = Arc::new(self.threads);
+        let partial_threads = Arc::new(Mutex::new(self.partial_threads));
+        let thread_error = self.thread_error;
+        let records = Arc::new(self.records);
+        let authors = records.clone();
+
+        let app = Router::new()
+            .route(
+                "/xrpc/app.bsky.bookmark.getBookmarks",
+                get(move || async move { Json(bookmarks) }),
+            )
+            .route(
+                "/xrpc/app.bsky.feed.getPostThread",
+                get(
+                    move |State(requests): State<Recorder>,
+                          Query(params): Query<HashMap<String, String>>| async move {
+                        let uri = params.get("uri").cloned().unwrap_or_default();
+                        record(
+                            &requests,
+                            "/xrpc/app.bsky.feed.getPostThread",
+                            json!({ "uri": uri }),
+                        );
+                        if let Some(status) = thread_error {
+                            return (status, Json(json!({ "error": "InvalidRequest" })))
+                                .into_response();
+                        }
+                        let partial = partial_threads.lock().unwrap().remove(&uri);
+                        match partial.as_ref().or_else(|| threads.get(&uri)) {
+                            Some(thread) => {
+                                let node = ThreadNode::Post(Box::new(thread.clone()));
+                                Json(json!({ "thread": node })).into_response()
+                            }
+                            None => (
+                                StatusCode::BAD_REQUEST,
+                                Json(json!({ "error": "NotFound", "message": "Post not found" })),
+                            )
+                                .into_response(),
+                        }
+                    },
+                ),
+            )
+            .route(
+                "/xrpc/com.atproto.repo.getRecord",
+                get(
+                    move |Query(params): Query<HashMap<String, String>>| async move {
+                        let uri = format!(
+                            "at://{}/{}/{}",
+                            params["repo"], params["collection"], params["rkey"]
+                        );
+                        match records.get(&uri) {
+                            Some(post) => Json(json!({
+                                "uri": post.uri,
+                                "cid": post.cid,
+                                "value": post.record,
+                            }))
+                            .into_response(),
+                            None => (
+                                StatusCode::BAD_REQUEST,
+                                Json(json!({ "error": "RecordNotFound" })),
+                            )
+                                .into_response(),
+                        }
+                    },
+                ),
+            )
+            .route(
+                "/:did",
+                get(
+                    move |Path(did): Path<String>, headers: HeaderMap| async move {
+                        let Some(author) = authors
+                            .values()
+                            .map(|post| &post.author)
+                            .find(|author| author.did == did)
+                        else {
+                            return StatusCode::NOT_FOUND.into_response();
+                        };
+                        let host = headers[header::HOST].to_str().unwrap_or_default();
+                        Json(json!({
+                            "id": did,
+                            "alsoKnownAs": [format!("at://{}", author.handle)],
+                            "service": [{
+                                "id": "#atproto_pds",
+                                "type": "AtprotoPersonalDataServer",
+                                "serviceEndpoint": format!("http://{}", host),
+                            }],
+                            "verificationMethod": [],
+                        }))
+                        .into_response()
+                    },
+                ),
+            )
+            .route(
+                "/xrpc/chat.bsky.convo.sendMessage",
+                routing::post(
+                    |State(requests): State<Recorder>, Json(body): Json<Value>| async move {
+                        let n = record(&requests, "/xrpc/chat.bsky.convo.sendMessage", body);
+                        Json(json!({ "id": format!("msg-{}", n) }))
+                    },
+                ),
+            )
+            .with_state(requests.clone());
+
+        MockServer::start(app, requests).await
+    }
+}
+
+/// Canned Readwise API: highlight and Reader saves succeed unless `failing`
+pub struct MockReadwise;
+
+impl MockReadwise {
+    pub async fn start() -> MockServer {
+        let requests = Recorder::default();
+        let app = Router::new()
+            .route(
+                "/v2/highlights/",
+                routing::post(
+                    |State(requests): State<Recorder>, Json(body): Json<Value>| async move {
+                        let n = record(&requests, "/v2/highlights/", body);
+                        Json(json!([{ "id": 1, "modified_highlights": [n] }]))
+                    },
+                ),
+            )
+            .route(
+                "/v3/save/",
+                routing::post(
+                    |State(requests): State<Recorder>, Json(body): Json<Value>| async move {
+                        let url = body["url"].clone();
+                        let n = record(&requests, "/v3/save/", body);
+                        (
+                            StatusCode::CREATED,
+                            Json(json!({ "id": format!("doc-{}", n), "url": url })),
+                        )
+                    },
+                ),
+            )
+            .route(
+                "/v2/auth/",
+                get(
+                    |State(requests): State<Recorder>, headers: HeaderMap| async move {
+                        let request_id = headers
+                            .get("x-request-id")
+                            .and_then(|value| value.to_str().ok());
+                        record(&requests, "/v2/auth/", json!({ "request_id": request_id }));
+                        StatusCode::NO_CONTENT
+                    },
+                ),
+            )
+            .with_state(requests.clone());
+
+        MockServer::start(app, requests).await
+    }
+
+    /// A Readwise API that answers every request with `status`
+    pub async fn failing(status: StatusCode) -> MockServer {
+        let requests = Recorder::default();
+        let app = Router::new()
+            .fallback(move || async move { (status, Json(json!({ "detail": "Mock failure" }))) });
+
+        MockServer::start(app, requests).await
+    }
+}
+
+/// DPoP nonce the mock authorization server requires
+pub const AUTH_SERVER_NONCE: &str = "server-nonce-1";
+
+/// Authorization server whose `/token` endpoint demands `AUTH_SERVER_NONCE`
+///
+/// Records `{"nonce": …}` from each request's DPoP proof.
+pub struct MockAuthServer;
+
+impl MockAuthServer {
+    pub async fn start() -> MockServer {
+        let requests = Recorder::default();
+        let app = Router::new()
+            .route(
+                "/token",
+                routing::post(
+                    |State(requests): State<Recorder>, headers: HeaderMap| async move {
+                        let nonce = headers
+                            .get("DPoP")
+                            .and_then(|proof| proof.to_str().ok())
+                            .and_then(dpop_nonce);
+                        let accepted = nonce.as_deref() == Some(AUTH_SERVER_NONCE);
+                        record(&requests, "/token", json!({ "nonce": nonce }));
+
+                        let (status, body) = if accepted {
+                            (StatusCode::OK, json!({ "ok": true }))
+                        } else {
+                            (
+                                StatusCode::BAD_REQUEST,
+                                json!({ "error": "use_dpop_nonce" }),
+                            )
+                        };
+                        (status, [("DPoP-Nonce", AUTH_SERVER_NONCE)], Json(body))
+                    },
+                ),
+            )
+            .with_state(requests.clone());
+
+        MockServer::start(app, requests).await
+    }
+}
+
+/// The `nonce` claim of a DPoP proof JWT
+fn dpop_nonce(proof: &str) -> Option<String> {
+    use base64::Engine;
+
+    let claims = proof.split('.').nth(1)?;
+    let claims = base64::engine::general_purpose::URL_SAFE_NO_PAD
+        .decode(claims)
+        .ok()?;
+    let claims: Value = serde_json::from_slice(&claims).ok()?;
+    claims["nonce"].as_str().map(str::to_string)
+}
+
+/// Builder for `PostView` fixtures
+pub struct PostBuilder {
+    post: PostView,
+}
+
+/// A post with record key `rkey` by `author.bsky.social`
+pub fn post(rkey: &str) -> PostBuilder {
+    let at: DateTime<Utc> = "2025-01-01T00:00:00Z".parse().unwrap();
+    PostBuilder {
+        post: PostView {
+            uri: format!("at://did:plc:author/app.bsky.feed.post/{}", rkey),
+            cid: format!("bafy{}", rkey),
+            author: Author {
+                did: "did:plc:author".to_string(),
+                handle: "author.bsky.social".to_string(),
+                display_name: None,
+                labels: Vec::new(),
+            },
+            record: PostRecord {
+                text: "A post worth keeping".to_string(),
+                created_at: at,
+                reply: None,
+                facets: None,
+                embed: None,
+            },
+            indexed_at: at,
+            labels: Vec::new(),
+            like_count: None,
+            repost_count: None,
+            reply_count: None,
+        },
+    }
+}
+
+impl PostBuilder {
+    pub fn text(mut self, text: &str) -> Self {
+        self.post.record.text = text.to_string();
+        self
+    }
+
+    /// Written by this account (the URI moves to its DID)
+    pub fn author(mut self, did: &str, handle: &str) -> Self {
+        let rkey = self
+            .post
+            .uri
+            .rsplit('/')
+            .next()
+            .unwrap_or_default()
+            .to_string();
+        self.post.uri = format!("at://{}/app.bsky.feed.post/{}", did, rkey);
+        self.post.author = Author {
+            did: did.to_string(),
+            handle: handle.to_string(),
+            display_name: None,
+            labels: Vec::new(),
+        };
+        self
+    }
+
+    pub fn embed(mut self, embed: Embed) -> Self {
+        self.post.record.embed = Some(embed);
+        self
+    }
+
+    /// A reply to `parent` (and to `parent`'s root, if it's a reply itself)
+    pub fn reply_to(mut self, parent: &PostView) -> Self {
+        let parent_ref = StrongRef {
+            uri: parent.uri.clone(),
+            cid: parent.cid.clone(),
+        };
+        let root = match &parent.record.reply {
+            Some(reply) => reply.root.clone(),
+            None => parent_ref.clone(),
+        };
+        self.post.record.reply = Some(ReplyRef {
+            root,
+            parent: parent_ref,
+        });
+        self
+    }
+
+    pub fn build(self) -> PostView {
+        self.post
+    }
+}
+
+/// A thread view of a single post
+pub fn thread(post: PostView) -> ThreadViewPost {
+    ThreadViewPost {
+        post,
+        parent: None,
+        replies: None,
+    }
+}
+
+/// A thread view of `post` under `parent`
+pub fn thread_under(post: PostView, parent: ThreadViewPost) -> ThreadViewPost {
+    ThreadViewPost {
+        post,
+        parent: Some(Box::new(parent.into())),
+        replies: None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::bluesky::{BlueskyClient, HttpBlueskyClient};
+    use crate::readwise::client::HttpReadwiseClient;
+    use crate::services::processor::{OutcomeKind, PostProcessor, ProcessOptions};
+
+    #[tokio::test]
+    async fn test_bookmarked_post_saved_through_http_clients() {
+        let bookmarked = post("3kabc").text("Saved end to end").build();
+        let bluesky = MockBluesky::new().with_bookmark(&bookmarked).start().await;
+        let readwise = MockReadwise::start().await;
+        let client = HttpBlueskyClient::new()
+            .authenticated("access".to_string(), "did:plc:reader".to_string())
+            .with_base_url(&bluesky.url)
+            .with_public_url(&bluesky.url);
+
+        let bookmarks = client.get_bookmarks(None).await.unwrap();
+        assert_eq!(bookmarks.bookmarks[0].subject.uri, bookmarked.uri);
+
+        let processor = PostProcessor::new(
+            client,
+            HttpReadwiseClient::new().with_base_url(&readwise.url),
+        );
+        let outcome = processor
+            .process_post(&bookmarked.uri, "rw-token", ProcessOptions::default())
+            .await
+            .unwrap();
+
+        assert_eq!(outcome.kind, OutcomeKind::Highlight);
+        assert_eq!(outcome.readwise_id.as_deref(), Some("1"));
+        let saved = readwise.received("/v2/highlights/");
+        assert_eq!(saved[0]["highlights"][0]["text"], "Saved end to end");
+    }
+
+    #[tokio::test]
+    async fn test_threads_and_dms_served() {
+        let root = post("root").text("First").build();
+        let reply = post("reply").text("Second").reply_to(&root).build();
+        let bluesky = MockBluesky::new()
+            .with_thread(thread_under(reply.clone(), thread(root)))
+            .start()
+            .await;
+        let client = HttpBlueskyClient::new()
+            .authenticated("access".to_string(), "did:plc:bot".to_string())
+            .with_base_url(&bluesky.url)
+            .with_public_url(&bluesky.url);
+
+        let fetched = client.get_post_thread(&reply.uri).await.unwrap();
+        assert_eq!(
+            fetched.thread.parent_post().unwrap().post.record.text,
+            "First"
+        );
+        assert!(client
+            .get_post_thread("at://did:plc:x/app.bsky.feed.post/gone")
+            .await
+            .is_err());
+
+        client.send_dm("convo-1", "Saved!").await.unwrap();
+        let sent = bluesky.received("/xrpc/chat.bsky.convo.sendMessage");
+        assert_eq!(sent[0]["convoId"], "convo-1");
+        assert_eq!(sent[0]["message"]["text"], "Saved!");
+    }
+}
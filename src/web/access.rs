@@ -0,0 +1,243 @@
+//! Operator access guard for `/metrics` and `/admin`
+//!
+//! When basic-auth credentials or an IP allowlist are configured, requests
+//! under the protected path prefixes must come from an allowed address or
+//! carry the credentials. Unknown clients get 401 (with a basic-auth
+//! challenge) when credentials are configured, 403 otherwise.
+//!
+//! The client address is the TCP peer, so behind a reverse proxy the
+//! allowlist sees the proxy; use basic auth there instead.
+
+use std::net::{IpAddr, SocketAddr};
+use std::sync::Arc;
+
+use anyhow::{anyhow, bail, Context, Result};
+use axum::{
+    extract::{ConnectInfo, Request, State},
+    http::{header, StatusCode},
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+use base64::{engine::general_purpose::STANDARD, Engine};
+
+use crate::config::Config;
+
+/// An allowed address or CIDR range
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct IpRange {
+    network: IpAddr,
+    prefix: u8,
+}
+
+impl IpRange {
+    /// Parse `10.0.0.1`, `10.0.0.0/8`, or the IPv6 equivalents
+    fn parse(s: &str) -> Result<Self> {
+        let (addr, prefix) = match s.split_once('/') {
+            Some((addr, prefix)) => (addr, Some(prefix)),
+            None => (s, None),
+        };
+        let network: IpAddr = addr
+            .parse()
+            .with_context(|| format!("Invalid IP address: {}", s))?;
+        let max = if network.is_ipv4() { 32 } else { 128 };
+        let prefix = match prefix {
+            Some(prefix) => prefix
+                .parse::<u8>()
+                .ok()
+                .filter(|prefix| *prefix <= max)
+                .ok_or_else(|| anyhow!("Invalid prefix length: {}", s))?,
+            None => max,
+        };
+        Ok(Self { network, prefix })
+    }
+
+    fn contains(&self, ip: IpAddr) -> bool {
+        // Treat IPv4-mapped IPv6 peers (dual-stack listeners) as IPv4
+        let ip = match ip {
+            IpAddr::V6(v6) => v6.to_ipv4_mapped().map_or(ip, IpAddr::V4),
+            ip => ip,
+        };
+        let (network, ip, bits) = match (self.network, ip) {
+            (IpAddr::V4(n), IpAddr::V4(i)) => (u32::from(n).into(), u32::from(i).into(), 32),
+            (IpAddr::V6(n), IpAddr::V6(i)) => (u128::from(n), u128::from(i), 128),
+            _ => return false,
+        };
+        let shift = bits - u32::from(self.prefix);
+        shift == bits || (network ^ ip) >> shift == 0
+    }
+}
+
+/// Parse a comma-separated IP/CIDR allowlist
+fn parse_allowlist(list: &str) -> Result<Vec<IpRange>> {
+    list.split(',')
+        .map(str::trim)
+        .filter(|entry| !entry.is_empty())
+        .map(IpRange::parse)
+        .collect()
+}
+
+/// Check the protection settings, for config validation
+pub fn validate(basic_auth: Option<&str>, allowed_ips: Option<&str>) -> Result<()> {
+    if let Some(credentials) = basic_auth {
+        match credentials.split_once(':') {
+            Some((user, password)) if !user.is_empty() && !password.is_empty() => {}
+            _ => bail!("protected_basic_auth must be \"user:password\""),
+        }
+    }
+    if let Some(list) = allowed_ips {
+        parse_allowlist(list).context("protected_allowed_ips")?;
+    }
+    Ok(())
+}
+
+/// Which paths are protected and who may use them
+#[derive(Debug, Clone)]
+pub struct AccessGuard {
+    prefixes: Vec<String>,
+    /// Expected `Authorization` header value
+    authorization: Option<String>,
+    allowed: Vec<IpRange>,
+}
+
+impl AccessGuard {
+    /// Guard from config, or None (no protection) if neither basic auth
+    /// nor an allowlist is configured
+    ///
+    /// Expects a validated config; unparsable allowlist entries are skipped.
+    pub fn from_config(config: &Config) -> Option<Self> {
+        let allowed: Vec<IpRange> = config
+            .protected_allowed_ips
+            .as_deref()
+            .unwrap_or_default()
+            .split(',')
+            .map(str::trim)
+            .filter(|entry| !entry.is_empty())
+            .filter_map(|entry| IpRange::parse(entry).ok())
+            .collect();
+        let authorization = config
+            .protected_basic_auth
+            .as_deref()
+            .filter(|credentials| !credentials.is_empty())
+            .map(|credentials| format!("Basic {}", STANDARD.encode(credentials)));
+        if authorization.is_none() && allowed.is_empty() {
+            return None;
+        }
+
+        let prefixes = config
+            .protected_paths
+            .split(',')
+            .map(|prefix| prefix.trim().trim_end_matches('/').to_string())
+            .filter(|prefix| !prefix.is_empty())
+            .collect();
+        Some(Self {
+            prefixes,
+            authorization,
+            allowed,
+        })
+    }
+
+    fn protects(&self, path: &str) -> bool {
+        self.prefixes.iter().any(|prefix| {
+            path.strip_prefix(prefix.as_str())
+                .is_some_and(|rest| rest.is_empty() || rest.starts_with('/'))
+        })
+    }
+
+    fn ip_allowed(&self, ip: Option<IpAddr>) -> bool {
+        ip.is_some_and(|ip| self.allowed.iter().any(|range| range.contains(ip)))
+    }
+
+    fn credentials_valid(&self, provided: Option<&str>) -> bool {
+        match (&self.authorization, provided) {
+            (Some(expected), Some(provided)) => {
+                constant_time_eq(expected.as_bytes(), provided.as_bytes())
+            }
+            _ => false,
+        }
+    }
+}
+
+/// Compare without leaking where the first difference is
+fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    a.len() == b.len() && a.iter().zip(b).fold(0, |acc, (x, y)| acc | (x ^ y)) == 0
+}
+
+/// Middleware rejecting unauthorized requests to protected paths
+pub async fn require_access(
+    State(guard): State<Arc<AccessGuard>>,
+    request: Request,
+    next: Next,
+) -> Response {
+    if !guard.protects(request.uri().path()) {
+        return next.run(request).await;
+    }
+
+    let ip = request
+        .extensions()
+        .get::<ConnectInfo<SocketAddr>>()
+        .map(|ConnectInfo(addr)| addr.ip());
+    let provided = request
+        .headers()
+        .get(header::AUTHORIZATION)
+        .and_then(|value| value.to_str().ok());
+    if guard.ip_allowed(ip) || guard.credentials_valid(provided) {
+        return next.run(request).await;
+    }
+
+    tracing::warn!(
+        "Rejected {} from {:?} (protected path)",
+        request.uri().path(),
+        ip
+    );
+    if guard.authorization.is_some() {
+        (
+            StatusCode::UNAUTHORIZED,
+            [(header::WWW_AUTHENTICATE, r#"Basic realm="operator""#)],
+            "Unauthorized",
+        )
+            .into_response()
+    } else {
+        (StatusCode::FORBIDDEN, "Forbidden").into_response()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_allowlist_matches_addresses_and_ranges() {
+        let allowed = parse_allowlist("127.0.0.1, 10.0.0.0/8, fd00::/8").unwrap();
+        let ok = |ip: &str| allowed.iter().any(|r| r.contains(ip.parse().unwrap()));
+
+        assert!(ok("127.0.0.1"));
+        assert!(ok("10.20.30.40"));
+        assert!(ok("::ffff:10.1.2.3"));
+        assert!(ok("fd12::1"));
+        assert!(!ok("127.0.0.2"));
+        assert!(!ok("11.0.0.1"));
+        assert!(!ok("fe80::1"));
+        assert!(parse_allowlist("0.0.0.0/0").unwrap()[0].contains("8.8.8.8".parse().unwrap()));
+
+        assert!(parse_allowlist("10.0.0.0/33").is_err());
+        assert!(parse_allowlist("localhost").is_err());
+        assert!(validate(Some("admin"), None).is_err());
+        assert!(validate(Some("admin:secret"), Some("10.0.0.0/8")).is_ok());
+    }
+
+    #[test]
+    fn test_only_prefixed_paths_protected() {
+        let guard = AccessGuard::from_config(&Config {
+            protected_basic_auth: Some("ops:secret".to_string()),
+            ..Config::test_default()
+        })
+        .unwrap();
+
+        assert!(guard.protects("/metrics"));
+        assert!(guard.protects("/admin/users"));
+        assert!(!guard.protects("/administrator"));
+        assert!(!guard.protects("/dashboard"));
+
+        assert!(AccessGuard::from_config(&Config::test_default()).is_none());
+    }
+}
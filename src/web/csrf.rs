@@ -0,0 +1,176 @@
+//! CSRF protection for state-changing requests
+//!
+//! Each session gets a random token that rendered forms embed as a hidden
+//! field. POST requests must echo it back (form field or header) or are
+//! rejected with 403.
+
+use axum::{
+    body::{to_bytes, Body},
+    extract::Request,
+    http::{Method, StatusCode},
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+use tower_sessions::Session;
+use uuid::Uuid;
+
+/// Session key holding the CSRF token
+const CSRF_SESSION_KEY: &str = "csrf_token";
+
+/// Form field carrying the CSRF token
+pub const CSRF_FIELD: &str = "csrf_token";
+
+/// Header carrying the CSRF token (for non-form clients)
+pub const CSRF_HEADER: &str = "x-csrf-token";
+
+/// Largest form body we'll buffer to look for the token
+const MAX_FORM_BYTES: usize = 64 * 1024;
+
+/// Get the session's CSRF token, creating one if needed
+pub async fn csrf_token(session: &Session) -> Result<String, tower_sessions::session::Error> {
+    if let Some(token) = session.get::<String>(CSRF_SESSION_KEY).await? {
+        return Ok(token);
+    }
+
+    let token = Uuid::new_v4().simple().to_string();
+    session.insert(CSRF_SESSION_KEY, &token).await?;
+    Ok(token)
+}
+
+/// Hidden form input carrying the session's CSRF token
+pub async fn csrf_field(session: &Session) -> String {
+    let token = match csrf_token(session).await {
+        Ok(token) => token,
+        Err(e) => {
+            tracing::error!("Failed to issue CSRF token: {}", e);
+            String::new()
+        }
+    };
+    format!(
+        r#"<input type="hidden" name="{}" value="{}">"#,
+        CSRF_FIELD, token
+    )
+}
+
+/// Middleware rejecting POST requests without a valid CSRF token
+pub async fn verify_csrf(session: Session, request: Request, next: Next) -> Response {
+    if request.method() != Method::POST {
+        return next.run(request).await;
+    }
+
+    let expected = session.get::<String>(CSRF_SESSION_KEY).await.ok().flatten();
+
+    let (parts, body) = request.into_parts();
+    let bytes = match to_bytes(body, MAX_FORM_BYTES).await {
+        Ok(bytes) => bytes,
+        Err(_) => return (StatusCode::PAYLOAD_TOO_LARGE, "Request too large").into_response(),
+    };
+
+    let provided = parts
+        .headers
+        .get(CSRF_HEADER)
+        .and_then(|value| value.to_str().ok())
+        .map(str::to_string)
+        .or_else(|| {
+            url::form_urlencoded::parse(&bytes)
+                .find(|(key, _)| key == CSRF_FIELD)
+                .map(|(_, value)| value.into_owned())
+        });
+
+    match (expected, provided) {
+        (Some(expected), Some(provided)) if tokens_match(&expected, &provided) => {
+            next.run(Request::from_parts(parts, Body::from(bytes)))
+                .await
+        }
+        _ => {
+            tracing::warn!("Rejected {} without a valid CSRF token", parts.uri.path());
+            (StatusCode::FORBIDDEN, "Invalid CSRF token").into_response()
+        }
+    }
+}
+
+/// Constant-time token comparison
+fn tokens_match(a: &str, b: &str) -> bool {
+    a.len() == b.len()
+        && a.bytes()
+            .zip(b.bytes())
+            .fold(0u8, |acc, (x, y)| acc | (x ^ y))
+            == 0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::http::header;
+    use axum::{middleware, routing::get, Router};
+    use tower::ServiceExt;
+    use tower_sessions::{MemoryStore, SessionManagerLayer};
+
+    fn app() -> Router {
+        Router::new()
+            .route(
+                "/",
+                get(|session: Session| async move { csrf_token(&session).await.unwrap() })
+                    .post(|| async { "ok" }),
+            )
+            .layer(middleware::from_fn(verify_csrf))
+            .layer(SessionManagerLayer::new(MemoryStore::default()).with_secure(false))
+    }
+
+    /// Fetch a token and the session cookie that goes with it
+    async fn issue_token(app: &Router) -> (String, String) {
+        let response = app
+            .clone()
+            .oneshot(Request::get("/").body(Body::empty()).unwrap())
+            .await
+            .unwrap();
+        let cookie = response.headers()[header::SET_COOKIE]
+            .to_str()
+            .unwrap()
+            .split(';')
+            .next()
+            .unwrap()
+            .to_string();
+        let body = to_bytes(response.into_body(), 1024).await.unwrap();
+        (String::from_utf8(body.to_vec()).unwrap(), cookie)
+    }
+
+    fn post(cookie: &str, body: String) -> Request {
+        Request::post("/")
+            .header(header::COOKIE, cookie)
+            .header(header::CONTENT_TYPE, "application/x-www-form-urlencoded")
+            .body(Body::from(body))
+            .unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_valid_token_passes() {
+        let app = app();
+        let (token, cookie) = issue_token(&app).await;
+
+        let response = app
+            .oneshot(post(&cookie, format!("{}={}", CSRF_FIELD, token)))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+    }
+
+    #[tokio::test]
+    async fn test_missing_or_wrong_token_rejected() {
+        let app = app();
+        let (_, cookie) = issue_token(&app).await;
+
+        let missing = app
+            .clone()
+            .oneshot(post(&cookie, String::new()))
+            .await
+            .unwrap();
+        assert_eq!(missing.status(), StatusCode::FORBIDDEN);
+
+        let wrong = app
+            .oneshot(post(&cookie, format!("{}=wrong", CSRF_FIELD)))
+            .await
+            .unwrap();
+        assert_eq!(wrong.status(), StatusCode::FORBIDDEN);
+    }
+}
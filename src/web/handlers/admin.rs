@@ -0,0 +1,206 @@
+//! Operator routes
+//!
+//! Only DIDs listed in `admin_did` may use these; everyone else gets 403.
+
+use std::sync::Arc;
+
+use axum::{extract::State, Json};
+use serde::Deserialize;
+use tower_sessions::Session;
+
+use crate::db::models::UserWithStats;
+use crate::features::{Feature, Features};
+use crate::web::error::ApiError;
+use crate::web::session::current_did;
+use crate::AppState;
+
+/// Fail unless the session belongs to a configured admin
+async fn require_admin(state: &AppState, session: &Session) -> Result<(), ApiError> {
+    let did = current_did(session).await.ok_or(ApiError::Unauthorized)?;
+    if !state.config.is_admin(&did) {
+        tracing::warn!("Non-admin {} tried an admin route", did);
+        return Err(ApiError::Forbidden);
+    }
+    Ok(())
+}
+
+/// Every user with sync status and activity totals
+pub async fn list_users(
+    State(state): State<Arc<AppState>>,
+    session: Session,
+) -> Result<Json<Vec<UserWithStats>>, ApiError> {
+    require_admin(&state, &session).await?;
+
+    let users = state.db.list_users_with_stats().await.map_err(|e| {
+        tracing::error!("Failed to list users: {}", e);
+        ApiError::Internal("Failed to list users".to_string())
+    })?;
+    Ok(Json(users))
+}
+
+/// Current feature flags, after overrides
+pub async fn features(
+    State(state): State<Arc<AppState>>,
+    session: Session,
+) -> Result<Json<Features>, ApiError> {
+    require_admin(&state, &session).await?;
+    Ok(Json(state.features.current().await))
+}
+
+/// Body of `POST /admin/features`
+#[derive(Debug, Deserialize)]
+pub struct SetFeatureRequest {
+    pub name: String,
+    pub enabled: bool,
+}
+
+/// Turn a feature on or off for everyone, returning the resulting flags
+pub async fn set_feature(
+    State(state): State<Arc<AppState>>,
+    session: Session,
+    Json(request): Json<SetFeatureRequest>,
+) -> Result<Json<Features>, ApiError> {
+    require_admin(&state, &session).await?;
+
+    let feature = Feature::from_name(&request.name)
+        .ok_or_else(|| ApiError::BadRequest(format!("Unknown feature: {}", request.name)))?;
+    if state.features.overridden(feature) {
+        return Err(ApiError::BadRequest(format!(
+            "{} is pinned by feature_overrides",
+            feature.name()
+        )));
+    }
+    if feature == Feature::DetectLanguage && request.enabled && !crate::content::language::AVAILABLE
+    {
+        return Err(ApiError::BadRequest(
+            "detect_language needs a build with the language-detection feature".to_string(),
+        ));
+    }
+
+    state
+        .features
+        .set(feature, request.enabled)
+        .await
+        .map_err(|e| {
+            tracing::error!("Failed to set feature {}: {}", feature.name(), e);
+            ApiError::Internal("Failed to set feature".to_string())
+        })?;
+    tracing::info!("Feature {} set to {}", feature.name(), request.enabled);
+    Ok(Json(state.features.current().await))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::Config;
+    use crate::crypto::EncryptionKey;
+    use crate::db::queries::Database;
+    use crate::web::session::DID_KEY;
+    use axum::body::to_bytes;
+    use axum::http::StatusCode;
+    use axum::response::IntoResponse;
+    use sqlx::PgPool;
+    use tower_sessions::MemoryStore;
+
+    fn admin_state(db: Database) -> Arc<AppState> {
+        Arc::new(AppState {
+            config: Config {
+                admin_did: Some("did:plc:other, did:plc:admin".to_string()),
+                ..Config::test_default()
+            },
+            ..AppState::test(db)
+        })
+    }
+
+    async fn session_for(did: &str) -> Session {
+        let session = Session::new(None, Arc::new(MemoryStore::default()), None);
+        session.insert(DID_KEY, did).await.unwrap();
+        session
+    }
+
+    #[sqlx::test]
+    async fn test_admin_sees_users_without_tokens(pool: PgPool) {
+        let db = Database::new(pool, EncryptionKey::test_key());
+        let user = db
+            .create_user("did:plc:reader", "reader.bsky.social")
+            .await
+            .unwrap();
+        db.create_user_settings(user.id, "rw-secret-token", true, false)
+            .await
+            .unwrap();
+        db.mark_bookmark_processed(user.id, "at://did:plc:x/app.bsky.feed.post/1")
+            .await
+            .unwrap();
+        db.create_user("did:plc:idle", "idle.bsky.social")
+            .await
+            .unwrap();
+
+        let response = list_users(State(admin_state(db)), session_for("did:plc:admin").await)
+            .await
+            .into_response();
+
+        assert_eq!(response.status(), StatusCode::OK);
+        let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        assert!(!String::from_utf8_lossy(&body).contains("rw-secret-token"));
+        let users: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(users[0]["bluesky_handle"], "reader.bsky.social");
+        assert_eq!(users[0]["bookmark_sync_enabled"], true);
+        assert_eq!(users[0]["bookmarks_processed"], 1);
+        assert!(users[0]["last_activity_at"].is_string());
+        assert_eq!(users[1]["bookmark_sync_enabled"], serde_json::Value::Null);
+        assert_eq!(users[1]["bookmarks_processed"], 0);
+    }
+
+    #[sqlx::test]
+    async fn test_non_admin_is_forbidden(pool: PgPool) {
+        let state = admin_state(Database::new(pool, EncryptionKey::test_key()));
+
+        let response = list_users(State(state.clone()), session_for("did:plc:reader").await)
+            .await
+            .into_response();
+        assert_eq!(response.status(), StatusCode::FORBIDDEN);
+
+        let logged_out = Session::new(None, Arc::new(MemoryStore::default()), None);
+        let response = list_users(State(state), logged_out).await.into_response();
+        assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+    }
+
+    #[sqlx::test]
+    async fn test_admin_flips_feature(pool: PgPool) {
+        let state = admin_state(Database::new(pool, EncryptionKey::test_key()));
+        let request = |name: &str, enabled| {
+            Json(SetFeatureRequest {
+                name: name.to_string(),
+                enabled,
+            })
+        };
+
+        let Json(features) = set_feature(
+            State(state.clone()),
+            session_for("did:plc:admin").await,
+            request("expand_quotes", false),
+        )
+        .await
+        .unwrap();
+        assert!(!features.expand_quotes);
+        assert!(!state.features.enabled(Feature::ExpandQuotes).await);
+
+        let response = set_feature(
+            State(state.clone()),
+            session_for("did:plc:admin").await,
+            request("sparkles", true),
+        )
+        .await
+        .into_response();
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+
+        let response = set_feature(
+            State(state),
+            session_for("did:plc:reader").await,
+            request("digests", false),
+        )
+        .await
+        .into_response();
+        assert_eq!(response.status(), StatusCode::FORBIDDEN);
+    }
+}
@@ -3,47 +3,1331 @@
 use std::sync::Arc;
 
 use axum::{
-    extract::State,
-    http::StatusCode,
+    extract::{Query, State},
+    http::{header, HeaderMap, HeaderValue, StatusCode},
     response::{IntoResponse, Redirect, Response},
-    Form,
+    Form, Json,
 };
-use serde::Deserialize;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use tower_sessions::Session;
+use uuid::Uuid;
 
+use crate::bluesky::{parse_at_uri, parse_post_reference};
+use crate::db::models::{ProcessedBookmark, SaveEvent, SettingsUpdate, UserSettings};
+use crate::db::pagination::{decode_cursor, Page, PageRequest};
+use crate::readwise::client::{is_highlight_category, HIGHLIGHT_CATEGORIES};
+use crate::services::author_filter::parse_author_list;
+use crate::services::post_class::parse_post_class_rules;
+use crate::services::processor::{ProcessError, ProcessOutcome, SavePayload};
+use crate::services::readwise_token::{self, TokenRotation};
+use crate::web::error::ApiError;
+use crate::web::session::current_user_id;
 use crate::AppState;
 
 /// Form data for updating settings
-#[derive(Debug, Deserialize)]
+///
+/// Fields left out of the form keep their current value; a blank text field
+/// clears it. A blank Readwise token keeps the stored one. Browsers leave
+/// unchecked boxes out, so the dashboard sets `full_form` to have those
+/// read as off.
+#[derive(Debug, Default, Deserialize)]
 pub struct SettingsForm {
-    pub readwise_token: String,
-    #[serde(default)]
-    pub bookmark_sync: bool,
+    /// Every field was submitted; missing checkboxes are unchecked
     #[serde(default)]
-    pub extract_links: bool,
+    pub full_form: bool,
+    /// Required the first time settings are saved
+    pub readwise_token: Option<String>,
+    pub bookmark_sync: Option<bool>,
+    pub extract_links: Option<bool>,
+    /// Comma- or newline-separated handles/DIDs to save from (empty allows all)
+    pub author_allowlist: Option<String>,
+    /// Comma- or newline-separated handles/DIDs never to save from
+    pub author_denylist: Option<String>,
+    /// Skip posts shorter than this many characters (blank for no minimum)
+    pub min_post_length: Option<String>,
+    /// Seconds between bookmark polls (blank for the server default)
+    pub poll_interval_secs: Option<String>,
+    /// Readwise category for saved highlights (blank for the server default)
+    pub highlight_category: Option<String>,
+    pub save_image_alt_text: Option<bool>,
+    pub include_engagement: Option<bool>,
+    pub daily_digest: Option<bool>,
+    /// IANA timezone for saved post times and the digest, e.g. `Europe/Berlin` (blank for UTC)
+    pub timezone: Option<String>,
+    /// Comma- or newline-separated `class=target[:category]` save overrides
+    pub post_class_rules: Option<String>,
+    pub skip_labeled: Option<bool>,
+    /// Comma- or whitespace-separated labels to skip (blank for adult/graphic content)
+    pub skip_labels: Option<String>,
+}
+
+impl SettingsForm {
+    /// Read checkboxes missing from a full form as unchecked
+    fn fill_unchecked(&mut self) {
+        if !self.full_form {
+            return;
+        }
+        for checkbox in [
+            &mut self.bookmark_sync,
+            &mut self.extract_links,
+            &mut self.save_image_alt_text,
+            &mut self.include_engagement,
+            &mut self.daily_digest,
+            &mut self.skip_labeled,
+        ] {
+            checkbox.get_or_insert(false);
+        }
+    }
 }
 
 /// Update user settings
+///
+/// Only the fields present in the form change, and the bookmark cursor is
+/// never touched. The dashboard pre-fills every field with the saved value.
 pub async fn update_settings(
-    State(_state): State<Arc<AppState>>,
-    Form(form): Form<SettingsForm>,
-) -> Response {
-    // TODO: Get user from session
-    // TODO: Validate Readwise token by making a test API call
-    // TODO: Update settings in database
+    State(state): State<Arc<AppState>>,
+    session: Session,
+    Form(mut form): Form<SettingsForm>,
+) -> Result<Redirect, ApiError> {
+    let user_id = current_user_id(&session)
+        .await
+        .ok_or(ApiError::Unauthorized)?;
+    form.fill_unchecked();
 
     tracing::info!(
-        "Settings update requested: bookmark_sync={}, extract_links={}",
+        "Settings update requested: bookmark_sync={:?}, extract_links={:?}",
         form.bookmark_sync,
         form.extract_links
     );
 
-    // Validate that token is not empty
-    if form.readwise_token.trim().is_empty() {
-        return (StatusCode::BAD_REQUEST, "Readwise token is required").into_response();
+    let readwise_token = form
+        .readwise_token
+        .as_deref()
+        .map(str::trim)
+        .filter(|token| !token.is_empty());
+
+    let min_post_length = match form.min_post_length.as_deref().map(str::trim) {
+        None => None,
+        Some("") => Some(0),
+        Some(value) => Some(value.parse::<u16>().map_err(|_| {
+            ApiError::BadRequest("Minimum post length must be a whole number".to_string())
+        })?),
+    };
+
+    let poll_interval_secs = match form.poll_interval_secs.as_deref().map(str::trim) {
+        None => None,
+        Some("") => Some(None),
+        Some(value) => Some(Some(value.parse::<u16>().map_err(|_| {
+            ApiError::BadRequest("Poll interval must be a whole number of seconds".to_string())
+        })?)),
+    };
+
+    let highlight_category = match form.highlight_category.as_deref().map(str::trim) {
+        None => None,
+        Some("") => Some(None),
+        Some(category) if is_highlight_category(category) => Some(Some(category)),
+        Some(category) => {
+            return Err(ApiError::BadRequest(format!(
+                "Highlight category must be one of {} (got {})",
+                HIGHLIGHT_CATEGORIES.join(", "),
+                category
+            )))
+        }
+    };
+
+    let timezone = match form.timezone.as_deref().map(str::trim) {
+        None => None,
+        Some("") => Some(None),
+        Some(timezone) if timezone.parse::<chrono_tz::Tz>().is_ok() => Some(Some(timezone)),
+        Some(timezone) => {
+            return Err(ApiError::BadRequest(format!(
+                "Unknown timezone {} (use a name like Europe/Berlin)",
+                timezone
+            )))
+        }
+    };
+
+    let post_class_rules = form
+        .post_class_rules
+        .as_deref()
+        .map(parse_post_class_rules)
+        .transpose()
+        .map_err(ApiError::BadRequest)?;
+
+    let update = SettingsUpdate {
+        readwise_token: readwise_token.map(str::to_string),
+        bookmark_sync_enabled: form.bookmark_sync,
+        extract_links: form.extract_links,
+        author_allowlist: form.author_allowlist.as_deref().map(parse_author_list),
+        author_denylist: form.author_denylist.as_deref().map(parse_author_list),
+        min_post_length: min_post_length.map(i32::from),
+        poll_interval_secs: poll_interval_secs.map(|secs| secs.map(i32::from)),
+        highlight_category: highlight_category.map(|category| category.map(str::to_string)),
+        save_image_alt_text: form.save_image_alt_text,
+        include_engagement: form.include_engagement,
+        daily_digest: form.daily_digest,
+        timezone: timezone.map(|timezone| timezone.map(str::to_string)),
+        post_class_rules: post_class_rules.map(|rules| rules.to_entries()),
+        skip_labeled: form.skip_labeled,
+        skip_labels: form.skip_labels.as_deref().map(|labels| {
+            labels
+                .split(|c: char| c == ',' || c.is_whitespace())
+                .filter(|label| !label.is_empty())
+                .map(str::to_ascii_lowercase)
+                .collect()
+        }),
+    };
+    let save_failed = |e: anyhow::Error| {
+        tracing::error!("Failed to save settings for {}: {}", user_id, e);
+        ApiError::Internal("Failed to save settings".to_string())
+    };
+
+    let existing = state
+        .db
+        .get_user_settings(user_id)
+        .await
+        .map_err(save_failed)?;
+    if existing.is_none() {
+        let Some(readwise_token) = readwise_token else {
+            return Err(ApiError::BadRequest(
+                "Readwise token is required".to_string(),
+            ));
+        };
+        state
+            .db
+            .create_user_settings(
+                user_id,
+                readwise_token,
+                form.bookmark_sync.unwrap_or(false),
+                form.extract_links.unwrap_or(false),
+            )
+            .await
+            .map_err(save_failed)?;
     }
 
-    // TODO: Actually save settings
+    // Every submitted field lands in one UPDATE, so a failure part way
+    // through can't leave some fields saved and others not
+    state
+        .db
+        .update_user_settings(user_id, &update)
+        .await
+        .map_err(save_failed)?
+        .ok_or_else(|| ApiError::Internal("Failed to save settings".to_string()))?;
+
+    if let Some(enabled) = form.bookmark_sync {
+        apply_sync_toggle(&state, user_id, enabled).await;
+    }
 
     // Redirect back to dashboard with success message
-    Redirect::to("/dashboard?saved=true").into_response()
+    Ok(Redirect::to("/dashboard?saved=true"))
+}
+
+/// Start or stop the user's bookmark sync loop to match the saved setting
+///
+/// A user who has to log in again is left stopped; logging in restarts it.
+/// A loop that fails to start only logs, since the setting is saved anyway.
+async fn apply_sync_toggle(state: &AppState, user_id: Uuid, enabled: bool) {
+    if !enabled {
+        state.sync_tasks.cancel(user_id);
+        return;
+    }
+    if state.sync_tasks.is_running(user_id) {
+        return;
+    }
+    match state.db.get_user_by_id(user_id).await {
+        Ok(Some(user)) if !user.needs_reauth => {
+            if let Err(e) = crate::services::spawn_user_sync(state, user).await {
+                tracing::warn!("Failed to start bookmark sync for {}: {}", user_id, e);
+            }
+        }
+        Ok(_) => {}
+        Err(e) => tracing::error!("Failed to load user {}: {}", user_id, e),
+    }
+}
+
+/// Form data for deleting an account
+#[derive(Debug, Deserialize)]
+pub struct DeleteAccountForm {
+    /// Must be exactly "DELETE" to confirm
+    #[serde(default)]
+    pub confirm: String,
+}
+
+/// Delete the logged-in user's account and all associated data
+pub async fn delete_account(
+    State(state): State<Arc<AppState>>,
+    session: Session,
+    Form(form): Form<DeleteAccountForm>,
+) -> Result<Redirect, ApiError> {
+    let user_id = current_user_id(&session)
+        .await
+        .ok_or(ApiError::Unauthorized)?;
+
+    if form.confirm != "DELETE" {
+        return Err(ApiError::BadRequest(
+            "Type DELETE to confirm account deletion".to_string(),
+        ));
+    }
+
+    state.db.delete_user(user_id).await.map_err(|e| {
+        tracing::error!("Failed to delete user {}: {}", user_id, e);
+        ApiError::Internal("Failed to delete account".to_string())
+    })?;
+
+    state.sync_tasks.cancel(user_id);
+
+    if let Err(e) = session.flush().await {
+        tracing::warn!("Failed to clear session after account deletion: {}", e);
+    }
+
+    tracing::info!("Deleted account {}", user_id);
+    Ok(Redirect::to("/"))
+}
+
+/// Request body for rotating the Readwise token
+#[derive(Debug, Deserialize)]
+pub struct ReadwiseTokenRequest {
+    pub readwise_token: String,
+}
+
+/// Successful token rotation
+#[derive(Debug, Serialize)]
+pub struct ReadwiseTokenResponse {
+    pub rotated: bool,
+}
+
+/// Replace the stored Readwise token without touching other settings
+pub async fn rotate_readwise_token(
+    State(state): State<Arc<AppState>>,
+    session: Session,
+    Json(request): Json<ReadwiseTokenRequest>,
+) -> Result<Json<ReadwiseTokenResponse>, ApiError> {
+    let user_id = current_user_id(&session)
+        .await
+        .ok_or(ApiError::Unauthorized)?;
+
+    let token = request.readwise_token.trim();
+    if token.is_empty() {
+        return Err(ApiError::BadRequest(
+            "Readwise token is required".to_string(),
+        ));
+    }
+
+    let rotation =
+        readwise_token::rotate_readwise_token(&state.db, &*state.readwise, user_id, token)
+            .await
+            .map_err(|e| {
+                tracing::error!("Failed to rotate Readwise token for {}: {}", user_id, e);
+                ApiError::Internal("Failed to update Readwise token".to_string())
+            })?;
+
+    match rotation {
+        TokenRotation::Rotated => {
+            tracing::info!("Rotated Readwise token for {}", user_id);
+            Ok(Json(ReadwiseTokenResponse { rotated: true }))
+        }
+        TokenRotation::Rejected => Err(ApiError::BadRequest(
+            "Readwise didn't accept that token".to_string(),
+        )),
+        TokenRotation::NotRegistered => Err(ApiError::BadRequest(
+            "Save your settings before rotating your Readwise token".to_string(),
+        )),
+    }
+}
+
+/// Sync status for external dashboards
+#[derive(Debug, Serialize)]
+pub struct StatusResponse {
+    pub handle: String,
+    pub did: String,
+    /// None until the user has saved settings
+    pub settings: Option<SettingsStatus>,
+    pub last_synced_at: Option<DateTime<Utc>>,
+    pub bookmarks_processed: i64,
+    pub bookmarks_processed_today: i64,
+    /// None when there's no token or Readwise couldn't be reached
+    pub readwise_token_valid: Option<bool>,
+    /// Newest-first processed bookmarks, paged with `before`/`after`
+    pub recent_saves: Page<RecentSave>,
+    /// Newest-first save attempts, including failures
+    pub recent_attempts: Vec<SaveEvent>,
+}
+
+/// A processed bookmark in the status response
+#[derive(Debug, Serialize)]
+pub struct RecentSave {
+    pub post_uri: String,
+    pub processed_at: DateTime<Utc>,
+}
+
+impl From<ProcessedBookmark> for RecentSave {
+    fn from(row: ProcessedBookmark) -> Self {
+        Self {
+            post_uri: row.post_uri,
+            processed_at: row.processed_at,
+        }
+    }
+}
+
+/// Recent saves shown per status page
+const RECENT_SAVES_PAGE_SIZE: i64 = 20;
+
+/// Save attempts shown in the status response
+const RECENT_ATTEMPTS: i64 = 20;
+
+/// Save attempts per page of `/api/save-events`
+const SAVE_EVENTS_PAGE_SIZE: i64 = 50;
+
+/// Paging parameters for `/api/status` and `/api/save-events`
+#[derive(Debug, Default, Deserialize)]
+pub struct StatusQuery {
+    /// Cursor for older saves
+    pub before: Option<String>,
+    /// Cursor for newer saves
+    pub after: Option<String>,
+}
+
+impl StatusQuery {
+    fn page(&self) -> Result<PageRequest, ApiError> {
+        let invalid = |e: crate::db::pagination::CursorError| ApiError::BadRequest(e.to_string());
+        match (&self.before, &self.after) {
+            (None, None) => Ok(PageRequest::First),
+            (Some(before), None) => {
+                Ok(PageRequest::Before(decode_cursor(before).map_err(invalid)?))
+            }
+            (None, Some(after)) => Ok(PageRequest::After(decode_cursor(after).map_err(invalid)?)),
+            (Some(_), Some(_)) => Err(ApiError::BadRequest(
+                "Pass either before or after, not both".to_string(),
+            )),
+        }
+    }
+}
+
+/// User settings with the Readwise token left out
+#[derive(Debug, Serialize)]
+pub struct SettingsStatus {
+    pub bookmark_sync_enabled: bool,
+    pub extract_links: bool,
+    pub author_allowlist: Vec<String>,
+    pub author_denylist: Vec<String>,
+    pub min_post_length: i32,
+    pub poll_interval_secs: Option<i32>,
+    pub highlight_category: Option<String>,
+    pub save_image_alt_text: bool,
+    pub include_engagement: bool,
+    pub daily_digest: bool,
+    pub timezone: Option<String>,
+    /// Progress of the one-time bookmark import, while it runs
+    pub backfill: Option<BackfillProgress>,
+    pub updated_at: DateTime<Utc>,
+}
+
+/// How far the one-time import of existing bookmarks has got
+#[derive(Debug, Serialize)]
+pub struct BackfillProgress {
+    pub done: i32,
+    pub total: i32,
+}
+
+impl From<&UserSettings> for SettingsStatus {
+    fn from(settings: &UserSettings) -> Self {
+        Self {
+            bookmark_sync_enabled: settings.bookmark_sync_enabled,
+            extract_links: settings.extract_links,
+            author_allowlist: settings.author_allowlist.clone(),
+            author_denylist: settings.author_denylist.clone(),
+            min_post_length: settings.min_post_length,
+            poll_interval_secs: settings.poll_interval_secs,
+            highlight_category: settings.highlight_category.clone(),
+            save_image_alt_text: settings.save_image_alt_text,
+            include_engagement: settings.include_engagement,
+            daily_digest: settings.daily_digest,
+            timezone: settings.timezone.clone(),
+            backfill: settings
+                .backfill_progress()
+                .map(|(done, total)| BackfillProgress { done, total }),
+            updated_at: settings.updated_at,
+        }
+    }
+}
+
+/// JSON sync status for the logged-in user
+///
+/// Sends an ETag and Last-Modified; a matching `If-None-Match` gets a 304.
+pub async fn status(
+    State(state): State<Arc<AppState>>,
+    session: Session,
+    Query(query): Query<StatusQuery>,
+    headers: HeaderMap,
+) -> Result<Response, ApiError> {
+    let user_id = current_user_id(&session)
+        .await
+        .ok_or(ApiError::Unauthorized)?;
+    let page = query.page()?;
+
+    let status = load_status(&state, user_id, page).await.map_err(|e| {
+        tracing::error!("Failed to load status for {}: {}", user_id, e);
+        ApiError::Internal("Failed to load status".to_string())
+    })?;
+    let Some((mut status, unchecked_token)) = status else {
+        return Err(ApiError::Unauthorized);
+    };
+    let not_modified = |etag: &str| {
+        headers
+            .get(header::IF_NONE_MATCH)
+            .is_some_and(|value| value.as_bytes() == etag.as_bytes())
+    };
+
+    // A client that already has this status is answered without a token
+    // check; otherwise check the token (unless a recent check is cached)
+    let mut etag = status_etag(&status)?;
+    if let Some(token) = unchecked_token.filter(|_| !not_modified(&etag)) {
+        status.readwise_token_valid = match state
+            .token_checks
+            .verify(&*state.readwise, user_id, &token)
+            .await
+        {
+            Ok(valid) => Some(valid),
+            Err(e) => {
+                tracing::warn!("Couldn't verify Readwise token for {}: {}", user_id, e);
+                None
+            }
+        };
+        etag = status_etag(&status)?;
+    }
+    let last_modified = status
+        .settings
+        .as_ref()
+        .map(|settings| settings.updated_at)
+        .max(status.last_synced_at)
+        .map(|at| at.format("%a, %d %b %Y %H:%M:%S GMT").to_string());
+
+    let mut response = if not_modified(&etag) {
+        StatusCode::NOT_MODIFIED.into_response()
+    } else {
+        Json(status).into_response()
+    };
+
+    let response_headers = response.headers_mut();
+    if let Ok(value) = HeaderValue::from_str(&etag) {
+        response_headers.insert(header::ETAG, value);
+    }
+    if let Some(value) = last_modified.and_then(|at| HeaderValue::from_str(&at).ok()) {
+        response_headers.insert(header::LAST_MODIFIED, value);
+    }
+    Ok(response)
+}
+
+/// The logged-in user's save attempts, newest first, a page at a time
+///
+/// The full `save_events` history; `/api/status` shows only the latest few.
+pub async fn save_events(
+    State(state): State<Arc<AppState>>,
+    session: Session,
+    Query(query): Query<StatusQuery>,
+) -> Result<Json<Page<SaveEvent>>, ApiError> {
+    let user_id = current_user_id(&session)
+        .await
+        .ok_or(ApiError::Unauthorized)?;
+    let page = query.page()?;
+
+    state
+        .db
+        .save_events_page(user_id, page, SAVE_EVENTS_PAGE_SIZE)
+        .await
+        .map(Json)
+        .map_err(|e| {
+            tracing::error!("Failed to load save events for {}: {}", user_id, e);
+            ApiError::Internal("Failed to load save history".to_string())
+        })
+}
+
+/// Tokens expiring within this window are reported as expiring
+const TOKEN_EXPIRING_WINDOW: chrono::Duration = chrono::Duration::minutes(5);
+
+/// Who the session belongs to, for debugging auth and reconnect prompts
+#[derive(Debug, Serialize)]
+pub struct WhoamiResponse {
+    pub did: String,
+    pub handle: String,
+    /// Tokens were rejected; the user must log in again
+    pub needs_reauth: bool,
+    /// None when no Bluesky tokens are stored
+    pub tokens: Option<TokenStatus>,
+}
+
+/// Stored Bluesky tokens, without their values
+#[derive(Debug, Serialize)]
+pub struct TokenStatus {
+    pub has_refresh_token: bool,
+    pub expires_at: Option<DateTime<Utc>>,
+    /// Expired or about to expire
+    pub expiring: bool,
+}
+
+/// The logged-in user and the state of their tokens (values redacted)
+pub async fn whoami(
+    State(state): State<Arc<AppState>>,
+    session: Session,
+) -> Result<Json<WhoamiResponse>, ApiError> {
+    let user_id = current_user_id(&session)
+        .await
+        .ok_or(ApiError::Unauthorized)?;
+    let internal = |e: anyhow::Error| {
+        tracing::error!("Failed to load session user {}: {}", user_id, e);
+        ApiError::Internal("Failed to load user".to_string())
+    };
+
+    let user = state
+        .db
+        .get_user_by_id(user_id)
+        .await
+        .map_err(internal)?
+        .ok_or(ApiError::Unauthorized)?;
+    let tokens = state.db.get_tokens(user_id).await.map_err(internal)?;
+
+    Ok(Json(WhoamiResponse {
+        did: user.bluesky_did,
+        handle: user.bluesky_handle,
+        needs_reauth: user.needs_reauth,
+        tokens: tokens.map(|tokens| TokenStatus {
+            has_refresh_token: tokens.refresh_token.is_some(),
+            expires_at: tokens.expires_at,
+            expiring: tokens
+                .expires_at
+                .is_some_and(|at| at <= Utc::now() + TOKEN_EXPIRING_WINDOW),
+        }),
+    }))
+}
+
+/// Gather a user's status, or None if the user no longer exists
+///
+/// Token validity comes from the token check cache only; when nothing fresh
+/// is cached, the token is returned alongside for the caller to check.
+async fn load_status(
+    state: &AppState,
+    user_id: Uuid,
+    page: PageRequest,
+) -> anyhow::Result<Option<(StatusResponse, Option<String>)>> {
+    let Some(user) = state.db.get_user_by_id(user_id).await? else {
+        return Ok(None);
+    };
+    let settings = state.db.get_user_settings(user_id).await?;
+
+    let readwise_token_valid = settings
+        .as_ref()
+        .and_then(|s| state.token_checks.cached(user_id, &s.readwise_token));
+    let unchecked_token = settings
+        .as_ref()
+        .filter(|_| readwise_token_valid.is_none())
+        .map(|s| s.readwise_token.clone());
+
+    let status = StatusResponse {
+        handle: user.bluesky_handle,
+        did: user.bluesky_did,
+        settings: settings.as_ref().map(SettingsStatus::from),
+        last_synced_at: state.db.last_bookmark_processed_at(user_id).await?,
+        bookmarks_processed: state.db.processed_bookmark_count(user_id).await?,
+        bookmarks_processed_today: state.db.saves_today(user_id).await?,
+        readwise_token_valid,
+        recent_saves: recent_saves(state, user_id, page).await?,
+        recent_attempts: state
+            .db
+            .recent_save_events(user_id, RECENT_ATTEMPTS)
+            .await?,
+    };
+    Ok(Some((status, unchecked_token)))
+}
+
+/// One page of recent saves for the status response
+async fn recent_saves(
+    state: &AppState,
+    user_id: Uuid,
+    page: PageRequest,
+) -> anyhow::Result<Page<RecentSave>> {
+    let page = state
+        .db
+        .recent_saves(user_id, page, RECENT_SAVES_PAGE_SIZE)
+        .await?;
+    Ok(Page {
+        items: page.items.into_iter().map(RecentSave::from).collect(),
+        before: page.before,
+        after: page.after,
+    })
+}
+
+/// Query for `GET /api/preview`
+#[derive(Debug, Deserialize)]
+pub struct PreviewQuery {
+    /// Post AT-URI (`at://{did or handle}/app.bsky.feed.post/{rkey}`)
+    pub uri: String,
+}
+
+/// Message for a post that was deleted or is hidden by a block
+const POST_NOT_FOUND: &str = "Post not found";
+
+/// What saving a post would send to Readwise, without saving it
+pub async fn preview(
+    State(state): State<Arc<AppState>>,
+    session: Session,
+    Query(query): Query<PreviewQuery>,
+) -> Result<Json<SavePayload>, ApiError> {
+    let user_id = current_user_id(&session)
+        .await
+        .ok_or(ApiError::Unauthorized)?;
+    let uri = parse_at_uri(&query.uri).map_err(|e| ApiError::BadRequest(e.to_string()))?;
+
+    let outcome = crate::services::preview_post(&state, user_id, &uri.to_string())
+        .await
+        .map_err(|e| match e.downcast_ref::<ProcessError>() {
+            Some(ProcessError::PostUnavailable) => ApiError::NotFound(POST_NOT_FOUND.to_string()),
+            _ => {
+                tracing::warn!("Preview of {} failed: {}", uri, e);
+                ApiError::Internal("Failed to fetch post".to_string())
+            }
+        })?;
+
+    outcome
+        .preview
+        .map(Json)
+        .ok_or_else(|| ApiError::Internal("Nothing to preview".to_string()))
+}
+
+/// Body of `POST /api/reprocess`
+#[derive(Debug, Deserialize)]
+pub struct ReprocessRequest {
+    /// bsky.app post URL or post AT-URI
+    pub url: String,
+}
+
+/// Save a post again with the logged-in user's settings and Readwise token,
+/// even if it was processed before
+pub async fn reprocess(
+    State(state): State<Arc<AppState>>,
+    session: Session,
+    Json(request): Json<ReprocessRequest>,
+) -> Result<Json<ProcessOutcome>, ApiError> {
+    let user_id = current_user_id(&session)
+        .await
+        .ok_or(ApiError::Unauthorized)?;
+    let uri =
+        parse_post_reference(&request.url).map_err(|e| ApiError::BadRequest(e.to_string()))?;
+
+    // Processed records are keyed by DID URIs, as bookmarks come in
+    let post_uri = if uri.did_or_handle.starts_with("did:") {
+        uri.to_string()
+    } else {
+        let did = state
+            .handles
+            .resolve_cached(&uri.did_or_handle)
+            .await
+            .map_err(|e| ApiError::BadRequest(e.to_string()))?;
+        format!("at://{}/{}/{}", did, uri.collection, uri.rkey)
+    };
+
+    crate::services::reprocess_post(&state, user_id, &post_uri)
+        .await
+        .map(Json)
+        .map_err(|e| match e.downcast_ref::<ProcessError>() {
+            Some(ProcessError::PostUnavailable) => ApiError::NotFound(POST_NOT_FOUND.to_string()),
+            _ => {
+                tracing::warn!("Reprocessing {} failed: {}", post_uri, e);
+                ApiError::Internal("Failed to reprocess post".to_string())
+            }
+        })
+}
+
+/// Quoted ETag for a status response
+///
+/// FNV-1a rather than `DefaultHasher`, whose output can change between Rust
+/// releases; a redeploy shouldn't invalidate every client's cached status.
+fn status_etag(status: &StatusResponse) -> Result<String, ApiError> {
+    let body = serde_json::to_vec(status)
+        .map_err(|e| ApiError::Internal(format!("Failed to encode status: {}", e)))?;
+    let hash = body.iter().fold(0xcbf2_9ce4_8422_2325_u64, |hash, &byte| {
+        (hash ^ u64::from(byte)).wrapping_mul(0x0100_0000_01b3)
+    });
+    Ok(format!("\"{:016x}\"", hash))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::content::format_post_as_highlight;
+    use crate::crypto::EncryptionKey;
+    use crate::db::queries::Database;
+    use crate::readwise::client::{Document, Highlight, ReaderDocument, ReadwiseClient};
+    use crate::test_support::{self, thread, MockBluesky, MockReadwise};
+    use crate::web::session::USER_ID_KEY;
+    use anyhow::Result;
+    use async_trait::async_trait;
+    use axum::body::to_bytes;
+    use sqlx::PgPool;
+    use tower_sessions::MemoryStore;
+
+    async fn logged_in_session() -> Session {
+        let session = Session::new(None, Arc::new(MemoryStore::default()), None);
+        session.insert(USER_ID_KEY, Uuid::new_v4()).await.unwrap();
+        session
+    }
+
+    #[sqlx::test]
+    async fn test_empty_token_returns_json_error(pool: PgPool) {
+        let state = Arc::new(AppState::test(Database::new(
+            pool,
+            EncryptionKey::test_key(),
+        )));
+        let form = SettingsForm {
+            readwise_token: Some("  ".to_string()),
+            bookmark_sync: Some(true),
+            ..Default::default()
+        };
+
+        let response = update_settings(State(state), logged_in_session().await, Form(form))
+            .await
+            .into_response();
+
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+        let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let body: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(body["error"], "bad_request");
+        assert_eq!(body["message"], "Readwise token is required");
+    }
+
+    #[sqlx::test]
+    async fn test_toggling_extract_links_keeps_cursor_and_token(pool: PgPool) {
+        let db = Database::new(pool, EncryptionKey::test_key());
+        let user = db
+            .create_user("did:plc:reader", "reader.bsky.social")
+            .await
+            .unwrap();
+        db.create_user_settings(user.id, "rw-secret-token", true, false)
+            .await
+            .unwrap();
+        sqlx::query(
+            "UPDATE user_settings SET last_bookmark_cursor = 'cursor-42' WHERE user_id = $1",
+        )
+        .bind(user.id)
+        .execute(db.pool())
+        .await
+        .unwrap();
+        let state = Arc::new(AppState::test(db.clone()));
+        let session = Session::new(None, Arc::new(MemoryStore::default()), None);
+        session.insert(USER_ID_KEY, user.id).await.unwrap();
+        let form = SettingsForm {
+            extract_links: Some(true),
+            ..Default::default()
+        };
+
+        let response = update_settings(State(state), session, Form(form))
+            .await
+            .into_response();
+
+        assert_eq!(response.status(), StatusCode::SEE_OTHER);
+        let settings = db.get_user_settings(user.id).await.unwrap().unwrap();
+        assert!(settings.extract_links);
+        assert!(settings.bookmark_sync_enabled);
+        assert_eq!(settings.readwise_token, "rw-secret-token");
+        assert_eq!(settings.last_bookmark_cursor.as_deref(), Some("cursor-42"));
+    }
+
+    #[sqlx::test]
+    async fn test_unchecking_bookmark_sync_disables_it(pool: PgPool) {
+        let db = Database::new(pool, EncryptionKey::test_key());
+        let user = db
+            .create_user("did:plc:reader", "reader.bsky.social")
+            .await
+            .unwrap();
+        db.create_user_settings(user.id, "rw-secret-token", true, true)
+            .await
+            .unwrap();
+        let state = Arc::new(AppState::test(db.clone()));
+        let session = Session::new(None, Arc::new(MemoryStore::default()), None);
+        session.insert(USER_ID_KEY, user.id).await.unwrap();
+        let running = tokio::spawn(std::future::pending::<()>());
+        state.sync_tasks.register(user.id, running.abort_handle());
+
+        // The dashboard's submission with every box unchecked except one
+        let form = SettingsForm {
+            full_form: true,
+            readwise_token: Some(String::new()),
+            extract_links: Some(true),
+            ..Default::default()
+        };
+        let response = update_settings(State(state.clone()), session, Form(form))
+            .await
+            .into_response();
+
+        assert_eq!(response.status(), StatusCode::SEE_OTHER);
+        let settings = db.get_user_settings(user.id).await.unwrap().unwrap();
+        assert!(!settings.bookmark_sync_enabled);
+        assert!(!state.sync_tasks.is_running(user.id));
+        assert!(running.await.unwrap_err().is_cancelled());
+        assert!(settings.extract_links);
+        assert_eq!(settings.readwise_token, "rw-secret-token");
+    }
+
+    #[sqlx::test]
+    async fn test_first_save_stores_every_field(pool: PgPool) {
+        let db = Database::new(pool, EncryptionKey::test_key());
+        let user = db
+            .create_user("did:plc:reader", "reader.bsky.social")
+            .await
+            .unwrap();
+        let state = Arc::new(AppState::test(db.clone()));
+        let session = Session::new(None, Arc::new(MemoryStore::default()), None);
+        session.insert(USER_ID_KEY, user.id).await.unwrap();
+
+        let form = SettingsForm {
+            full_form: true,
+            readwise_token: Some("rw-token".to_string()),
+            author_denylist: Some("spam.bsky.social".to_string()),
+            min_post_length: Some("20".to_string()),
+            poll_interval_secs: Some("60".to_string()),
+            highlight_category: Some("books".to_string()),
+            daily_digest: Some(true),
+            timezone: Some("Europe/Berlin".to_string()),
+            skip_labels: Some("Spoiler, nudity".to_string()),
+            ..Default::default()
+        };
+        let response = update_settings(State(state), session, Form(form))
+            .await
+            .into_response();
+
+        assert_eq!(response.status(), StatusCode::SEE_OTHER);
+        let settings = db.get_user_settings(user.id).await.unwrap().unwrap();
+        assert_eq!(settings.readwise_token, "rw-token");
+        assert!(!settings.bookmark_sync_enabled);
+        assert_eq!(settings.author_denylist, vec!["spam.bsky.social"]);
+        assert_eq!(settings.min_post_length, 20);
+        assert_eq!(settings.poll_interval_secs, Some(60));
+        assert_eq!(settings.highlight_category.as_deref(), Some("books"));
+        assert!(settings.daily_digest);
+        assert_eq!(settings.timezone.as_deref(), Some("Europe/Berlin"));
+        assert_eq!(settings.skip_labels, vec!["spoiler", "nudity"]);
+    }
+
+    struct ValidTokenClient;
+
+    #[async_trait]
+    impl ReadwiseClient for ValidTokenClient {
+        async fn save_highlight(
+            &self,
+            _token: &str,
+            _highlight: Highlight,
+        ) -> Result<Option<String>> {
+            Ok(None)
+        }
+
+        async fn save_document(&self, _token: &str, _document: Document) -> Result<Option<String>> {
+            Ok(None)
+        }
+
+        async fn delete_document(&self, _token: &str, _id: &str) -> Result<()> {
+            Ok(())
+        }
+
+        async fn verify_token(&self, token: &str) -> Result<bool> {
+            Ok(token == "rw-secret-token")
+        }
+
+        async fn list_documents(
+            &self,
+            _token: &str,
+            _updated_after: Option<DateTime<Utc>>,
+            _location: Option<&str>,
+        ) -> Result<Vec<ReaderDocument>> {
+            Ok(vec![])
+        }
+    }
+
+    #[sqlx::test]
+    async fn test_whoami_reports_tokens_without_values(pool: PgPool) {
+        let db = Database::new(pool, EncryptionKey::test_key());
+        let user = db
+            .create_user("did:plc:whoami", "whoami.bsky.social")
+            .await
+            .unwrap();
+        db.store_tokens(
+            user.id,
+            "secret-access",
+            Some("secret-refresh"),
+            Some(Utc::now() + chrono::Duration::minutes(1)),
+            None,
+        )
+        .await
+        .unwrap();
+        let state = Arc::new(AppState::test(db));
+        let session = Session::new(None, Arc::new(MemoryStore::default()), None);
+        session.insert(USER_ID_KEY, user.id).await.unwrap();
+
+        let response = whoami(State(state), session).await.into_response();
+
+        assert_eq!(response.status(), StatusCode::OK);
+        let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        assert!(!String::from_utf8_lossy(&body).contains("secret-"));
+        let body: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(body["did"], "did:plc:whoami");
+        assert_eq!(body["handle"], "whoami.bsky.social");
+        assert_eq!(body["needs_reauth"], false);
+        assert_eq!(body["tokens"]["has_refresh_token"], true);
+        assert!(body["tokens"]["expires_at"].is_string());
+        assert_eq!(body["tokens"]["expiring"], true);
+    }
+
+    #[sqlx::test]
+    async fn test_whoami_requires_session(pool: PgPool) {
+        let state = Arc::new(AppState::test(Database::new(
+            pool,
+            EncryptionKey::test_key(),
+        )));
+
+        let logged_out = Session::new(None, Arc::new(MemoryStore::default()), None);
+        let response = whoami(State(state.clone()), logged_out)
+            .await
+            .into_response();
+        assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+
+        // A session for a deleted user is as good as none
+        let response = whoami(State(state), logged_in_session().await)
+            .await
+            .into_response();
+        assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+    }
+
+    #[sqlx::test]
+    async fn test_status_json_redacts_token(pool: PgPool) {
+        let db = Database::new(pool, EncryptionKey::test_key());
+        let user = db
+            .create_user("did:plc:status", "status.bsky.social")
+            .await
+            .unwrap();
+        db.create_user_settings(user.id, "rw-secret-token", true, false)
+            .await
+            .unwrap();
+        db.mark_bookmark_processed(user.id, "at://did:plc:x/app.bsky.feed.post/1")
+            .await
+            .unwrap();
+        let state = Arc::new(AppState {
+            readwise: Arc::new(ValidTokenClient),
+            ..AppState::test(db)
+        });
+
+        let session = Session::new(None, Arc::new(MemoryStore::default()), None);
+        session.insert(USER_ID_KEY, user.id).await.unwrap();
+
+        let response = status(
+            State(state.clone()),
+            session.clone(),
+            Query(StatusQuery::default()),
+            HeaderMap::new(),
+        )
+        .await
+        .into_response();
+        assert_eq!(response.status(), StatusCode::OK);
+        let etag = response.headers()[header::ETAG].clone();
+        assert!(response.headers().contains_key(header::LAST_MODIFIED));
+
+        let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        assert!(!String::from_utf8_lossy(&body).contains("rw-secret-token"));
+        let body: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(body["handle"], "status.bsky.social");
+        assert_eq!(body["settings"]["bookmark_sync_enabled"], true);
+        assert!(body["settings"].get("readwise_token").is_none());
+        assert_eq!(body["bookmarks_processed"], 1);
+        assert_eq!(body["bookmarks_processed_today"], 1);
+        assert_eq!(body["readwise_token_valid"], true);
+        assert!(body["last_synced_at"].is_string());
+        assert_eq!(
+            body["recent_saves"]["items"][0]["post_uri"],
+            "at://did:plc:x/app.bsky.feed.post/1"
+        );
+        assert!(body["recent_saves"]["before"].is_null());
+
+        let mut headers = HeaderMap::new();
+        headers.insert(header::IF_NONE_MATCH, etag);
+        let response = status(
+            State(state.clone()),
+            session.clone(),
+            Query(StatusQuery::default()),
+            headers,
+        )
+        .await
+        .into_response();
+        assert_eq!(response.status(), StatusCode::NOT_MODIFIED);
+
+        let query = StatusQuery {
+            before: Some("garbage".to_string()),
+            after: None,
+        };
+        let response = status(State(state), session, Query(query), HeaderMap::new())
+            .await
+            .into_response();
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+    }
+
+    #[sqlx::test]
+    async fn test_status_reuses_recent_token_check(pool: PgPool) {
+        let db = Database::new(pool, EncryptionKey::test_key());
+        let user = db
+            .create_user("did:plc:status", "status.bsky.social")
+            .await
+            .unwrap();
+        db.create_user_settings(user.id, "rw-token", true, false)
+            .await
+            .unwrap();
+        let readwise = MockReadwise::start().await;
+        let state = Arc::new(AppState {
+            readwise: Arc::new(
+                crate::readwise::client::HttpReadwiseClient::new().with_base_url(&readwise.url),
+            ),
+            ..AppState::test(db)
+        });
+        let session = Session::new(None, Arc::new(MemoryStore::default()), None);
+        session.insert(USER_ID_KEY, user.id).await.unwrap();
+
+        let get_status = |headers: HeaderMap| {
+            status(
+                State(state.clone()),
+                session.clone(),
+                Query(StatusQuery::default()),
+                headers,
+            )
+        };
+        let response = get_status(HeaderMap::new()).await.into_response();
+        assert_eq!(response.status(), StatusCode::OK);
+        let etag = response.headers()[header::ETAG].clone();
+
+        let mut headers = HeaderMap::new();
+        headers.insert(header::IF_NONE_MATCH, etag);
+        let response = get_status(headers).await.into_response();
+        assert_eq!(response.status(), StatusCode::NOT_MODIFIED);
+        let response = get_status(HeaderMap::new()).await.into_response();
+        assert_eq!(response.status(), StatusCode::OK);
+
+        assert_eq!(readwise.received("/v2/auth/").len(), 1);
+    }
+
+    /// A registered user with sync on, plus a state whose Readwise accepts one token
+    async fn registered_user(pool: PgPool) -> (Arc<AppState>, Session, Uuid) {
+        let db = Database::new(pool, EncryptionKey::test_key());
+        let user = db
+            .create_user("did:plc:rotate", "rotate.bsky.social")
+            .await
+            .unwrap();
+        db.create_user_settings(user.id, "rw-old-token", true, true)
+            .await
+            .unwrap();
+        let state = Arc::new(AppState {
+            readwise: Arc::new(ValidTokenClient),
+            ..AppState::test(db)
+        });
+        let session = Session::new(None, Arc::new(MemoryStore::default()), None);
+        session.insert(USER_ID_KEY, user.id).await.unwrap();
+        (state, session, user.id)
+    }
+
+    #[sqlx::test]
+    async fn test_rotate_readwise_token_keeps_other_settings(pool: PgPool) {
+        let (state, session, user_id) = registered_user(pool).await;
+        let request = ReadwiseTokenRequest {
+            readwise_token: " rw-secret-token ".to_string(),
+        };
+
+        let response = rotate_readwise_token(State(state.clone()), session, Json(request))
+            .await
+            .into_response();
+
+        assert_eq!(response.status(), StatusCode::OK);
+        let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let body: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(body["rotated"], true);
+        let settings = state.db.get_user_settings(user_id).await.unwrap().unwrap();
+        assert_eq!(settings.readwise_token, "rw-secret-token");
+        assert!(settings.bookmark_sync_enabled);
+        assert!(settings.extract_links);
+    }
+
+    #[sqlx::test]
+    async fn test_rotate_readwise_token_rejects_invalid_token(pool: PgPool) {
+        let (state, session, user_id) = registered_user(pool).await;
+        let request = ReadwiseTokenRequest {
+            readwise_token: "rw-wrong-token".to_string(),
+        };
+
+        let response = rotate_readwise_token(State(state.clone()), session, Json(request))
+            .await
+            .into_response();
+
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+        let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let body: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(body["error"], "bad_request");
+        let settings = state.db.get_user_settings(user_id).await.unwrap().unwrap();
+        assert_eq!(settings.readwise_token, "rw-old-token");
+    }
+
+    #[sqlx::test]
+    async fn test_preview_returns_formatted_highlight(pool: PgPool) {
+        let post = test_support::post("abc123").text("Worth keeping").build();
+        let public_api = MockBluesky::new()
+            .with_thread(thread(post.clone()))
+            .start()
+            .await;
+        let state = Arc::new(AppState {
+            config: crate::config::Config {
+                bsky_public_api_base: public_api.url.clone(),
+                ..crate::config::Config::test_default()
+            },
+            ..AppState::test(Database::new(pool, EncryptionKey::test_key()))
+        });
+        let query = PreviewQuery {
+            uri: post.uri.clone(),
+        };
+
+        let response = preview(
+            State(state.clone()),
+            logged_in_session().await,
+            Query(query),
+        )
+        .await
+        .into_response();
+
+        assert_eq!(response.status(), StatusCode::OK);
+        let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let body: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        let expected = SavePayload::Highlight(format_post_as_highlight(
+            &post,
+            None,
+            false,
+            &state.config.highlight_templates(),
+        ));
+        assert_eq!(body, serde_json::to_value(expected).unwrap());
+        assert_eq!(body["type"], "highlight");
+    }
+
+    #[sqlx::test]
+    async fn test_reprocess_saves_processed_post_again(pool: PgPool) {
+        let post = test_support::post("abc123").text("Missed save").build();
+        let public_api = MockBluesky::new()
+            .with_thread(thread(post.clone()))
+            .start()
+            .await;
+        let readwise = MockReadwise::start().await;
+        let db = Database::new(pool, EncryptionKey::test_key());
+        let user = db
+            .create_user("did:plc:reader", "reader.bsky.social")
+            .await
+            .unwrap();
+        db.create_user_settings(user.id, "rw-token", true, false)
+            .await
+            .unwrap();
+        db.mark_bookmark_processed(user.id, &post.uri)
+            .await
+            .unwrap();
+        let state = Arc::new(AppState {
+            config: crate::config::Config {
+                bsky_public_api_base: public_api.url.clone(),
+                readwise_api_base: readwise.url.clone(),
+                ..crate::config::Config::test_default()
+            },
+            ..AppState::test(db.clone())
+        });
+        let session = Session::new(None, Arc::new(MemoryStore::default()), None);
+        session.insert(USER_ID_KEY, user.id).await.unwrap();
+        let request = ReprocessRequest {
+            url: "https://bsky.app/profile/did:plc:author/post/abc123".to_string(),
+        };
+
+        let response = reprocess(State(state), session, Json(request))
+            .await
+            .into_response();
+
+        assert_eq!(response.status(), StatusCode::OK);
+        let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let body: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(body["kind"], "highlight");
+        let saved = readwise.received("/v2/highlights/");
+        assert_eq!(saved.len(), 1);
+        assert_eq!(saved[0]["highlights"][0]["text"], "Missed save");
+        assert!(db.is_bookmark_processed(user.id, &post.uri).await.unwrap());
+    }
+
+    #[sqlx::test]
+    async fn test_preview_rejects_malformed_uri(pool: PgPool) {
+        let state = Arc::new(AppState::test(Database::new(
+            pool,
+            EncryptionKey::test_key(),
+        )));
+        for uri in [
+            "https://bsky.app/profile/x",
+            "at://did:plc:x/app.bsky.feed.like/1",
+        ] {
+            let query = PreviewQuery {
+                uri: uri.to_string(),
+            };
+            let response = preview(
+                State(state.clone()),
+                logged_in_session().await,
+                Query(query),
+            )
+            .await
+            .into_response();
+            assert_eq!(response.status(), StatusCode::BAD_REQUEST, "{}", uri);
+        }
+    }
+
+    #[sqlx::test]
+    async fn test_missing_post_is_not_found(pool: PgPool) {
+        let public_api = MockBluesky::new().start().await;
+        let db = Database::new(pool, EncryptionKey::test_key());
+        let user = db
+            .create_user("did:plc:reader", "reader.bsky.social")
+            .await
+            .unwrap();
+        db.create_user_settings(user.id, "rw-token", true, false)
+            .await
+            .unwrap();
+        let state = Arc::new(AppState {
+            config: crate::config::Config {
+                bsky_public_api_base: public_api.url.clone(),
+                ..crate::config::Config::test_default()
+            },
+            ..AppState::test(db)
+        });
+        let session = || async {
+            let session = Session::new(None, Arc::new(MemoryStore::default()), None);
+            session.insert(USER_ID_KEY, user.id).await.unwrap();
+            session
+        };
+        let uri = "at://did:plc:author/app.bsky.feed.post/gone";
+
+        let previewed = preview(
+            State(state.clone()),
+            session().await,
+            Query(PreviewQuery {
+                uri: uri.to_string(),
+            }),
+        )
+        .await
+        .into_response();
+        let reprocessed = reprocess(
+            State(state),
+            session().await,
+            Json(ReprocessRequest {
+                url: uri.to_string(),
+            }),
+        )
+        .await
+        .into_response();
+
+        for response in [previewed, reprocessed] {
+            assert_eq!(response.status(), StatusCode::NOT_FOUND);
+            let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+            let body: serde_json::Value = serde_json::from_slice(&body).unwrap();
+            assert_eq!(body["error"], "not_found");
+            assert_eq!(body["message"], "Post not found");
+        }
+    }
 }
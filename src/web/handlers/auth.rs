@@ -2,12 +2,23 @@
 
 use std::sync::Arc;
 
+use atproto_identity::key::{identify_key, to_public};
+use atproto_oauth::jwk::{self, WrappedJsonWebKeySet};
 use axum::{
-    extract::{Query, State},
+    extract::{Path, Query, State},
+    http::StatusCode,
     response::{Html, IntoResponse, Redirect, Response},
+    Form, Json,
 };
 use serde::Deserialize;
+use serde_json::json;
+use tower_sessions::Session;
 
+use crate::bluesky::oauth::{CompletedLogin, DEFAULT_SCOPE};
+use crate::content::html::html_escape;
+use crate::web::csrf::csrf_field;
+use crate::web::redirect::{local_path, safe_redirect};
+use crate::web::session::{DID_KEY, RETURN_TO_KEY, USER_ID_KEY};
 use crate::AppState;
 
 /// Query parameters for OAuth callback
@@ -17,81 +28,499 @@ pub struct CallbackParams {
     pub state: Option<String>,
     pub error: Option<String>,
     pub error_description: Option<String>,
+    /// Issuer of the authorization response (RFC 9207)
+    pub iss: Option<String>,
 }
 
-/// Initiate OAuth login flow
-pub async fn login(State(_state): State<Arc<AppState>>) -> Response {
-    // TODO: Generate PKCE verifier and state
-    // TODO: Build authorization URL using atproto-oauth
-    // TODO: Store state in session
-    // TODO: Redirect to Bluesky authorization endpoint
+/// Query parameters for the login page
+#[derive(Debug, Deserialize)]
+pub struct LoginPageParams {
+    /// Local path to go to once logged in (defaults to the dashboard)
+    pub return_to: Option<String>,
+}
+
+/// Form data for starting a login
+#[derive(Debug, Deserialize)]
+pub struct LoginForm {
+    pub handle: String,
+    /// Optional PDS URL for self-hosted accounts; skips handle resolution
+    #[serde(default)]
+    pub pds_url: Option<String>,
+}
+
+/// Render a simple error page
+fn error_page(status: StatusCode, title: &str, message: &str) -> Response {
+    (
+        status,
+        Html(format!(
+            r#"<!DOCTYPE html>
+<html>
+<head><title>{title}</title></head>
+<body>
+<h1>{title}</h1>
+<p>{}</p>
+<p><a href="/auth/login">Try again</a></p>
+</body>
+</html>"#,
+            html_escape(message)
+        )),
+    )
+        .into_response()
+}
+
+/// Login page asking for the user's handle
+///
+/// Remembers a local `return_to` for the callback; anything else is ignored.
+pub async fn login_page(session: Session, Query(params): Query<LoginPageParams>) -> Html<String> {
+    let return_to = params.return_to.as_deref().and_then(local_path);
+    if let Some(path) = params.return_to.as_deref().filter(|_| return_to.is_none()) {
+        tracing::warn!("Ignoring non-local return_to {:?}", path);
+    }
+    let remembered = match return_to {
+        Some(path) => session.insert(RETURN_TO_KEY, path).await,
+        None => session.remove::<String>(RETURN_TO_KEY).await.map(|_| ()),
+    };
+    if let Err(e) = remembered {
+        tracing::warn!("Failed to remember return_to: {}", e);
+    }
 
-    // For now, return a placeholder
     Html(
         r#"<!DOCTYPE html>
 <html>
-<head><title>Login</title></head>
+<head>
+    <title>Login - Readwise Autosave</title>
+    <style>
+        body { font-family: system-ui, sans-serif; max-width: 600px; margin: 2rem auto; padding: 1rem; }
+        h1 { color: #1185fe; }
+        input[type="text"] { width: 100%; padding: 0.5rem; border: 1px solid #ccc; border-radius: 4px; }
+        .btn { background: #1185fe; color: white; padding: 0.75rem 1.5rem;
+               border: none; border-radius: 6px; cursor: pointer; margin-top: 1rem; }
+        .btn:hover { background: #0066cc; }
+    </style>
+</head>
 <body>
-<h1>OAuth Login</h1>
-<p>OAuth flow not yet implemented. Coming soon!</p>
-<p><a href="/">Back to home</a></p>
+    <h1>Connect with Bluesky</h1>
+    <form action="/auth/login" method="POST">
+        {csrf_field}
+        <label for="handle">Your Bluesky handle</label>
+        <input type="text" id="handle" name="handle" placeholder="you.bsky.social" required>
+        <details>
+            <summary>Self-hosted PDS?</summary>
+            <label for="pds_url">PDS URL (optional)</label>
+            <input type="text" id="pds_url" name="pds_url" placeholder="https://pds.example.com">
+        </details>
+        <button type="submit" class="btn">Continue</button>
+    </form>
+    <p><a href="/">Back to home</a></p>
 </body>
-</html>"#,
+</html>"#
+            .replace("{csrf_field}", &csrf_field(&session).await),
     )
-    .into_response()
+}
+
+/// Initiate OAuth login flow
+pub async fn login(State(state): State<Arc<AppState>>, Form(form): Form<LoginForm>) -> Response {
+    let Some(oauth) = &state.oauth else {
+        return error_page(
+            StatusCode::SERVICE_UNAVAILABLE,
+            "Login Unavailable",
+            "OAuth is not configured on this server.",
+        );
+    };
+
+    let pds_url = form
+        .pds_url
+        .as_deref()
+        .map(str::trim)
+        .filter(|url| !url.is_empty());
+
+    match oauth.initiate_login(form.handle.trim(), pds_url).await {
+        // Built by us for the user's authorization server, so not a local path
+        Ok(auth_url) => Redirect::to(&auth_url).into_response(),
+        Err(e) => {
+            tracing::warn!("Failed to start login: {}", e);
+            error_page(StatusCode::BAD_REQUEST, "Login Failed", &e.to_string())
+        }
+    }
 }
 
 /// Handle OAuth callback
 pub async fn callback(
-    State(_state): State<Arc<AppState>>,
+    State(state): State<Arc<AppState>>,
+    session: Session,
     Query(params): Query<CallbackParams>,
 ) -> Response {
     // Check for errors from the OAuth provider
     if let Some(error) = params.error {
         let description = params.error_description.unwrap_or_default();
-        return Html(format!(
-            r#"<!DOCTYPE html>
-<html>
-<head><title>Login Error</title></head>
-<body>
-<h1>Login Failed</h1>
-<p>Error: {} - {}</p>
-<p><a href="/">Try again</a></p>
-</body>
-</html>"#,
-            error, description
-        ))
-        .into_response();
+        return error_page(
+            StatusCode::BAD_REQUEST,
+            "Login Failed",
+            &format!("Error: {} - {}", error, description),
+        );
     }
 
-    // TODO: Verify state parameter
-    // TODO: Exchange code for tokens using PKCE
-    // TODO: Get user info (DID, handle)
-    // TODO: Create or update user in database
-    // TODO: Create session
-    // TODO: Redirect to dashboard
-
-    if let Some(_code) = params.code {
-        // Placeholder success response
-        Redirect::to("/dashboard").into_response()
-    } else {
-        Html(
-            r#"<!DOCTYPE html>
-<html>
-<head><title>Error</title></head>
-<body>
-<h1>Missing Authorization Code</h1>
-<p><a href="/">Try again</a></p>
-</body>
-</html>"#,
+    let (Some(code), Some(oauth_state)) = (params.code, params.state) else {
+        return error_page(
+            StatusCode::BAD_REQUEST,
+            "Missing Authorization Code",
+            "The login response was incomplete.",
+        );
+    };
+
+    let Some(oauth) = &state.oauth else {
+        return error_page(
+            StatusCode::SERVICE_UNAVAILABLE,
+            "Login Unavailable",
+            "OAuth is not configured on this server.",
+        );
+    };
+
+    let login = match oauth
+        .complete_login(&code, &oauth_state, params.iss.as_deref())
+        .await
+    {
+        Ok(login) => login,
+        Err(e) => {
+            tracing::warn!("Failed to complete login: {}", e);
+            crate::metrics::oauth_error();
+            return error_page(StatusCode::BAD_REQUEST, "Login Failed", &e.to_string());
+        }
+    };
+
+    match persist_login(&state, &session, &login).await {
+        Ok(()) => {
+            safe_redirect(&return_to(&session).await, &state.config.base_url()).into_response()
+        }
+        Err(e) => {
+            tracing::error!("Failed to save login for {}: {}", login.did, e);
+            error_page(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "Login Failed",
+                "Could not save your account. Please try again.",
+            )
+        }
+    }
+}
+
+/// Where to send the user after login: their remembered path, or the dashboard
+async fn return_to(session: &Session) -> String {
+    session
+        .remove::<String>(RETURN_TO_KEY)
+        .await
+        .ok()
+        .flatten()
+        .unwrap_or_else(|| "/dashboard".to_string())
+}
+
+/// Create or update the user and their tokens, then log them in
+async fn persist_login(
+    state: &AppState,
+    session: &Session,
+    login: &CompletedLogin,
+) -> anyhow::Result<()> {
+    let user = state.db.upsert_user(&login.did, &login.handle).await?;
+    state
+        .db
+        .store_tokens(
+            user.id,
+            &login.access_token,
+            login.refresh_token.as_deref(),
+            login.expires_at,
+            login.dpop_key.as_deref(),
         )
-        .into_response()
+        .await?;
+    state.db.clear_needs_reauth(user.id).await?;
+
+    start_session(session, user.id, &user.bluesky_did).await?;
+
+    tracing::info!("User {} logged in", user.bluesky_did);
+    Ok(())
+}
+
+/// Log in via a single-use magic link sent by the DM bot
+pub async fn magic_link(
+    State(state): State<Arc<AppState>>,
+    session: Session,
+    Path(token): Path<String>,
+) -> Response {
+    let user = match state.db.consume_login_token(&token).await {
+        Ok(Some(did)) => state.db.get_user_by_did(&did).await,
+        Ok(None) => Ok(None),
+        Err(e) => Err(e),
+    };
+
+    let user = match user {
+        Ok(Some(user)) => user,
+        Ok(None) => {
+            return error_page(
+                StatusCode::BAD_REQUEST,
+                "Link Expired",
+                "This login link is invalid, expired, or already used. DM the bot \"settings\" for a new one.",
+            )
+        }
+        Err(e) => {
+            tracing::error!("Failed to check magic link: {}", e);
+            return error_page(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "Login Failed",
+                "Could not check your login link. Please try again.",
+            );
+        }
+    };
+
+    match start_session(&session, user.id, &user.bluesky_did).await {
+        Ok(()) => Redirect::to("/dashboard").into_response(),
+        Err(e) => {
+            tracing::error!("Failed to start session for {}: {}", user.bluesky_did, e);
+            error_page(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "Login Failed",
+                "Could not log you in. Please try again.",
+            )
+        }
     }
 }
 
+/// Log a user into the session, rotating the ID to prevent fixation
+async fn start_session(
+    session: &Session,
+    user_id: uuid::Uuid,
+    did: &str,
+) -> Result<(), tower_sessions::session::Error> {
+    session.cycle_id().await?;
+    session.insert(USER_ID_KEY, user_id).await?;
+    session.insert(DID_KEY, did).await?;
+    Ok(())
+}
+
 /// Handle logout
-pub async fn logout(State(_state): State<Arc<AppState>>) -> Redirect {
-    // TODO: Clear session
+pub async fn logout(State(_state): State<Arc<AppState>>, session: Session) -> Redirect {
+    if let Err(e) = session.flush().await {
+        tracing::warn!("Failed to clear session: {}", e);
+    }
     // TODO: Revoke tokens if needed
     Redirect::to("/")
 }
+
+/// OAuth client metadata document (served at the client ID URL)
+pub async fn client_metadata(State(state): State<Arc<AppState>>) -> Response {
+    let Some(client_id) = &state.config.oauth_client_id else {
+        return StatusCode::NOT_FOUND.into_response();
+    };
+    let redirect_uri = state.config.redirect_uri();
+    let jwks_uri = format!("{}/jwks.json", state.config.base_url());
+
+    Json(json!({
+        "client_id": client_id,
+        "client_name": "Readwise Autosave",
+        "application_type": "web",
+        "grant_types": ["authorization_code", "refresh_token"],
+        "response_types": ["code"],
+        "redirect_uris": [redirect_uri],
+        "scope": state
+            .config
+            .oauth_scope()
+            .unwrap_or_else(|_| DEFAULT_SCOPE.to_string()),
+        "token_endpoint_auth_method": "private_key_jwt",
+        "token_endpoint_auth_signing_alg": "ES256",
+        "dpop_bound_access_tokens": true,
+        "jwks_uri": jwks_uri,
+    }))
+    .into_response()
+}
+
+/// Public key set used to verify our client assertions
+pub async fn jwks(State(state): State<Arc<AppState>>) -> Response {
+    let Some(signing_key) = &state.config.oauth_signing_key else {
+        return StatusCode::NOT_FOUND.into_response();
+    };
+
+    let key = identify_key(signing_key)
+        .map_err(anyhow::Error::from)
+        .and_then(|key| Ok(to_public(&key)?))
+        .and_then(|key| jwk::generate(&key));
+
+    match key {
+        Ok(key) => Json(WrappedJsonWebKeySet { keys: vec![key] }).into_response(),
+        Err(e) => {
+            tracing::error!("Invalid OAuth signing key: {}", e);
+            StatusCode::INTERNAL_SERVER_ERROR.into_response()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::bluesky::oauth::{OAuthError, OAuthService, RefreshedTokens};
+    use crate::crypto::EncryptionKey;
+    use crate::db::queries::Database;
+    use async_trait::async_trait;
+    use axum::body::Body;
+    use axum::http::{header, Request};
+    use sqlx::PgPool;
+    use tower::ServiceExt;
+    use tower_sessions::{MemoryStore, SessionManagerLayer};
+
+    struct MockOAuthService;
+
+    #[async_trait]
+    impl OAuthService for MockOAuthService {
+        async fn initiate_login(
+            &self,
+            _handle: &str,
+            _pds_override: Option<&str>,
+        ) -> Result<String, OAuthError> {
+            Ok("https://bsky.social/oauth/authorize".to_string())
+        }
+
+        async fn complete_login(
+            &self,
+            _code: &str,
+            _state: &str,
+            _iss: Option<&str>,
+        ) -> Result<CompletedLogin, OAuthError> {
+            Ok(CompletedLogin {
+                did: "did:plc:test".to_string(),
+                handle: "test.bsky.social".to_string(),
+                access_token: "access".to_string(),
+                refresh_token: Some("refresh".to_string()),
+                expires_at: None,
+                dpop_key: Some("z-dpop".to_string()),
+            })
+        }
+
+        async fn refresh_tokens(
+            &self,
+            _did: &str,
+            _refresh_token: &str,
+            _dpop_key: &str,
+        ) -> Result<RefreshedTokens, OAuthError> {
+            Err(OAuthError::Request("not used".to_string()))
+        }
+
+        fn cleanup_expired(&self) -> usize {
+            0
+        }
+    }
+
+    #[sqlx::test]
+    async fn test_callback_persists_user_and_tokens(pool: PgPool) {
+        let db = Database::new(pool, EncryptionKey::test_key());
+        let state = Arc::new(AppState {
+            oauth: Some(Arc::new(MockOAuthService)),
+            ..AppState::test(db.clone())
+        });
+        let app = crate::web::create_router(state)
+            .layer(SessionManagerLayer::new(MemoryStore::default()));
+
+        let response = app
+            .oneshot(
+                Request::get("/auth/callback?code=abc&state=xyz")
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+
+        assert_eq!(response.status(), StatusCode::SEE_OTHER);
+        assert_eq!(response.headers()[header::LOCATION], "/dashboard");
+
+        let user = db.get_user_by_did("did:plc:test").await.unwrap().unwrap();
+        assert_eq!(user.bluesky_handle, "test.bsky.social");
+        let tokens = db.get_tokens(user.id).await.unwrap().unwrap();
+        assert_eq!(tokens.access_token, "access");
+        assert_eq!(tokens.dpop_key.as_deref(), Some("z-dpop"));
+    }
+
+    /// Visit the login page with `return_to`, then complete the callback in the same session
+    async fn login_returning_to(db: Database, return_to: &str) -> Response {
+        let state = Arc::new(AppState {
+            oauth: Some(Arc::new(MockOAuthService)),
+            ..AppState::test(db)
+        });
+        let app = crate::web::create_router(state)
+            .layer(SessionManagerLayer::new(MemoryStore::default()));
+
+        let page = app
+            .clone()
+            .oneshot(
+                Request::get(format!(
+                    "/auth/login?return_to={}",
+                    urlencoding::encode(return_to)
+                ))
+                .body(Body::empty())
+                .unwrap(),
+            )
+            .await
+            .unwrap();
+        let cookie = page.headers()[header::SET_COOKIE]
+            .to_str()
+            .unwrap()
+            .split(';')
+            .next()
+            .unwrap()
+            .to_string();
+
+        app.oneshot(
+            Request::get("/auth/callback?code=abc&state=xyz")
+                .header(header::COOKIE, cookie)
+                .body(Body::empty())
+                .unwrap(),
+        )
+        .await
+        .unwrap()
+    }
+
+    #[sqlx::test]
+    async fn test_callback_honors_local_return_to(pool: PgPool) {
+        let db = Database::new(pool, EncryptionKey::test_key());
+
+        let response = login_returning_to(db, "/settings?tab=sync").await;
+
+        assert_eq!(response.status(), StatusCode::SEE_OTHER);
+        assert_eq!(response.headers()[header::LOCATION], "/settings?tab=sync");
+    }
+
+    #[sqlx::test]
+    async fn test_callback_ignores_absolute_return_to(pool: PgPool) {
+        let db = Database::new(pool, EncryptionKey::test_key());
+
+        let response = login_returning_to(db, "https://evil.example/phish").await;
+
+        assert_eq!(response.status(), StatusCode::SEE_OTHER);
+        assert_eq!(response.headers()[header::LOCATION], "/dashboard");
+        assert_eq!(local_path("//evil.example"), None);
+        assert_eq!(local_path("/\\evil.example"), None);
+    }
+
+    #[sqlx::test]
+    async fn test_callback_updates_changed_handle(pool: PgPool) {
+        let db = Database::new(pool, EncryptionKey::test_key());
+        let existing = db
+            .create_user("did:plc:test", "old.bsky.social")
+            .await
+            .unwrap();
+        let state = Arc::new(AppState {
+            oauth: Some(Arc::new(MockOAuthService)),
+            ..AppState::test(db.clone())
+        });
+        let app = crate::web::create_router(state)
+            .layer(SessionManagerLayer::new(MemoryStore::default()));
+
+        let response = app
+            .oneshot(
+                Request::get("/auth/callback?code=abc&state=xyz")
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+
+        assert_eq!(response.status(), StatusCode::SEE_OTHER);
+        let user = db.get_user_by_did("did:plc:test").await.unwrap().unwrap();
+        assert_eq!(user.id, existing.id);
+        assert_eq!(user.bluesky_handle, "test.bsky.social");
+    }
+}
@@ -3,17 +3,26 @@
 use std::sync::Arc;
 
 use axum::{extract::State, response::Html};
+use tower_sessions::Session;
 
+use crate::content::html::html_escape;
+use crate::db::models::UserSettings;
+use crate::readwise::client::HIGHLIGHT_CATEGORIES;
+use crate::web::csrf::csrf_field;
+use crate::web::session::current_user_id;
 use crate::AppState;
 
-/// User settings dashboard
-pub async fn settings(State(_state): State<Arc<AppState>>) -> Html<String> {
-    // TODO: Get user from session
-    // TODO: Fetch user settings from database
-    // TODO: Render actual settings form
+/// Shown when bookmark sync stopped because the user's tokens were rejected
+const REAUTH_NOTICE: &str = r#"<div class="status warning">
+        <strong>Reconnect needed:</strong> Bluesky stopped accepting this app's access, so bookmark sync is paused.<br>
+        <a href="/auth/login">Log in with Bluesky again</a> and re-enable bookmark sync below.
+    </div>"#;
 
-    Html(
-        r#"<!DOCTYPE html>
+/// User settings dashboard, with the form filled in from saved settings
+pub async fn settings(State(state): State<Arc<AppState>>, session: Session) -> Html<String> {
+    let settings = saved_settings(&state, &session).await;
+
+    let page = r#"<!DOCTYPE html>
 <html>
 <head>
     <title>Settings - Readwise Autosave</title>
@@ -22,7 +31,7 @@ pub async fn settings(State(_state): State<Arc<AppState>>) -> Html<String> {
         h1 { color: #1185fe; }
         .form-group { margin: 1.5rem 0; }
         label { display: block; margin-bottom: 0.5rem; font-weight: 500; }
-        input[type="text"], input[type="password"] {
+        input[type="text"], input[type="password"], input[type="number"], textarea, select {
             width: 100%; padding: 0.5rem; border: 1px solid #ccc; border-radius: 4px;
         }
         .checkbox-group { display: flex; align-items: center; gap: 0.5rem; }
@@ -32,6 +41,7 @@ pub async fn settings(State(_state): State<Arc<AppState>>) -> Html<String> {
         .btn-danger { background: #dc3545; }
         .btn-danger:hover { background: #c82333; }
         .status { padding: 1rem; background: #e8f4fd; border-radius: 6px; margin-bottom: 1rem; }
+        .warning { background: #fff3cd; }
         .nav { margin-bottom: 2rem; }
         .nav a { color: #1185fe; }
     </style>
@@ -40,28 +50,33 @@ pub async fn settings(State(_state): State<Arc<AppState>>) -> Html<String> {
     <div class="nav">
         <a href="/">← Back to Home</a> |
         <form action="/auth/logout" method="POST" style="display: inline;">
+            {csrf_field}
             <button type="submit" style="background: none; border: none; color: #dc3545; cursor: pointer;">Logout</button>
         </form>
     </div>
 
     <h1>⚙️ Settings</h1>
 
+    {reauth_notice}
+    {backfill_notice}
     <div class="status">
         <strong>Status:</strong> Not connected<br>
         <small>Connect with Bluesky to enable bookmark sync.</small>
     </div>
 
     <form action="/api/settings" method="POST">
+        {csrf_field}
+        <input type="hidden" name="full_form" value="true">
         <div class="form-group">
             <label for="readwise_token">Readwise Access Token</label>
             <input type="password" id="readwise_token" name="readwise_token"
-                   placeholder="Get from readwise.io/access_token" required>
-            <small>Get your token at <a href="https://readwise.io/access_token" target="_blank">readwise.io/access_token</a></small>
+                   placeholder="Get from readwise.io/access_token">
+            <small>Get your token at <a href="https://readwise.io/access_token" target="_blank">readwise.io/access_token</a>. Leave blank to keep the one you saved.</small>
         </div>
 
         <div class="form-group">
             <div class="checkbox-group">
-                <input type="checkbox" id="bookmark_sync" name="bookmark_sync" checked>
+                <input type="checkbox" id="bookmark_sync" name="bookmark_sync" value="true"{bookmark_sync}>
                 <label for="bookmark_sync" style="margin-bottom: 0;">Enable bookmark sync</label>
             </div>
             <small>Automatically save bookmarked posts to Readwise</small>
@@ -69,18 +84,331 @@ pub async fn settings(State(_state): State<Arc<AppState>>) -> Html<String> {
 
         <div class="form-group">
             <div class="checkbox-group">
-                <input type="checkbox" id="extract_links" name="extract_links">
+                <input type="checkbox" id="extract_links" name="extract_links" value="true"{extract_links}>
                 <label for="extract_links" style="margin-bottom: 0;">Extract links from posts</label>
             </div>
             <small>Also save URLs found in bookmarked posts to Readwise Reader</small>
         </div>
 
+        <div class="form-group">
+            <div class="checkbox-group">
+                <input type="checkbox" id="save_image_alt_text" name="save_image_alt_text" value="true"{save_image_alt_text}>
+                <label for="save_image_alt_text" style="margin-bottom: 0;">Save image descriptions</label>
+            </div>
+            <small>Also save each image's alt text as its own highlight, tagged alt-text</small>
+        </div>
+
+        <div class="form-group">
+            <div class="checkbox-group">
+                <input type="checkbox" id="include_engagement" name="include_engagement" value="true"{include_engagement}>
+                <label for="include_engagement" style="margin-bottom: 0;">Include engagement counts</label>
+            </div>
+            <small>Add the post's likes, reposts, and replies at the time it was saved</small>
+        </div>
+
+        <div class="form-group">
+            <div class="checkbox-group">
+                <input type="checkbox" id="daily_digest" name="daily_digest" value="true"{daily_digest}>
+                <label for="daily_digest" style="margin-bottom: 0;">Daily digest</label>
+            </div>
+            <small>Get a DM each morning recapping the last day's saves</small>
+        </div>
+
+        <div class="form-group">
+            <div class="checkbox-group">
+                <input type="checkbox" id="skip_labeled" name="skip_labeled" value="true"{skip_labeled}>
+                <label for="skip_labeled" style="margin-bottom: 0;">Skip labeled posts</label>
+            </div>
+            <input type="text" id="skip_labels" name="skip_labels" value="{skip_labels}"
+                   placeholder="porn, sexual, nudity, graphic-media, gore">
+            <small>Don't auto-save bookmarks whose post or author carries these labels. Leave blank for adult and graphic content.</small>
+        </div>
+
+        <div class="form-group">
+            <label for="timezone">Timezone</label>
+            <input type="text" id="timezone" name="timezone" value="{timezone}" placeholder="UTC">
+            <small>For post times in saved threads and the daily digest, e.g. Europe/Berlin</small>
+        </div>
+
+        <div class="form-group">
+            <label for="min_post_length">Minimum post length</label>
+            <input type="number" id="min_post_length" name="min_post_length" min="0"
+                   value="{min_post_length}" placeholder="0">
+            <small>Skip bookmarked posts shorter than this many characters (a trailing link doesn't count)</small>
+        </div>
+
+        <div class="form-group">
+            <label for="poll_interval_secs">Check bookmarks every (seconds)</label>
+            <input type="number" id="poll_interval_secs" name="poll_interval_secs" min="10"
+                   value="{poll_interval_secs}" placeholder="30">
+            <small>Leave blank for the default. Values under 10 seconds are raised to 10.</small>
+        </div>
+
+        <div class="form-group">
+            <label for="highlight_category">Save posts as</label>
+            <select id="highlight_category" name="highlight_category">
+                <option value="">Default ({default_category})</option>
+                {highlight_category_options}
+            </select>
+            <small>Readwise category single-post highlights are filed under</small>
+        </div>
+
+        <div class="form-group">
+            <label for="post_class_rules">Save by post type</label>
+            <textarea id="post_class_rules" name="post_class_rules" rows="2"
+                      placeholder="reply=highlight:books, quote=document">{post_class_rules}</textarea>
+            <small>One rule per post type (top_level, reply, self_thread, quote): highlight or document, optionally with a category after a colon</small>
+        </div>
+
+        <div class="form-group">
+            <label for="author_allowlist">Only save posts by</label>
+            <textarea id="author_allowlist" name="author_allowlist" rows="2"
+                      placeholder="alice.bsky.social, did:plc:...">{author_allowlist}</textarea>
+            <small>Handles or DIDs, separated by commas or new lines. Leave empty to save from everyone.</small>
+        </div>
+
+        <div class="form-group">
+            <label for="author_denylist">Never save posts by</label>
+            <textarea id="author_denylist" name="author_denylist" rows="2">{author_denylist}</textarea>
+            <small>Takes precedence over the list above</small>
+        </div>
+
         <div class="form-group">
             <button type="submit" class="btn">Save Settings</button>
         </div>
     </form>
+
+    <h2>Delete Account</h2>
+    <form action="/api/delete-account" method="POST">
+        {csrf_field}
+        <div class="form-group">
+            <label for="confirm">Type DELETE to permanently remove your account and all saved data</label>
+            <input type="text" id="confirm" name="confirm" placeholder="DELETE" required>
+        </div>
+
+        <div class="form-group">
+            <button type="submit" class="btn btn-danger">Delete Account</button>
+        </div>
+    </form>
 </body>
 </html>"#
-            .to_string(),
+        .replace("{csrf_field}", &csrf_field(&session).await)
+        .replace(
+            "{reauth_notice}",
+            if needs_reauth(&state, &session).await {
+                REAUTH_NOTICE
+            } else {
+                ""
+            },
+        )
+        .replace("{backfill_notice}", &backfill_notice(settings.as_ref()))
+        .replace("{default_category}", &state.config.highlight_category);
+
+    Html(fill_settings_form(page, settings.as_ref()))
+}
+
+/// The logged-in user's settings, if they've saved any
+async fn saved_settings(state: &AppState, session: &Session) -> Option<UserSettings> {
+    let user_id = current_user_id(session).await?;
+    match state.db.get_user_settings(user_id).await {
+        Ok(settings) => settings,
+        Err(e) => {
+            tracing::warn!("Failed to load settings for {}: {}", user_id, e);
+            None
+        }
+    }
+}
+
+/// Fill the settings form's fields with the saved values
+///
+/// The form is submitted whole, so a field left empty here would clear the
+/// setting on the next save. Before anything is saved, only bookmark sync
+/// starts checked.
+fn fill_settings_form(page: String, settings: Option<&UserSettings>) -> String {
+    let checked = |enabled: bool| if enabled { " checked" } else { "" };
+    // Braces are escaped too, so saved text can't be taken for a placeholder
+    let text =
+        |value: Option<String>| html_escape(&value.unwrap_or_default()).replace('{', "&#123;");
+    let joined = |values: Option<&Vec<String>>, separator: &str| {
+        text(values.map(|values| values.join(separator)))
+    };
+
+    page.replace(
+        "{bookmark_sync}",
+        checked(settings.is_none_or(|s| s.bookmark_sync_enabled)),
+    )
+    .replace(
+        "{extract_links}",
+        checked(settings.is_some_and(|s| s.extract_links)),
+    )
+    .replace(
+        "{save_image_alt_text}",
+        checked(settings.is_some_and(|s| s.save_image_alt_text)),
+    )
+    .replace(
+        "{include_engagement}",
+        checked(settings.is_some_and(|s| s.include_engagement)),
+    )
+    .replace(
+        "{daily_digest}",
+        checked(settings.is_some_and(|s| s.daily_digest)),
+    )
+    .replace(
+        "{skip_labeled}",
+        checked(settings.is_some_and(|s| s.skip_labeled)),
+    )
+    .replace(
+        "{skip_labels}",
+        &joined(settings.map(|s| &s.skip_labels), ", "),
+    )
+    .replace(
+        "{timezone}",
+        &text(settings.and_then(|s| s.timezone.clone())),
     )
+    .replace(
+        "{min_post_length}",
+        &text(
+            settings
+                .map(|s| s.min_post_length)
+                .filter(|&length| length > 0)
+                .map(|length| length.to_string()),
+        ),
+    )
+    .replace(
+        "{poll_interval_secs}",
+        &text(
+            settings
+                .and_then(|s| s.poll_interval_secs)
+                .map(|secs| secs.to_string()),
+        ),
+    )
+    .replace(
+        "{highlight_category_options}",
+        &category_options(settings.and_then(|s| s.highlight_category.as_deref())),
+    )
+    .replace(
+        "{post_class_rules}",
+        &joined(settings.map(|s| &s.post_class_rules), ", "),
+    )
+    .replace(
+        "{author_allowlist}",
+        &joined(settings.map(|s| &s.author_allowlist), "\n"),
+    )
+    .replace(
+        "{author_denylist}",
+        &joined(settings.map(|s| &s.author_denylist), "\n"),
+    )
+}
+
+/// `<option>`s for each highlight category, with the saved one selected
+fn category_options(selected: Option<&str>) -> String {
+    HIGHLIGHT_CATEGORIES
+        .iter()
+        .map(|category| {
+            format!(
+                r#"<option value="{}"{}>{}</option>"#,
+                category,
+                if selected == Some(*category) {
+                    " selected"
+                } else {
+                    ""
+                },
+                capitalize(category)
+            )
+        })
+        .collect::<Vec<_>>()
+        .join("\n                ")
+}
+
+fn capitalize(word: &str) -> String {
+    let mut chars = word.chars();
+    match chars.next() {
+        Some(first) => first.to_uppercase().chain(chars).collect(),
+        None => String::new(),
+    }
+}
+
+/// Progress of the user's bookmark import, while it runs
+fn backfill_notice(settings: Option<&UserSettings>) -> String {
+    match settings.and_then(UserSettings::backfill_progress) {
+        Some((done, total)) => format!(
+            r#"<div class="status">
+        <strong>Importing your existing bookmarks:</strong> {} of {} done.
+    </div>"#,
+            done, total
+        ),
+        None => String::new(),
+    }
+}
+
+/// Whether the logged-in user has to log in again to resume sync
+async fn needs_reauth(state: &AppState, session: &Session) -> bool {
+    let Some(user_id) = current_user_id(session).await else {
+        return false;
+    };
+    match state.db.get_user_by_id(user_id).await {
+        Ok(user) => user.is_some_and(|user| user.needs_reauth),
+        Err(e) => {
+            tracing::warn!("Failed to load user {}: {}", user_id, e);
+            false
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::crypto::EncryptionKey;
+    use crate::db::models::SettingsUpdate;
+    use crate::db::queries::Database;
+    use crate::web::session::USER_ID_KEY;
+    use sqlx::PgPool;
+    use tower_sessions::MemoryStore;
+
+    #[sqlx::test]
+    async fn test_form_filled_with_saved_settings(pool: PgPool) {
+        let db = Database::new(pool, EncryptionKey::test_key());
+        let user = db
+            .create_user("did:plc:reader", "reader.bsky.social")
+            .await
+            .unwrap();
+        db.create_user_settings(user.id, "rw-secret-token", false, true)
+            .await
+            .unwrap();
+        db.update_user_settings(
+            user.id,
+            &SettingsUpdate {
+                daily_digest: Some(true),
+                timezone: Some(Some("Europe/Berlin".to_string())),
+                highlight_category: Some(Some("books".to_string())),
+                author_allowlist: Some(vec![
+                    "alice.bsky.social".to_string(),
+                    "<b>{timezone}".to_string(),
+                ]),
+                ..Default::default()
+            },
+        )
+        .await
+        .unwrap();
+        let session = Session::new(None, Arc::new(MemoryStore::default()), None);
+        session.insert(USER_ID_KEY, user.id).await.unwrap();
+
+        let state = AppState {
+            config: crate::config::Config {
+                highlight_category: "articles".to_string(),
+                ..crate::config::Config::test_default()
+            },
+            ..AppState::test(db)
+        };
+        let Html(page) = settings(State(Arc::new(state)), session).await;
+
+        assert!(page.contains(r#"name="bookmark_sync" value="true">"#));
+        assert!(page.contains(r#"name="extract_links" value="true" checked>"#));
+        assert!(page.contains(r#"name="daily_digest" value="true" checked>"#));
+        assert!(page.contains(r#"value="Europe/Berlin""#));
+        assert!(page.contains(r#"<option value="books" selected>Books</option>"#));
+        assert!(page.contains(r#"<option value="">Default (articles)</option>"#));
+        assert!(page.contains(">alice.bsky.social\n&lt;b&gt;&#123;timezone}</textarea>"));
+        assert!(!page.contains("rw-secret-token"));
+    }
 }
@@ -1,10 +1,24 @@
 //! HTTP handlers
 
+pub mod admin;
 pub mod api;
 pub mod auth;
 pub mod dashboard;
 
-use axum::response::Html;
+use std::future::Future;
+use std::sync::Arc;
+use std::time::Duration;
+
+use axum::extract::State;
+use axum::http::{header, StatusCode};
+use axum::response::{Html, IntoResponse};
+use axum::Json;
+use serde_json::{json, Value};
+
+use crate::AppState;
+
+/// Longest a single readiness check may take
+const READY_CHECK_TIMEOUT: Duration = Duration::from_secs(2);
 
 /// Landing page
 pub async fn index() -> Html<&'static str> {
@@ -35,7 +49,136 @@ pub async fn index() -> Html<&'static str> {
     )
 }
 
-/// Health check endpoint
+/// Health check endpoint (liveness; doesn't touch dependencies)
 pub async fn health() -> &'static str {
     "ok"
 }
+
+/// Readiness check: database and Bluesky public API
+///
+/// Returns 503 if any dependency fails or doesn't answer in time.
+pub async fn ready(State(state): State<Arc<AppState>>) -> impl IntoResponse {
+    let bluesky = state.bluesky_client();
+    let (database, bluesky) =
+        tokio::join!(check(state.db.ping()), check(bluesky.check_public_api()),);
+
+    let ready = database.is_ok() && bluesky.is_ok();
+    let status = if ready {
+        StatusCode::OK
+    } else {
+        StatusCode::SERVICE_UNAVAILABLE
+    };
+    let body = json!({
+        "status": if ready { "ready" } else { "unavailable" },
+        "checks": {
+            "database": check_status(&database),
+            "bluesky": check_status(&bluesky),
+        },
+    });
+    (status, Json(body))
+}
+
+/// Run a dependency check, failing it if it takes too long
+async fn check(probe: impl Future<Output = anyhow::Result<()>>) -> anyhow::Result<()> {
+    tokio::time::timeout(READY_CHECK_TIMEOUT, probe)
+        .await
+        .map_err(|_| anyhow::anyhow!("timed out"))?
+}
+
+/// JSON summary of a single check
+fn check_status(result: &anyhow::Result<()>) -> Value {
+    match result {
+        Ok(()) => json!("ok"),
+        Err(e) => json!(format!("error: {}", e)),
+    }
+}
+
+/// Prometheus metrics in the text exposition format
+pub async fn metrics() -> impl IntoResponse {
+    (
+        [(header::CONTENT_TYPE, "text/plain; version=0.0.4")],
+        crate::metrics::render(),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::body::{to_bytes, Body};
+    use axum::http::{Request, StatusCode};
+    use axum::routing::get;
+    use axum::Router;
+    use sqlx::PgPool;
+    use tower::ServiceExt;
+    use tower_sessions::{MemoryStore, SessionManagerLayer};
+
+    use crate::config::Config;
+    use crate::crypto::EncryptionKey;
+    use crate::db::queries::Database;
+
+    /// Local stand-in for the Bluesky public API health endpoint
+    async fn mock_public_api() -> String {
+        let app = Router::new().route("/xrpc/_health", get(|| async { "{}" }));
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+        format!("http://{}", addr)
+    }
+
+    async fn get_ready(db: Database) -> (StatusCode, Value) {
+        let state = Arc::new(AppState {
+            config: Config {
+                bsky_public_api_base: mock_public_api().await,
+                ..Config::test_default()
+            },
+            ..AppState::test(db)
+        });
+        let response = crate::web::create_router(state)
+            .layer(SessionManagerLayer::new(MemoryStore::default()))
+            .oneshot(Request::get("/ready").body(Body::empty()).unwrap())
+            .await
+            .unwrap();
+
+        let status = response.status();
+        let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        (status, serde_json::from_slice(&body).unwrap())
+    }
+
+    #[sqlx::test]
+    async fn test_ready_when_dependencies_are_up(pool: PgPool) {
+        let (status, body) = get_ready(Database::new(pool, EncryptionKey::test_key())).await;
+
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body["status"], "ready");
+        assert_eq!(body["checks"]["database"], "ok");
+        assert_eq!(body["checks"]["bluesky"], "ok");
+    }
+
+    #[sqlx::test]
+    async fn test_not_ready_when_database_is_down(pool: PgPool) {
+        pool.close().await;
+        let (status, body) = get_ready(Database::new(pool, EncryptionKey::test_key())).await;
+
+        assert_eq!(status, StatusCode::SERVICE_UNAVAILABLE);
+        assert_eq!(body["status"], "unavailable");
+        assert_ne!(body["checks"]["database"], "ok");
+        assert_eq!(body["checks"]["bluesky"], "ok");
+    }
+
+    #[tokio::test]
+    async fn test_metrics_endpoint_renders_counters() {
+        crate::metrics::init().unwrap();
+        crate::metrics::dm_processed();
+
+        let app = Router::new().route("/metrics", get(metrics));
+        let response = app
+            .oneshot(Request::get("/metrics").body(Body::empty()).unwrap())
+            .await
+            .unwrap();
+
+        assert_eq!(response.status(), StatusCode::OK);
+        let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let body = String::from_utf8(body.to_vec()).unwrap();
+        assert!(body.contains("dms_processed_total"));
+    }
+}
@@ -2,7 +2,12 @@
 //!
 //! Handles HTTP routes, OAuth flow, and dashboard.
 
+pub mod access;
+pub mod csrf;
+pub mod error;
 pub mod handlers;
+pub mod redirect;
 pub mod routes;
+pub mod session;
 
 pub use routes::create_router;
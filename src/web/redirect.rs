@@ -0,0 +1,81 @@
+//! Redirects to user-influenced targets
+//!
+//! Anything a user could have supplied (e.g. a login `return_to`) goes
+//! through [`safe_redirect`], so a crafted target can't send them to
+//! another site. URLs we build ourselves, like the OAuth authorization
+//! URL, don't need it.
+
+use axum::response::Redirect;
+
+/// Where a rejected target redirects instead
+const FALLBACK: &str = "/";
+
+/// Redirect to `target` if it stays on this site, otherwise to `/`
+///
+/// Local paths are allowed, as are absolute URLs with the same origin as
+/// `base_url` (the server's public URL).
+pub fn safe_redirect(target: &str, base_url: &str) -> Redirect {
+    if local_path(target).is_some() || same_origin(target, base_url) {
+        Redirect::to(target)
+    } else {
+        tracing::warn!("Refusing to redirect off-site to {:?}", target);
+        Redirect::to(FALLBACK)
+    }
+}
+
+/// `path` if it's a path on this site (`/settings?tab=sync`), so
+/// redirecting to it can't send the user elsewhere
+///
+/// Rejects absolute and scheme-relative URLs (`//evil.com`), including
+/// the backslash forms browsers treat as `//`.
+pub fn local_path(path: &str) -> Option<&str> {
+    let local = path.starts_with('/')
+        && !path.starts_with("//")
+        && !path.contains('\\')
+        && !path.chars().any(char::is_control);
+    local.then_some(path)
+}
+
+/// Whether `target` is an absolute URL on `base_url`'s origin
+fn same_origin(target: &str, base_url: &str) -> bool {
+    match (url::Url::parse(target), url::Url::parse(base_url)) {
+        (Ok(target), Ok(base)) => {
+            matches!(target.scheme(), "http" | "https") && target.origin() == base.origin()
+        }
+        _ => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::http::header;
+    use axum::response::IntoResponse;
+
+    fn location(target: &str) -> String {
+        let response = safe_redirect(target, "https://autosave.example.com").into_response();
+        response.headers()[header::LOCATION]
+            .to_str()
+            .unwrap()
+            .to_string()
+    }
+
+    #[test]
+    fn test_off_site_targets_rejected() {
+        assert_eq!(location("//evil.com"), "/");
+        assert_eq!(location("https://evil.com"), "/");
+        assert_eq!(location("/\\evil.com"), "/");
+        assert_eq!(location("javascript:alert(1)"), "/");
+        assert_eq!(location("https://autosave.example.com.evil.com/"), "/");
+    }
+
+    #[test]
+    fn test_local_and_same_origin_targets_allowed() {
+        assert_eq!(location("/dashboard"), "/dashboard");
+        assert_eq!(location("/settings?tab=sync"), "/settings?tab=sync");
+        assert_eq!(
+            location("https://autosave.example.com/dashboard"),
+            "https://autosave.example.com/dashboard"
+        );
+    }
+}
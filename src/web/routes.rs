@@ -1,28 +1,209 @@
 //! Route definitions
 
 use std::sync::Arc;
+use std::time::Duration;
 
 use axum::{
+    http::StatusCode,
+    middleware,
     routing::{get, post},
     Router,
 };
+use tower_http::limit::RequestBodyLimitLayer;
+use tower_http::timeout::TimeoutLayer;
 
-use super::handlers;
+use super::{access, csrf, handlers};
+use crate::request_id;
 use crate::AppState;
 
+/// Largest request body accepted (forms are far smaller)
+const MAX_BODY_BYTES: usize = 64 * 1024;
+
+/// Inbound timeout for ordinary requests
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Inbound timeout for login routes, which wait on OAuth round trips
+const AUTH_REQUEST_TIMEOUT: Duration = Duration::from_secs(30);
+
 /// Create the application router
+///
+/// `/metrics` and `/admin` get the operator access guard when it's configured.
 pub fn create_router(state: Arc<AppState>) -> Router {
-    Router::new()
+    // Login and callback call out to PDSes and authorization servers
+    let oauth_routes = Router::new()
+        .route(
+            "/auth/login",
+            get(handlers::auth::login_page).post(handlers::auth::login),
+        )
+        .route("/auth/callback", get(handlers::auth::callback))
+        .layer(TimeoutLayer::with_status_code(
+            StatusCode::REQUEST_TIMEOUT,
+            AUTH_REQUEST_TIMEOUT,
+        ));
+
+    let router = Router::new()
         // Public routes
         .route("/", get(handlers::index))
         .route("/health", get(handlers::health))
+        .route("/ready", get(handlers::ready))
+        .route("/metrics", get(handlers::metrics))
         // Auth routes
-        .route("/auth/login", get(handlers::auth::login))
-        .route("/auth/callback", get(handlers::auth::callback))
+        .route("/auth/magic/:token", get(handlers::auth::magic_link))
         .route("/auth/logout", post(handlers::auth::logout))
+        .route(
+            "/client-metadata.json",
+            get(handlers::auth::client_metadata),
+        )
+        .route("/jwks.json", get(handlers::auth::jwks))
         // Dashboard routes
         .route("/dashboard", get(handlers::dashboard::settings))
         .route("/api/settings", post(handlers::api::update_settings))
-        // Share state with all routes
-        .with_state(state)
+        .route(
+            "/api/readwise-token",
+            post(handlers::api::rotate_readwise_token),
+        )
+        .route("/api/status", get(handlers::api::status))
+        .route("/api/save-events", get(handlers::api::save_events))
+        .route("/api/whoami", get(handlers::api::whoami))
+        .route("/api/preview", get(handlers::api::preview))
+        .route("/api/reprocess", post(handlers::api::reprocess))
+        .route("/api/delete-account", post(handlers::api::delete_account))
+        // Operator routes
+        .route("/admin/users", get(handlers::admin::list_users))
+        .route(
+            "/admin/features",
+            get(handlers::admin::features).post(handlers::admin::set_feature),
+        )
+        .layer(TimeoutLayer::with_status_code(
+            StatusCode::REQUEST_TIMEOUT,
+            REQUEST_TIMEOUT,
+        ))
+        .merge(oauth_routes)
+        // Reject POSTs without a valid CSRF token
+        .layer(middleware::from_fn(csrf::verify_csrf))
+        // Bound request bodies before anything buffers them
+        .layer(RequestBodyLimitLayer::new(MAX_BODY_BYTES));
+
+    // Keep operational routes away from the public
+    let router = match access::AccessGuard::from_config(&state.config) {
+        Some(guard) => router.layer(middleware::from_fn_with_state(
+            Arc::new(guard),
+            access::require_access,
+        )),
+        None => router,
+    };
+
+    // Give each request an ID for its logs and outbound calls
+    let router = router.layer(middleware::from_fn(request_id::assign_request_id));
+
+    // Share state with all routes
+    router.with_state(state)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::crypto::EncryptionKey;
+    use crate::db::queries::Database;
+    use axum::body::Body;
+    use axum::http::{header, Request};
+    use sqlx::PgPool;
+    use tower::ServiceExt;
+    use tower_sessions::{MemoryStore, SessionManagerLayer};
+
+    #[sqlx::test]
+    async fn test_oversized_body_rejected(pool: PgPool) {
+        let state = Arc::new(AppState::test(Database::new(
+            pool,
+            EncryptionKey::test_key(),
+        )));
+        let app = create_router(state).layer(SessionManagerLayer::new(MemoryStore::default()));
+
+        let body = "a".repeat(MAX_BODY_BYTES + 1);
+        let response = app
+            .oneshot(
+                Request::post("/api/settings")
+                    .header(header::CONTENT_TYPE, "application/x-www-form-urlencoded")
+                    .header(header::CONTENT_LENGTH, body.len())
+                    .body(Body::from(body))
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+
+        assert_eq!(response.status(), StatusCode::PAYLOAD_TOO_LARGE);
+    }
+
+    async fn status_of(app: Router, request: Request<Body>) -> StatusCode {
+        let app = app.layer(SessionManagerLayer::new(MemoryStore::default()));
+        app.oneshot(request).await.unwrap().status()
+    }
+
+    #[sqlx::test]
+    async fn test_metrics_require_basic_auth(pool: PgPool) {
+        use base64::{engine::general_purpose::STANDARD, Engine};
+
+        let state = Arc::new(AppState {
+            config: crate::config::Config {
+                protected_basic_auth: Some("ops:secret".to_string()),
+                ..crate::config::Config::test_default()
+            },
+            ..AppState::test(Database::new(pool, EncryptionKey::test_key()))
+        });
+        let app = create_router(state);
+        let request = |auth: Option<&str>| {
+            let mut request = Request::get("/metrics");
+            if let Some(credentials) = auth {
+                let value = format!("Basic {}", STANDARD.encode(credentials));
+                request = request.header(header::AUTHORIZATION, value);
+            }
+            request.body(Body::empty()).unwrap()
+        };
+
+        assert_eq!(
+            status_of(app.clone(), request(Some("ops:secret"))).await,
+            StatusCode::OK
+        );
+        assert_eq!(
+            status_of(app.clone(), request(Some("ops:wrong"))).await,
+            StatusCode::UNAUTHORIZED
+        );
+        assert_eq!(
+            status_of(app.clone(), request(None)).await,
+            StatusCode::UNAUTHORIZED
+        );
+        // Unprotected routes are untouched
+        let health = Request::get("/health").body(Body::empty()).unwrap();
+        assert_eq!(status_of(app, health).await, StatusCode::OK);
+    }
+
+    #[sqlx::test]
+    async fn test_metrics_ip_allowlist(pool: PgPool) {
+        use axum::extract::ConnectInfo;
+        use std::net::SocketAddr;
+
+        let state = Arc::new(AppState {
+            config: crate::config::Config {
+                protected_allowed_ips: Some("10.0.0.0/8".to_string()),
+                ..crate::config::Config::test_default()
+            },
+            ..AppState::test(Database::new(pool, EncryptionKey::test_key()))
+        });
+        let app = create_router(state);
+        let from = |peer: &str| {
+            Request::get("/metrics")
+                .extension(ConnectInfo(peer.parse::<SocketAddr>().unwrap()))
+                .body(Body::empty())
+                .unwrap()
+        };
+
+        assert_eq!(
+            status_of(app.clone(), from("10.1.2.3:5000")).await,
+            StatusCode::OK
+        );
+        assert_eq!(
+            status_of(app, from("203.0.113.9:5000")).await,
+            StatusCode::FORBIDDEN
+        );
+    }
 }
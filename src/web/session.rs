@@ -0,0 +1,25 @@
+//! Session helpers
+//!
+//! Keys and accessors for data stored in the user's session.
+
+use tower_sessions::Session;
+use uuid::Uuid;
+
+/// Session key for the logged-in user's database ID
+pub const USER_ID_KEY: &str = "user_id";
+
+/// Session key for the logged-in user's DID
+pub const DID_KEY: &str = "did";
+
+/// Session key for the local path to return to after logging in
+pub const RETURN_TO_KEY: &str = "return_to";
+
+/// Get the logged-in user's ID from the session, if any
+pub async fn current_user_id(session: &Session) -> Option<Uuid> {
+    session.get::<Uuid>(USER_ID_KEY).await.ok().flatten()
+}
+
+/// Get the logged-in user's DID from the session, if any
+pub async fn current_did(session: &Session) -> Option<String> {
+    session.get::<String>(DID_KEY).await.ok().flatten()
+}